@@ -12,8 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::ethereum::ethereum_signing_hash;
 use crate::handler::verify_tx_sig;
 use crate::hashable::Hashable;
+use crate::multisig::verify_multisig_sigs;
+use crate::sponsor::verify_sponsor_sig;
 use cita_types::H256;
 use libproto::TryInto;
 use libproto::{BlockTxn, GetBlockTxn, Origin, SignedTransaction};
@@ -112,10 +115,28 @@ impl BlockTxnMessage {
             .map(|(short_id, transaction)| {
                 let tx_hash = H256::from_slice(short_id);
                 // TODO: move verify tx sig to transaction?
-                let bytes: Vec<u8> = transaction.get_transaction().try_into().unwrap();
-                let hash = bytes.crypt_hash();
+                let hash =
+                    ethereum_signing_hash(transaction.get_transaction()).unwrap_or_else(|| {
+                        let bytes: Vec<u8> = transaction.get_transaction().try_into().unwrap();
+                        bytes.crypt_hash()
+                    });
                 let result =
-                    verify_tx_sig(transaction.get_crypto(), &hash, transaction.get_signature());
+                    verify_tx_sig(transaction.get_crypto(), &hash, transaction.get_signature())
+                        .and_then(|pubkey| {
+                            verify_sponsor_sig(
+                                transaction.get_crypto(),
+                                transaction.get_transaction(),
+                                &pubkey,
+                            )
+                            .map(|_| pubkey)
+                        })
+                        .and_then(|pubkey| {
+                            verify_multisig_sigs(
+                                transaction.get_crypto(),
+                                transaction.get_transaction(),
+                            )
+                            .map(|_| pubkey)
+                        });
                 match result {
                     Ok(pubkey) => {
                         let mut signed_tx = SignedTransaction::new();
@@ -0,0 +1,161 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum transactions, sent with `eth_sendRawTransaction` and signed as in
+//! EIP-155.
+//!
+//! jsonrpc wraps the raw transaction in a placeholder, which is converted here
+//! into a transaction of the chain version. The envelope and the signing hash
+//! are those of common-types, see `ethereum_unverified_transaction` and
+//! `Transaction::ethereum_hash`. Whether the Ethereum nonce is the current one
+//! is up to the executor.
+
+use crate::transaction_verify::Error;
+use cita_types::H256;
+use libproto::blockchain::Transaction as ProtoTransaction;
+use libproto::{UnverifiedTransaction, VerifyTxReq};
+use types::transaction::{
+    ethereum_unverified_transaction, Transaction, ENVELOPE_TX_VERSION, ETHEREUM_DATA_PREFIX,
+    RAW_ETHEREUM_TX_PREFIX,
+};
+
+/// The raw Ethereum transaction jsonrpc wrapped in `un_tx`, if any.
+pub fn raw_ethereum_tx(un_tx: &UnverifiedTransaction) -> Option<&[u8]> {
+    let data = un_tx.get_transaction().get_data();
+    if un_tx.get_signature().is_empty() && data.starts_with(RAW_ETHEREUM_TX_PREFIX) {
+        Some(&data[RAW_ETHEREUM_TX_PREFIX.len()..])
+    } else {
+        None
+    }
+}
+
+/// Replace the raw Ethereum transaction wrapped in `un_tx` with the converted
+/// one of `version`, valid until `valid_until_block`. Other transactions are
+/// kept as they are.
+pub fn convert_ethereum_tx(
+    un_tx: &mut UnverifiedTransaction,
+    version: u32,
+    valid_until_block: u64,
+) -> Result<(), Error> {
+    let converted = match raw_ethereum_tx(un_tx) {
+        Some(raw) => ethereum_unverified_transaction(raw, version, valid_until_block)
+            .map_err(|_| Error::InvalidValue)?,
+        None => return Ok(()),
+    };
+    *un_tx = converted;
+    Ok(())
+}
+
+/// The EIP-155 signing hash of an Ethereum transaction, which its signature
+/// is made on instead of the hash of the transaction.
+pub fn ethereum_signing_hash(tx: &ProtoTransaction) -> Option<H256> {
+    if tx.get_version() < ENVELOPE_TX_VERSION || !tx.get_data().starts_with(ETHEREUM_DATA_PREFIX) {
+        return None;
+    }
+    Transaction::create(tx)
+        .ok()
+        .and_then(|tx| tx.ethereum_hash())
+}
+
+/// The verify request of `un_tx`, with the hash its signature is made on.
+pub fn tx_verify_req(un_tx: &UnverifiedTransaction) -> VerifyTxReq {
+    let mut req = un_tx.tx_verify_req_msg();
+    if let Some(hash) = ethereum_signing_hash(un_tx.get_transaction()) {
+        req.set_hash(hash.to_vec());
+    }
+    req
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of EIP-155
+    const RAW_TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn wrap(raw: &[u8]) -> UnverifiedTransaction {
+        let mut data = RAW_ETHEREUM_TX_PREFIX.to_vec();
+        data.extend_from_slice(raw);
+        let mut tx = ProtoTransaction::new();
+        tx.set_data(data);
+        let mut un_tx = UnverifiedTransaction::new();
+        un_tx.set_transaction(tx);
+        un_tx
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_convert_ethereum_tx() {
+        let mut un_tx = wrap(&from_hex(RAW_TX));
+        assert!(raw_ethereum_tx(&un_tx).is_some());
+        convert_ethereum_tx(&mut un_tx, ENVELOPE_TX_VERSION, 99).unwrap();
+        assert!(raw_ethereum_tx(&un_tx).is_none());
+        assert_eq!(un_tx.get_transaction().get_valid_until_block(), 99);
+        assert_eq!(un_tx.get_transaction().get_version(), ENVELOPE_TX_VERSION);
+
+        let req = tx_verify_req(&un_tx);
+        assert_eq!(
+            req.get_hash(),
+            &from_hex("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")[..]
+        );
+        // The hash of the transaction is kept.
+        assert_eq!(req.get_tx_hash(), un_tx.tx_verify_req_msg().get_tx_hash());
+
+        // Converted transactions and the others are kept as they are.
+        let converted = un_tx.clone();
+        convert_ethereum_tx(&mut un_tx, ENVELOPE_TX_VERSION, 100).unwrap();
+        assert_eq!(un_tx, converted);
+
+        let mut un_tx = wrap(&from_hex(RAW_TX)[1..]);
+        assert_eq!(
+            convert_ethereum_tx(&mut un_tx, ENVELOPE_TX_VERSION, 99),
+            Err(Error::InvalidValue)
+        );
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_verify_ethereum_sig() {
+        use crate::handler::verify_tx_sig;
+        use crypto::{pubkey_to_address, PubKey};
+        use libproto::blockchain::Crypto;
+
+        let mut un_tx = wrap(&from_hex(RAW_TX));
+        convert_ethereum_tx(&mut un_tx, ENVELOPE_TX_VERSION, 99).unwrap();
+        let req = tx_verify_req(&un_tx);
+        let pubkey = verify_tx_sig(
+            Crypto::DEFAULT,
+            &H256::from(req.get_hash()),
+            req.get_signature(),
+        )
+        .unwrap();
+        assert_eq!(
+            pubkey_to_address(&PubKey::from_slice(&pubkey)).to_vec(),
+            from_hex("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")
+        );
+
+        // The signature is not made on the hash of the transaction.
+        let hash = un_tx.tx_verify_req_msg().get_hash().to_vec();
+        assert_ne!(
+            verify_tx_sig(Crypto::DEFAULT, &H256::from(&hash[..]), req.get_signature()),
+            Ok(pubkey)
+        );
+    }
+}
@@ -15,7 +15,10 @@
 use crate::block_txn::{BlockTxnMessage, BlockTxnReq};
 use crate::block_verify::BlockVerify;
 use crate::dispatcher::Dispatcher;
+use crate::ethereum::{convert_ethereum_tx, tx_verify_req};
 use crate::history::HistoryHeights;
+use crate::multisig::verify_multisig_sigs;
+use crate::sponsor::verify_sponsor_sig;
 use crate::transaction_verify::Error;
 use cita_types::traits::LowerHex;
 use cita_types::{clean_0x, Address, H256, U256};
@@ -38,7 +41,9 @@ use rayon::ThreadPoolBuilder;
 use std::collections::{HashMap, HashSet};
 use std::convert::Into;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use types::raw_message::RawMessage;
+use types::transaction::split_ethereum_data;
 use util::BLOCKLIMIT;
 
 const TX_OK: &str = "OK";
@@ -48,6 +53,8 @@ const G_TX_DATA_NON_ZERO: usize = 68;
 const G_TRANSACTION: usize = 21000;
 // Paid for contract create
 const G_CREATE: usize = 32000;
+// Interval of the heartbeats collected by the health check of jsonrpc
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(3);
 
 // verify signature
 pub fn verify_tx_sig(crypto: Crypto, hash: &H256, sig_bytes: &[u8]) -> Result<Vec<u8>, ()> {
@@ -95,10 +102,12 @@ pub struct MsgHandler {
     tx_pool_limit: usize,
     is_snapshot: bool,
     black_list_cache: HashMap<Address, i8>,
+    frozen_accounts: HashSet<Address>,
     is_need_proposal_new_block: bool,
     config_info: SysConfigInfo,
     block_txn_req: Option<BlockTxnReq>,
     verify_block_req: Option<VerifyBlockReq>,
+    last_heartbeat: Option<Instant>,
 }
 
 impl MsgHandler {
@@ -127,6 +136,7 @@ impl MsgHandler {
             tx_pool_limit,
             is_snapshot: false,
             black_list_cache: HashMap::new(),
+            frozen_accounts: HashSet::new(),
             is_need_proposal_new_block: false,
             config_info: SysConfigInfo {
                 block_quota_limit: 0,
@@ -137,6 +147,7 @@ impl MsgHandler {
             },
             block_txn_req: None,
             verify_block_req: None,
+            last_heartbeat: None,
         }
     }
 
@@ -207,7 +218,7 @@ impl MsgHandler {
             if !to.is_empty() && Address::from_str(to).is_err() {
                 return Err(Error::InvalidValue);
             }
-        } else if tx_version < 3 {
+        } else if tx_version < 4 {
             // old to must be empty
             if !tx.get_to().is_empty() {
                 return Err(Error::InvalidValue);
@@ -245,6 +256,56 @@ impl MsgHandler {
         }
     }
 
+    /// Verify the sender and the callee of a transaction are not frozen
+    fn verify_frozen(&self, req: &VerifyTxReq, tx: &Transaction) -> Result<(), Error> {
+        if self.frozen_accounts.is_empty() {
+            return Ok(());
+        }
+        let sender = pubkey_to_address(&PubKey::from_slice(req.get_signer()));
+        let to = if tx.get_version() == 0 {
+            Address::from_str(clean_0x(tx.get_to())).ok()
+        } else if tx.get_to_v1().len() == 20 {
+            Some(Address::from_slice(tx.get_to_v1()))
+        } else {
+            None
+        };
+        if self.frozen_accounts.contains(&sender)
+            || to.map_or(false, |to| self.frozen_accounts.contains(&to))
+        {
+            return Err(Error::Frozen);
+        }
+        Ok(())
+    }
+
+    /// Verify the signatures carried in the data of a transaction: those of
+    /// the signers of a multisig transaction, and that of the sponsor of a
+    /// sponsored transaction, who is checked against the black list like the
+    /// sender. Neither the multisig account nor the sponsor may be frozen.
+    fn verify_data_sigs(&self, req: &VerifyTxReq, tx: &Transaction) -> Result<(), Error> {
+        let account =
+            verify_multisig_sigs(req.get_crypto(), tx).map_err(|_| Error::BadMultisigSig)?;
+        if account.map_or(false, |account| self.frozen_accounts.contains(&account)) {
+            return Err(Error::Frozen);
+        }
+        let sponsor = verify_sponsor_sig(req.get_crypto(), tx, req.get_signer())
+            .map_err(|_| Error::BadSponsorSig)?;
+        if let Some(pubkey) = sponsor {
+            let sponsor = pubkey_to_address(&PubKey::from_slice(&pubkey));
+            if self.frozen_accounts.contains(&sponsor) {
+                return Err(Error::Frozen);
+            }
+            if self
+                .black_list_cache
+                .get(&sponsor)
+                .map(|credit| *credit < 0)
+                .unwrap_or(false)
+            {
+                return Err(Error::Forbidden);
+            }
+        }
+        Ok(())
+    }
+
     fn verify_tx_req_chain_id(&self, req: &VerifyTxReq) -> Result<(), Error> {
         let version = self.config_info.version.unwrap();
 
@@ -258,7 +319,7 @@ impl MsgHandler {
                     Some(ChainId::V0(chain_id))
                 }
             }
-            version if version < 3 => {
+            version if version < 4 => {
                 // old chain id must be empty
                 if req.get_chain_id() != 0 || req.get_chain_id_v1().len() != 32 {
                     None
@@ -368,6 +429,17 @@ impl MsgHandler {
             .unwrap();
     }
 
+    // The transactions which entered the pool, for the pending transaction filters of the chain.
+    fn publish_pending_transactions(&self, hashes: Vec<H256>) {
+        if hashes.is_empty() {
+            return;
+        }
+        let message = RawMessage::PendingTransactions { hashes };
+        self.tx_pub
+            .send((routing_key!(Auth >> RawBytes).into(), message.to_bytes()))
+            .unwrap();
+    }
+
     fn forward_request(&self, tx_req: Request) {
         let _ = self.tx_request.send(tx_req);
     }
@@ -416,6 +488,39 @@ impl MsgHandler {
         }
     }
 
+    // Report the readiness conditions to jsonrpc, which serves them as `/health` and `/ready`.
+    fn heartbeat(&mut self) {
+        if self
+            .last_heartbeat
+            .map_or(false, |last| last.elapsed() < HEARTBEAT_INTERVAL)
+        {
+            return;
+        }
+        self.last_heartbeat = Some(Instant::now());
+
+        let heartbeat = serde_json::json!({
+            "service": "auth",
+            "height": self.history_heights.max_height(),
+            "ready": self.is_ready(),
+            "conditions": {
+                "history_heights_init": self.history_heights.is_init(),
+                "chain_id_received": self.chain_id.is_some(),
+                "version_received": self.config_info.version.is_some(),
+                "not_snapshot": !self.is_snapshot,
+            },
+            "details": {
+                "tx_pool_len": self.dispatcher.tx_pool_len(),
+                "tx_pool_limit": self.tx_pool_limit,
+            },
+        });
+        if let Err(e) = self.tx_pub.send((
+            routing_key!(Auth >> RawBytes).into(),
+            heartbeat.to_string().into_bytes(),
+        )) {
+            error!("Send heartbeat error {:?}", e);
+        }
+    }
+
     fn get_chain_id(&mut self) {
         if self.chain_id.is_none() && self.config_info.version.is_some() {
             trace!("chain id is not ready");
@@ -435,6 +540,12 @@ impl MsgHandler {
 
     fn process_msg(&mut self) {
         if let Ok((key, payload)) = self.rx_sub.recv_timeout(Duration::new(3, 0)) {
+            // The raw bytes of executor are not a message.
+            if RoutingKey::from(&key) == routing_key!(Executor >> RawBytes) {
+                self.deal_raw_bytes(&payload);
+                return;
+            }
+
             if Message::try_from(&payload).is_err() {
                 error!("Can not get message from payload {:?}", &payload);
                 return;
@@ -529,6 +640,7 @@ impl MsgHandler {
 
             // Daily tasks
             self.daily_task();
+            self.heartbeat();
 
             // process message from MQ
             self.process_msg();
@@ -558,7 +670,7 @@ impl MsgHandler {
                 Some(Address::from(block_tx_hashes.get_admin_address()))
             };
             let block_tx_version = block_tx_hashes.get_version();
-            let check_version = block_tx_version > 0 && block_tx_version < 3;
+            let check_version = block_tx_version > 0 && block_tx_version < 4;
             // Get chain id according to version
             if check_version && self.config_info.version == Some(0) {
                 trace!("Fetch new chain id");
@@ -595,6 +707,14 @@ impl MsgHandler {
         self.history_hashes.entry(height).or_insert(tx_hashes_h256);
     }
 
+    fn deal_raw_bytes(&mut self, body: &[u8]) {
+        // The heartbeats are ignored.
+        if let Some(RawMessage::FrozenAccounts { accounts }) = RawMessage::from_bytes(body) {
+            self.frozen_accounts = accounts.into_iter().collect();
+            debug!("Current frozen accounts are {:?}", self.frozen_accounts);
+        }
+    }
+
     fn deal_black_list(&mut self, black_list: &BlackList) {
         black_list
             .get_clear_list()
@@ -625,8 +745,45 @@ impl MsgHandler {
             });
     }
 
+    /// Convert the raw Ethereum transactions jsonrpc wrapped into transactions
+    /// valid until the last block they may be included in. The requests whose
+    /// transactions can not be converted fail.
+    fn convert_ethereum_txs(&self, newtx_req: &mut Request) {
+        let version = self.config_info.version.unwrap();
+        let valid_until_block = self.history_heights.next_height() + BLOCKLIMIT - 1;
+        if newtx_req.has_batch_req() {
+            let tx_reqs: Vec<Request> = newtx_req
+                .mut_batch_req()
+                .take_new_tx_requests()
+                .into_iter()
+                .filter_map(|mut tx_req| {
+                    match convert_ethereum_tx(tx_req.mut_un_tx(), version, valid_until_block) {
+                        Ok(()) => Some(tx_req),
+                        Err(e) => {
+                            self.publish_tx_failed_result(tx_req.get_request_id().to_vec(), &e);
+                            None
+                        }
+                    }
+                })
+                .collect();
+            newtx_req
+                .mut_batch_req()
+                .set_new_tx_requests(tx_reqs.into());
+        } else if newtx_req.has_un_tx() {
+            if let Err(e) = convert_ethereum_tx(newtx_req.mut_un_tx(), version, valid_until_block) {
+                self.publish_tx_failed_result(newtx_req.get_request_id().to_vec(), &e);
+                newtx_req.clear_un_tx();
+            }
+        }
+    }
+
     #[allow(unknown_lints, clippy::cognitive_complexity)] // TODO clippy
-    fn deal_request(&mut self, is_local: bool, newtx_req: Request) {
+    fn deal_request(&mut self, is_local: bool, mut newtx_req: Request) {
+        // Only jsonrpc sends raw Ethereum transactions, which are converted
+        // before they are forwarded.
+        if is_local && self.is_ready() {
+            self.convert_ethereum_txs(&mut newtx_req);
+        }
         if newtx_req.has_batch_req() {
             let batch_new_tx = newtx_req.get_batch_req().get_new_tx_requests();
             trace!(
@@ -658,7 +815,7 @@ impl MsgHandler {
             let mut requests = HashMap::new();
             let mut requests_no_cached = HashMap::new();
             for tx_req in batch_new_tx {
-                let req = tx_req.get_un_tx().tx_verify_req_msg();
+                let req = tx_verify_req(tx_req.get_un_tx());
                 let tx_hash = H256::from_slice(req.get_tx_hash());
                 if let Some(option_pubkey) = self.get_ret_from_cache(&tx_hash) {
                     if option_pubkey.is_none() {
@@ -708,6 +865,7 @@ impl MsgHandler {
             }
 
             // other verify
+            let mut pending_hashes = Vec::new();
             requests
                 .into_iter()
                 .filter(|(_tx_hash, (_req, _tx_req, flag))| *flag)
@@ -722,6 +880,17 @@ impl MsgHandler {
                         true
                     }
                 })
+                .filter(|(_tx_hash, (ref req, ref tx_req, _flag))| {
+                    if let Err(e) = self.verify_frozen(&req, tx_req.get_un_tx().get_transaction()) {
+                        if is_local {
+                            let request_id = tx_req.get_request_id().to_vec();
+                            self.publish_tx_failed_result(request_id, &e);
+                        }
+                        false
+                    } else {
+                        true
+                    }
+                })
                 .filter(|(_tx_hash, (ref _req, ref tx_req, _flag))| {
                     if let Err(e) = self.verify_request(tx_req) {
                         if is_local {
@@ -733,6 +902,19 @@ impl MsgHandler {
                         true
                     }
                 })
+                .filter(|(_tx_hash, (ref req, ref tx_req, _flag))| {
+                    if let Err(e) =
+                        self.verify_data_sigs(&req, tx_req.get_un_tx().get_transaction())
+                    {
+                        if is_local {
+                            let request_id = tx_req.get_request_id().to_vec();
+                            self.publish_tx_failed_result(request_id, &e);
+                        }
+                        false
+                    } else {
+                        true
+                    }
+                })
                 .filter(|(_tx_hash, (ref req, ref tx_req, _flag))| {
                     if let Err(e) = self.verify_tx_req(&req) {
                         if is_local {
@@ -754,6 +936,7 @@ impl MsgHandler {
                         if is_local {
                             self.publish_tx_success_result(request_id, tx_hash);
                         }
+                        pending_hashes.push(tx_hash);
                         // new tx need forward to other nodes
                         self.forward_request(tx_req.clone());
                     } else if is_local {
@@ -761,6 +944,7 @@ impl MsgHandler {
                         self.publish_tx_failed_result(request_id, &Error::Dup);
                     }
                 });
+            self.publish_pending_transactions(pending_hashes);
         } else if newtx_req.has_un_tx() {
             trace!("get single new tx request from Jsonrpc");
             let request_id = newtx_req.get_request_id().to_vec();
@@ -778,7 +962,7 @@ impl MsgHandler {
                 }
                 return;
             }
-            let mut req = newtx_req.get_un_tx().tx_verify_req_msg();
+            let mut req = tx_verify_req(newtx_req.get_un_tx());
             // verify with cache
             let tx_hash = H256::from_slice(req.get_tx_hash());
             if let Some(option_pubkey) = self.get_ret_from_cache(&tx_hash) {
@@ -815,6 +999,13 @@ impl MsgHandler {
                 return;
             }
 
+            if let Err(e) = self.verify_frozen(&req, newtx_req.get_un_tx().get_transaction()) {
+                if is_local {
+                    self.publish_tx_failed_result(request_id, &e);
+                }
+                return;
+            }
+
             if let Err(e) = self.verify_request(&newtx_req) {
                 if is_local {
                     self.publish_tx_failed_result(request_id, &e);
@@ -822,6 +1013,13 @@ impl MsgHandler {
                 return;
             }
 
+            if let Err(e) = self.verify_data_sigs(&req, newtx_req.get_un_tx().get_transaction()) {
+                if is_local {
+                    self.publish_tx_failed_result(request_id, &e);
+                }
+                return;
+            }
+
             // other verify
             if let Err(e) = self.verify_tx_req(&req) {
                 if is_local {
@@ -839,6 +1037,7 @@ impl MsgHandler {
                 if is_local {
                     self.publish_tx_success_result(request_id, tx_hash);
                 }
+                self.publish_pending_transactions(vec![tx_hash]);
                 // new tx need forward to other nodes
                 self.forward_request(newtx_req);
             } else if is_local {
@@ -871,6 +1070,7 @@ impl MsgHandler {
                 self.history_heights.reset();
                 self.history_hashes.clear();
                 self.black_list_cache.clear();
+                self.frozen_accounts.clear();
 
                 snapshot_response(&self.tx_pub, Resp::ClearAck, true);
             }
@@ -887,7 +1087,7 @@ impl MsgHandler {
         if let Some(version) = self.config_info.version {
             self.chain_id = if version == 0 {
                 Some(ChainId::V0(miscellaneous.chain_id))
-            } else if version < 3 {
+            } else if version < 4 {
                 if miscellaneous.chain_id_v1.len() == 32 {
                     Some(ChainId::V1(U256::from(
                         miscellaneous.chain_id_v1.as_slice(),
@@ -1109,12 +1309,14 @@ pub fn verify_base_quota_required(tx: &Transaction) -> bool {
     match tx.get_version() {
         0..=2 => true,
         _ => {
+            // The sender of an Ethereum transaction signed the payload only.
+            let data_len = split_ethereum_data(tx.get_data())
+                .map_or(tx.data.len(), |(_, _, payload)| payload.len());
             let to = tx.get_to_v1();
             if to.is_empty() || Address::from(to) == Address::zero() {
-                tx.get_quota() as usize
-                    >= tx.data.len() * G_TX_DATA_NON_ZERO + G_TRANSACTION + G_CREATE
+                tx.get_quota() as usize >= data_len * G_TX_DATA_NON_ZERO + G_TRANSACTION + G_CREATE
             } else {
-                tx.get_quota() as usize >= tx.data.len() * G_TX_DATA_NON_ZERO + G_TRANSACTION
+                tx.get_quota() as usize >= data_len * G_TX_DATA_NON_ZERO + G_TRANSACTION
             }
         }
     }
@@ -31,6 +31,7 @@
 //!     | auth  | Net       | Request           |
 //!     | auth  | Snapshot  | SnapshotReq       |
 //!     | auth  | Executor  | Miscellaneous     |
+//!     | auth  | Executor  | RawBytes          |
 //!     | auth  | Net       | GetBlockTxn       |
 //!     | auth  | Net       | BlockTxn          |
 //!
@@ -47,6 +48,8 @@
 //!     | auth  | Auth      | Executor  | MiscellaneousReq |
 //!     | auth  | Auth      | Net       | GetBlockTxn      |
 //!     | auth  | Auth      | Net       | BlockTxn         |
+//!     | auth  | Auth      | Jsonrpc   | RawBytes         |
+//!     | auth  | Auth      | Chain     | RawBytes         |
 //!
 //! ### Key behavior
 //!
@@ -79,6 +82,7 @@ extern crate serde_derive;
 extern crate tempfile;
 #[macro_use]
 extern crate util;
+extern crate common_types as types;
 extern crate hashable;
 
 use batch_forward::BatchForward;
@@ -97,8 +101,11 @@ pub mod block_txn;
 pub mod block_verify;
 pub mod config;
 pub mod dispatcher;
+pub mod ethereum;
 pub mod handler;
 pub mod history;
+pub mod multisig;
+pub mod sponsor;
 mod transaction_verify;
 pub mod txwal;
 
@@ -149,6 +156,7 @@ fn main() {
             Net >> Request,
             Snapshot >> SnapshotReq,
             Executor >> Miscellaneous,
+            Executor >> RawBytes,
             Net >> GetBlockTxn,
             Net >> BlockTxn,
         ]),
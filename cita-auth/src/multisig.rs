@@ -0,0 +1,91 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transactions sent as a multi-signature account.
+//!
+//! The envelope and the hash the signers sign are those of common-types, see
+//! `Transaction::multisig_data` and `Transaction::multisig_hash`. Only the
+//! signatures are checked here; whether the signers reach the threshold of the
+//! account, and whether the nonce is the current one, is up to the executor,
+//! which keeps the signer sets and the nonces.
+
+use crate::handler::verify_tx_sig;
+use cita_types::Address;
+use libproto::blockchain::{Crypto, Transaction as ProtoTransaction};
+use types::transaction::{Transaction, ENVELOPE_TX_VERSION, MULTISIG_DATA_PREFIX};
+
+/// Verify the signatures of a multisig transaction.
+///
+/// Returns the account the transaction is sent as, or none if it is not
+/// multisig. A malformed envelope or any unrecoverable signature fails.
+pub fn verify_multisig_sigs(crypto: Crypto, tx: &ProtoTransaction) -> Result<Option<Address>, ()> {
+    if tx.get_version() < ENVELOPE_TX_VERSION || !tx.get_data().starts_with(MULTISIG_DATA_PREFIX) {
+        return Ok(None);
+    }
+    let tx = Transaction::create(tx).map_err(|_| ())?;
+    let (account, nonce, signatures, _) = tx.multisig_data().ok_or(())?;
+    let hash = tx.multisig_hash(&account, nonce);
+    for signature in signatures {
+        verify_tx_sig(crypto, &hash, signature)?;
+    }
+    Ok(Some(account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::{CreateKey, KeyPair, Sign, Signature};
+
+    #[test]
+    fn test_verify_multisig_sigs() {
+        let signer = KeyPair::gen_keypair();
+        let account = Address::from(0x1234);
+        let mut tx = ProtoTransaction::new();
+        tx.set_nonce("0".to_owned());
+        tx.set_quota(100_000);
+        tx.set_valid_until_block(99);
+        tx.set_value(vec![0; 32]);
+        tx.set_version(ENVELOPE_TX_VERSION);
+        tx.set_chain_id_v1(vec![1; 32]);
+        tx.set_to_v1(vec![2; 20]);
+        tx.set_data(vec![3, 4]);
+
+        // Not multisig.
+        assert_eq!(verify_multisig_sigs(Crypto::DEFAULT, &tx), Ok(None));
+
+        let hash = Transaction::create(&tx).unwrap().multisig_hash(&account, 7);
+        let signature = Signature::sign(signer.privkey(), &hash).unwrap();
+        let mut data = MULTISIG_DATA_PREFIX.to_vec();
+        data.extend_from_slice(&account);
+        data.extend_from_slice(&7u64.to_be_bytes());
+        data.push(1);
+        data.extend_from_slice(&signature.to_vec());
+        data.extend_from_slice(&[3, 4]);
+        tx.set_data(data.clone());
+        assert_eq!(
+            verify_multisig_sigs(Crypto::DEFAULT, &tx),
+            Ok(Some(account))
+        );
+
+        // Earlier versions carry no envelope.
+        tx.set_version(ENVELOPE_TX_VERSION - 1);
+        assert_eq!(verify_multisig_sigs(Crypto::DEFAULT, &tx), Ok(None));
+        tx.set_version(ENVELOPE_TX_VERSION);
+
+        // More signatures counted than carried.
+        data[MULTISIG_DATA_PREFIX.len() + 28] = 2;
+        tx.set_data(data);
+        assert_eq!(verify_multisig_sigs(Crypto::DEFAULT, &tx), Err(()));
+    }
+}
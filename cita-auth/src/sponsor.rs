@@ -0,0 +1,103 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sponsored transactions, whose quota is paid by a sponsor instead of the
+//! sender.
+//!
+//! The envelope and the hash the sponsor signs are those of common-types, see
+//! `Transaction::sponsored_data` and `Transaction::sponsor_hash`, which the
+//! executor recovers the sponsor with as well. Whether the sender nonce the
+//! sponsor signed is the current one is up to the executor.
+
+use crate::handler::verify_tx_sig;
+use crypto::{pubkey_to_address, PubKey};
+use libproto::blockchain::{Crypto, Transaction as ProtoTransaction};
+use types::transaction::{Transaction, SPONSORED_DATA_PREFIX};
+
+/// Verify the sponsor signature of `tx` sent by the owner of `signer`.
+///
+/// Returns the public key of the sponsor, or none if `tx` is not sponsored.
+pub fn verify_sponsor_sig(
+    crypto: Crypto,
+    tx: &ProtoTransaction,
+    signer: &[u8],
+) -> Result<Option<Vec<u8>>, ()> {
+    if !tx.get_data().starts_with(SPONSORED_DATA_PREFIX) {
+        return Ok(None);
+    }
+    let tx = Transaction::create(tx).map_err(|_| ())?;
+    match tx.sponsored_data() {
+        Some((nonce, signature, _)) => {
+            let sender = pubkey_to_address(&PubKey::from_slice(signer));
+            verify_tx_sig(crypto, &tx.sponsor_hash(&sender, nonce), signature).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::{CreateKey, KeyPair, Sign, Signature};
+    use types::transaction::ENVELOPE_TX_VERSION;
+
+    #[test]
+    fn test_verify_sponsor_sig() {
+        let sender = KeyPair::gen_keypair();
+        let sponsor = KeyPair::gen_keypair();
+        let mut tx = ProtoTransaction::new();
+        tx.set_nonce("0".to_owned());
+        tx.set_quota(100_000);
+        tx.set_valid_until_block(99);
+        tx.set_value(vec![0; 32]);
+        tx.set_version(ENVELOPE_TX_VERSION);
+        tx.set_chain_id_v1(vec![1; 32]);
+        tx.set_to_v1(vec![2; 20]);
+        tx.set_data(vec![3, 4]);
+
+        // Not sponsored.
+        assert_eq!(
+            verify_sponsor_sig(Crypto::DEFAULT, &tx, &sender.pubkey().to_vec()),
+            Ok(None)
+        );
+
+        let hash = Transaction::create(&tx)
+            .unwrap()
+            .sponsor_hash(&sender.address(), 0);
+        let signature = Signature::sign(sponsor.privkey(), &hash).unwrap();
+        let mut data = SPONSORED_DATA_PREFIX.to_vec();
+        data.extend_from_slice(&0u64.to_be_bytes());
+        data.extend_from_slice(&signature.to_vec());
+        data.extend_from_slice(&[3, 4]);
+        tx.set_data(data);
+        assert_eq!(
+            verify_sponsor_sig(Crypto::DEFAULT, &tx, &sender.pubkey().to_vec()),
+            Ok(Some(sponsor.pubkey().to_vec()))
+        );
+
+        // The signature is bound to the sender.
+        let other = KeyPair::gen_keypair();
+        assert_ne!(
+            verify_sponsor_sig(Crypto::DEFAULT, &tx, &other.pubkey().to_vec()),
+            Ok(Some(sponsor.pubkey().to_vec()))
+        );
+
+        // Earlier versions carry no envelope.
+        tx.set_version(ENVELOPE_TX_VERSION - 1);
+        assert_eq!(
+            verify_sponsor_sig(Crypto::DEFAULT, &tx, &sender.pubkey().to_vec()),
+            Ok(None)
+        );
+    }
+}
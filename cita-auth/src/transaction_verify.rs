@@ -20,12 +20,15 @@ pub enum Error {
     Dup,
     InvalidUntilBlock,
     BadSig,
+    BadSponsorSig,
+    BadMultisigSig,
     NotReady,
     Busy,
     BadChainId,
     // TODO: rename to QuotaOverflow
     QuotaNotEnough,
     Forbidden,
+    Frozen,
     InvalidValue,
     InvalidVersion,
 }
@@ -38,11 +41,14 @@ impl fmt::Display for Error {
             Dup => write!(f, "Dup"),
             InvalidUntilBlock => write!(f, "InvalidUntilBlock"),
             BadSig => write!(f, "BadSig"),
+            BadSponsorSig => write!(f, "BadSponsorSig"),
+            BadMultisigSig => write!(f, "BadMultisigSig"),
             NotReady => write!(f, "NotReady"),
             Busy => write!(f, "Busy"),
             BadChainId => write!(f, "BadChainId"),
             QuotaNotEnough => write!(f, "QuotaNotEnough"),
             Forbidden => write!(f, "Forbidden"),
+            Frozen => write!(f, "Frozen"),
             InvalidValue => write!(f, "InvalidValue"),
             InvalidVersion => write!(f, "InvalidVersion"),
         }
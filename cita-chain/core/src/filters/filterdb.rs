@@ -12,10 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::cita_db::{DataCategory, Database, RocksDB};
+use crate::db_indexes::{CurrentFilters, DBIndex, FilterId2Filter};
+use cita_types::H256;
 use jsonrpc_types::rpc_types::Filter;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::SystemTime;
 
+/// Max number of transactions a pending transaction filter keeps until it's polled,
+/// the earliest ones are dropped.
+const MAX_PENDING_TRANSACTIONS: usize = 10_000;
+
 // TODO Refactor:
 // * use generic data type
 // * use one hashmap: use tuple type
@@ -73,40 +81,112 @@ impl LogsFilter {
     }
 }
 
+/// A filter persisted in the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredFilter {
+    block: Option<u64>,
+    logs: Option<Filter>,
+    #[serde(default)]
+    client: Option<String>,
+    #[serde(default)]
+    pending: bool,
+}
+
 #[derive(Default)]
 pub struct FilterDB {
-    /// Self-increase ID.
-    next_available_id: usize,
     /// To save the last update timestamp
     last_update: HashMap<usize, u64>,
     /// Refactor: Note: logs filter includes block filter.
     block_filter: BlockFilter,
     logs_filter: LogsFilter,
+    /// The transactions which entered the pool since each pending transaction filter was
+    /// polled. They are not persisted.
+    pending_filter: HashMap<usize, Vec<H256>>,
+    /// The clients which created the filters, the others can't use them
+    clients: HashMap<usize, String>,
     /// lifetime of fileter id
     lifetime: u32,
+    /// Max number of filters
+    max_filters: usize,
+    /// To persist the filters, so they survive a restart
+    db: Option<Arc<RocksDB>>,
 }
 
 impl FilterDB {
-    pub fn new() -> Self {
+    pub fn new(lifetime: u32, max_filters: usize) -> Self {
         FilterDB {
-            // Set the lifetime: 60s
-            lifetime: 60,
+            lifetime,
+            max_filters,
             ..Default::default()
         }
     }
 
+    /// Load the persisted filters.
+    /// They are all given a fresh lifetime, since the time of the restart is unknown.
+    pub fn load(db: Arc<RocksDB>, lifetime: u32, max_filters: usize) -> Self {
+        let mut filterdb = FilterDB::new(lifetime, max_filters);
+        let ids: Vec<u64> = db
+            .get(Some(DataCategory::Extra), &CurrentFilters.get_index())
+            .unwrap_or(None)
+            .map(|ids| rlp::decode_list(&ids))
+            .unwrap_or_default();
+
+        let now = now();
+        for id in ids {
+            let stored = db
+                .get(Some(DataCategory::Extra), &FilterId2Filter(id).get_index())
+                .unwrap_or(None)
+                .and_then(|value| serde_json::from_slice::<StoredFilter>(&value).ok());
+            if let Some(stored) = stored {
+                let id = id as usize;
+                filterdb.last_update.insert(id, now);
+                if let Some(block) = stored.block {
+                    filterdb.block_filter.insert(id, block);
+                }
+                if let Some(filter) = stored.logs {
+                    filterdb.logs_filter.insert(id, filter);
+                }
+                if stored.pending {
+                    filterdb.pending_filter.insert(id, Vec::new());
+                }
+                if let Some(client) = stored.client {
+                    filterdb.clients.insert(id, client);
+                }
+            }
+        }
+        info!("load {} filters", filterdb.last_update.len());
+
+        filterdb.db = Some(db);
+        filterdb
+    }
+
     #[cfg(test)]
     pub fn set_lifetime(&mut self, lifetime: u32) {
         self.lifetime = lifetime;
     }
 
-    /// Generate a new fresh id
+    /// Generate a new fresh id of the client, `None` if there are too many filters.
     /// Prune the hashmap first.
-    pub fn gen_id(&mut self) -> usize {
+    ///
+    /// The id is random, so a client can't guess the filters of others.
+    pub fn gen_id(&mut self, client: Option<&str>) -> Option<usize> {
         self.prune();
-        let id = self.next_available_id;
-        self.next_available_id = self.next_available_id.wrapping_add(1);
-        id
+        if self.last_update.len() >= self.max_filters {
+            warn!("too many filters: {}", self.last_update.len());
+            return None;
+        }
+        let id = loop {
+            let id = rand::random::<u64>() as usize;
+            if !self.last_update.contains_key(&id) {
+                break id;
+            }
+        };
+        self.last_update.insert(id, now());
+        if let Some(client) = client {
+            self.clients.insert(id, client.to_owned());
+        }
+        self.save_ids();
+        Some(id)
     }
 
     /// Generate a new normal filter
@@ -114,6 +194,7 @@ impl FilterDB {
         let now = now();
         self.last_update.insert(id, now);
         self.logs_filter.insert(id, filter);
+        self.save_filter(id);
     }
 
     /// Generate a new filter for block
@@ -121,6 +202,36 @@ impl FilterDB {
         let now = now();
         self.last_update.insert(id, now);
         self.block_filter.insert(id, filter);
+        self.save_filter(id);
+    }
+
+    /// Generate a new filter for pending transactions
+    pub fn gen_pending_filter(&mut self, id: usize) {
+        let now = now();
+        self.last_update.insert(id, now);
+        self.pending_filter.insert(id, Vec::new());
+        self.save_filter(id);
+    }
+
+    /// Add the transactions which entered the pool to the pending transaction filters.
+    pub fn add_pending_transactions(&mut self, hashes: &[H256]) {
+        for pending in self.pending_filter.values_mut() {
+            pending.extend_from_slice(hashes);
+            if pending.len() > MAX_PENDING_TRANSACTIONS {
+                let overflow = pending.len() - MAX_PENDING_TRANSACTIONS;
+                pending.drain(..overflow);
+            }
+        }
+    }
+
+    /// Take the transactions of the pending transaction filter since it was polled.
+    /// Prune the hashmap first.
+    pub fn take_pending_transactions(&mut self, id: usize) -> Option<Vec<H256>> {
+        let now = now();
+        self.prune();
+        let pending = self.pending_filter.get_mut(&id)?;
+        self.last_update.insert(id, now);
+        Some(::std::mem::replace(pending, Vec::new()))
     }
 
     /// Uninstall the filter id
@@ -129,11 +240,8 @@ impl FilterDB {
         // Logs filter includes the block filter.
         // Remove block filter if is filter.
         if self.is_filter(id) {
-            self.block_filter.remove(id);
-            if self.is_logs_filter(id) {
-                self.logs_filter.remove(id);
-            }
-
+            self.remove(id);
+            self.save_ids();
             true
         } else {
             false
@@ -144,11 +252,65 @@ impl FilterDB {
     /// Remove all the ids that: (now-lastupdate) > self.lifetime
     pub fn prune(&mut self) {
         let now = now();
-        for (id, time) in self.last_update.clone().iter() {
-            if (now - *time) >= self.lifetime.into() {
-                trace!("Prune filter, time: {:?}", (now - *time));
-                self.block_filter.remove(*id);
-                self.logs_filter.remove(*id);
+        let overdue = self
+            .last_update
+            .iter()
+            .filter(|(_, time)| (now - **time) >= self.lifetime.into())
+            .map(|(id, _)| *id)
+            .collect::<Vec<usize>>();
+        if overdue.is_empty() {
+            return;
+        }
+        trace!("Prune filters: {:?}", overdue);
+        for id in overdue {
+            self.remove(id);
+        }
+        self.save_ids();
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.last_update.remove(&id);
+        self.block_filter.remove(id);
+        self.logs_filter.remove(id);
+        self.pending_filter.remove(&id);
+        self.clients.remove(&id);
+        if let Some(ref db) = self.db {
+            let _ = db.remove(
+                Some(DataCategory::Extra),
+                &FilterId2Filter(id as u64).get_index(),
+            );
+        }
+    }
+
+    fn save_ids(&self) {
+        if let Some(ref db) = self.db {
+            let ids = self
+                .last_update
+                .keys()
+                .map(|id| *id as u64)
+                .collect::<Vec<u64>>();
+            let _ = db.insert(
+                Some(DataCategory::Extra),
+                CurrentFilters.get_index(),
+                rlp::encode_list(&ids).into_vec(),
+            );
+        }
+    }
+
+    fn save_filter(&self, id: usize) {
+        if let Some(ref db) = self.db {
+            let stored = StoredFilter {
+                block: self.block_filter.get(id).cloned(),
+                logs: self.logs_filter.get(id).cloned(),
+                client: self.clients.get(&id).cloned(),
+                pending: self.pending_filter.contains_key(&id),
+            };
+            if let Ok(value) = serde_json::to_vec(&stored) {
+                let _ = db.insert(
+                    Some(DataCategory::Extra),
+                    FilterId2Filter(id as u64).get_index(),
+                    value,
+                );
             }
         }
     }
@@ -159,7 +321,9 @@ impl FilterDB {
         let now = now();
         trace!("Get logs filter: {:?}", self.logs_filter.get(id));
         self.prune();
-        self.last_update.insert(id, now);
+        if self.is_filter(id) {
+            self.last_update.insert(id, now);
+        }
         self.logs_filter.get(id)
     }
 
@@ -169,7 +333,9 @@ impl FilterDB {
         let now = now();
         trace!("Get block filter: {:?}", self.block_filter.get(id));
         self.prune();
-        self.last_update.insert(id, now);
+        if self.is_filter(id) {
+            self.last_update.insert(id, now);
+        }
         self.block_filter.get(id)
     }
 
@@ -184,9 +350,21 @@ impl FilterDB {
         self.logs_filter.is_filter(id)
     }
 
-    /// Check the id is block filter or logs filter
+    /// Check the id is for pending transactions
+    pub fn is_pending_filter(&self, id: usize) -> bool {
+        self.pending_filter.contains_key(&id)
+    }
+
+    /// Check the id is block filter, logs filter or pending transaction filter
     pub fn is_filter(&self, id: usize) -> bool {
-        self.logs_filter.is_filter(id) || self.block_filter.is_filter(id)
+        self.logs_filter.is_filter(id)
+            || self.block_filter.is_filter(id)
+            || self.pending_filter.contains_key(&id)
+    }
+
+    /// Check the filter was created by the client, `None` for the filters of no client.
+    pub fn is_client(&self, id: usize, client: Option<&str>) -> bool {
+        self.clients.get(&id).map(String::as_str) == client
     }
 }
 
@@ -200,22 +378,27 @@ fn now() -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::FilterDB;
+    use super::{FilterDB, MAX_PENDING_TRANSACTIONS};
+    use cita_types::H256;
     use jsonrpc_types::rpc_types::BlockNumber;
     use jsonrpc_types::rpc_types::Filter;
 
     #[test]
     fn test_gen_id() {
-        let mut filterdb = FilterDB::new();
-        let id = filterdb.next_available_id;
-        filterdb.gen_id();
-        assert_eq!(filterdb.next_available_id, id + 1);
+        let mut filterdb = FilterDB::new(60, 2);
+        let id = filterdb.gen_id(None).unwrap();
+        assert_ne!(filterdb.gen_id(None).unwrap(), id);
+        // Too many filters
+        assert_eq!(filterdb.gen_id(None), None);
+        filterdb.gen_block_filter(id, 0);
+        assert!(filterdb.uninstall(id));
+        assert!(filterdb.gen_id(None).is_some());
     }
 
     #[test]
     fn test_gen_logs_filter_and_uninstall() {
-        let mut filterdb = FilterDB::new();
-        let id = filterdb.gen_id();
+        let mut filterdb = FilterDB::new(60, 10);
+        let id = filterdb.gen_id(None).unwrap();
         let filter = Filter::new(BlockNumber::earliest(), BlockNumber::earliest(), None, None);
         assert_eq!(filterdb.is_logs_filter(id), false);
         assert_eq!(filterdb.get_logs_filter(id), None);
@@ -231,8 +414,8 @@ mod tests {
 
     #[test]
     fn test_gen_block_filter_and_uninstall() {
-        let mut filterdb = FilterDB::new();
-        let id = filterdb.gen_id();
+        let mut filterdb = FilterDB::new(60, 10);
+        let id = filterdb.gen_id(None).unwrap();
         let filter = 0;
         assert_eq!(filterdb.is_block_filter(id), false);
         assert_eq!(filterdb.get_block_filter(id), None);
@@ -248,8 +431,8 @@ mod tests {
 
     #[test]
     fn test_prune() {
-        let mut filterdb = FilterDB::new();
-        let id = filterdb.gen_id();
+        let mut filterdb = FilterDB::new(60, 10);
+        let id = filterdb.gen_id(None).unwrap();
         let filter = 0;
         filterdb.gen_block_filter(id, filter.clone());
         assert_eq!(filterdb.is_filter(id), true);
@@ -260,4 +443,48 @@ mod tests {
         assert_eq!(filterdb.get_block_filter(id), None);
         assert_eq!(filterdb.is_filter(id), false);
     }
+
+    #[test]
+    fn test_pending_filter() {
+        let mut filterdb = FilterDB::new(60, 10);
+        let id = filterdb.gen_id(None).unwrap();
+        filterdb.gen_pending_filter(id);
+        assert!(filterdb.is_pending_filter(id));
+        assert!(filterdb.is_filter(id));
+        assert!(!filterdb.is_block_filter(id));
+
+        filterdb.add_pending_transactions(&[H256::from(1), H256::from(2)]);
+        assert_eq!(
+            filterdb.take_pending_transactions(id),
+            Some(vec![H256::from(1), H256::from(2)])
+        );
+        assert_eq!(filterdb.take_pending_transactions(id), Some(Vec::new()));
+
+        // Only the latest transactions are kept.
+        let hashes = (0..=MAX_PENDING_TRANSACTIONS as u64)
+            .map(H256::from)
+            .collect::<Vec<H256>>();
+        filterdb.add_pending_transactions(&hashes);
+        assert_eq!(
+            filterdb.take_pending_transactions(id).unwrap(),
+            &hashes[1..]
+        );
+
+        assert!(filterdb.uninstall(id));
+        assert_eq!(filterdb.take_pending_transactions(id), None);
+    }
+
+    #[test]
+    fn test_client() {
+        let mut filterdb = FilterDB::new(60, 10);
+        let id = filterdb.gen_id(Some("client")).unwrap();
+        filterdb.gen_block_filter(id, 0);
+        assert!(filterdb.is_client(id, Some("client")));
+        assert!(!filterdb.is_client(id, Some("other")));
+        assert!(!filterdb.is_client(id, None));
+
+        let id = filterdb.gen_id(None).unwrap();
+        assert!(filterdb.is_client(id, None));
+        assert!(!filterdb.is_client(id, Some("client")));
+    }
 }
@@ -21,26 +21,31 @@ use jsonrpc_types::rpc_types::{Filter, FilterChanges, Log};
 /// The RPC interfaces about filter.
 ///     * newFilter
 ///     * newBlockFilter
+///     * newPendingTransactionFilter
 ///     * getFilterChanges
 ///     * getFilterLogs
 ///     * uninstallFilter
 /// *Not include `getLogs`*.
+///
+/// A filter is only used by the client which created it, `None` for the requests of no client.
 pub trait RpcFilter {
-    // Create a new filter and return the filter id
+    // Create a new filter and return the filter id, `None` if there are too many filters
     // https://docs.citahub.com/zh-CN/cita/rpc-guide/rpc#newfilter
-    fn new_filter(&self, filter: Filter) -> usize;
+    fn new_filter(&self, filter: Filter, client: Option<&str>) -> Option<usize>;
     // Create a new filter that can listen the new block.
     // https://docs.citahub.com/zh-CN/cita/rpc-guide/rpc#newblockfilter
-    fn new_block_filter(&self) -> usize;
+    fn new_block_filter(&self, client: Option<&str>) -> Option<usize>;
+    // Create a new filter that can listen the transactions entering the pool of auth.
+    fn new_pending_transaction_filter(&self, client: Option<&str>) -> Option<usize>;
     // Get the logs for the filter with the given id since last time it was called.
     // https://docs.citahub.com/zh-CN/cita/rpc-guide/rpc#getfilterchanges
-    fn get_filter_changes(&self, id: usize) -> Option<FilterChanges>;
+    fn get_filter_changes(&self, id: usize, client: Option<&str>) -> Option<FilterChanges>;
     // Get the logs for the filter with the given id.
     // https://docs.citahub.com/zh-CN/cita/rpc-guide/rpc#getfilterlogs
-    fn get_filter_logs(&self, id: usize) -> Option<Vec<Log>>;
+    fn get_filter_logs(&self, id: usize, client: Option<&str>) -> Option<Vec<Log>>;
     // Remove the filter with the given id.
     // https://docs.citahub.com/zh-CN/cita/rpc-guide/rpc#uninstallfilter
-    fn uninstall_filter(&self, id: usize) -> bool;
+    fn uninstall_filter(&self, id: usize, client: Option<&str>) -> bool;
 }
 
 /// Helper for RpcFilter
@@ -77,9 +82,9 @@ impl FilterHelper for Chain {
 }
 
 impl RpcFilter for Chain {
-    fn new_filter(&self, filter: Filter) -> usize {
+    fn new_filter(&self, filter: Filter, client: Option<&str>) -> Option<usize> {
         let filterdb = self.filter_db();
-        let id = filterdb.try_lock().unwrap().gen_id();
+        let id = filterdb.try_lock().unwrap().gen_id(client)?;
         let block_number = self.get_current_height();
         filterdb.try_lock().unwrap().gen_logs_filter(id, filter);
         filterdb
@@ -87,32 +92,54 @@ impl RpcFilter for Chain {
             .unwrap()
             .gen_block_filter(id, block_number);
         drop(filterdb);
-        id
+        Some(id)
     }
 
-    fn new_block_filter(&self) -> usize {
+    fn new_block_filter(&self, client: Option<&str>) -> Option<usize> {
         let filterdb = self.filter_db();
         let block_number = self.get_current_height();
-        let id = filterdb.try_lock().unwrap().gen_id();
+        let id = filterdb.try_lock().unwrap().gen_id(client)?;
         filterdb
             .try_lock()
             .unwrap()
             .gen_block_filter(id, block_number);
         drop(filterdb);
-        id
+        Some(id)
     }
 
-    fn get_filter_changes(&self, id: usize) -> Option<FilterChanges> {
+    fn new_pending_transaction_filter(&self, client: Option<&str>) -> Option<usize> {
+        let filterdb = self.filter_db();
+        let id = filterdb.try_lock().unwrap().gen_id(client)?;
+        filterdb.try_lock().unwrap().gen_pending_filter(id);
+        drop(filterdb);
+        Some(id)
+    }
+
+    fn get_filter_changes(&self, id: usize, client: Option<&str>) -> Option<FilterChanges> {
         let filterdb = self.filter_db();
         let mut changes = Some(FilterChanges::Empty);
         let current_number = self.get_current_height();
         let block_filter = self.get_block_filter(id);
 
-        if !filterdb.try_lock().unwrap().is_filter(id) {
+        let is_filter = {
+            let filterdb = filterdb.try_lock().unwrap();
+            filterdb.is_filter(id) && filterdb.is_client(id, client)
+        };
+        if !is_filter {
             drop(filterdb);
             return changes;
         }
 
+        // Check the pending transactions, which have no block
+        let pending = filterdb.try_lock().unwrap().take_pending_transactions(id);
+        if let Some(hashes) = pending {
+            trace!("Pending transaction filter changes: {:?}", hashes);
+            drop(filterdb);
+            return Some(FilterChanges::Hashes(
+                hashes.into_iter().map(Into::into).collect(),
+            ));
+        }
+
         // Check the logs
         if let Some(filter) = filterdb.try_lock().unwrap().get_logs_filter(id) {
             trace!("Into filter changes: logs");
@@ -143,8 +170,12 @@ impl RpcFilter for Chain {
         changes
     }
 
-    fn get_filter_logs(&self, id: usize) -> Option<Vec<Log>> {
+    fn get_filter_logs(&self, id: usize, client: Option<&str>) -> Option<Vec<Log>> {
         let filterdb = self.filter_db();
+        if !filterdb.try_lock().unwrap().is_client(id, client) {
+            drop(filterdb);
+            return None;
+        }
         let block_filter = self.get_block_filter(id);
         let logs = match filterdb.try_lock().unwrap().get_logs_filter(id) {
             Some(filter) => Some(self.get_logs_with_filter(filter.clone(), block_filter)),
@@ -154,9 +185,12 @@ impl RpcFilter for Chain {
         logs
     }
 
-    fn uninstall_filter(&self, id: usize) -> bool {
+    fn uninstall_filter(&self, id: usize, client: Option<&str>) -> bool {
         let filterdb = self.filter_db();
-        let uninstall_ok = filterdb.try_lock().unwrap().uninstall(id);
+        let uninstall_ok = {
+            let mut filterdb = filterdb.try_lock().unwrap();
+            filterdb.is_client(id, client) && filterdb.uninstall(id)
+        };
         drop(filterdb);
         uninstall_ok
     }
@@ -17,6 +17,9 @@ use crate::bloomchain::group::{
 };
 use crate::bloomchain::{Bloom, Config as BloomChainConfig, Number as BloomChainNumber};
 use crate::header::{BlockNumber, Header};
+use crate::libchain::quota_price::{
+    BlockQuotaUsage, QuotaPriceChanges, QuotaPriceHistory, QuotaPriceQuery,
+};
 use crate::libchain::status::Status;
 use crate::log_blooms::LogBloomGroup;
 use crate::receipt::{Receipt, RichReceipt};
@@ -37,18 +40,20 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::Into;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use util::{Mutex, RwLock};
+use util::{Mutex, RwLock, BLOCKLIMIT};
 
 use crate::db_indexes::{
     BlockNumber2Body, BlockNumber2Header, CurrentHash, CurrentHeight, CurrentProof,
     Hash2BlockNumber, Hash2BlockReceipts, Hash2TransactionIndex, LogGroupPosition,
+    LogIndexedHeight, LogTerm2Positions, PrunedHeight, QuotaPriceChangedHeights,
 };
 
 use crate::types::block::{Block, BlockBody, OpenBlock};
 use crate::types::{
     block_number::BlockTag, block_number::Tag, block_number::TransactionHash,
-    block_receipts::BlockReceipts, filter::Filter, log::LocalizedLog, log::Log,
-    transaction::Action, transaction::SignedTransaction, transaction_index::TransactionIndex,
+    block_receipts::BlockReceipts, filter::Filter, filter::LogsCursor, log::LocalizedLog, log::Log,
+    log_index::log_index_chunk, log_index::LogPosition, log_index::LogTerm, transaction::Action,
+    transaction::SignedTransaction, transaction_index::TransactionIndex,
 };
 use cita_types::traits::LowerHex;
 use cita_types::{Address, Bloom as LogBloom, H256, U256};
@@ -248,14 +253,70 @@ pub enum BlockSource {
     NET = 1,
 }
 
+// Default limits of a getLogs query
+const DEFAULT_MAX_LOGS_BLOCK_RANGE: u64 = 10_000;
+const DEFAULT_MAX_LOGS_RESULTS: usize = 10_000;
+// Default lifetime of a filter in seconds, and max number of filters
+const DEFAULT_FILTER_LIFETIME: u32 = 60;
+const DEFAULT_MAX_FILTERS: usize = 1024;
+// Max number of blocks pruned after a block is added, a newly enabled pruning catches up gradually.
+const MAX_PRUNE_BLOCKS: u64 = 1000;
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub struct Config {
     pub prooftype: u8,
+    /// Max number of blocks a getLogs query could scan
+    #[serde(default = "default_max_logs_block_range")]
+    pub max_logs_block_range: u64,
+    /// Max number of logs a getLogs query could return
+    #[serde(default = "default_max_logs_results")]
+    pub max_logs_results: usize,
+    /// Filters not polled within the lifetime are removed
+    #[serde(default = "default_filter_lifetime")]
+    pub filter_lifetime: u32,
+    #[serde(default = "default_max_filters")]
+    pub max_filters: usize,
+    /// Bodies, receipts and transaction indexes older than this number of blocks are pruned,
+    /// 0 keeps the whole history.
+    #[serde(default = "default_history_blocks")]
+    pub history_blocks: u64,
+    /// Keep an exact index from the addresses and topics to the logs,
+    /// getLogs uses it instead of the blooms.
+    #[serde(default)]
+    pub log_index: bool,
+}
+
+fn default_max_logs_block_range() -> u64 {
+    DEFAULT_MAX_LOGS_BLOCK_RANGE
+}
+
+fn default_max_logs_results() -> usize {
+    DEFAULT_MAX_LOGS_RESULTS
+}
+
+fn default_filter_lifetime() -> u32 {
+    DEFAULT_FILTER_LIFETIME
+}
+
+fn default_max_filters() -> usize {
+    DEFAULT_MAX_FILTERS
+}
+
+fn default_history_blocks() -> u64 {
+    0
 }
 
 impl Config {
     pub fn default() -> Self {
-        Config { prooftype: 2 }
+        Config {
+            prooftype: 2,
+            max_logs_block_range: DEFAULT_MAX_LOGS_BLOCK_RANGE,
+            max_logs_results: DEFAULT_MAX_LOGS_RESULTS,
+            filter_lifetime: DEFAULT_FILTER_LIFETIME,
+            max_filters: DEFAULT_MAX_FILTERS,
+            history_blocks: default_history_blocks(),
+            log_index: false,
+        }
     }
 
     pub fn new(path: &str) -> Self {
@@ -277,6 +338,63 @@ impl BloomGroupDatabase for Chain {
     }
 }
 
+/// Logs of a range of blocks, at most the max results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogsPage {
+    pub logs: Vec<LocalizedLog>,
+    /// Where the next page starts, `None` if the whole range is scanned
+    pub next: Option<LogsCursor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogsError {
+    UnknownBlock,
+    BlockRangeTooLarge(u64),
+    Pruned(BlockNumber),
+    InvalidCursor,
+}
+
+impl ::std::fmt::Display for LogsError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            LogsError::UnknownBlock => write!(f, "unknown block"),
+            LogsError::BlockRangeTooLarge(max) => {
+                write!(f, "block range is larger than {} blocks", max)
+            }
+            LogsError::Pruned(height) => write!(
+                f,
+                "logs of the blocks lower than {} are pruned, query an archive node",
+                height
+            ),
+            LogsError::InvalidCursor => write!(f, "cursor is out of the block range"),
+        }
+    }
+}
+
+/// Error of a query on the block history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryError {
+    NotFound,
+    /// Bodies, receipts and transaction indexes of the blocks lower than the height are pruned.
+    ///
+    /// The transaction indexes are pruned too, so a transaction not found is reported as pruned
+    /// once the history is pruned.
+    Pruned(BlockNumber),
+}
+
+impl ::std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            QueryError::NotFound => write!(f, "not found"),
+            QueryError::Pruned(height) => write!(
+                f,
+                "not found, the history lower than block {} is pruned, query an archive node",
+                height
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum BlockInQueue {
     Proposal(OpenBlock),
@@ -307,6 +425,17 @@ pub struct Chain {
     pub filterdb: Arc<Mutex<FilterDB>>,
     /// Proof type
     pub prooftype: u8,
+    /// Limits of a getLogs query
+    pub max_logs_block_range: u64,
+    pub max_logs_results: usize,
+    /// Number of the recent blocks keeping the bodies and receipts, 0 keeps all
+    pub history_blocks: u64,
+    /// Blocks lower than it are pruned
+    pub pruned_height: AtomicUsize,
+    /// Whether the exact log index is kept
+    pub log_index: bool,
+    /// Heights where the quota price is changed, with the prices
+    quota_price_changes: RwLock<QuotaPriceChanges>,
     // snapshot flag
     pub is_snapshot: RwLock<bool>,
     admin_address: RwLock<Option<Address>>,
@@ -346,6 +475,15 @@ pub fn get_chain(db: &RocksDB) -> Option<Header> {
     None
 }
 
+pub fn get_pruned_height(db: &RocksDB) -> BlockNumber {
+    db.get(
+        Some(cita_db::DataCategory::Extra),
+        &PrunedHeight.get_index(),
+    )
+    .unwrap_or(None)
+    .map_or(0, |res| rlp::decode(&res))
+}
+
 pub fn get_chain_body_height(db: &RocksDB) -> Option<BlockNumber> {
     db.get(
         Some(cita_db::DataCategory::Extra),
@@ -387,6 +525,34 @@ impl Chain {
             max_store_height, current_height
         );
 
+        // Auth checks the duplicated transactions in the recent blocks.
+        let history_blocks =
+            if chain_config.history_blocks != 0 && chain_config.history_blocks < BLOCKLIMIT {
+                warn!(
+                    "history_blocks {} is less than {}, use {}",
+                    chain_config.history_blocks, BLOCKLIMIT, BLOCKLIMIT
+                );
+                BLOCKLIMIT
+            } else {
+                chain_config.history_blocks
+            };
+        let pruned_height = AtomicUsize::new(get_pruned_height(&*db) as usize);
+
+        let quota_price_changes = db
+            .get(
+                Some(cita_db::DataCategory::Extra),
+                &QuotaPriceChangedHeights.get_index(),
+            )
+            .unwrap_or(None)
+            .and_then(|res| serde_json::from_slice(&res).ok())
+            .unwrap_or_default();
+
+        let filterdb = FilterDB::load(
+            Arc::clone(&db),
+            chain_config.filter_lifetime,
+            chain_config.max_filters,
+        );
+
         let chain = Chain {
             blooms_config,
             current_header: RwLock::new(header),
@@ -394,7 +560,7 @@ impl Chain {
             max_store_height,
             block_map: RwLock::new(BTreeMap::new()),
             db,
-            filterdb: Arc::new(Mutex::new(FilterDB::new())),
+            filterdb: Arc::new(Mutex::new(filterdb)),
             nodes: RwLock::new(Vec::new()),
             validators: RwLock::new(Vec::new()),
             // need to be cautious here
@@ -404,6 +570,12 @@ impl Chain {
             account_quota_limit: RwLock::new(ProtoAccountGasLimit::new()),
             check_quota: AtomicBool::new(false),
             prooftype: chain_config.prooftype,
+            max_logs_block_range: chain_config.max_logs_block_range,
+            max_logs_results: chain_config.max_logs_results,
+            history_blocks,
+            pruned_height,
+            log_index: chain_config.log_index,
+            quota_price_changes: RwLock::new(quota_price_changes),
             proof_map: RwLock::new(BTreeMap::new()),
             is_snapshot: RwLock::new(false),
             admin_address: RwLock::new(None),
@@ -419,6 +591,15 @@ impl Chain {
                     .insert(proof.height as u64, proto_proof);
             }
         }
+
+        // Catch up the blocks added while the index is disabled.
+        if chain.log_index {
+            let from = chain
+                .log_indexed_height()
+                .map_or(0, |height| height + 1)
+                .max(chain.get_pruned_height());
+            chain.rebuild_log_index(from);
+        }
         chain
     }
 
@@ -513,12 +694,15 @@ impl Chain {
         };
 
         // Save hash -> receipts
-        if !info.get_receipts().is_empty() {
-            let receipts: Vec<Receipt> = info
-                .get_receipts()
-                .iter()
-                .map(|r| Receipt::from(r.get_receipt().clone()))
-                .collect();
+        let receipts: Vec<Receipt> = info
+            .get_receipts()
+            .iter()
+            .map(|r| Receipt::from(r.get_receipt().clone()))
+            .collect();
+        if self.log_index {
+            self.index_logs(number, &receipts);
+        }
+        if !receipts.is_empty() {
             let block_receipts = BlockReceipts::new(receipts);
             let hash_key = Hash2BlockReceipts(header_hash).get_index();
             let _ = self.db.insert(
@@ -587,6 +771,153 @@ impl Chain {
         *self.current_header.write() = header;
         self.current_height.store(number as usize, Ordering::SeqCst);
         self.clean_proof_with_height(number);
+        self.prune_history(number);
+    }
+
+    /// Replace the quota prices paid by the blocks from `from` with the ones read by the
+    /// executor from `PriceManager`.
+    pub fn update_quota_prices(&self, from: BlockNumber, prices: &[(BlockNumber, U256)]) {
+        let mut changes = self.quota_price_changes.write();
+        if changes.update(from, prices) {
+            let _ = self.db.insert(
+                Some(cita_db::DataCategory::Extra),
+                QuotaPriceChangedHeights.get_index(),
+                serde_json::to_vec(&*changes).unwrap(),
+            );
+        }
+    }
+
+    /// The quota price paid by the transactions of a block, none if the executor hasn't sent it.
+    pub fn quota_price_at(&self, number: BlockNumber) -> Option<U256> {
+        self.quota_price_changes.read().price_at(number)
+    }
+
+    /// The quota used and the fees of the latest `block_count` blocks, with the fees at the
+    /// percentiles in basis points. The blocks whose receipts are pruned, or whose prices are
+    /// not sent by the executor, are left out.
+    pub fn quota_price_history(
+        &self,
+        block_count: u64,
+        percentiles: Vec<u64>,
+    ) -> Result<QuotaPriceHistory, String> {
+        let query = QuotaPriceQuery::new(block_count, percentiles)?;
+        let newest = self.get_current_height();
+        let price = self
+            .quota_price_at(newest + 1)
+            .ok_or_else(|| "The quota price is not known yet".to_owned())?;
+        let first_priced = self
+            .quota_price_changes
+            .read()
+            .first_height()
+            .unwrap_or(newest + 1);
+        let oldest = (newest + 1)
+            .saturating_sub(query.block_count)
+            .max(self.get_pruned_height())
+            .max(first_priced);
+        let mut blocks = Vec::new();
+        for number in oldest..=newest {
+            let header = self
+                .block_header_by_height(number)
+                .ok_or_else(|| format!("Block {} is not found", number))?;
+            // An empty block has no receipts.
+            let receipts = self
+                .block_receipts(header.hash().unwrap())
+                .map(|block_receipts| block_receipts.receipts)
+                .unwrap_or_default();
+            let block_price = self
+                .quota_price_at(number)
+                .ok_or_else(|| format!("The quota price of block {} is not known", number))?;
+            blocks.push(BlockQuotaUsage::new(
+                number,
+                *header.quota_limit(),
+                &receipts,
+                block_price,
+                &query.percentiles,
+            ));
+        }
+        Ok(QuotaPriceHistory::new(oldest, blocks, price))
+    }
+
+    /// Blocks lower than it have no bodies, receipts and transaction indexes.
+    pub fn get_pruned_height(&self) -> BlockNumber {
+        self.pruned_height.load(Ordering::SeqCst) as BlockNumber
+    }
+
+    // Drop the bodies, receipts, transaction indexes and log indexes out of the history window.
+    // The headers are kept, the proof of a block is in the header of the next one,
+    // so the chain is still verifiable.
+    fn prune_history(&self, current_height: BlockNumber) {
+        if self.history_blocks == 0 || current_height <= self.history_blocks {
+            return;
+        }
+        let pruned_height = self.get_pruned_height();
+        let target = (current_height - self.history_blocks).min(pruned_height + MAX_PRUNE_BLOCKS);
+        if target <= pruned_height {
+            return;
+        }
+
+        let mut log_chunks = HashSet::new();
+        for number in pruned_height..target {
+            if let Some(body) = self.block_body_by_height(number) {
+                for hash in body.transaction_hashes() {
+                    let _ = self.db.remove(
+                        Some(cita_db::DataCategory::Extra),
+                        &Hash2TransactionIndex(hash).get_index(),
+                    );
+                }
+                let _ = self.db.remove(
+                    Some(cita_db::DataCategory::Bodies),
+                    &BlockNumber2Body(number).get_index(),
+                );
+            }
+            if let Some(hash) = self.block_hash_by_height(number) {
+                if self.log_index {
+                    let receipts = self.block_receipts(hash).map(|r| r.receipts);
+                    for log in receipts.iter().flatten().flat_map(|r| r.logs.iter()) {
+                        for term in LogTerm::terms(log) {
+                            log_chunks.insert((term, log_index_chunk(number)));
+                        }
+                    }
+                }
+                let _ = self.db.remove(
+                    Some(cita_db::DataCategory::Extra),
+                    &Hash2BlockReceipts(hash).get_index(),
+                );
+            }
+        }
+        for (term, chunk) in log_chunks {
+            let mut positions = self.log_positions(term, chunk);
+            positions.retain(|position| position.block_number >= target);
+            let key = LogTerm2Positions(term, chunk).get_index();
+            let _ = if positions.is_empty() {
+                self.db.remove(Some(cita_db::DataCategory::Extra), &key)
+            } else {
+                self.db.insert(
+                    Some(cita_db::DataCategory::Extra),
+                    key,
+                    rlp::encode_list(&positions).into_vec(),
+                )
+            };
+        }
+
+        let _ = self.db.insert(
+            Some(cita_db::DataCategory::Extra),
+            PrunedHeight.get_index(),
+            rlp::encode(&target).into_vec(),
+        );
+        self.pruned_height.store(target as usize, Ordering::SeqCst);
+        debug!("history is pruned to height {}", target);
+    }
+
+    // The data not found is pruned if it's lower than the pruned height.
+    fn not_found(&self, number: Option<BlockNumber>) -> QueryError {
+        let pruned_height = self.get_pruned_height();
+        match number {
+            Some(number) if number >= pruned_height => QueryError::NotFound,
+            // The indexes of the transactions are pruned, so the unknown ones might be pruned.
+            _ if pruned_height > 0 => QueryError::Pruned(pruned_height),
+            _ => QueryError::NotFound,
+        }
     }
 
     pub fn broadcast_current_status(&self, ctx_pub: &Sender<(String, Vec<u8>)>) {
@@ -690,7 +1021,7 @@ impl Chain {
     }
 
     /// Get block by BlockTag
-    pub fn block(&self, tag: BlockTag) -> Option<Block> {
+    pub fn block(&self, tag: BlockTag) -> Result<Block, QueryError> {
         match tag {
             BlockTag::Hash(hash) => self.block_by_hash(hash),
             BlockTag::Height(number) => self.block_by_height(number),
@@ -701,19 +1032,21 @@ impl Chain {
     }
 
     /// Get block by hash
-    pub fn block_by_hash(&self, hash: H256) -> Option<Block> {
+    pub fn block_by_hash(&self, hash: H256) -> Result<Block, QueryError> {
         self.block_height_by_hash(hash)
+            .ok_or(QueryError::NotFound)
             .and_then(|h| self.block_by_height(h))
     }
 
     /// Get block by height
-    pub fn block_by_height(&self, number: BlockNumber) -> Option<Block> {
+    pub fn block_by_height(&self, number: BlockNumber) -> Result<Block, QueryError> {
         match (
             self.block_header_by_height(number),
             self.block_body_by_height(number),
         ) {
-            (Some(h), Some(b)) => Some(Block { header: h, body: b }),
-            _ => None,
+            (Some(h), Some(b)) => Ok(Block { header: h, body: b }),
+            (Some(_), None) => Err(self.not_found(Some(number))),
+            _ => Err(QueryError::NotFound),
         }
     }
 
@@ -792,12 +1125,14 @@ impl Chain {
     }
 
     /// Get transaction by hash
-    pub fn transaction(&self, hash: TransactionHash) -> Option<SignedTransaction> {
-        self.transaction_index(hash).and_then(|addr| {
-            let index = addr.index;
-            let hash = addr.block_hash;
-            self.transaction_by_address(hash, index)
-        })
+    pub fn transaction(&self, hash: TransactionHash) -> Result<SignedTransaction, QueryError> {
+        self.transaction_index(hash)
+            .and_then(|addr| {
+                let index = addr.index;
+                let hash = addr.block_hash;
+                self.transaction_by_address(hash, index)
+            })
+            .ok_or_else(|| self.not_found(None))
     }
 
     /// Get address of transaction by hash.
@@ -824,20 +1159,21 @@ impl Chain {
     }
 
     /// Get full transaction by hash
-    pub fn full_transaction(&self, hash: TransactionHash) -> Option<FullTransaction> {
-        self.transaction_index(hash).and_then(|addr| {
-            let index = addr.index;
-            let hash = addr.block_hash;
-            self.block_by_hash(hash).map(|block| {
-                let transactions = block.body().transactions();
-                let tx = transactions[index].protobuf();
-                let mut full_ts = FullTransaction::new();
-                full_ts.set_transaction(tx);
-                full_ts.set_block_number(block.number());
-                full_ts.set_block_hash(hash.to_vec());
-                full_ts.set_index(index as u32);
-                full_ts
-            })
+    pub fn full_transaction(&self, hash: TransactionHash) -> Result<FullTransaction, QueryError> {
+        let addr = self
+            .transaction_index(hash)
+            .ok_or_else(|| self.not_found(None))?;
+        let index = addr.index;
+        let hash = addr.block_hash;
+        self.block_by_hash(hash).map(|block| {
+            let transactions = block.body().transactions();
+            let tx = transactions[index].protobuf();
+            let mut full_ts = FullTransaction::new();
+            full_ts.set_transaction(tx);
+            full_ts.set_block_number(block.number());
+            full_ts.set_block_hash(hash.to_vec());
+            full_ts.set_index(index as u32);
+            full_ts
         })
     }
 
@@ -845,6 +1181,7 @@ impl Chain {
         self.transaction_index(hash)
             .and_then(|addr| {
                 self.block_by_hash(addr.block_hash)
+                    .ok()
                     .map(|block| (addr, block))
             })
             .and_then(|(addr, block)| {
@@ -886,28 +1223,28 @@ impl Chain {
                 })
             })
             .and_then(|(tx, receipt, receipt_proof, block_header)| {
-                self.block_by_height(block_header.number() + 1)
-                    .map(|next_block| {
+                self.block_header_by_height(block_header.number() + 1)
+                    .map(|next_header| {
                         (
                             tx,
                             receipt,
                             receipt_proof,
                             block_header,
-                            next_block.header().proposal(),
+                            next_header.proposal(),
                         )
                     })
             })
             .and_then(
                 |(tx, receipt, receipt_proof, block_header, next_proposal_header)| {
-                    self.block_by_height(next_proposal_header.number() + 1)
-                        .map(|third_block| {
+                    self.block_header_by_height(next_proposal_header.number() + 1)
+                        .map(|third_header| {
                             (
                                 tx,
                                 receipt,
                                 receipt_proof,
                                 block_header,
                                 next_proposal_header,
-                                third_block.header().proof().clone(),
+                                third_header.proof().clone(),
                             )
                         })
                 },
@@ -939,7 +1276,7 @@ impl Chain {
         self.block_header(tag).map(|x| x.rlp_bytes().into_vec())
     }
 
-    pub fn get_rich_receipt(&self, tx_hash: TransactionHash) -> Option<RichReceipt> {
+    pub fn get_rich_receipt(&self, tx_hash: TransactionHash) -> Result<RichReceipt, QueryError> {
         trace!("Get receipt by hash: {:?}", tx_hash);
         if let Some(transaction_index) = self.transaction_index(tx_hash) {
             let block_hash = transaction_index.block_hash;
@@ -991,12 +1328,15 @@ impl Chain {
                         state_root: last_receipt.state_root,
                         error: last_receipt.error,
                     };
-                    return Some(receipt);
+                    return Ok(receipt);
                 }
+            } else {
+                let block_number = self.block_height_by_hash(block_hash);
+                return Err(self.not_found(block_number));
             }
         }
         info!("Get receipt by hash failed {:?}", tx_hash);
-        None
+        Err(self.not_found(None))
     }
 
     #[inline]
@@ -1079,61 +1419,11 @@ impl Chain {
         // sort in reverse order
         blocks.sort_by(|a, b| b.cmp(a));
 
-        let mut log_index = 0;
         let mut logs = blocks
             .into_iter()
-            .filter_map(|number| self.block_hash_by_height(number).map(|hash| (number, hash)))
-            .filter_map(|(number, hash)| {
-                self.block_receipts(hash)
-                    .map(|r| (number, hash, r.receipts))
-            })
-            .filter_map(|(number, hash, receipts)| {
-                self.block_body_by_hash(hash)
-                    .map(|ref b| (number, hash, receipts, b.transaction_hashes()))
-            })
-            .flat_map(|(number, hash, mut receipts, mut hashes)| {
-                if receipts.len() != hashes.len() {
-                    warn!(
-                        "Block {} ({}) has different number of receipts ({}) to transactions ({}). Database corrupt?",
-                        number,
-                        hash,
-                        receipts.len(),
-                        hashes.len()
-                    );
-                    unreachable!();
-                }
-                log_index = receipts
-                    .iter()
-                    .fold(0, |sum, receipt| sum + receipt.logs.len());
-
-                let receipts_len = receipts.len();
-                hashes.reverse();
-                receipts.reverse();
-                receipts
-                    .into_iter()
-                    .map(|receipt| receipt.logs)
-                    .zip(hashes)
-                    .enumerate()
-                    .flat_map(move |(index, (mut logs, tx_hash))| {
-                        let current_log_index = log_index;
-                        let no_of_logs = logs.len();
-                        log_index -= no_of_logs;
-
-                        logs.reverse();
-                        logs.into_iter().enumerate().map(move |(i, log)| {
-                            LocalizedLog {
-                                log,
-                                block_hash: hash,
-                                block_number: number,
-                                transaction_hash: tx_hash,
-                                // iterating in reverse order
-                                transaction_index: receipts_len - index - 1,
-                                transaction_log_index: no_of_logs - i - 1,
-                                log_index: current_log_index - i - 1,
-                            }
-                        })
-                    })
-            })
+            .filter_map(|number| self.block_logs(number))
+            // iterating in reverse order
+            .flat_map(|logs| logs.into_iter().rev())
             .filter(|log| matches(&log.log))
             .take(limit.unwrap_or(::std::usize::MAX))
             .collect::<Vec<LocalizedLog>>();
@@ -1141,6 +1431,110 @@ impl Chain {
         logs
     }
 
+    /// Get the logs of a block from its receipts, without loading the body.
+    fn block_logs(&self, number: BlockNumber) -> Option<Vec<LocalizedLog>> {
+        let hash = self.block_hash_by_height(number)?;
+        let receipts = self.block_receipts(hash)?.receipts;
+
+        let mut logs = Vec::new();
+        for (transaction_index, receipt) in receipts.into_iter().enumerate() {
+            let transaction_hash = receipt.transaction_hash;
+            for (transaction_log_index, log) in receipt.logs.into_iter().enumerate() {
+                let log_index = logs.len();
+                logs.push(LocalizedLog {
+                    log,
+                    block_hash: hash,
+                    block_number: number,
+                    transaction_hash,
+                    transaction_index,
+                    transaction_log_index,
+                    log_index,
+                });
+            }
+        }
+        Some(logs)
+    }
+
+    fn log_indexed_height(&self) -> Option<BlockNumber> {
+        self.db
+            .get(
+                Some(cita_db::DataCategory::Extra),
+                &LogIndexedHeight.get_index(),
+            )
+            .unwrap_or(None)
+            .map(|res| rlp::decode(&res))
+    }
+
+    fn log_positions(&self, term: LogTerm, chunk: u64) -> Vec<LogPosition> {
+        self.db
+            .get(
+                Some(cita_db::DataCategory::Extra),
+                &LogTerm2Positions(term, chunk).get_index(),
+            )
+            .unwrap_or(None)
+            .map_or_else(Vec::new, |res| rlp::decode_list(&res))
+    }
+
+    // The positions of the block indexed before are replaced, so a block could be indexed again.
+    fn index_logs(&self, number: BlockNumber, receipts: &[Receipt]) {
+        let mut block_positions: HashMap<LogTerm, Vec<LogPosition>> = HashMap::new();
+        for (transaction_index, receipt) in receipts.iter().enumerate() {
+            for (transaction_log_index, log) in receipt.logs.iter().enumerate() {
+                let position = LogPosition {
+                    block_number: number,
+                    transaction_index,
+                    transaction_log_index,
+                };
+                for term in LogTerm::terms(log) {
+                    block_positions
+                        .entry(term)
+                        .or_insert_with(Vec::new)
+                        .push(position);
+                }
+            }
+        }
+
+        let chunk = log_index_chunk(number);
+        for (term, mut positions) in block_positions {
+            let mut chunk_positions = self.log_positions(term, chunk);
+            chunk_positions.retain(|position| position.block_number < number);
+            chunk_positions.append(&mut positions);
+            let _ = self.db.insert(
+                Some(cita_db::DataCategory::Extra),
+                LogTerm2Positions(term, chunk).get_index(),
+                rlp::encode_list(&chunk_positions).into_vec(),
+            );
+        }
+        let _ = self.db.insert(
+            Some(cita_db::DataCategory::Extra),
+            LogIndexedHeight.get_index(),
+            rlp::encode(&number).into_vec(),
+        );
+    }
+
+    /// Index the logs of the blocks from `from_block` to the current height by their receipts.
+    pub fn rebuild_log_index(&self, from_block: BlockNumber) {
+        let current_height = self.get_current_height();
+        if from_block > current_height {
+            return;
+        }
+        info!(
+            "rebuild log index from {} to {}",
+            from_block, current_height
+        );
+        for number in from_block..=current_height {
+            let receipts = self
+                .block_hash_by_height(number)
+                .and_then(|hash| self.block_receipts(hash))
+                .map(|block_receipts| block_receipts.receipts)
+                .unwrap_or_default();
+            self.index_logs(number, &receipts);
+            if number % 10_000 == 0 {
+                info!("log index is rebuilt to {}", number);
+            }
+        }
+    }
+
     /// Returns numbers of blocks containing given bloom.
     pub fn blocks_with_bloom(
         &self,
@@ -1176,22 +1570,151 @@ impl Chain {
         }
     }
 
-    pub fn get_logs(&self, filter: &Filter) -> Vec<LocalizedLog> {
-        let blocks = filter
-            .zip_blooms()
-            .iter()
-            .filter_map(|bloom| {
-                self.blocks_with_bloom_by_id(bloom, filter.from_block, filter.to_block)
-            })
-            .flat_map(|m| m)
-            // remove duplicate elements
+    /// Returns numbers of blocks in `[from_block, to_block]` which might have the logs of
+    /// the filter.
+    ///
+    /// They are exact if the log index is enabled and the filter has an address or a topic,
+    /// otherwise they come from the blooms.
+    fn blocks_with_logs(
+        &self,
+        filter: &Filter,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> Vec<BlockNumber> {
+        let term_groups = filter.index_terms();
+        if !self.log_index || term_groups.is_empty() {
+            return filter
+                .zip_blooms()
+                .iter()
+                .flat_map(|bloom| self.blocks_with_bloom(bloom, from_block, to_block))
+                // remove duplicate elements
+                .collect::<HashSet<u64>>()
+                .into_iter()
+                .collect();
+        }
+
+        let chunks = log_index_chunk(from_block)..=log_index_chunk(to_block);
+        let mut matched: Option<HashSet<LogPosition>> = None;
+        for terms in term_groups {
+            let positions = terms
+                .into_iter()
+                .flat_map(|term| {
+                    chunks
+                        .clone()
+                        .flat_map(move |chunk| self.log_positions(term, chunk))
+                })
+                .filter(|position| {
+                    position.block_number >= from_block && position.block_number <= to_block
+                })
+                .collect::<HashSet<LogPosition>>();
+            matched = Some(match matched {
+                Some(matched) => matched.intersection(&positions).cloned().collect(),
+                None => positions,
+            });
+        }
+        matched
+            .unwrap_or_default()
+            .into_iter()
+            .map(|position| position.block_number)
             .collect::<HashSet<u64>>()
             .into_iter()
-            .collect::<Vec<u64>>();
+            .collect()
+    }
+
+    pub fn get_logs(&self, filter: &Filter) -> Vec<LocalizedLog> {
+        let blocks = match (
+            self.block_number(filter.from_block),
+            self.block_number(filter.to_block),
+        ) {
+            (Some(from), Some(to)) => {
+                self.blocks_with_logs(filter, from, to.min(self.get_pending_height()))
+            }
+            _ => Vec::new(),
+        };
 
         self.logs(blocks, |entry| filter.matches(entry), filter.limit)
     }
 
+    /// Get a page of the logs within the configured limits, from the cursor the last page
+    /// stopped at.
+    ///
+    /// The block range of the filter, from the cursor on, must be no larger than
+    /// `max_logs_block_range`. The blocks are scanned in order, and the page stops at
+    /// `max_logs_results` logs, the next page starts from the cursor of the next log.
+    pub fn get_logs_page(
+        &self,
+        filter: &Filter,
+        cursor: Option<LogsCursor>,
+    ) -> Result<LogsPage, LogsError> {
+        let from = self
+            .block_number(filter.from_block)
+            .ok_or(LogsError::UnknownBlock)?;
+        let to = self
+            .block_number(filter.to_block)
+            .ok_or(LogsError::UnknownBlock)?
+            .min(self.get_pending_height());
+        let from = match cursor {
+            Some(cursor) if cursor.block_number < from || cursor.block_number > to => {
+                return Err(LogsError::InvalidCursor);
+            }
+            Some(cursor) => cursor.block_number,
+            None => from,
+        };
+        if from > to {
+            return Ok(LogsPage {
+                logs: Vec::new(),
+                next: None,
+            });
+        }
+        if to - from >= self.max_logs_block_range {
+            return Err(LogsError::BlockRangeTooLarge(self.max_logs_block_range));
+        }
+        let pruned_height = self.get_pruned_height();
+        if from < pruned_height {
+            return Err(LogsError::Pruned(pruned_height));
+        }
+
+        let mut blocks = self.blocks_with_logs(filter, from, to);
+
+        // The latest logs are bounded by the limit already.
+        match filter.limit {
+            Some(limit) if limit <= self.max_logs_results => {
+                return Ok(LogsPage {
+                    logs: self.logs(blocks, |entry| filter.matches(entry), Some(limit)),
+                    next: None,
+                });
+            }
+            _ => {}
+        }
+
+        blocks.sort();
+        let mut logs = Vec::new();
+        for number in blocks {
+            let skipped = match cursor {
+                Some(cursor) if cursor.block_number == number => cursor.log_index,
+                _ => 0,
+            };
+            let block_logs = self.block_logs(number).unwrap_or_default();
+            for log in block_logs
+                .into_iter()
+                .skip(skipped)
+                .filter(|log| filter.matches(&log.log))
+            {
+                if logs.len() >= self.max_logs_results {
+                    return Ok(LogsPage {
+                        logs,
+                        next: Some(LogsCursor {
+                            block_number: number,
+                            log_index: log.log_index,
+                        }),
+                    });
+                }
+                logs.push(log);
+            }
+        }
+        Ok(LogsPage { logs, next: None })
+    }
+
     /// Delivery block tx hashes to auth
     pub fn delivery_block_tx_hashes(
         &self,
@@ -1341,8 +1864,8 @@ impl Chain {
         match self.current_header.read().proof_type() {
             Some(ProofType::Bft) => {
                 // TODO: use CONSTANT to replace the '1'.
-                self.block_by_height(height + 1)
-                    .map(|block| block.header.proof().clone())
+                self.block_header_by_height(height + 1)
+                    .map(|header| header.proof().clone())
             }
             _ => None,
         }
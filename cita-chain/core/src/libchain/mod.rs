@@ -13,5 +13,6 @@
 // limitations under the License.
 
 pub mod chain;
+pub mod quota_price;
 pub mod rich_status;
 pub mod status;
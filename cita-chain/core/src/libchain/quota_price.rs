@@ -0,0 +1,311 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Quota price history of the recent blocks, answered to the `getQuotaPriceHistory` queries.
+//!
+//! The quota used by the transactions comes from the receipts of the blocks. All of them pay
+//! the quota price of `PriceManager`, which the executor reads from its state and sends to
+//! the chain.
+
+use crate::header::BlockNumber;
+use crate::raw_message::MAX_QUOTA_PRICE_HISTORY_BLOCKS;
+use crate::receipt::Receipt;
+use cita_types::U256;
+
+/// Max number of percentiles of a query
+pub const MAX_PERCENTILES: usize = 100;
+/// Percentiles and fullness are in basis points.
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Recent blocks fuller than it raise the suggested price.
+const TARGET_FULLNESS: u64 = 5_000;
+/// The suggested price is raised by 1/8 of the price at most, when the recent blocks are full.
+const MAX_RAISE_DIVISOR: u64 = 8;
+
+/// A query of the history: the number of blocks up to the latest one, and the percentiles of
+/// the transaction fees in basis points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaPriceQuery {
+    pub block_count: u64,
+    pub percentiles: Vec<u64>,
+}
+
+impl QuotaPriceQuery {
+    pub fn new(block_count: u64, percentiles: Vec<u64>) -> Result<Self, String> {
+        if block_count == 0 || block_count > MAX_QUOTA_PRICE_HISTORY_BLOCKS {
+            return Err(format!(
+                "The block count should be from 1 to {}",
+                MAX_QUOTA_PRICE_HISTORY_BLOCKS
+            ));
+        }
+        if percentiles.len() > MAX_PERCENTILES {
+            return Err(format!(
+                "There should be {} percentiles at most",
+                MAX_PERCENTILES
+            ));
+        }
+        let increasing = percentiles.windows(2).all(|pair| pair[0] <= pair[1]);
+        let highest = percentiles.last().cloned().unwrap_or(0);
+        if !increasing || highest > BASIS_POINTS {
+            return Err("The percentiles should be increasing, from 0 to 100".to_owned());
+        }
+        Ok(QuotaPriceQuery {
+            block_count,
+            percentiles,
+        })
+    }
+}
+
+/// Quota used by a block and the fees its transactions paid.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockQuotaUsage {
+    pub height: BlockNumber,
+    pub quota_used: U256,
+    pub quota_limit: U256,
+    /// Quota used over the quota limit of the block
+    pub quota_used_ratio: f64,
+    /// Quota price paid by the transactions of the block
+    pub quota_price: U256,
+    /// Fees of the transactions at the percentiles, zeros for an empty block
+    pub fees: Vec<U256>,
+}
+
+impl BlockQuotaUsage {
+    pub fn new(
+        height: BlockNumber,
+        quota_limit: U256,
+        receipts: &[Receipt],
+        quota_price: U256,
+        percentiles: &[u64],
+    ) -> Self {
+        // The quota used of a receipt is the cumulative one of the block.
+        let mut prior_quota_used = U256::zero();
+        let mut quotas: Vec<U256> = receipts
+            .iter()
+            .map(|receipt| {
+                let quota = receipt.quota_used.saturating_sub(prior_quota_used);
+                prior_quota_used = receipt.quota_used;
+                quota
+            })
+            .collect();
+        quotas.sort();
+
+        let fees = percentiles
+            .iter()
+            .map(|percentile| {
+                if quotas.is_empty() {
+                    return U256::zero();
+                }
+                // Nearest rank of the percentile.
+                let rank = (percentile * quotas.len() as u64 + BASIS_POINTS - 1) / BASIS_POINTS;
+                let index = (rank.max(1) - 1) as usize;
+                quotas[index].saturating_mul(quota_price)
+            })
+            .collect();
+
+        let quota_used = prior_quota_used;
+        let quota_used_ratio = if quota_limit.is_zero() {
+            0.0
+        } else {
+            quota_used.low_u64() as f64 / quota_limit.low_u64() as f64
+        };
+        BlockQuotaUsage {
+            height,
+            quota_used,
+            quota_limit,
+            quota_used_ratio,
+            quota_price,
+            fees,
+        }
+    }
+}
+
+/// Answer of a query of the history
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaPriceHistory {
+    pub oldest_block: BlockNumber,
+    pub blocks: Vec<BlockQuotaUsage>,
+    pub suggested_quota_price: U256,
+}
+
+impl QuotaPriceHistory {
+    /// The suggested price is the price of the next block, raised as the recent blocks fill
+    /// up over `TARGET_FULLNESS`.
+    ///
+    /// A transaction pays the price of the block it's packed in, whatever it offers, so the
+    /// raise is a margin of the balance for the price being raised while it waits in the pool.
+    pub fn new(oldest_block: BlockNumber, blocks: Vec<BlockQuotaUsage>, price: U256) -> Self {
+        let (quota_used, quota_limit) =
+            blocks
+                .iter()
+                .fold((U256::zero(), U256::zero()), |(used, limit), block| {
+                    (
+                        used.saturating_add(block.quota_used),
+                        limit.saturating_add(block.quota_limit),
+                    )
+                });
+        let fullness = if quota_limit.is_zero() {
+            0
+        } else {
+            (quota_used.saturating_mul(U256::from(BASIS_POINTS)) / quota_limit)
+                .min(U256::from(BASIS_POINTS))
+                .low_u64()
+        };
+        let excess = fullness.saturating_sub(TARGET_FULLNESS);
+        let raise = price.saturating_mul(U256::from(excess))
+            / U256::from((BASIS_POINTS - TARGET_FULLNESS) * MAX_RAISE_DIVISOR);
+        QuotaPriceHistory {
+            oldest_block,
+            blocks,
+            suggested_quota_price: price.saturating_add(raise),
+        }
+    }
+}
+
+/// Heights where the quota price paid by the blocks is changed, with the prices.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct QuotaPriceChanges {
+    changes: Vec<(BlockNumber, U256)>,
+}
+
+impl QuotaPriceChanges {
+    /// The price paid by the transactions of block `height`, none if it's not known.
+    pub fn price_at(&self, height: BlockNumber) -> Option<U256> {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(changed, _)| *changed <= height)
+            .map(|(_, price)| *price)
+    }
+
+    /// The lowest block whose price is known.
+    pub fn first_height(&self) -> Option<BlockNumber> {
+        self.changes.first().map(|(changed, _)| *changed)
+    }
+
+    /// Replace the prices of the blocks from `from` with the ones sent by the executor.
+    /// Returns whether the changes are modified.
+    pub fn update(&mut self, from: BlockNumber, prices: &[(BlockNumber, U256)]) -> bool {
+        let len = self.changes.len();
+        self.changes.retain(|(changed, _)| *changed < from);
+        let mut modified = self.changes.len() != len;
+        for (height, price) in prices.iter().filter(|(height, _)| *height >= from) {
+            if self.changes.last().map(|(_, last)| last) != Some(price) {
+                self.changes.push((*height, *price));
+                modified = true;
+            }
+        }
+        modified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cita_types::H256;
+
+    fn receipt(cumulative_quota_used: u64) -> Receipt {
+        Receipt::new(
+            None,
+            U256::from(cumulative_quota_used),
+            vec![],
+            None,
+            U256::zero(),
+            H256::zero(),
+        )
+    }
+
+    #[test]
+    fn test_new_query() {
+        assert_eq!(
+            QuotaPriceQuery::new(4, vec![2500, 5000]),
+            Ok(QuotaPriceQuery {
+                block_count: 4,
+                percentiles: vec![2500, 5000],
+            })
+        );
+        assert!(QuotaPriceQuery::new(0, vec![]).is_err());
+        assert!(QuotaPriceQuery::new(MAX_QUOTA_PRICE_HISTORY_BLOCKS + 1, vec![]).is_err());
+        assert!(QuotaPriceQuery::new(1, vec![BASIS_POINTS + 1]).is_err());
+        assert!(QuotaPriceQuery::new(1, vec![5000, 2500]).is_err());
+        assert!(QuotaPriceQuery::new(1, vec![0; MAX_PERCENTILES + 1]).is_err());
+    }
+
+    #[test]
+    fn test_block_quota_usage() {
+        let receipts = [receipt(100), receipt(400), receipt(600), receipt(1000)];
+        let usage = BlockQuotaUsage::new(
+            7,
+            U256::from(2000),
+            &receipts,
+            U256::from(3),
+            &[0, 2500, 5000, 10000],
+        );
+        assert_eq!(usage.quota_used, U256::from(1000));
+        assert!((usage.quota_used_ratio - 0.5).abs() < std::f64::EPSILON);
+        // The quotas are 100, 200, 300 and 400.
+        assert_eq!(
+            usage.fees,
+            vec![
+                U256::from(300),
+                U256::from(300),
+                U256::from(600),
+                U256::from(1200)
+            ]
+        );
+
+        let empty = BlockQuotaUsage::new(8, U256::from(2000), &[], U256::from(3), &[5000]);
+        assert_eq!(empty.quota_used, U256::zero());
+        assert_eq!(empty.fees, vec![U256::zero()]);
+    }
+
+    #[test]
+    fn test_suggested_quota_price() {
+        let block = |quota_used: u64| BlockQuotaUsage {
+            height: 1,
+            quota_used: U256::from(quota_used),
+            quota_limit: U256::from(1000),
+            quota_used_ratio: 0.0,
+            quota_price: U256::from(800),
+            fees: vec![],
+        };
+        let price = U256::from(800);
+        let history = |blocks| QuotaPriceHistory::new(1, blocks, price).suggested_quota_price;
+        assert_eq!(history(vec![]), price);
+        assert_eq!(history(vec![block(100), block(900)]), price);
+        assert_eq!(history(vec![block(1000), block(500)]), U256::from(850));
+        assert_eq!(history(vec![block(1000), block(1000)]), U256::from(900));
+    }
+
+    #[test]
+    fn test_quota_price_changes() {
+        let mut changes = QuotaPriceChanges::default();
+        assert_eq!(changes.price_at(3), None);
+        assert!(changes.update(3, &[(3, U256::from(1)), (6, U256::from(2))]));
+        assert_eq!(changes.first_height(), Some(3));
+        assert_eq!(changes.price_at(2), None);
+        assert_eq!(changes.price_at(5), Some(U256::from(1)));
+        assert_eq!(changes.price_at(6), Some(U256::from(2)));
+
+        // The price paid from block 8 is unchanged.
+        assert!(!changes.update(8, &[(8, U256::from(2))]));
+
+        // Block 5 executed again sets another price.
+        assert!(changes.update(6, &[(6, U256::from(1)), (7, U256::from(3))]));
+        assert_eq!(changes.price_at(6), Some(U256::from(1)));
+        assert_eq!(changes.price_at(9), Some(U256::from(3)));
+    }
+}
@@ -19,7 +19,7 @@ use std::sync::Arc;
 
 use cita_types::H256;
 use core::filters::rpc_filter::RpcFilter as FilterMethod;
-use core::libchain::chain::{BlockInQueue, Chain};
+use core::libchain::chain::{BlockInQueue, Chain, LogsPage, QueryError};
 use error::ErrorCode;
 use jsonrpc_types::rpc_types::{
     BlockNumber as RpcBlockNumber, BlockParamsByHash, BlockParamsByNumber, Filter as RpcFilter,
@@ -37,6 +37,7 @@ use pubsub::channel::Sender;
 use crate::types::block::OpenBlock;
 use crate::types::block_number::BlockTag;
 use crate::types::filter::Filter;
+use crate::types::raw_message::{Query, RawMessage};
 
 /// Message forwarding and query data
 #[derive(Clone)]
@@ -62,6 +63,40 @@ impl Forward {
 
     // 注意: 划分函数处理流程
     pub fn dispatch_msg(&self, key: &str, msg_bytes: &[u8]) {
+        // The raw bytes of jsonrpc are not a message.
+        if RoutingKey::from(key) == routing_key!(Jsonrpc >> RawBytes) {
+            if let Some(RawMessage::Query { request_id, query }) = RawMessage::from_bytes(msg_bytes)
+            {
+                if query.is_for_chain() {
+                    self.reply_query(request_id, query);
+                }
+            }
+            return;
+        }
+        // The transactions entering the pool of auth are for the pending transaction filters,
+        // the other raw bytes of auth are heartbeats.
+        if RoutingKey::from(key) == routing_key!(Auth >> RawBytes) {
+            if let Some(RawMessage::PendingTransactions { hashes }) =
+                RawMessage::from_bytes(msg_bytes)
+            {
+                self.chain
+                    .filter_db()
+                    .lock()
+                    .add_pending_transactions(&hashes);
+            }
+            return;
+        }
+        // The quota prices paid by the blocks are read by the executor, the other raw bytes of
+        // the executor are heartbeats, frozen accounts and query results.
+        if RoutingKey::from(key) == routing_key!(Executor >> RawBytes) {
+            if let Some(RawMessage::QuotaPrices { from, prices }) =
+                RawMessage::from_bytes(msg_bytes)
+            {
+                self.chain.update_quota_prices(from, &prices);
+            }
+            return;
+        }
+
         let mut msg = Message::try_from(msg_bytes).unwrap();
         let origin = msg.get_origin();
         match RoutingKey::from(key) {
@@ -127,7 +162,7 @@ impl Forward {
                         let hash = param.hash;
                         let include_txs = param.include_txs;
                         match self.chain.block_by_hash(H256::from(hash.as_slice())) {
-                            Some(block) => {
+                            Ok(block) => {
                                 let rpc_block = RpcBlock::new(
                                     hash,
                                     include_txs,
@@ -140,7 +175,11 @@ impl Forward {
                                         response.set_error_msg(format!("{:?}", err));
                                     });
                             }
-                            None => response.set_none(true),
+                            Err(QueryError::NotFound) => response.set_none(true),
+                            Err(err) => {
+                                response.set_code(ErrorCode::query_error());
+                                response.set_error_msg(format!("{}", err));
+                            }
                         }
                     }
                     Err(err) => {
@@ -155,7 +194,7 @@ impl Forward {
                     serde_json::from_str(&block_height).expect("Invalid param");
                 let include_txs = block_height.include_txs;
                 match self.chain.block(block_height.block_id.into()) {
-                    Some(block) => {
+                    Ok(block) => {
                         let rpc_block = RpcBlock::new(
                             block.hash().unwrap().to_vec(),
                             include_txs,
@@ -168,20 +207,28 @@ impl Forward {
                                 response.set_error_msg(format!("{:?}", err));
                             });
                     }
-                    None => {
+                    Err(QueryError::NotFound) => {
                         response.set_none(true);
                     }
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{}", err));
+                    }
                 }
             }
 
             Request::transaction(hash) => {
                 match self.chain.full_transaction(H256::from_slice(&hash)) {
-                    Some(ts) => {
+                    Ok(ts) => {
                         response.set_ts(ts);
                     }
-                    None => {
+                    Err(QueryError::NotFound) => {
                         response.set_none(true);
                     }
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{}", err));
+                    }
                 }
             }
 
@@ -198,13 +245,19 @@ impl Forward {
 
             Request::transaction_receipt(hash) => {
                 let tx_hash = H256::from_slice(&hash);
-                let receipt = self.chain.get_rich_receipt(tx_hash);
-                if let Some(receipt) = receipt {
-                    let rpc_receipt: RpcReceipt = receipt.into();
-                    let serialized = serde_json::to_string(&rpc_receipt).unwrap();
-                    response.set_receipt(serialized);
-                } else {
-                    response.set_none(true);
+                match self.chain.get_rich_receipt(tx_hash) {
+                    Ok(receipt) => {
+                        let rpc_receipt: RpcReceipt = receipt.into();
+                        let serialized = serde_json::to_string(&rpc_receipt).unwrap();
+                        response.set_receipt(serialized);
+                    }
+                    Err(QueryError::NotFound) => {
+                        response.set_none(true);
+                    }
+                    Err(err) => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(format!("{}", err));
+                    }
                 }
             }
 
@@ -215,9 +268,24 @@ impl Forward {
                     response.set_error_msg(format!("{:?}", err));
                 }) {
                     let filter: Filter = rpc_filter.into();
-                    let logs = self.chain.get_logs(&filter);
-                    let rpc_logs: Vec<RpcLog> = logs.into_iter().map(Into::into).collect();
-                    response.set_logs(serde_json::to_string(&rpc_logs).unwrap());
+                    match self.chain.get_logs_page(&filter, None) {
+                        Ok(LogsPage { logs, next: None }) => {
+                            let rpc_logs: Vec<RpcLog> = logs.into_iter().map(Into::into).collect();
+                            response.set_logs(serde_json::to_string(&rpc_logs).unwrap());
+                        }
+                        // Too many logs, the pages with their cursors are only for `getLogs`.
+                        Ok(LogsPage { next: Some(_), .. }) => {
+                            response.set_code(ErrorCode::query_error());
+                            response.set_error_msg(format!(
+                                "query returned more than {} logs, retry with a smaller block range, or page them with getLogs",
+                                self.chain.max_logs_results,
+                            ));
+                        }
+                        Err(err) => {
+                            response.set_code(ErrorCode::query_error());
+                            response.set_error_msg(format!("{}", err));
+                        }
+                    }
                 };
             }
 
@@ -282,23 +350,35 @@ impl Forward {
                 let new_filter: RpcFilter =
                     serde_json::from_str(&new_filter).expect("Invalid param");
                 trace!("new_filter {:?}", new_filter);
-                response.set_filter_id(self.chain.new_filter(new_filter) as u64);
+                match self.chain.new_filter(new_filter, None) {
+                    Some(filter_id) => response.set_filter_id(filter_id as u64),
+                    None => {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg("too many filters".to_owned());
+                    }
+                }
             }
 
-            Request::new_block_filter(_) => {
-                let block_filter = self.chain.new_block_filter();
-                response.set_filter_id(block_filter as u64);
-            }
+            Request::new_block_filter(_) => match self.chain.new_block_filter(None) {
+                Some(filter_id) => response.set_filter_id(filter_id as u64),
+                None => {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg("too many filters".to_owned());
+                }
+            },
 
             Request::uninstall_filter(filter_id) => {
                 trace!("uninstall_filter's id is {:?}", filter_id);
-                let b = self.chain.uninstall_filter(filter_id as usize);
+                let b = self.chain.uninstall_filter(filter_id as usize, None);
                 response.set_uninstall_filter(b);
             }
 
             Request::filter_changes(filter_id) => {
                 trace!("filter_changes's id is {:?}", filter_id);
-                let log = self.chain.get_filter_changes(filter_id as usize).unwrap();
+                let log = self
+                    .chain
+                    .get_filter_changes(filter_id as usize, None)
+                    .unwrap();
                 trace!("Log is: {:?}", log);
                 response.set_filter_changes(serde_json::to_string(&log).unwrap());
             }
@@ -307,7 +387,7 @@ impl Forward {
                 trace!("filter_log's id is {:?}", filter_id);
                 let log = self
                     .chain
-                    .get_filter_logs(filter_id as usize)
+                    .get_filter_logs(filter_id as usize, None)
                     .unwrap_or_default();
                 trace!("Log is: {:?}", log);
                 response.set_filter_logs(serde_json::to_string(&log).unwrap());
@@ -360,6 +440,61 @@ impl Forward {
     }
 
     // Consensus block enqueue
+    fn reply_query(&self, request_id: Vec<u8>, query: Query) {
+        let result = match query {
+            // The quota price history is worked out from the receipts the chain keeps.
+            Query::QuotaPriceHistory {
+                block_count,
+                percentiles,
+            } => self
+                .chain
+                .quota_price_history(block_count, percentiles)
+                .and_then(|history| serde_json::to_value(history).map_err(|err| err.to_string())),
+            // All the logs in an array, or a page of them with the cursor of the next page.
+            Query::Logs { filter, cursor } => self
+                .chain
+                .get_logs_page(&filter.into(), cursor)
+                .map_err(|err| err.to_string())
+                .map(|LogsPage { logs, next }| {
+                    let rpc_logs: Vec<RpcLog> = logs.into_iter().map(Into::into).collect();
+                    match next {
+                        Some(next) => {
+                            serde_json::json!({"logs": rpc_logs, "next": next.to_string()})
+                        }
+                        None => serde_json::json!(rpc_logs),
+                    }
+                }),
+            // The filters of jsonrpc are only used by the clients which created them.
+            Query::NewFilter { client, filter } => {
+                filter_id(self.chain.new_filter(filter, Some(&client)))
+            }
+            Query::NewBlockFilter { client } => {
+                filter_id(self.chain.new_block_filter(Some(&client)))
+            }
+            Query::NewPendingTransactionFilter { client } => {
+                filter_id(self.chain.new_pending_transaction_filter(Some(&client)))
+            }
+            Query::FilterChanges { client, id } => {
+                serde_json::to_value(self.chain.get_filter_changes(id as usize, Some(&client)))
+                    .map_err(|err| err.to_string())
+            }
+            Query::FilterLogs { client, id } => serde_json::to_value(
+                self.chain
+                    .get_filter_logs(id as usize, Some(&client))
+                    .unwrap_or_default(),
+            )
+            .map_err(|err| err.to_string()),
+            Query::UninstallFilter { client, id } => Ok(serde_json::Value::Bool(
+                self.chain.uninstall_filter(id as usize, Some(&client)),
+            )),
+            _ => Err(format!("Query {:?} is not answered by chain", query)),
+        };
+        let message = RawMessage::QueryResult { request_id, result };
+        self.ctx_pub
+            .send((routing_key!(Chain >> RawBytes).into(), message.to_bytes()))
+            .unwrap();
+    }
+
     fn consensus_block_enqueue(&self, proof_blk: BlockWithProof) {
         let current_height = self.chain.get_current_height() as usize;
         let mut proof_blk = proof_blk;
@@ -403,6 +538,16 @@ impl Forward {
             origin, heights
         );
 
+        // Decline the pruned blocks, the peer should sync them from an archive node.
+        let pruned_height = self.chain.get_pruned_height();
+        if heights.iter().any(|height| *height < pruned_height) {
+            info!(
+                "sync: decline node {}, blocks lower than {} are pruned",
+                origin, pruned_height
+            );
+            return;
+        }
+
         let res_vec = self.sync_response(heights);
 
         debug!(
@@ -450,7 +595,7 @@ impl Forward {
             .into_iter()
             .filter(|height| *height <= self.chain.get_current_height())
         {
-            if let Some(block) = self.chain.block(BlockTag::Height(height)) {
+            if let Ok(block) = self.chain.block(BlockTag::Height(height)) {
                 res_vec.mut_blocks().push(block.protobuf());
                 //push double
                 if height == self.chain.get_current_height() {
@@ -614,3 +759,9 @@ impl Forward {
         }
     }
 }
+
+// The id of a new filter, an error if there are too many filters.
+fn filter_id(id: Option<usize>) -> Result<serde_json::Value, String> {
+    id.map(|id| serde_json::Value::from(format!("{:#x}", id)))
+        .ok_or_else(|| "too many filters".to_owned())
+}
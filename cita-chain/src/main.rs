@@ -30,6 +30,9 @@
 //!     | chain   | Executor    | ExecutedResult   |
 //!     | chain   | Snapshot    | SnapshotReq      |
 //!     | chain   | Executor    | StateSignal      |
+//!     | chain   | Jsonrpc     | RawBytes         |
+//!     | chain   | Auth        | RawBytes         |
+//!     | chain   | Executor    | RawBytes         |
 //!
 //! 2. Publish channel
 //!
@@ -45,6 +48,7 @@
 //!     | chain | Chain     | Executor      | LocalSync     |
 //!     | chain | Chain     | Consensus     | RichStatus    |
 //!     | chain | Chain     | Executor      | RichStatus    |
+//!     | chain | Chain     | Jsonrpc       | RawBytes      |
 //!
 //! ### Key behavior
 //!
@@ -89,6 +93,9 @@ use core::libchain;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use pubsub::{channel, start_pubsub};
 
+// Interval of the heartbeats collected by the health check of jsonrpc
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(3);
+
 include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
 
 fn main() {
@@ -122,6 +129,9 @@ fn main() {
             Executor >> ExecutedResult,
             Executor >> StateSignal,
             Snapshot >> SnapshotReq,
+            Jsonrpc >> RawBytes,
+            Auth >> RawBytes,
+            Executor >> RawBytes,
         ]),
         tx,
         crx_pub,
@@ -141,8 +151,29 @@ fn main() {
     let (write_sender, write_receiver) = channel::unbounded();
     let forward = Forward::new(Arc::clone(&chain), ctx_pub.clone(), write_sender);
 
+    let heartbeat_chain = Arc::clone(&chain);
+    let heartbeat_pub = ctx_pub.clone();
     let block_processor = BlockProcessor::new(Arc::clone(&chain), ctx_pub);
 
+    // Heartbeat: report the heights to jsonrpc
+    thread::spawn(move || loop {
+        let is_snapshot = *heartbeat_chain.is_snapshot.read();
+        let heartbeat = serde_json::json!({
+            "service": "chain",
+            "height": heartbeat_chain.get_current_height(),
+            "highest": heartbeat_chain.get_max_store_height(),
+            "ready": !is_snapshot,
+            "conditions": {
+                "not_snapshot": !is_snapshot,
+            },
+        });
+        let _ = heartbeat_pub.send((
+            routing_key!(Chain >> RawBytes).into(),
+            heartbeat.to_string().into_bytes(),
+        ));
+        thread::sleep(HEARTBEAT_INTERVAL);
+    });
+
     // Two threads, one for reading, one for writing
     // Read: dispatch msg
     thread::spawn(move || loop {
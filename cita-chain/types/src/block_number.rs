@@ -18,14 +18,14 @@ use jsonrpc_types::rpc_types::{BlockNumber as RpcBlockNumber, BlockTag as RpcBlo
 pub type TransactionHash = H256;
 pub type BlockNumber = u64;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum BlockTag {
     Tag(Tag),
     Height(u64),
     Hash(H256),
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum Tag {
     Latest,
     Earliest,
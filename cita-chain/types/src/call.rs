@@ -0,0 +1,296 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A call and its overrides as they are given to the `call` method of JSON-RPC.
+
+use crate::context::Context;
+use crate::Bytes;
+use cita_trie::DB;
+use cita_types::{Address, H256, U256};
+use cita_vm::state::{State as CitaState, StateObjectEntry, StateObjectInfo};
+use std::collections::HashMap;
+
+/// Parameters of a call
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CallParams {
+    /// From
+    #[serde(default)]
+    pub from: Option<Address>,
+    /// To
+    pub to: Address,
+    /// Quota
+    #[serde(default)]
+    pub gas: Option<U256>,
+    /// Quota price
+    #[serde(default, rename = "gasPrice")]
+    pub gas_price: Option<U256>,
+    /// Value
+    #[serde(default)]
+    pub value: Option<U256>,
+    /// Data
+    #[serde(default, alias = "input", with = "hex_bytes")]
+    pub data: Option<Bytes>,
+}
+
+/// Overrides of an account, applied to the throwaway state of a call
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AccountOverride {
+    /// Balance
+    pub balance: Option<U256>,
+    /// Nonce
+    pub nonce: Option<U256>,
+    /// Code
+    #[serde(with = "hex_bytes")]
+    pub code: Option<Bytes>,
+    /// Storage slots to be replaced, the others are kept
+    pub state_diff: HashMap<H256, H256>,
+}
+
+/// Overrides of the block context of a call
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ContextOverride {
+    /// Block number
+    pub block_number: Option<u64>,
+    /// Timestamp
+    pub timestamp: Option<u64>,
+    /// Coin base
+    pub coin_base: Option<Address>,
+    /// Block quota limit
+    pub block_quota_limit: Option<U256>,
+}
+
+/// State and context overrides of a call
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct CallOverrides {
+    /// Account overrides
+    pub accounts: HashMap<Address, AccountOverride>,
+    /// Context overrides
+    pub context: ContextOverride,
+}
+
+impl CallOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.context == ContextOverride::default()
+    }
+
+    /// Apply the context overrides.
+    pub fn apply_to_context(&self, context: &mut Context) {
+        if let Some(block_number) = self.context.block_number {
+            context.block_number = block_number;
+        }
+        if let Some(timestamp) = self.context.timestamp {
+            context.timestamp = timestamp;
+        }
+        if let Some(coin_base) = self.context.coin_base {
+            context.coin_base = coin_base;
+        }
+        if let Some(block_quota_limit) = self.context.block_quota_limit {
+            context.block_quota_limit = block_quota_limit;
+        }
+    }
+
+    /// Apply the account overrides. The state should never be committed.
+    pub fn apply_to_state<B: DB + 'static>(&self, state: &mut CitaState<B>) -> Result<(), String> {
+        for (address, account) in &self.accounts {
+            let exist = state.exist(address).map_err(|e| format!("{:?}", e))?;
+            if !exist {
+                state.new_contract(
+                    address,
+                    account.balance.unwrap_or_default(),
+                    account.nonce.unwrap_or_default(),
+                    account.code.clone().unwrap_or_default(),
+                );
+            } else {
+                if let Some(balance) = account.balance {
+                    let now_val = state.balance(address).map_err(|e| format!("{:?}", e))?;
+                    if now_val > balance {
+                        state.sub_balance(address, now_val - balance)
+                    } else {
+                        state.add_balance(address, balance - now_val)
+                    }
+                    .map_err(|e| format!("{:?}", e))?;
+                }
+                if let Some(nonce) = account.nonce {
+                    if let Some(mut state_object) = state
+                        .get_state_object(address)
+                        .map_err(|e| format!("{:?}", e))?
+                    {
+                        state_object.nonce = nonce;
+                        state
+                            .insert_cache(address, StateObjectEntry::new_dirty(Some(state_object)));
+                    }
+                }
+                if let Some(ref code) = account.code {
+                    state
+                        .set_code(address, code.clone())
+                        .map_err(|e| format!("{:?}", e))?;
+                }
+            }
+            for (key, value) in &account.state_diff {
+                state
+                    .set_storage(address, *key, *value)
+                    .map_err(|e| format!("{:?}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Bytes in hex with the `0x` prefix.
+mod hex_bytes {
+    use crate::Bytes;
+    use rustc_hex::{FromHex, ToHex};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Bytes>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => serializer.serialize_some(&format!("0x{}", bytes.to_hex())),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Bytes>, D::Error> {
+        let hex: Option<String> = Option::deserialize(deserializer)?;
+        hex.map(|hex| {
+            hex.trim_start_matches("0x")
+                .from_hex()
+                .map_err(|err| D::Error::custom(format!("Invalid hex: {}", err)))
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountOverride, CallOverrides, CallParams, ContextOverride};
+    use crate::context::Context;
+    use cita_trie::MemoryDB;
+    use cita_types::{Address, H256, U256};
+    use cita_vm::state::{State, StateObjectInfo};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut state = State::new(Arc::new(MemoryDB::new(false))).unwrap();
+        let exist = Address::from(0x100);
+        let absent = Address::from(0x200);
+        state.add_balance(&exist, U256::from(100)).unwrap();
+        state.inc_nonce(&exist).unwrap();
+
+        let mut overrides = CallOverrides::default();
+        let mut account = AccountOverride::default();
+        account.balance = Some(U256::from(42));
+        account.nonce = Some(U256::from(3));
+        account.state_diff.insert(H256::from(1), H256::from(2));
+        overrides.accounts.insert(exist, account);
+        let mut account = AccountOverride::default();
+        account.code = Some(vec![0x60, 0x00]);
+        overrides.accounts.insert(absent, account);
+        overrides.context = ContextOverride {
+            block_number: Some(10),
+            timestamp: Some(1_000),
+            coin_base: None,
+            block_quota_limit: None,
+        };
+        assert!(!overrides.is_empty());
+
+        overrides.apply_to_state(&mut state).unwrap();
+        assert_eq!(state.balance(&exist).unwrap(), U256::from(42));
+        assert_eq!(state.nonce(&exist).unwrap(), U256::from(3));
+        assert_eq!(
+            state.get_storage(&exist, &H256::from(1)).unwrap(),
+            H256::from(2)
+        );
+        assert_eq!(state.code(&absent).unwrap(), vec![0x60, 0x00]);
+
+        let mut context = Context::default();
+        overrides.apply_to_context(&mut context);
+        assert_eq!(context.block_number, 10);
+        assert_eq!(context.timestamp, 1_000);
+        assert_eq!(context.coin_base, Address::default());
+
+        // Nonce could be lowered as well
+        let mut overrides = CallOverrides::default();
+        let mut account = AccountOverride::default();
+        account.nonce = Some(U256::from(0));
+        overrides.accounts.insert(exist, account);
+        overrides.apply_to_state(&mut state).unwrap();
+        assert_eq!(state.nonce(&exist).unwrap(), U256::zero());
+        assert_eq!(state.balance(&exist).unwrap(), U256::from(42));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let params: CallParams = serde_json::from_value(json!({
+            "to": "0x0000000000000000000000000000000000000100",
+            "data": "0x6000"
+        }))
+        .unwrap();
+        assert_eq!(params.from, None);
+        assert_eq!(params.to, Address::from(0x100));
+        assert_eq!(params.data, Some(vec![0x60, 0x00]));
+        assert_eq!(params.value, None);
+
+        let params: CallParams = serde_json::from_value(json!({
+            "to": "0x0000000000000000000000000000000000000100",
+            "gas": "0x5208",
+            "gasPrice": "0x1",
+            "value": "0x2a",
+            "input": "0x6000"
+        }))
+        .unwrap();
+        assert_eq!(params.gas, Some(U256::from(21_000)));
+        assert_eq!(params.gas_price, Some(U256::one()));
+        assert_eq!(params.value, Some(U256::from(42)));
+        assert_eq!(params.data, Some(vec![0x60, 0x00]));
+
+        let overrides: CallOverrides = serde_json::from_value(json!({
+            "accounts": {
+                "0x0000000000000000000000000000000000000100": {
+                    "balance": "0x2a",
+                    "code": "0x6000",
+                    "stateDiff": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                        "0x0000000000000000000000000000000000000000000000000000000000000002"
+                    }
+                }
+            },
+            "context": {"blockNumber": 10}
+        }))
+        .unwrap();
+        let account = &overrides.accounts[&Address::from(0x100)];
+        assert_eq!(account.balance, Some(U256::from(42)));
+        assert_eq!(account.nonce, None);
+        assert_eq!(account.code, Some(vec![0x60, 0x00]));
+        assert_eq!(account.state_diff[&H256::from(1)], H256::from(2));
+        assert_eq!(overrides.context.block_number, Some(10));
+        assert_eq!(
+            serde_json::from_value::<CallOverrides>(serde_json::to_value(&overrides).unwrap())
+                .unwrap(),
+            overrides
+        );
+
+        assert!(serde_json::from_value::<AccountOverride>(json!({"code": "0x600"})).is_err());
+    }
+}
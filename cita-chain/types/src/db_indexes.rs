@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::block_number::BlockNumber;
+use crate::log_index::LogTerm;
 use bloomchain::group::GroupPosition;
 use cita_types::{H256, H264};
 
@@ -22,6 +23,11 @@ const BLOCKSBLOOMS_INDEX: u8 = 2;
 const BLOCKHASH_INDEX: u8 = 3;
 const BLOCKHEADHASH_INDEX: u8 = 4;
 const BLOCKBODYHASH_INDEX: u8 = 5;
+const BLOCKSTATEDIFF_INDEX: u8 = 6;
+const FILTER_INDEX: u8 = 7;
+const LOG_INDEX: u8 = 8;
+const SYSCONFIGCHANGE_INDEX: u8 = 9;
+const FEEREPORT_INDEX: u8 = 10;
 
 pub trait DBIndex {
     fn get_index(&self) -> Vec<u8>;
@@ -51,6 +57,48 @@ impl DBIndex for CurrentHeight {
     }
 }
 
+pub struct CurrentFilters;
+
+impl DBIndex for CurrentFilters {
+    fn get_index(&self) -> Vec<u8> {
+        H256::from("7cabfb7709b29c16d9e876e876c9988d03f9c3414e1d3ff77ec1de2d0ee59f69").to_vec()
+    }
+}
+
+pub struct PrunedHeight;
+
+impl DBIndex for PrunedHeight {
+    fn get_index(&self) -> Vec<u8> {
+        H256::from("7cabfb7709b29c16d9e876e876c9988d03f9c3414e1d3ff77ec1de2d0ee59f6a").to_vec()
+    }
+}
+
+pub struct LogIndexedHeight;
+
+impl DBIndex for LogIndexedHeight {
+    fn get_index(&self) -> Vec<u8> {
+        H256::from("7cabfb7709b29c16d9e876e876c9988d03f9c3414e1d3ff77ec1de2d0ee59f6b").to_vec()
+    }
+}
+
+/// Heights where the system config was changed
+pub struct SysConfigChangedHeights;
+
+impl DBIndex for SysConfigChangedHeights {
+    fn get_index(&self) -> Vec<u8> {
+        H256::from("7cabfb7709b29c16d9e876e876c9988d03f9c3414e1d3ff77ec1de2d0ee59f6c").to_vec()
+    }
+}
+
+/// Heights where the quota price paid by the blocks is changed, with the prices
+pub struct QuotaPriceChangedHeights;
+
+impl DBIndex for QuotaPriceChangedHeights {
+    fn get_index(&self) -> Vec<u8> {
+        H256::from("7cabfb7709b29c16d9e876e876c9988d03f9c3414e1d3ff77ec1de2d0ee59f6d").to_vec()
+    }
+}
+
 pub struct Hash2Header(pub H256);
 
 impl DBIndex for Hash2Header {
@@ -111,6 +159,101 @@ impl DBIndex for BlockNumber2Body {
     }
 }
 
+pub struct BlockNumber2StateDiff(pub BlockNumber);
+
+impl DBIndex for BlockNumber2StateDiff {
+    fn get_index(&self) -> Vec<u8> {
+        let mut result = [0u8; 9];
+        result[0] = BLOCKSTATEDIFF_INDEX as u8;
+        result[1] = (self.0 >> 56) as u8;
+        result[2] = (self.0 >> 48) as u8;
+        result[3] = (self.0 >> 40) as u8;
+        result[4] = (self.0 >> 32) as u8;
+        result[5] = (self.0 >> 24) as u8;
+        result[6] = (self.0 >> 16) as u8;
+        result[7] = (self.0 >> 8) as u8;
+        result[8] = self.0 as u8;
+        result.to_vec()
+    }
+}
+
+pub struct BlockNumber2SysConfigChange(pub BlockNumber);
+
+impl DBIndex for BlockNumber2SysConfigChange {
+    fn get_index(&self) -> Vec<u8> {
+        let mut result = [0u8; 9];
+        result[0] = SYSCONFIGCHANGE_INDEX as u8;
+        result[1] = (self.0 >> 56) as u8;
+        result[2] = (self.0 >> 48) as u8;
+        result[3] = (self.0 >> 40) as u8;
+        result[4] = (self.0 >> 32) as u8;
+        result[5] = (self.0 >> 24) as u8;
+        result[6] = (self.0 >> 16) as u8;
+        result[7] = (self.0 >> 8) as u8;
+        result[8] = self.0 as u8;
+        result.to_vec()
+    }
+}
+
+pub struct BlockNumber2FeeReport(pub BlockNumber);
+
+impl DBIndex for BlockNumber2FeeReport {
+    fn get_index(&self) -> Vec<u8> {
+        let mut result = [0u8; 9];
+        result[0] = FEEREPORT_INDEX as u8;
+        result[1] = (self.0 >> 56) as u8;
+        result[2] = (self.0 >> 48) as u8;
+        result[3] = (self.0 >> 40) as u8;
+        result[4] = (self.0 >> 32) as u8;
+        result[5] = (self.0 >> 24) as u8;
+        result[6] = (self.0 >> 16) as u8;
+        result[7] = (self.0 >> 8) as u8;
+        result[8] = self.0 as u8;
+        result.to_vec()
+    }
+}
+
+pub struct FilterId2Filter(pub u64);
+
+impl DBIndex for FilterId2Filter {
+    fn get_index(&self) -> Vec<u8> {
+        let mut result = [0u8; 9];
+        result[0] = FILTER_INDEX as u8;
+        result[1] = (self.0 >> 56) as u8;
+        result[2] = (self.0 >> 48) as u8;
+        result[3] = (self.0 >> 40) as u8;
+        result[4] = (self.0 >> 32) as u8;
+        result[5] = (self.0 >> 24) as u8;
+        result[6] = (self.0 >> 16) as u8;
+        result[7] = (self.0 >> 8) as u8;
+        result[8] = self.0 as u8;
+        result.to_vec()
+    }
+}
+
+/// Positions of the logs with the term, in a chunk of blocks
+pub struct LogTerm2Positions(pub LogTerm, pub u64);
+
+impl DBIndex for LogTerm2Positions {
+    fn get_index(&self) -> Vec<u8> {
+        let mut result = [0u8; 42];
+        result[0] = LOG_INDEX as u8;
+        match self.0 {
+            LogTerm::Address(ref address) => {
+                result[2..22].clone_from_slice(address);
+            }
+            LogTerm::Topic(index, ref topic) => {
+                result[1] = index as u8 + 1;
+                result[2..34].clone_from_slice(topic);
+            }
+        }
+        for (i, byte) in result[34..].iter_mut().enumerate() {
+            *byte = (self.1 >> (56 - 8 * i)) as u8;
+        }
+        result.to_vec()
+    }
+}
+
 pub struct BlockNumber2Hash(pub BlockNumber);
 
 impl DBIndex for BlockNumber2Hash {
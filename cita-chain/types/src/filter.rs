@@ -16,9 +16,12 @@ use crate::block_number::BlockTag;
 #[cfg(test)]
 use crate::block_number::Tag;
 use crate::log::Log;
+use crate::log_index::LogTerm;
 use cita_types::traits::BloomTools;
 use cita_types::{Address, Bloom, H256};
 use jsonrpc_types::rpc_types::{Filter as RpcFilter, FilterAddress, Topic, VariadicValue};
+use std::fmt;
+use std::str::FromStr;
 
 /// Address Filter.
 #[derive(Debug, PartialEq, Clone)]
@@ -179,6 +182,31 @@ impl Filter {
         self.addresses.matches(log) && self.topics.matches(log)
     }
 
+    /// Terms of the log index to match, a log matches if it has one of the terms in each group.
+    ///
+    /// It's empty if neither address nor topic is given.
+    pub fn index_terms(&self) -> Vec<Vec<LogTerm>> {
+        let mut groups = Vec::new();
+        if let Some(ref addresses) = self.addresses.addresses {
+            if !addresses.is_empty() {
+                groups.push(addresses.iter().cloned().map(LogTerm::Address).collect());
+            }
+        }
+        for (index, topics) in self.topics.topics.iter().enumerate() {
+            if let Some(ref topics) = *topics {
+                if !topics.is_empty() {
+                    groups.push(
+                        topics
+                            .iter()
+                            .map(|topic| LogTerm::Topic(index, *topic))
+                            .collect(),
+                    );
+                }
+            }
+        }
+        groups
+    }
+
     // For test
     #[cfg(test)]
     pub fn new_with_address_and_topic(addresses: AddressFilter, topics: TopicFilter) -> Self {
@@ -204,10 +232,45 @@ impl From<RpcFilter> for Filter {
     }
 }
 
+/// Where a page of logs stops: the block of the next log, and its offset in the logs of the
+/// block.
+///
+/// Clients get it as an opaque string, which is the block number and the offset in hex.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LogsCursor {
+    pub block_number: u64,
+    pub log_index: usize,
+}
+
+impl fmt::Display for LogsCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:016x}{:08x}", self.block_number, self.log_index)
+    }
+}
+
+impl FromStr for LogsCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid logs cursor {}", s);
+        let hex = s.trim_start_matches("0x");
+        if hex.len() != 24 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let block_number = u64::from_str_radix(&hex[..16], 16).map_err(|_| invalid())?;
+        let log_index = u32::from_str_radix(&hex[16..], 16).map_err(|_| invalid())?;
+        Ok(LogsCursor {
+            block_number,
+            log_index: log_index as usize,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::filter::{AddressFilter, Filter, TopicFilter};
+    use crate::filter::{AddressFilter, Filter, LogsCursor, TopicFilter};
     use crate::log::Log;
+    use crate::log_index::LogTerm;
     use cita_types::{Address, Bloom, H256};
 
     #[test]
@@ -378,4 +441,40 @@ mod tests {
         assert_eq!(filter.addresses.matches(&entry1), true);
         assert_eq!(filter.addresses.matches(&entry2), false);
     }
+
+    #[test]
+    fn test_index_terms() {
+        let none_filter =
+            Filter::new_with_address_and_topic(Default::default(), Default::default());
+        assert!(none_filter.index_terms().is_empty());
+
+        let topics = vec![None, Some(vec![H256::from(1), H256::from(2)]), None, None];
+        let addresses = Some(vec![Address::from(3)]);
+        let filter = Filter::new_with_address_and_topic(
+            AddressFilter::new(addresses),
+            TopicFilter::new(topics),
+        );
+        assert_eq!(
+            filter.index_terms(),
+            vec![
+                vec![LogTerm::Address(Address::from(3))],
+                vec![
+                    LogTerm::Topic(1, H256::from(1)),
+                    LogTerm::Topic(1, H256::from(2))
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_logs_cursor() {
+        let cursor = LogsCursor {
+            block_number: 0x1234,
+            log_index: 5,
+        };
+        assert_eq!(cursor.to_string(), "0x000000000000123400000005");
+        assert_eq!(cursor.to_string().parse(), Ok(cursor));
+        assert!("0x1234".parse::<LogsCursor>().is_err());
+        assert!("0x00000000000012340000000g".parse::<LogsCursor>().is_err());
+    }
 }
@@ -27,6 +27,7 @@ pub type Bytes = Vec<u8>;
 pub mod block;
 pub mod block_number;
 pub mod block_receipts;
+pub mod call;
 pub mod context;
 pub mod db_indexes;
 pub mod errors;
@@ -34,6 +35,8 @@ pub mod filter;
 pub mod header;
 pub mod log;
 pub mod log_blooms;
+pub mod log_index;
+pub mod raw_message;
 pub mod receipt;
 pub mod reserved_addresses;
 pub mod state_proof;
@@ -0,0 +1,118 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exact index of the logs, from an address or a topic to the positions of the logs.
+//!
+//! The positions of a term are grouped by chunks of `LOG_INDEX_CHUNK_BLOCKS` blocks,
+//! a chunk is one value in the database.
+
+use crate::block_number::BlockNumber;
+use crate::log::Log;
+use cita_types::{Address, H256};
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+pub const LOG_INDEX_CHUNK_BLOCKS: BlockNumber = 256;
+
+/// The address of a log, or a topic of a log at its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogTerm {
+    Address(Address),
+    Topic(usize, H256),
+}
+
+impl LogTerm {
+    /// Terms of a log, only the first four topics are indexed.
+    pub fn terms(log: &Log) -> Vec<LogTerm> {
+        let mut terms = vec![LogTerm::Address(log.address)];
+        terms.extend(
+            log.topics
+                .iter()
+                .take(4)
+                .enumerate()
+                .map(|(index, topic)| LogTerm::Topic(index, *topic)),
+        );
+        terms
+    }
+}
+
+/// Position of a log in the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LogPosition {
+    pub block_number: BlockNumber,
+    pub transaction_index: usize,
+    pub transaction_log_index: usize,
+}
+
+impl Encodable for LogPosition {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        s.append(&self.block_number);
+        s.append(&self.transaction_index);
+        s.append(&self.transaction_log_index);
+    }
+}
+
+impl Decodable for LogPosition {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        Ok(LogPosition {
+            block_number: rlp.val_at(0)?,
+            transaction_index: rlp.val_at(1)?,
+            transaction_log_index: rlp.val_at(2)?,
+        })
+    }
+}
+
+/// Chunk of the block in the index.
+pub fn log_index_chunk(number: BlockNumber) -> u64 {
+    number / LOG_INDEX_CHUNK_BLOCKS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogPosition, LogTerm};
+    use crate::log::Log;
+    use cita_types::{Address, H256};
+
+    #[test]
+    fn test_log_terms() {
+        let log = Log {
+            address: Address::from(1),
+            topics: (0..5u64).map(H256::from).collect(),
+            data: vec![],
+        };
+        let terms = LogTerm::terms(&log);
+        assert_eq!(terms.len(), 5);
+        assert_eq!(terms[0], LogTerm::Address(Address::from(1)));
+        assert_eq!(terms[4], LogTerm::Topic(3, H256::from(3)));
+    }
+
+    #[test]
+    fn test_log_position_rlp() {
+        let positions = vec![
+            LogPosition {
+                block_number: 100,
+                transaction_index: 2,
+                transaction_log_index: 0,
+            },
+            LogPosition {
+                block_number: 101,
+                transaction_index: 0,
+                transaction_log_index: 3,
+            },
+        ];
+        let encoded = rlp::encode_list(&positions);
+        let decoded: Vec<LogPosition> = rlp::decode_list(&encoded);
+        assert_eq!(decoded, positions);
+    }
+}
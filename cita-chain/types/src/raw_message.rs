@@ -0,0 +1,236 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Messages the services exchange as JSON in `RawBytes`, for what the
+//! protobuf messages of libproto can't carry.
+//!
+//! Each message is tagged with its `type`. The heartbeats published as
+//! `RawBytes` have no `type`, so they are never taken for one of these.
+
+use crate::block_number::BlockTag;
+use crate::call::{CallOverrides, CallParams};
+use crate::filter::LogsCursor;
+use cita_types::{Address, H256, U256};
+use jsonrpc_types::rpc_types::Filter as RpcFilter;
+use serde_json::Value;
+
+/// Max number of blocks of a quota price history query
+pub const MAX_QUOTA_PRICE_HISTORY_BLOCKS: u64 = 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RawMessage {
+    /// The accounts frozen by the emergency intervention, from the executor to
+    /// auth. It replaces the list sent before.
+    FrozenAccounts { accounts: Vec<Address> },
+    /// The quota prices of `PriceManager` paid by the blocks from `from` on, with the heights
+    /// they are changed at, from the executor to the chain. They replace the ones from `from`
+    /// sent before.
+    QuotaPrices { from: u64, prices: Vec<(u64, U256)> },
+    /// The transactions which entered the pool, from auth to the pending transaction
+    /// filters of the chain.
+    PendingTransactions { hashes: Vec<H256> },
+    /// A query of the CITA namespace, from jsonrpc to the service answering it.
+    Query { request_id: Vec<u8>, query: Query },
+    /// The result of a query, from the service back to jsonrpc.
+    QueryResult {
+        request_id: Vec<u8>,
+        result: Result<Value, String>,
+    },
+}
+
+impl RawMessage {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Serialize raw message error.")
+    }
+
+    /// Parse the body of a `RawBytes` message, none if it's not one of these,
+    /// such as a heartbeat.
+    pub fn from_bytes(body: &[u8]) -> Option<Self> {
+        serde_json::from_slice(body).ok()
+    }
+}
+
+/// The queries of the CITA namespace which have no request in libproto.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum Query {
+    /// Changes of the system config in the heights `[from, to]`.
+    SysConfigChanges { from: u64, to: u64 },
+    /// Where the fees of a block went.
+    FeeReport { height: u64 },
+    /// Quota used and fees paid in the latest `block_count` blocks, the percentiles of the
+    /// fees are in basis points.
+    QuotaPriceHistory {
+        block_count: u64,
+        percentiles: Vec<u64>,
+    },
+    /// The accounts frozen by the emergency intervention at a block.
+    FrozenAccounts { block_tag: BlockTag },
+    /// The state diff recorded by the executor for a block.
+    StateDiff { height: u64 },
+    /// A call at a block, with the state and the context of the block overridden.
+    Call {
+        request: CallParams,
+        block_tag: BlockTag,
+        overrides: CallOverrides,
+    },
+    /// The quota a call needs at a block, at most the quota of the call.
+    EstimateQuota {
+        request: CallParams,
+        block_tag: BlockTag,
+    },
+    /// A page of the logs of a filter, from the cursor the last page stopped at.
+    Logs {
+        filter: RpcFilter,
+        cursor: Option<LogsCursor>,
+    },
+    /// A new filter of logs, of the client.
+    NewFilter { client: String, filter: RpcFilter },
+    /// A new filter of blocks, of the client.
+    NewBlockFilter { client: String },
+    /// A new filter of the transactions entering the pool, of the client.
+    NewPendingTransactionFilter { client: String },
+    /// What a filter of the client got since it was polled.
+    FilterChanges { client: String, id: u64 },
+    /// All the logs of a filter of the client.
+    FilterLogs { client: String, id: u64 },
+    /// Remove a filter of the client.
+    UninstallFilter { client: String, id: u64 },
+}
+
+impl Query {
+    /// Whether the query is answered by cita-chain, otherwise by the executor.
+    pub fn is_for_chain(&self) -> bool {
+        match self {
+            Query::QuotaPriceHistory { .. }
+            | Query::Logs { .. }
+            | Query::NewFilter { .. }
+            | Query::NewBlockFilter { .. }
+            | Query::NewPendingTransactionFilter { .. }
+            | Query::FilterChanges { .. }
+            | Query::FilterLogs { .. }
+            | Query::UninstallFilter { .. } => true,
+            Query::SysConfigChanges { .. }
+            | Query::FeeReport { .. }
+            | Query::FrozenAccounts { .. }
+            | Query::StateDiff { .. }
+            | Query::Call { .. }
+            | Query::EstimateQuota { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, RawMessage};
+    use crate::block_number::BlockTag;
+    use crate::call::{CallOverrides, CallParams};
+    use crate::filter::LogsCursor;
+    use cita_types::{Address, H256, U256};
+    use serde_json::json;
+
+    #[test]
+    fn test_raw_message() {
+        let message = RawMessage::FrozenAccounts {
+            accounts: vec![Address::from(1), Address::from(2)],
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::PendingTransactions {
+            hashes: vec![H256::from(1)],
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::QuotaPrices {
+            from: 16,
+            prices: vec![(16, U256::from(100)), (20, U256::from(200))],
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let heartbeat = br#"{"service":"executor","height":1,"highest":1,"ready":true}"#;
+        assert_eq!(RawMessage::from_bytes(heartbeat), None);
+    }
+
+    #[test]
+    fn test_query() {
+        let message = RawMessage::Query {
+            request_id: vec![1, 2, 3],
+            query: Query::SysConfigChanges { from: 1, to: 16 },
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "type": "query",
+                "request_id": [1, 2, 3],
+                "query": {"method": "sysConfigChanges", "from": 1, "to": 16},
+            })
+        );
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::Query {
+            request_id: vec![1, 2, 3],
+            query: Query::FrozenAccounts {
+                block_tag: BlockTag::Height(16),
+            },
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::Query {
+            request_id: vec![1, 2, 3],
+            query: Query::Call {
+                request: CallParams {
+                    from: None,
+                    to: Address::from(0x100),
+                    value: Some(42.into()),
+                    data: Some(vec![0x60, 0x00]),
+                    ..Default::default()
+                },
+                block_tag: BlockTag::Height(16),
+                overrides: CallOverrides::default(),
+            },
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::Query {
+            request_id: vec![1, 2, 3],
+            query: Query::Logs {
+                filter: serde_json::from_value(json!({"fromBlock": "0x1", "toBlock": "latest"}))
+                    .unwrap(),
+                cursor: Some(LogsCursor {
+                    block_number: 16,
+                    log_index: 2,
+                }),
+            },
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let query = Query::FilterChanges {
+            client: "127.0.0.1".to_owned(),
+            id: 16,
+        };
+        assert!(query.is_for_chain());
+        let message = RawMessage::Query {
+            request_id: vec![1, 2, 3],
+            query,
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+
+        let message = RawMessage::QueryResult {
+            request_id: vec![1, 2, 3],
+            result: Err("Block 16 is not executed yet".to_owned()),
+        };
+        assert_eq!(RawMessage::from_bytes(&message.to_bytes()), Some(message));
+    }
+}
@@ -24,7 +24,11 @@
 //
 // # CITA builtin
 //
-// edrecover: 0x0000000000000000000000000000000000ff0001
+// edrecover:      0x0000000000000000000000000000000000ff0001
+// ed25519verify:  0x0000000000000000000000000000000000ff0002
+// sm3:            0x0000000000000000000000000000000000ff0003
+// sm2verify:      0x0000000000000000000000000000000000ff0004
+// sm2verifyhash:  0x0000000000000000000000000000000000ff0005
 //
 // # All
 //
@@ -75,11 +79,17 @@ pub const IDENTITY_ADDRESS: &str = "0000000000000000000000000000000000000004";
 
 // CITA builtin address
 pub const EDRECOVER_ADDRESS: &str = "0000000000000000000000000000000000ff0001";
+pub const ED25519_VERIFY_ADDRESS: &str = "0000000000000000000000000000000000ff0002";
+pub const SM3_ADDRESS: &str = "0000000000000000000000000000000000ff0003";
+pub const SM2_VERIFY_ADDRESS: &str = "0000000000000000000000000000000000ff0004";
+pub const SM2_VERIFY_HASH_ADDRESS: &str = "0000000000000000000000000000000000ff0005";
 
 // Normal Action Address
 pub const STORE_ADDRESS: &str = "ffffffffffffffffffffffffffffffffff010000";
 pub const ABI_ADDRESS: &str = "ffffffffffffffffffffffffffffffffff010001";
 pub const AMEND_ADDRESS: &str = "ffffffffffffffffffffffffffffffffff010002";
+// Logs of sponsored transactions are emitted from it
+pub const SPONSOR_ADDRESS: &str = "ffffffffffffffffffffffffffffffffff010003";
 // Normal System Contracts
 pub const SYS_CONFIG: &str = "ffffffffffffffffffffffffffffffffff020000";
 pub const NODE_MANAGER: &str = "ffffffffffffffffffffffffffffffffff020001";
@@ -131,3 +141,4 @@ pub const PERMISSION_VERSION: &str = "ffffffffffffffffffffffffffffffffff021028";
 pub const NATIVE_SIMPLE_STORAGE: &str = "ffffffffffffffffffffffffffffffffff030000";
 pub const NATIVE_ZK_PRIVACY: &str = "ffffffffffffffffffffffffffffffffff030001";
 pub const NATIVE_CROSS_CHAIN_VERIFY: &str = "ffffffffffffffffffffffffffffffffff030002";
+pub const NATIVE_MULTISIG: &str = "ffffffffffffffffffffffffffffffffff030003";
@@ -15,11 +15,13 @@
 use super::Bytes;
 use crate::block_number::BlockNumber;
 use crate::crypto::{
-    pubkey_to_address, PubKey, Signature, HASH_BYTES_LEN, PUBKEY_BYTES_LEN, SIGNATURE_BYTES_LEN,
+    pubkey_to_address, PubKey, Sign, Signature, HASH_BYTES_LEN, PUBKEY_BYTES_LEN,
+    SIGNATURE_BYTES_LEN,
 };
 use crate::reserved_addresses::{ABI_ADDRESS, AMEND_ADDRESS, STORE_ADDRESS};
 use cita_types::traits::LowerHex;
 use cita_types::{clean_0x, Address, H256, U256};
+use hashable::Hashable;
 use libproto::blockchain::{
     Crypto as ProtoCrypto, SignedTransaction as ProtoSignedTransaction,
     Transaction as ProtoTransaction, UnverifiedTransaction as ProtoUnverifiedTransaction,
@@ -27,6 +29,7 @@ use libproto::blockchain::{
 use rlp::*;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use tiny_keccak::keccak256;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
@@ -36,6 +39,159 @@ pub enum Error {
     InvalidPubKey,
 }
 
+/// The first transaction version whose data may carry a sponsored or a
+/// multisig envelope. Transactions of earlier versions always run with their
+/// whole data, whatever it starts with.
+pub const ENVELOPE_TX_VERSION: u32 = 3;
+
+/// Prefix of the data of a sponsored transaction, whose quota is paid by a
+/// sponsor instead of the sender. The data of such a transaction is
+/// `SPONSORED_DATA_PREFIX ++ sender nonce (8 bytes) ++ sponsor signature ++ payload`,
+/// and it runs with the payload as its data.
+///
+/// The sender nonce is the account nonce of the sender the transaction is
+/// sponsored for, so the sponsor pays for one transaction only.
+pub const SPONSORED_DATA_PREFIX: &[u8] = b"\0cita-sponsored\0";
+
+/// Split sponsored transaction data into the sender nonce, the sponsor
+/// signature and the payload.
+pub fn split_sponsored_data(data: &[u8]) -> Option<(u64, &[u8], &[u8])> {
+    let header_len = SPONSORED_DATA_PREFIX.len() + 8;
+    if data.len() < header_len + SIGNATURE_BYTES_LEN || !data.starts_with(SPONSORED_DATA_PREFIX) {
+        return None;
+    }
+    let mut nonce = [0u8; 8];
+    nonce.copy_from_slice(&data[SPONSORED_DATA_PREFIX.len()..header_len]);
+    let (signature, payload) = data[header_len..].split_at(SIGNATURE_BYTES_LEN);
+    Some((u64::from_be_bytes(nonce), signature, payload))
+}
+
+/// Prefix of the data of a transaction sent as a multi-signature account. The
+/// data of such a transaction is
+/// `MULTISIG_DATA_PREFIX ++ account ++ multisig nonce (8 bytes) ++ signature count (1 byte) ++ signatures ++ payload`,
+/// and it runs with the payload as its data, sent by the account.
+///
+/// The multisig nonce is the one the `multisig` native contract keeps for the
+/// account, so each set of signatures is used only once.
+pub const MULTISIG_DATA_PREFIX: &[u8] = b"\0cita-multisig\0\0";
+
+/// Split multisig transaction data into the account, the multisig nonce, the
+/// signatures and the payload.
+pub fn split_multisig_data(data: &[u8]) -> Option<(Address, u64, Vec<&[u8]>, &[u8])> {
+    let header_len = MULTISIG_DATA_PREFIX.len() + 20 + 8 + 1;
+    if data.len() < header_len || !data.starts_with(MULTISIG_DATA_PREFIX) {
+        return None;
+    }
+    let account = Address::from(&data[MULTISIG_DATA_PREFIX.len()..MULTISIG_DATA_PREFIX.len() + 20]);
+    let mut nonce = [0u8; 8];
+    nonce.copy_from_slice(&data[header_len - 9..header_len - 1]);
+    let count = data[header_len - 1] as usize;
+    let data = &data[header_len..];
+    if count == 0 || data.len() < count * SIGNATURE_BYTES_LEN {
+        return None;
+    }
+    let (signatures, payload) = data.split_at(count * SIGNATURE_BYTES_LEN);
+    Some((
+        account,
+        u64::from_be_bytes(nonce),
+        signatures.chunks(SIGNATURE_BYTES_LEN).collect(),
+        payload,
+    ))
+}
+
+/// Prefix of the data of a transaction converted from a raw Ethereum
+/// transaction signed as in EIP-155. The data of such a transaction is
+/// `ETHEREUM_DATA_PREFIX ++ Ethereum nonce (8 bytes) ++ gas price (32 bytes) ++ payload`,
+/// and it runs with the payload as its data.
+///
+/// The sender signs `Transaction::ethereum_hash` instead of the hash of the
+/// CITA transaction, and the Ethereum nonce is the account nonce of the
+/// sender, so the transaction is executed only once.
+pub const ETHEREUM_DATA_PREFIX: &[u8] = b"\0cita-ethereum\0\0";
+
+/// Prefix of the data of the placeholder transaction jsonrpc wraps a raw
+/// Ethereum transaction in, `RAW_ETHEREUM_TX_PREFIX ++ raw transaction`.
+/// cita-auth replaces it with the converted transaction, see
+/// `ethereum_unverified_transaction`.
+pub const RAW_ETHEREUM_TX_PREFIX: &[u8] = b"\0cita-eth-raw\0\0\0";
+
+/// Split Ethereum transaction data into the Ethereum nonce, the gas price and
+/// the payload.
+pub fn split_ethereum_data(data: &[u8]) -> Option<(u64, U256, &[u8])> {
+    let header_len = ETHEREUM_DATA_PREFIX.len() + 8 + 32;
+    if data.len() < header_len || !data.starts_with(ETHEREUM_DATA_PREFIX) {
+        return None;
+    }
+    let mut nonce = [0u8; 8];
+    nonce.copy_from_slice(&data[ETHEREUM_DATA_PREFIX.len()..ETHEREUM_DATA_PREFIX.len() + 8]);
+    let gas_price = U256::from(&data[ETHEREUM_DATA_PREFIX.len() + 8..header_len]);
+    Some((u64::from_be_bytes(nonce), gas_price, &data[header_len..]))
+}
+
+/// Convert a raw Ethereum transaction signed as in EIP-155 into a CITA
+/// transaction of `version`, valid until `valid_until_block`.
+///
+/// The chain id is the one the transaction is signed for, which cita-auth
+/// checks as that of any other transaction. Signatures without a chain id are
+/// rejected, since they could be replayed on any chain.
+pub fn ethereum_unverified_transaction(
+    raw: &[u8],
+    version: u32,
+    valid_until_block: u64,
+) -> Result<ProtoUnverifiedTransaction, Error> {
+    if version < ENVELOPE_TX_VERSION {
+        return Err(Error::ParseError);
+    }
+    let rlp = UntrustedRlp::new(raw);
+    if !rlp.is_list() || rlp.item_count().map_err(|_| Error::ParseError)? != 9 {
+        return Err(Error::ParseError);
+    }
+    let field = |index| rlp.val_at::<U256>(index).map_err(|_| Error::ParseError);
+    let (nonce, gas_price, gas, value) = (field(0)?, field(1)?, field(2)?, field(4)?);
+    let (v, r, s) = (field(6)?, field(7)?, field(8)?);
+    let to = rlp
+        .at(3)
+        .and_then(|to| to.data().map(<[u8]>::to_vec))
+        .map_err(|_| Error::ParseError)?;
+    let payload: Vec<u8> = rlp.val_at(5).map_err(|_| Error::ParseError)?;
+    let max = U256::from(u64::max_value());
+    if nonce > max || gas > max || !(to.is_empty() || to.len() == 20) {
+        return Err(Error::ParseError);
+    }
+
+    // `v` is `chain id * 2 + 35 + recovery id` since EIP-155.
+    if v < U256::from(35) {
+        return Err(Error::InvalidSignature);
+    }
+    let chain_id = (v - U256::from(35)) / U256::from(2);
+    let recovery_id = ((v - U256::from(35)) % U256::from(2)).low_u32() as u8;
+
+    let mut data = ETHEREUM_DATA_PREFIX.to_vec();
+    data.extend_from_slice(&nonce.low_u64().to_be_bytes());
+    data.extend_from_slice(&<[u8; 32]>::from(gas_price));
+    data.extend(payload);
+
+    let mut tx = ProtoTransaction::new();
+    tx.set_nonce(nonce.low_u64().to_string());
+    tx.set_quota(gas.low_u64());
+    tx.set_to_v1(to);
+    tx.set_value(<[u8; 32]>::from(value).to_vec());
+    tx.set_data(data);
+    tx.set_valid_until_block(valid_until_block);
+    tx.set_chain_id_v1(<[u8; 32]>::from(chain_id).to_vec());
+    tx.set_version(version);
+
+    let mut signature = <[u8; 32]>::from(r).to_vec();
+    signature.extend_from_slice(&<[u8; 32]>::from(s));
+    signature.push(recovery_id);
+
+    let mut utx = ProtoUnverifiedTransaction::new();
+    utx.set_transaction(tx);
+    utx.set_signature(signature);
+    utx.set_crypto(ProtoCrypto::DEFAULT);
+    Ok(utx)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Transaction action type.
 pub enum Action {
@@ -268,6 +424,123 @@ impl Transaction {
         }
     }
 
+    /// The sender nonce, the sponsor signature and the payload of a sponsored
+    /// transaction.
+    pub fn sponsored_data(&self) -> Option<(u64, &[u8], &[u8])> {
+        if self.version < ENVELOPE_TX_VERSION {
+            return None;
+        }
+        split_sponsored_data(&self.data)
+    }
+
+    /// The account, the multisig nonce, the signatures and the payload of a
+    /// multisig transaction.
+    pub fn multisig_data(&self) -> Option<(Address, u64, Vec<&[u8]>, &[u8])> {
+        if self.version < ENVELOPE_TX_VERSION {
+            return None;
+        }
+        split_multisig_data(&self.data)
+    }
+
+    /// The Ethereum nonce, the gas price and the payload of a transaction
+    /// converted from a raw Ethereum transaction.
+    pub fn ethereum_data(&self) -> Option<(u64, U256, &[u8])> {
+        if self.version < ENVELOPE_TX_VERSION {
+            return None;
+        }
+        split_ethereum_data(&self.data)
+    }
+
+    /// Data the transaction runs with: the payload of a sponsored, multisig or
+    /// Ethereum transaction, the whole data otherwise.
+    pub fn payload(&self) -> &[u8] {
+        if let Some((_, _, payload)) = self.sponsored_data() {
+            payload
+        } else if let Some((_, _, _, payload)) = self.multisig_data() {
+            payload
+        } else if let Some((_, _, payload)) = self.ethereum_data() {
+            payload
+        } else {
+            &self.data
+        }
+    }
+
+    /// The hash a sponsor signs to pay the quota of this transaction sent by
+    /// `sender` with the account nonce `nonce`.
+    ///
+    /// It covers every field the sender signs, with the payload in place of the
+    /// data, and the sender and its nonce, so the signature pays for this
+    /// transaction only. cita-auth verifies sponsor signatures with it too.
+    pub fn sponsor_hash(&self, sender: &Address, nonce: u64) -> H256 {
+        let mut message = self.authorized_message(sender);
+        message.extend_from_slice(&nonce.to_be_bytes());
+        message.crypt_hash()
+    }
+
+    /// The hash each signer of multisig `account` signs to send this
+    /// transaction as the account, with the multisig nonce of the account.
+    pub fn multisig_hash(&self, account: &Address, nonce: u64) -> H256 {
+        let mut message = MULTISIG_DATA_PREFIX.to_vec();
+        message.extend(self.authorized_message(account));
+        message.extend_from_slice(&nonce.to_be_bytes());
+        message.crypt_hash()
+    }
+
+    /// The EIP-155 signing hash of a transaction converted from a raw Ethereum
+    /// transaction, which its sender signs instead of the hash of the CITA
+    /// transaction. cita-auth verifies the signature with it.
+    pub fn ethereum_hash(&self) -> Option<H256> {
+        let (nonce, gas_price, payload) = self.ethereum_data()?;
+        let mut s = RlpStream::new_list(9);
+        s.append(&nonce);
+        s.append(&gas_price);
+        s.append(&self.gas);
+        match self.to_address() {
+            Some(to) => s.append(&to),
+            None => s.append_empty_data(),
+        };
+        s.append(&self.value);
+        s.append(&payload.to_vec());
+        s.append(&self.chain_id);
+        s.append(&0u8);
+        s.append(&0u8);
+        Some(H256::from(keccak256(&s.out())))
+    }
+
+    fn to_address(&self) -> Option<Address> {
+        match self.action {
+            Action::Create => None,
+            Action::Call(ref to) => Some(*to),
+            Action::Store => Some(Address::from_str(STORE_ADDRESS).unwrap()),
+            Action::AbiStore => Some(Address::from_str(ABI_ADDRESS).unwrap()),
+            Action::AmendData => Some(Address::from_str(AMEND_ADDRESS).unwrap()),
+        }
+    }
+
+    /// The fields of the transaction with the payload in place of the data,
+    /// and the account the signature is made for.
+    fn authorized_message(&self, account: &Address) -> Vec<u8> {
+        let to = self.to_address();
+        let mut message = Vec::new();
+        message.extend_from_slice(&(self.nonce.len() as u64).to_be_bytes());
+        message.extend_from_slice(self.nonce.as_bytes());
+        message.extend_from_slice(&self.gas.low_u64().to_be_bytes());
+        match to {
+            Some(to) => {
+                message.push(20);
+                message.extend_from_slice(&to);
+            }
+            None => message.push(0),
+        }
+        message.extend_from_slice(&<[u8; 32]>::from(self.value));
+        message.extend_from_slice(&self.block_limit.to_be_bytes());
+        message.extend_from_slice(&<[u8; 32]>::from(self.chain_id));
+        message.extend_from_slice(&self.version.to_be_bytes());
+        message.extend_from_slice(account);
+        message.extend_from_slice(self.payload());
+        message
+    }
+
     /// Append object with a without signature into RLP stream
     pub fn rlp_append_unsigned_transaction(&self, s: &mut RlpStream) {
         s.begin_list(9);
@@ -540,6 +813,39 @@ impl SignedTransaction {
         &self.sender
     }
 
+    /// Returns the sponsor paying the quota of a sponsored transaction, and
+    /// the sender nonce it pays for.
+    pub fn sponsor(&self) -> Result<Option<(Address, u64)>, Error> {
+        match self.sponsored_data() {
+            Some((nonce, signature, _)) => Signature::from(signature)
+                .recover(&self.sponsor_hash(&self.sender, nonce))
+                .map(|public| Some((pubkey_to_address(&public), nonce)))
+                .map_err(|_| Error::InvalidSignature),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the account, the multisig nonce and the signers of a multisig
+    /// transaction.
+    pub fn multisig(&self) -> Result<Option<(Address, u64, Vec<Address>)>, Error> {
+        match self.multisig_data() {
+            Some((account, nonce, signatures, _)) => {
+                let hash = self.multisig_hash(&account, nonce);
+                signatures
+                    .into_iter()
+                    .map(|signature| {
+                        Signature::from(signature)
+                            .recover(&hash)
+                            .map(|public| pubkey_to_address(&public))
+                            .map_err(|_| Error::InvalidSignature)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|signers| Some((account, nonce, signers)))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Returns a public key of the sender.
     pub fn public_key(&self) -> &PubKey {
         &self.public
@@ -560,6 +866,7 @@ impl SignedTransaction {
 mod tests {
     use super::*;
     use rlp;
+    use rustc_hex::FromHex;
 
     #[test]
     fn test_encode_and_decode() {
@@ -586,6 +893,144 @@ mod tests {
         assert_eq!(stx_rlp, stx_encoded);
     }
 
+    #[test]
+    fn test_sponsored_data() {
+        let mut tx = Transaction::default();
+        tx.version = ENVELOPE_TX_VERSION;
+        tx.data = vec![1, 2, 3];
+        assert_eq!(tx.payload(), &[1, 2, 3]);
+
+        let mut data = SPONSORED_DATA_PREFIX.to_vec();
+        data.extend_from_slice(&5u64.to_be_bytes());
+        data.extend_from_slice(&[7; SIGNATURE_BYTES_LEN]);
+        data.extend_from_slice(&[1, 2, 3]);
+        let (nonce, signature, payload) = split_sponsored_data(&data).unwrap();
+        assert_eq!(nonce, 5);
+        assert_eq!(signature, &[7; SIGNATURE_BYTES_LEN][..]);
+        assert_eq!(payload, &[1, 2, 3]);
+
+        let hash = tx.sponsor_hash(&Address::from(1), 5);
+        tx.data = data;
+        assert_eq!(tx.payload(), &[1, 2, 3]);
+        // The sponsor signs the payload, the sender and its nonce.
+        assert_eq!(tx.sponsor_hash(&Address::from(1), 5), hash);
+        assert_ne!(tx.sponsor_hash(&Address::from(2), 5), hash);
+        assert_ne!(tx.sponsor_hash(&Address::from(1), 6), hash);
+
+        // Earlier versions run with the whole data.
+        tx.version = ENVELOPE_TX_VERSION - 1;
+        assert!(tx.sponsored_data().is_none());
+        assert_eq!(tx.payload(), &tx.data[..]);
+
+        assert!(split_sponsored_data(SPONSORED_DATA_PREFIX).is_none());
+    }
+
+    #[test]
+    fn test_multisig_data() {
+        let mut tx = Transaction::default();
+        tx.version = ENVELOPE_TX_VERSION;
+        tx.data = vec![1, 2, 3];
+        let hash = tx.multisig_hash(&Address::from(9), 5);
+        // Not the same message as a sponsor signs, nor for another nonce.
+        assert_ne!(hash, tx.sponsor_hash(&Address::from(9), 5));
+        assert_ne!(hash, tx.multisig_hash(&Address::from(9), 6));
+
+        let mut data = MULTISIG_DATA_PREFIX.to_vec();
+        data.extend_from_slice(&Address::from(9));
+        data.extend_from_slice(&5u64.to_be_bytes());
+        data.push(2);
+        data.extend_from_slice(&[7; SIGNATURE_BYTES_LEN]);
+        data.extend_from_slice(&[8; SIGNATURE_BYTES_LEN]);
+        data.extend_from_slice(&[1, 2, 3]);
+        let (account, nonce, signatures, payload) = split_multisig_data(&data).unwrap();
+        assert_eq!(account, Address::from(9));
+        assert_eq!(nonce, 5);
+        assert_eq!(
+            signatures,
+            vec![&[7; SIGNATURE_BYTES_LEN][..], &[8; SIGNATURE_BYTES_LEN][..]]
+        );
+        assert_eq!(payload, &[1, 2, 3]);
+
+        tx.data = data;
+        assert_eq!(tx.payload(), &[1, 2, 3]);
+        assert_eq!(tx.multisig_hash(&Address::from(9), 5), hash);
+
+        // Earlier versions run with the whole data.
+        tx.version = ENVELOPE_TX_VERSION - 1;
+        assert!(tx.multisig_data().is_none());
+        assert_eq!(tx.payload(), &tx.data[..]);
+
+        // Fewer signatures than counted.
+        tx.data
+            .truncate(MULTISIG_DATA_PREFIX.len() + 29 + SIGNATURE_BYTES_LEN);
+        assert!(split_multisig_data(&tx.data).is_none());
+    }
+
+    #[test]
+    fn test_ethereum_transaction() {
+        // The example of EIP-155
+        let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+            .from_hex::<Vec<u8>>()
+            .unwrap();
+        let utx = ethereum_unverified_transaction(&raw, ENVELOPE_TX_VERSION, 99).unwrap();
+        let signature = utx.get_signature();
+        assert_eq!(
+            signature[..32],
+            "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276"
+                .from_hex::<Vec<u8>>()
+                .unwrap()[..]
+        );
+        assert_eq!(
+            signature[32..64],
+            "67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+                .from_hex::<Vec<u8>>()
+                .unwrap()[..]
+        );
+        assert_eq!(signature[64], 0);
+
+        let tx = Transaction::create(utx.get_transaction()).unwrap();
+        assert_eq!(tx.nonce, "9");
+        assert_eq!(tx.gas, U256::from(21_000));
+        assert_eq!(tx.action, Action::Call(Address::from([0x35; 20])));
+        assert_eq!(tx.value, U256::from(1_000_000_000_000_000_000u64));
+        assert_eq!(tx.block_limit, 99);
+        assert_eq!(tx.chain_id, U256::from(1));
+        assert_eq!(
+            tx.ethereum_data(),
+            Some((9, U256::from(20_000_000_000u64), &[][..]))
+        );
+        assert!(tx.payload().is_empty());
+        assert_eq!(
+            tx.ethereum_hash(),
+            Some(H256::from(
+                "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+            ))
+        );
+
+        // Earlier versions carry no envelope.
+        assert!(ethereum_unverified_transaction(&raw, ENVELOPE_TX_VERSION - 1, 99).is_err());
+        let mut tx = tx;
+        tx.version = ENVELOPE_TX_VERSION - 1;
+        assert_eq!(tx.ethereum_hash(), None);
+
+        // Signatures without a chain id are rejected.
+        let mut s = RlpStream::new_list(9);
+        s.append(&9u8);
+        s.append(&U256::from(20_000_000_000u64));
+        s.append(&21_000u64);
+        s.append(&Address::from([0x35; 20]));
+        s.append(&U256::from(1_000_000_000_000_000_000u64));
+        s.append_empty_data();
+        s.append(&27u8);
+        s.append(&U256::from(1));
+        s.append(&U256::from(1));
+        assert_eq!(
+            ethereum_unverified_transaction(&s.out(), ENVELOPE_TX_VERSION, 99),
+            Err(Error::InvalidSignature)
+        );
+        assert!(ethereum_unverified_transaction(&raw[1..], ENVELOPE_TX_VERSION, 99).is_err());
+    }
+
     #[test]
     fn invalid_value() {
         let mut plain_transaction = ProtoTransaction::new();
@@ -12,76 +12,105 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 use crate::types::transaction::{Action, SignedTransaction};
 use cita_types::{Address, H160};
 
+use crate::contracts::precompiled;
 use crate::contracts::solc::{permission_management::contains_resource, Resource};
 use crate::libexecutor::sys_config::CheckOptions;
 use crate::types::errors::AuthenticationError;
 use crate::types::reserved_addresses;
 
-/// Check the sender's permission
+/// Check `account` and the contract called by `t` are not frozen. The frozen
+/// accounts are checked whatever the options are, even for the super admin.
+#[allow(unknown_lints, clippy::implicit_hasher)] // TODO clippy
+pub fn check_frozen(
+    frozen_accounts: &HashSet<Address>,
+    account: &Address,
+    t: &SignedTransaction,
+) -> Result<(), AuthenticationError> {
+    // It's eth_call when the account is zero.
+    if *account == Address::zero() {
+        return Ok(());
+    }
+
+    if frozen_accounts.contains(account) {
+        return Err(AuthenticationError::NoTransactionPermission);
+    }
+    if let Action::Call(ref address) = t.action {
+        if frozen_accounts.contains(address) {
+            return Err(AuthenticationError::NoCallPermission);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the permission of `account` to send `t`: the sender of it, or the
+/// sponsor paying its quota.
 #[allow(unknown_lints, clippy::implicit_hasher)] // TODO clippy
 pub fn check_permission(
     group_accounts: &HashMap<Address, Vec<Address>>,
     account_permissions: &HashMap<Address, Vec<Resource>>,
+    account: &Address,
     t: &SignedTransaction,
     options: CheckOptions,
 ) -> Result<(), AuthenticationError> {
-    let sender = *t.sender();
     // It's eth_call when the account is zero.
     // No need to check the options in case that the option is true.
-    if sender == Address::zero() {
+    if *account == Address::zero() {
         return Ok(());
     }
 
     if options.send_tx_permission {
-        check_send_tx(group_accounts, account_permissions, &sender)?;
+        check_send_tx(group_accounts, account_permissions, account)?;
     }
 
     match t.action {
         Action::Create => {
             if options.create_contract_permission {
-                check_create_contract(group_accounts, account_permissions, &sender)?;
+                check_create_contract(group_accounts, account_permissions, account)?;
             }
         }
         Action::Call(address) => {
             if options.call_permission {
                 let group_management_addr =
                     Address::from_str(reserved_addresses::GROUP_MANAGEMENT).unwrap();
-                trace!("t.data {:?}", t.data);
+                let data = t.payload();
+                trace!("t.data {:?}", data);
 
-                if t.data.is_empty() {
+                if data.is_empty() {
                     // Transfer transaction, no function call
                     return Ok(());
                 }
 
-                if t.data.len() < 4 {
+                if data.len() < 4 {
                     return Err(AuthenticationError::InvalidTransaction);
                 }
 
                 if address == group_management_addr {
-                    if t.data.len() < 36 {
+                    if data.len() < 36 {
                         return Err(AuthenticationError::InvalidTransaction);
                     }
                     check_origin_group(
                         account_permissions,
-                        &sender,
+                        account,
                         &address,
-                        &t.data[0..4],
-                        &H160::from(&t.data[16..36]),
+                        &data[0..4],
+                        &H160::from(&data[16..36]),
                     )?;
                 }
 
                 check_call_contract(
                     group_accounts,
                     account_permissions,
-                    &sender,
+                    account,
                     &address,
-                    &t.data[0..4],
+                    &data[0..4],
                 )?;
             }
         }
@@ -91,6 +120,141 @@ pub fn check_permission(
     Ok(())
 }
 
+/// The permissions of the internal calls.
+#[derive(Debug)]
+struct Permissions {
+    group_accounts: HashMap<Address, Vec<Address>>,
+    account_permissions: HashMap<Address, Vec<Resource>>,
+}
+
+/// Call permission guard for the internal calls of a transaction.
+///
+/// `check_permission` and `check_frozen` only see the top-level call. The guard
+/// is carried by the VM store and every CALL/CALLCODE/DELEGATECALL/STATICCALL frame is
+/// checked by it, so a contract can't be used as a proxy to call a frozen
+/// account. When the internal call permission check is enabled, the frames
+/// are checked against the permissions of the transaction origin as well.
+#[derive(Debug)]
+pub struct CallPermissionGuard {
+    origin: Address,
+    frozen_accounts: HashSet<Address>,
+    // None when the internal call permission needn't to be checked.
+    permissions: Option<Permissions>,
+    denied: Cell<Option<Address>>,
+}
+
+impl CallPermissionGuard {
+    pub fn new(origin: Address, frozen_accounts: HashSet<Address>) -> Self {
+        CallPermissionGuard {
+            origin,
+            frozen_accounts,
+            permissions: None,
+            denied: Cell::new(None),
+        }
+    }
+
+    /// Check the internal calls against the permissions of the origin too.
+    pub fn with_permissions(
+        mut self,
+        group_accounts: HashMap<Address, Vec<Address>>,
+        account_permissions: HashMap<Address, Vec<Resource>>,
+    ) -> Self {
+        self.permissions = Some(Permissions {
+            group_accounts,
+            account_permissions,
+        });
+        self
+    }
+
+    /// Check the origin could call `cont` with `data` from the frame of `caller`.
+    /// The first refused contract is recorded and could be got by `denied`.
+    pub fn check(&self, caller: &Address, cont: &Address, data: &[u8]) -> bool {
+        let has_permission = if self.frozen_accounts.contains(cont) {
+            false
+        } else if let Some(ref permissions) = self.permissions {
+            // System contracts call each other internally, and builtin
+            // contracts are not resources of the permission system.
+            // Transfer has no function call.
+            is_system_caller(caller)
+                || is_builtin_contract(cont)
+                || data.is_empty()
+                || (data.len() >= 4
+                    && has_resource(
+                        &permissions.group_accounts,
+                        &permissions.account_permissions,
+                        &self.origin,
+                        cont,
+                        &data[0..4],
+                    ))
+        } else {
+            true
+        };
+        trace!(
+            "origin {:?} has internal call permission of {:?}: {:?}",
+            self.origin,
+            cont,
+            has_permission
+        );
+
+        if !has_permission && self.denied.get().is_none() {
+            self.denied.set(Some(*cont));
+        }
+        has_permission
+    }
+
+    /// The first contract refused to the origin, if any.
+    pub fn denied(&self) -> Option<Address> {
+        self.denied.get()
+    }
+}
+
+lazy_static! {
+    // BatchTx is left out: it forwards any call of its sender.
+    static ref SYSTEM_CALLERS: [Address; 19] = [
+        Address::from_str(reserved_addresses::SYS_CONFIG).unwrap(),
+        Address::from_str(reserved_addresses::NODE_MANAGER).unwrap(),
+        Address::from_str(reserved_addresses::CHAIN_MANAGER).unwrap(),
+        Address::from_str(reserved_addresses::QUOTA_MANAGER).unwrap(),
+        Address::from_str(reserved_addresses::PERMISSION_MANAGEMENT).unwrap(),
+        Address::from_str(reserved_addresses::PERMISSION_CREATOR).unwrap(),
+        Address::from_str(reserved_addresses::AUTHORIZATION).unwrap(),
+        Address::from_str(reserved_addresses::ROLE_MANAGEMENT).unwrap(),
+        Address::from_str(reserved_addresses::ROLE_CREATOR).unwrap(),
+        Address::from_str(reserved_addresses::GROUP).unwrap(),
+        Address::from_str(reserved_addresses::GROUP_MANAGEMENT).unwrap(),
+        Address::from_str(reserved_addresses::GROUP_CREATOR).unwrap(),
+        Address::from_str(reserved_addresses::ADMIN).unwrap(),
+        Address::from_str(reserved_addresses::ROLE_AUTH).unwrap(),
+        Address::from_str(reserved_addresses::EMERGENCY_INTERVENTION).unwrap(),
+        Address::from_str(reserved_addresses::PRICE_MANAGEMENT).unwrap(),
+        Address::from_str(reserved_addresses::VERSION_MANAGEMENT).unwrap(),
+        Address::from_str(reserved_addresses::ALL_GROUPS).unwrap(),
+        Address::from_str(reserved_addresses::AUTO_EXEC).unwrap(),
+    ];
+}
+
+/// Whether the address is a system contract whose internal calls needn't to
+/// be checked: it only calls the other system contracts, or the contracts set
+/// by the admin, and never forwards the calls of its sender.
+fn is_system_caller(address: &Address) -> bool {
+    SYSTEM_CALLERS.contains(address)
+}
+
+lazy_static! {
+    static ref ETHEREUM_BUILTINS: [Address; 4] = [
+        Address::from_str(reserved_addresses::ECRECOVER_ADDRESS).unwrap(),
+        Address::from_str(reserved_addresses::SHA256_ADDRESS).unwrap(),
+        Address::from_str(reserved_addresses::RIPEMD160_ADDRESS).unwrap(),
+        Address::from_str(reserved_addresses::IDENTITY_ADDRESS).unwrap(),
+    ];
+}
+
+/// Whether the address is one of the builtin contracts: the Ethereum builtins
+/// ecrecover, sha256, ripemd160 and identity, or a CITA pre-compiled contract.
+fn is_builtin_contract(address: &Address) -> bool {
+    ETHEREUM_BUILTINS.contains(address) || precompiled::is_reserved(address)
+}
+
 /// Check permission: send transaction
 fn check_send_tx(
     group_accounts: &HashMap<Address, Vec<Address>>,
@@ -218,3 +382,129 @@ fn get_groups(group_accounts: &HashMap<Address, Vec<Address>>, account: &Address
 
     groups
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CallPermissionGuard;
+    use crate::contracts::solc::Resource;
+    use crate::types::reserved_addresses;
+    use cita_types::Address;
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_call_permission_guard() {
+        let origin = Address::from_str("1000000000000000000000000000000000000100").unwrap();
+        let proxy = Address::from_str("1000000000000000000000000000000000000200").unwrap();
+        let target = Address::from_str("1000000000000000000000000000000000000300").unwrap();
+        let group = Address::from_str("1000000000000000000000000000000000000400").unwrap();
+        let func = vec![0xaa, 0xbb, 0xcc, 0xdd];
+
+        let mut account_permissions = HashMap::new();
+        account_permissions.insert(
+            group,
+            vec![Resource {
+                cont: target,
+                func: func.clone(),
+            }],
+        );
+        let mut group_accounts = HashMap::new();
+        group_accounts.insert(group, vec![origin]);
+
+        let guard = CallPermissionGuard::new(origin, HashSet::new())
+            .with_permissions(group_accounts, account_permissions);
+
+        // Permission granted by group
+        assert!(guard.check(&proxy, &target, &func));
+        // Transfer
+        assert!(guard.check(&proxy, &proxy, &[]));
+        // Builtin contracts
+        assert!(guard.check(&proxy, &Address::from(1), &func));
+        assert!(guard.check(&proxy, &Address::from(4), &func));
+        let sm3 = Address::from_str(reserved_addresses::SM3_ADDRESS).unwrap();
+        assert!(guard.check(&proxy, &sm3, &func));
+        // Called by system contract
+        let sys_config = Address::from_str("ffffffffffffffffffffffffffffffffff020000").unwrap();
+        assert!(guard.check(&sys_config, &proxy, &func));
+        assert_eq!(guard.denied(), None);
+
+        // No permission
+        assert!(!guard.check(&target, &proxy, &func));
+        assert!(!guard.check(&proxy, &target, &[0xaa, 0xbb, 0xcc, 0x00]));
+        assert_eq!(guard.denied(), Some(proxy));
+
+        // Low addresses besides the builtins are ordinary accounts
+        let low = CallPermissionGuard::new(origin, HashSet::new())
+            .with_permissions(HashMap::new(), HashMap::new());
+        assert!(!low.check(&proxy, &Address::from(5), &func));
+        assert!(!low.check(&proxy, &Address::from(0x200), &func));
+        assert_eq!(low.denied(), Some(Address::from(5)));
+    }
+
+    #[test]
+    fn test_call_permission_guard_frozen() {
+        let origin = Address::from_str("1000000000000000000000000000000000000100").unwrap();
+        let proxy = Address::from_str("1000000000000000000000000000000000000200").unwrap();
+        let frozen = Address::from_str("1000000000000000000000000000000000000300").unwrap();
+        let func = vec![0xaa, 0xbb, 0xcc, 0xdd];
+        let mut frozen_accounts = HashSet::new();
+        frozen_accounts.insert(frozen);
+
+        // Without the permissions, only the frozen accounts are refused.
+        let guard = CallPermissionGuard::new(origin, frozen_accounts.clone());
+        assert!(guard.check(&origin, &proxy, &func));
+        assert!(!guard.check(&proxy, &frozen, &[]));
+        assert_eq!(guard.denied(), Some(frozen));
+
+        // The frozen accounts are refused even to the system contracts.
+        let mut account_permissions = HashMap::new();
+        account_permissions.insert(
+            origin,
+            vec![Resource {
+                cont: frozen,
+                func: func.clone(),
+            }],
+        );
+        let guard = CallPermissionGuard::new(origin, frozen_accounts)
+            .with_permissions(HashMap::new(), account_permissions);
+        let sys_config = Address::from_str("ffffffffffffffffffffffffffffffffff020000").unwrap();
+        assert!(!guard.check(&sys_config, &frozen, &func));
+        assert!(!guard.check(&proxy, &frozen, &func));
+        assert_eq!(guard.denied(), Some(frozen));
+    }
+
+    #[test]
+    fn test_call_permission_guard_batch_tx() {
+        use crate::contracts::tools::method;
+
+        let origin = Address::from_str("1000000000000000000000000000000000000100").unwrap();
+        let target = Address::from_str("1000000000000000000000000000000000000300").unwrap();
+        let batch_tx = Address::from_str(reserved_addresses::BATCH_TX).unwrap();
+        let multi_txs = method::encode_to_vec(b"multiTxs(bytes)");
+        let allowed = vec![0xaa, 0xbb, 0xcc, 0xdd];
+        let forbidden = vec![0xaa, 0xbb, 0xcc, 0x00];
+
+        let mut account_permissions = HashMap::new();
+        account_permissions.insert(
+            origin,
+            vec![
+                Resource {
+                    cont: batch_tx,
+                    func: multi_txs.clone(),
+                },
+                Resource {
+                    cont: target,
+                    func: allowed.clone(),
+                },
+            ],
+        );
+        let guard = CallPermissionGuard::new(origin, HashSet::new())
+            .with_permissions(HashMap::new(), account_permissions);
+
+        // The calls forwarded by BatchTx are checked like any other.
+        assert!(guard.check(&origin, &batch_tx, &multi_txs));
+        assert!(guard.check(&batch_tx, &target, &allowed));
+        assert!(!guard.check(&batch_tx, &target, &forbidden));
+        assert_eq!(guard.denied(), Some(target));
+    }
+}
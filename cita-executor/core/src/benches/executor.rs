@@ -13,11 +13,15 @@
 // limitations under the License.
 
 use cita_crypto::{CreateKey, KeyPair};
-use cita_types::Address;
+use cita_executive::create_address_from_address_and_nonce;
+use cita_types::{Address, U256};
 use libexecutor::block::OpenBlock;
 use libexecutor::command::Commander;
 use libexecutor::executor::Executor;
 use libexecutor::fsm::FSM;
+use libexecutor::state_cache::StateCache;
+use parking_lot::Mutex;
+use std::sync::Arc;
 use test::Bencher;
 use tests::helpers;
 
@@ -28,6 +32,22 @@ fn generate_block(executor: &Executor, txs: u32) -> OpenBlock {
     helpers::create_block(&executor, Address::from(0), &data, (0, txs), &privkey)
 }
 
+// Deploy a contract, then generate a block whose transactions all read its storage.
+fn generate_reading_block(executor: &mut Executor, txs: u32) -> OpenBlock {
+    let keypair = KeyPair::gen_keypair();
+    let privkey = keypair.privkey();
+    let data = helpers::generate_contract();
+    let block = helpers::create_block(&executor, Address::from(0), &data, (0, 1), &privkey);
+    let closed_block = executor.into_fsm(block);
+    executor.grow(&closed_block);
+    executor.keep_state_cache(closed_block);
+
+    let contract = create_address_from_address_and_nonce(&keypair.address(), &U256::zero());
+    // get()
+    let data = vec![0x6d, 0x4c, 0xe6, 0x3c];
+    helpers::create_block(&executor, contract, &data, (1, txs + 1), &privkey)
+}
+
 #[bench]
 fn test_block_with_10000_tx(b: &mut Bencher) {
     // One block with 10000 tx bench test takes 271.51ms
@@ -73,3 +93,24 @@ fn test_block_with_10000_tx_write_db(b: &mut Bencher) {
         closed_block.clear_cache();
     });
 }
+
+#[bench]
+fn test_block_with_10000_storage_reads(b: &mut Bencher) {
+    let mut executor = helpers::init_executor();
+    let block = generate_reading_block(&mut executor, 10000);
+
+    b.iter(|| {
+        executor.into_fsm(block.clone());
+    });
+}
+
+#[bench]
+fn test_block_with_10000_storage_reads_without_state_cache(b: &mut Bencher) {
+    let mut executor = helpers::init_executor();
+    executor.state_cache = Arc::new(Mutex::new(StateCache::new(0)));
+    let block = generate_reading_block(&mut executor, 10000);
+
+    b.iter(|| {
+        executor.into_fsm(block.clone());
+    });
+}
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::data_provider::{BlockDataProvider, DataProvider, Store as VMSubState};
+use crate::data_provider::{
+    AccessList, BlockDataProvider, DataProvider, Store as VMSubState, WrittenAccounts,
+};
 use cita_trie::DB;
 use cita_types::{Address, H160, H256, U256, U512};
 use cita_vm::{
@@ -25,20 +27,27 @@ use cita_vm::{
 };
 use rlp::RlpStream;
 use std::cell::RefCell;
+use std::str::FromStr;
 use std::sync::Arc;
 use types::Bytes;
+use util::sha3;
 
-use crate::authentication::check_permission;
+use crate::authentication::{check_frozen, check_permission, CallPermissionGuard};
 use crate::cita_vm_helper::{call_pure, get_interpreter_conf};
 use crate::contracts::native::factory::Factory as NativeFactory;
+use crate::contracts::native::multisig::Multisig;
+use crate::contracts::native::MULTISIG;
 use crate::exception::ExecutedException;
 use crate::libexecutor::economical_model::EconomicalModel;
+use crate::libexecutor::fee_distribution::FeeReport;
+use crate::libexecutor::state_cache::BlockStateCache;
 use crate::libexecutor::sys_config::BlockSysConfig;
 use crate::tx_gas_schedule::TxGasSchedule;
 use crate::types::context::Context;
 use crate::types::errors::AuthenticationError;
 use crate::types::errors::ExecutionError;
 use crate::types::log::Log;
+use crate::types::reserved_addresses;
 use crate::types::transaction::{Action, SignedTransaction};
 use ethbloom::{Bloom, Input as BloomInput};
 
@@ -62,6 +71,10 @@ pub struct CitaExecutive<'a, B> {
     state_provider: Arc<RefCell<State<B>>>,
     context: &'a Context,
     economical_model: EconomicalModel,
+    native_factory: Arc<NativeFactory>,
+    access_recorder: Option<Arc<RefCell<AccessList>>>,
+    write_recorder: Option<Arc<RefCell<WrittenAccounts>>>,
+    state_cache: Option<BlockStateCache>,
 }
 
 impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
@@ -70,23 +83,79 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
         state: Arc<RefCell<State<B>>>,
         context: &'a Context,
         economical_model: EconomicalModel,
+        native_factory: Arc<NativeFactory>,
     ) -> Self {
         Self {
             block_provider,
             state_provider: state,
             context,
             economical_model,
+            native_factory,
+            access_recorder: None,
+            write_recorder: None,
+            state_cache: None,
         }
     }
 
+    /// Record the accounts and storage keys accessed by the executed transactions.
+    pub fn with_access_recorder(mut self, recorder: Arc<RefCell<AccessList>>) -> Self {
+        self.access_recorder = Some(recorder);
+        self
+    }
+
+    /// Record the accounts whose storage or code are written by the executed transactions.
+    pub fn with_write_recorder(mut self, recorder: Arc<RefCell<WrittenAccounts>>) -> Self {
+        self.write_recorder = Some(recorder);
+        self
+    }
+
+    /// Read the storage of the accounts not written by the block through the state cache.
+    pub fn with_state_cache(mut self, cache: BlockStateCache) -> Self {
+        self.state_cache = Some(cache);
+        self
+    }
+
     pub fn exec(
         &mut self,
         t: &SignedTransaction,
         conf: &BlockSysConfig,
     ) -> Result<ExecutedResult, ExecutionError> {
-        let sender = *t.sender();
+        // A multisig transaction is sent by the account its signers control.
+        let sender = match t
+            .multisig()
+            .map_err(|_| ExecutionError::InvalidTransaction)?
+        {
+            Some((account, multisig_nonce, signers)) => {
+                self.check_multisig(&account, multisig_nonce, &signers)?;
+                account
+            }
+            None => *t.sender(),
+        };
         let nonce = self.state_provider.borrow_mut().nonce(&sender)?;
         trace!("transaction sender: {:?}, nonce: {:?}", sender, nonce);
+
+        // The sponsor of a sponsored transaction pays its quota, only for the
+        // sender nonce it signed.
+        let sponsor = match t
+            .sponsor()
+            .map_err(|_| ExecutionError::InvalidTransaction)?
+        {
+            Some((sponsor, sponsored_nonce)) => {
+                if U256::from(sponsored_nonce) != nonce {
+                    return Err(ExecutionError::InvalidNonce);
+                }
+                Some(sponsor)
+            }
+            None => None,
+        };
+        // An Ethereum transaction is signed for the account nonce of the
+        // sender, so it is executed only once.
+        if let Some((ethereum_nonce, _, _)) = t.ethereum_data() {
+            if U256::from(ethereum_nonce) != nonce {
+                return Err(ExecutionError::InvalidNonce);
+            }
+        }
+        let payer = sponsor.unwrap_or(sender);
         self.state_provider.borrow_mut().inc_nonce(&sender)?;
 
         trace!(
@@ -94,14 +163,29 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             (*conf).check_options.call_permission
         );
 
-        if conf.super_admin_account.is_none() || sender != conf.super_admin_account.unwrap() {
+        check_frozen(&conf.frozen_accounts, &sender, t)?;
+        let is_super_admin = conf.super_admin_account == Some(sender);
+        if !is_super_admin {
             check_permission(
                 &conf.group_accounts,
                 &conf.account_permissions,
+                &sender,
                 t,
                 conf.check_options,
             )?;
         }
+        if let Some(ref sponsor) = sponsor {
+            check_frozen(&conf.frozen_accounts, sponsor, t)?;
+            if conf.super_admin_account != Some(*sponsor) {
+                check_permission(
+                    &conf.group_accounts,
+                    &conf.account_permissions,
+                    sponsor,
+                    t,
+                    conf.check_options,
+                )?;
+            }
+        }
 
         let tx_gas_schedule = TxGasSchedule::default();
         let base_gas_required = match t.action {
@@ -109,6 +193,10 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             _ => tx_gas_schedule.tx_gas,
         } + match t.version {
             0..=2 => 0,
+            // The sender of an Ethereum transaction signed the payload only.
+            _ if t.ethereum_data().is_some() => {
+                t.payload().len() * tx_gas_schedule.tx_data_non_zero_gas
+            }
             _ => t.data.len() * tx_gas_schedule.tx_data_non_zero_gas,
         };
         if sender != Address::zero() && t.gas < U256::from(base_gas_required) {
@@ -121,24 +209,63 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             return Err(ExecutionError::NotEnoughBaseGas);
         }
 
-        if t.action == Action::AbiStore && !self.transact_set_abi(&t.data) {
+        if t.action == Action::AbiStore && !self.transact_set_abi(t.payload()) {
             return Err(ExecutionError::InvalidTransaction);
         }
 
         // Prepaid t.gas for the transaction.
-        self.prepaid(t.sender(), t.gas, t.gas_price, t.value)?;
+        self.prepaid(&sender, &payer, t.gas, t.gas_price, t.value)?;
         let init_gas = t.gas - U256::from(base_gas_required);
 
         let mut store = VMSubState::default();
         store.evm_context = build_evm_context(&self.context.clone());
         store.evm_cfg = get_interpreter_conf();
+        // Only contract code could make internal calls.
+        // It's eth_call when the sender is zero, same as `check_permission`.
+        let run_code = match t.action {
+            Action::Create | Action::Call(_) => true,
+            _ => false,
+        };
+        let check_internal_permission = conf.check_options.call_permission
+            && conf.check_options.internal_call_permission
+            && !is_super_admin;
+        if run_code
+            && sender != Address::zero()
+            && (check_internal_permission || !conf.frozen_accounts.is_empty())
+        {
+            // The frozen accounts are refused to the internal calls of all.
+            let mut guard = CallPermissionGuard::new(sender, conf.frozen_accounts.clone());
+            if check_internal_permission {
+                guard = guard.with_permissions(
+                    conf.group_accounts.clone(),
+                    conf.account_permissions.clone(),
+                );
+            }
+            store.call_permission_guard = Some(Arc::new(guard));
+            // Backup used in case of an internal call is refused.
+            self.state_provider.borrow_mut().checkpoint();
+        }
+        let call_guarded = store.call_permission_guard.is_some();
+        store.access_recorder = self.access_recorder.clone();
+        store.write_recorder = self.write_recorder.clone();
+        store.native_factory = self.native_factory.clone();
+        store.state_cache = self.state_cache.clone();
+        store.record_account(&sender);
+        store.record_account(&payer);
+        if let Action::Call(ref address) = t.action {
+            store.record_account(address);
+        }
+        if self.payment_required() {
+            store.record_account(&store.evm_context.coinbase);
+        }
         let store = Arc::new(RefCell::new(store));
 
         let result = match t.action {
             Action::Store | Action::AbiStore => {
                 // Maybe use tx_gas_schedule.tx_data_non_zero_gas for each byte store, it is more reasonable.
                 // But for the data compatible, just let it as tx_gas_schedule.create_data_gas for now.
-                let store_gas_used = U256::from(t.data.len() * tx_gas_schedule.create_data_gas);
+                let store_gas_used =
+                    U256::from(t.payload().len() * tx_gas_schedule.create_data_gas);
                 if let Some(gas_left) = init_gas.checked_sub(store_gas_used) {
                     Ok(InterpreterResult::Normal(vec![], gas_left.as_u64(), vec![]))
                 } else {
@@ -157,7 +284,7 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                     gas_price: t.gas_price(),
                     value: t.value,
                     nonce,
-                    data: Some(t.data.clone()),
+                    data: Some(t.payload().to_vec()),
                 };
 
                 let mut vm_exec_params = build_vm_exec_params(&params, self.state_provider.clone());
@@ -176,7 +303,7 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             Action::AmendData => {
                 trace!("amend action, conf admin {:?}", conf.super_admin_account);
                 if let Some(admin) = conf.super_admin_account {
-                    if *t.sender() != admin {
+                    if sender != admin {
                         return Err(ExecutionError::Authentication(
                             AuthenticationError::NoTransactionPermission,
                         ));
@@ -190,7 +317,7 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                 // Backup used in case of running error
                 self.state_provider.borrow_mut().checkpoint();
 
-                match self.call_amend_data(t.value, Some(t.data.clone())) {
+                match self.call_amend_data(t.value, Some(t.payload().to_vec())) {
                     Ok(Some(val)) => {
                         // Discard the checkpoint because of amend data ok.
                         self.state_provider.borrow_mut().discard_checkpoint();
@@ -221,7 +348,7 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                     gas_price: t.gas_price(),
                     value: t.value,
                     nonce,
-                    data: Some(t.data.clone()),
+                    data: Some(t.payload().to_vec()),
                 };
                 let mut vm_exec_params = build_vm_exec_params(&params, self.state_provider.clone());
                 if !self.payment_required() {
@@ -236,18 +363,59 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             }
         };
 
-        let mut finalize_result = self.finalize(result, store, t.gas, sender, t.gas_price());
+        let denied = store
+            .borrow()
+            .call_permission_guard
+            .as_ref()
+            .and_then(|guard| guard.denied());
+        let result = if call_guarded {
+            if let Some(address) = denied {
+                // The contract may ignore the failure of the internal call,
+                // so revert the whole transaction here.
+                trace!("internal call to {:?} refused, revert transaction", address);
+                self.state_provider.borrow_mut().revert_checkpoint();
+                Err(VMError::Evm(EVMError::CallError))
+            } else {
+                self.state_provider.borrow_mut().discard_checkpoint();
+                result
+            }
+        } else {
+            result
+        };
+
+        let mut finalize_result =
+            self.finalize(result, store, t.gas, sender, payer, t.gas_price(), conf);
+        if denied.is_some() {
+            finalize_result.exception = Some(ExecutedException::Authentication(
+                AuthenticationError::NoCallPermission,
+            ));
+        }
+        if let (Some(sponsor), true, None) =
+            (sponsor, self.payment_required(), &finalize_result.exception)
+        {
+            let log = sponsored_log(sponsor, sender, t.gas_price() * finalize_result.quota_used);
+            accrue_log(&mut finalize_result.logs_bloom, &log);
+            finalize_result.logs.push(log);
+        }
+        if let (Some(recorder), Some(address)) =
+            (&self.access_recorder, finalize_result.contract_address)
+        {
+            recorder.borrow_mut().entry(address).or_default();
+        }
         finalize_result.account_nonce = nonce;
         Ok(finalize_result)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn finalize(
         &mut self,
         result: Result<InterpreterResult, VMError>,
         store: Arc<RefCell<VMSubState>>,
         gas_limit: U256,
         sender: Address,
+        payer: Address,
         gas_price: U256,
+        conf: &BlockSysConfig,
     ) -> ExecutedResult {
         let mut finalize_result = ExecutedResult::default();
 
@@ -256,16 +424,20 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                 let refund = get_refund(store.clone(), sender, gas_limit.as_u64(), gas_left);
                 let gas_left = gas_left + refund;
                 if self.payment_required() {
-                    if let Err(e) = liquidtion(
+                    match liquidtion(
                         self.state_provider.clone(),
                         store.clone(),
-                        sender,
+                        payer,
                         gas_price,
                         gas_limit.as_u64(),
                         gas_left,
+                        conf,
                     ) {
-                        finalize_result.exception = Some(ExecutedException::VM(e));
-                        return finalize_result;
+                        Ok(fee_report) => finalize_result.fee_report = fee_report,
+                        Err(e) => {
+                            finalize_result.exception = Some(ExecutedException::VM(e));
+                            return finalize_result;
+                        }
                     }
                 }
                 // Handle self destruct: Kill it.
@@ -290,16 +462,20 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                 let refund = get_refund(store.clone(), sender, gas_limit.as_u64(), gas_left);
                 let gas_left = gas_left + refund;
                 if self.payment_required() {
-                    if let Err(e) = liquidtion(
+                    match liquidtion(
                         self.state_provider.clone(),
                         store.clone(),
-                        sender,
+                        payer,
                         gas_price,
                         gas_limit.as_u64(),
                         gas_left,
+                        conf,
                     ) {
-                        finalize_result.exception = Some(ExecutedException::VM(e));
-                        return finalize_result;
+                        Ok(fee_report) => finalize_result.fee_report = fee_report,
+                        Err(e) => {
+                            finalize_result.exception = Some(ExecutedException::VM(e));
+                            return finalize_result;
+                        }
                     }
                 }
                 self.state_provider
@@ -318,16 +494,20 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                 let refund = get_refund(store.clone(), sender, gas_limit.as_u64(), gas_left);
                 let gas_left = gas_left + refund;
                 if self.payment_required() {
-                    if let Err(e) = liquidtion(
+                    match liquidtion(
                         self.state_provider.clone(),
                         store.clone(),
-                        sender,
+                        payer,
                         gas_price,
                         gas_limit.as_u64(),
                         gas_left,
+                        conf,
                     ) {
-                        finalize_result.exception = Some(ExecutedException::VM(e));
-                        return finalize_result;
+                        Ok(fee_report) => finalize_result.fee_report = fee_report,
+                        Err(e) => {
+                            finalize_result.exception = Some(ExecutedException::VM(e));
+                            return finalize_result;
+                        }
                     }
                 }
 
@@ -350,16 +530,20 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
             }
             Err(e) => {
                 if self.payment_required() {
-                    if let Err(e) = liquidtion(
+                    match liquidtion(
                         self.state_provider.clone(),
                         store.clone(),
-                        sender,
+                        payer,
                         gas_price,
                         gas_limit.as_u64(),
                         0,
+                        conf,
                     ) {
-                        finalize_result.exception = Some(ExecutedException::VM(e));
-                        return finalize_result;
+                        Ok(fee_report) => finalize_result.fee_report = fee_report,
+                        Err(e) => {
+                            finalize_result.exception = Some(ExecutedException::VM(e));
+                            return finalize_result;
+                        }
                     }
                 }
                 self.state_provider
@@ -375,30 +559,74 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
         finalize_result
     }
 
+    /// Check that `signers` reach the threshold of multisig `account`, and take
+    /// the multisig nonce they signed.
+    fn check_multisig(
+        &self,
+        account: &Address,
+        nonce: u64,
+        signers: &[Address],
+    ) -> Result<(), ExecutionError> {
+        let contract = self
+            .native_factory
+            .active_address(MULTISIG, self.context.block_number)
+            .ok_or(ExecutionError::InvalidTransaction)?;
+        let mut store = VMSubState::default();
+        store.access_recorder = self.access_recorder.clone();
+        store.write_recorder = self.write_recorder.clone();
+        store.native_factory = self.native_factory.clone();
+        store.state_cache = self.state_cache.clone();
+        let mut data_provider = DataProvider::new(
+            self.block_provider.clone(),
+            self.state_provider.clone(),
+            Arc::new(RefCell::new(store)),
+        );
+        let multisig = Multisig::default();
+        match multisig.verify_signers(&data_provider, &contract, account, signers) {
+            Ok(true) => {}
+            _ => {
+                return Err(ExecutionError::Authentication(
+                    AuthenticationError::NoTransactionPermission,
+                ))
+            }
+        }
+        match multisig.use_nonce(&mut data_provider, &contract, account, nonce) {
+            Ok(true) => Ok(()),
+            _ => Err(ExecutionError::InvalidNonce),
+        }
+    }
+
     fn payment_required(&self) -> bool {
         self.economical_model == EconomicalModel::Charge
     }
 
+    /// Charge the quota of a transaction to the payer: the sponsor of it if any,
+    /// the sender otherwise. The sender has to afford the value it transfers.
     fn prepaid(
         &mut self,
         sender: &H160,
+        payer: &H160,
         gas: U256,
         gas_price: U256,
         value: U256,
     ) -> Result<(), ExecutionError> {
         if self.payment_required() {
-            let balance = self.state_provider.borrow_mut().balance(&sender)?;
             let gas_cost = gas.full_mul(gas_price);
-            let total_cost = U512::from(value) + gas_cost;
+            let (sender_cost, payer_cost) = if sender == payer {
+                (U512::from(value) + gas_cost, gas_cost)
+            } else {
+                (U512::from(value), gas_cost)
+            };
 
             // Avoid unaffordable transactions
-            let balance512 = U512::from(balance);
-            if balance512 < total_cost {
+            let sender_balance = U512::from(self.state_provider.borrow_mut().balance(&sender)?);
+            let payer_balance = U512::from(self.state_provider.borrow_mut().balance(&payer)?);
+            if sender_balance < sender_cost || payer_balance < payer_cost {
                 return Err(ExecutionError::NotEnoughBalance);
             }
             self.state_provider
                 .borrow_mut()
-                .sub_balance(&sender, U256::from(gas_cost))?;
+                .sub_balance(&payer, U256::from(gas_cost))?;
         }
         Ok(())
     }
@@ -425,12 +653,19 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
                 .is_ok()
     }
 
+    fn record_write(&self, address: &Address) {
+        if let Some(ref recorder) = self.write_recorder {
+            recorder.borrow_mut().insert(*address);
+        }
+    }
+
     fn transact_set_code(&mut self, data: &[u8]) -> bool {
         if data.len() <= 20 {
             return false;
         }
         let account = H160::from(&data[0..20]);
         let code = &data[20..];
+        self.record_write(&account);
         self.state_provider
             .borrow_mut()
             .set_code(&account, code.to_vec())
@@ -469,6 +704,7 @@ impl<'a, B: DB + 'static> CitaExecutive<'a, B> {
         }
         let loop_num: usize = (len - 20) / (32 * 2);
         let account = H160::from(&data[0..20]);
+        self.record_write(&account);
 
         for i in 0..loop_num {
             let base = 20 + 32 * 2 * i;
@@ -595,6 +831,8 @@ pub fn create<B: DB + 'static>(
         // The right result should be "summary(none)" and "0".
         vec![],
     );
+    // The storage of the address is reset.
+    store.borrow().record_write(&address);
     let mut reqchan = request.clone();
     reqchan.address = address;
     reqchan.receiver = address;
@@ -667,18 +905,30 @@ pub fn call<B: DB + 'static>(
     // Run
     state_provider.borrow_mut().checkpoint();
     let store_son = Arc::new(RefCell::new(store.borrow_mut().clone()));
-    let native_factory = NativeFactory::default();
+    let context = Context::from(store.borrow().evm_context.clone());
     // Check and call Native Contract.
-    if let Some(mut native_contract) = native_factory.new_contract(request.contract.code_address) {
+    // A native contract that is registered but not yet activated fails here
+    // rather than running whatever code is stored at its address.
+    let native_contract = store
+        .borrow()
+        .native_factory
+        .new_contract(request.contract.code_address, context.block_number);
+    let native_contract = match native_contract {
+        Ok(native_contract) => native_contract,
+        Err(e) => {
+            state_provider.borrow_mut().revert_checkpoint();
+            return Err(e.into());
+        }
+    };
+    if let Some(mut native_contract) = native_contract {
         let mut vm_data_provider = DataProvider::new(
             block_provider.clone(),
             state_provider.clone(),
             store.clone(),
         );
-        let context = store.borrow().evm_context.clone();
         match native_contract.exec(
             &VmExecParams::from(request.to_owned()),
-            &Context::from(context),
+            &context,
             &mut vm_data_provider,
         ) {
             Ok(ret) => {
@@ -744,15 +994,18 @@ fn get_refund(
     std::cmp::min(refunds_bound, (gas_limit - gas_left) >> 1)
 }
 
-/// Liquidtion for a transaction.
+/// Liquidtion for a transaction, refunding the quota left to the payer.
+/// Refund the gas left to the payer, and split the fee of the gas used by the
+/// fee distribution of `conf`.
 fn liquidtion<B: DB + 'static>(
     state_provider: Arc<RefCell<State<B>>>,
     store: Arc<RefCell<VMSubState>>,
-    sender: Address,
+    payer: Address,
     gas_price: U256,
     gas_limit: u64,
     gas_left: u64,
-) -> Result<(), VMError> {
+    conf: &BlockSysConfig,
+) -> Result<FeeReport, VMError> {
     trace!(
         "gas_price: {:?}, gas limit:{:?}, gas left: {:?}",
         gas_price,
@@ -761,12 +1014,48 @@ fn liquidtion<B: DB + 'static>(
     );
     state_provider
         .borrow_mut()
-        .add_balance(&sender, gas_price * gas_left)?;
-    state_provider.borrow_mut().add_balance(
-        &store.borrow().evm_context.coinbase,
+        .add_balance(&payer, gas_price * gas_left)?;
+    let coinbase = store.borrow().evm_context.coinbase;
+    let fee_report = conf.fee_distribution.split(
         gas_price * (gas_limit - gas_left),
-    )?;
-    Ok(())
+        &coinbase,
+        &conf.validator_stakes,
+    );
+    if !fee_report.recipients.contains_key(&coinbase) {
+        // Touched even without a share, as before the fee could be split.
+        state_provider
+            .borrow_mut()
+            .add_balance(&coinbase, U256::zero())?;
+    }
+    for (recipient, amount) in &fee_report.recipients {
+        store.borrow().record_account(recipient);
+        state_provider
+            .borrow_mut()
+            .add_balance(recipient, *amount)?;
+    }
+    Ok(fee_report)
+}
+
+lazy_static! {
+    static ref SPONSORED_TOPIC: H256 =
+        H256::from_slice(&sha3::keccak256(b"Sponsored(address,address,uint256)"));
+    static ref SPONSOR_ADDRESS: Address =
+        Address::from_str(reserved_addresses::SPONSOR_ADDRESS).unwrap();
+}
+
+/// Log of a successful sponsored transaction, with the sponsor, the sender and
+/// the fee paid. It is emitted from `SPONSOR_ADDRESS`, which no contract can
+/// emit logs from, so it shows up in the receipt and can't be forged.
+fn sponsored_log(sponsor: Address, sender: Address, fee: U256) -> Log {
+    let mut sponsor_topic = H256::zero();
+    sponsor_topic[12..].copy_from_slice(&sponsor);
+    let mut sender_topic = H256::zero();
+    sender_topic[12..].copy_from_slice(&sender);
+    Log {
+        address: *SPONSOR_ADDRESS,
+        topics: vec![*SPONSORED_TOPIC, sponsor_topic, sender_topic],
+        data: <[u8; 32]>::from(fee).to_vec(),
+    }
 }
 
 fn transform_logs(logs: Vec<EVMLog>) -> Vec<Log> {
@@ -996,17 +1285,22 @@ pub struct ExecutedResult {
 
     /// Transaction output.
     pub output: Bytes,
+
+    /// Where the fee went, only in Charge mode.
+    pub fee_report: FeeReport,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{CitaExecutive, Context, ExecutionError, TxGasSchedule};
+    use super::{CitaExecutive, Context, ExecutionError, NativeFactory, TxGasSchedule};
     use crate::libexecutor::economical_model::EconomicalModel;
     use crate::libexecutor::{block::EVMBlockDataProvider, sys_config::BlockSysConfig};
     use crate::tests::helpers::*;
     use crate::types::transaction::Action;
-    use crate::types::transaction::Transaction;
-    use cita_crypto::{CreateKey, KeyPair};
+    use crate::types::transaction::{
+        SignedTransaction, Transaction, MULTISIG_DATA_PREFIX, SPONSORED_DATA_PREFIX,
+    };
+    use cita_crypto::{CreateKey, KeyPair, Sign, Signature};
     use cita_types::{Address, H256, U256};
     use cita_vm::state::StateObjectInfo;
     use rustc_hex::FromHex;
@@ -1062,6 +1356,7 @@ mod tests {
                 state,
                 &context,
                 EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &BlockSysConfig::default())
         };
@@ -1112,6 +1407,7 @@ mod tests {
                 state.clone(),
                 &context,
                 EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf)
             .unwrap()
@@ -1133,6 +1429,350 @@ mod tests {
         assert_eq!(state.borrow_mut().nonce(&sender).unwrap(), U256::from(1));
     }
 
+    #[test]
+    fn test_sponsored_transfer_for_charge() {
+        use crate::types::reserved_addresses;
+        use crate::types::transaction::ENVELOPE_TX_VERSION;
+
+        let keypair = KeyPair::gen_keypair();
+        let sponsor = KeyPair::gen_keypair();
+        let receiver = Address::from(0x1234);
+        let mut t = Transaction {
+            action: Action::Call(receiver),
+            value: U256::from(17),
+            data: vec![],
+            gas: U256::from(100_000),
+            gas_price: U256::one(),
+            nonce: U256::zero().to_string(),
+            block_limit: 100u64,
+            chain_id: 1.into(),
+            version: ENVELOPE_TX_VERSION,
+        };
+        let signature =
+            Signature::sign(sponsor.privkey(), &t.sponsor_hash(&keypair.address(), 0)).unwrap();
+        t.data = SPONSORED_DATA_PREFIX.to_vec();
+        t.data.extend_from_slice(&0u64.to_be_bytes());
+        t.data.extend_from_slice(&signature.to_vec());
+        let t = t.fake_sign(keypair.address().clone());
+        assert_eq!(t.sponsor(), Ok(Some((sponsor.address().clone(), 0))));
+
+        let mut state = get_temp_state();
+        state.add_balance(t.sender(), U256::from(34)).unwrap();
+        state
+            .add_balance(&sponsor.address(), U256::from(100_000))
+            .unwrap();
+
+        let mut context = Context::default();
+        context.block_quota_limit = U256::from(100_000);
+
+        let state = Arc::new(RefCell::new(state));
+        let exec = |t: &SignedTransaction| {
+            CitaExecutive::new(
+                Arc::new(EVMBlockDataProvider::new(context.clone())),
+                state.clone(),
+                &context,
+                EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
+            )
+            .exec(t, &BlockSysConfig::default())
+        };
+        let executed = exec(&t).unwrap();
+
+        let schedule = TxGasSchedule::default();
+        let quota_used = schedule.tx_gas + t.data.len() * schedule.tx_data_non_zero_gas;
+
+        // The sender only pays the value, and the sponsor pays the quota.
+        assert_eq!(executed.quota_used, U256::from(quota_used));
+        assert_eq!(
+            state.borrow_mut().balance(t.sender()).unwrap(),
+            U256::from(17)
+        );
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+        assert_eq!(
+            state.borrow_mut().balance(&sponsor.address()).unwrap(),
+            U256::from(100_000 - quota_used)
+        );
+        assert_eq!(executed.logs.len(), 1);
+        assert_eq!(
+            executed.logs[0].address,
+            Address::from_str(reserved_addresses::SPONSOR_ADDRESS).unwrap()
+        );
+        assert_eq!(&executed.logs[0].topics[1][12..], &sponsor.address()[..]);
+        assert_eq!(&executed.logs[0].topics[2][12..], &t.sender()[..]);
+
+        // The sponsor paid for the sender nonce 0 only.
+        match exec(&t) {
+            Err(ExecutionError::InvalidNonce) => {}
+            _ => panic!("the sponsor signature should not be replayed"),
+        }
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+    }
+
+    #[test]
+    fn test_ethereum_transfer() {
+        use crate::types::transaction::{ENVELOPE_TX_VERSION, ETHEREUM_DATA_PREFIX};
+
+        let keypair = KeyPair::gen_keypair();
+        let receiver = Address::from(0x1234);
+        let mut data = ETHEREUM_DATA_PREFIX.to_vec();
+        data.extend_from_slice(&0u64.to_be_bytes());
+        data.extend_from_slice(&[0; 32]);
+        let t = Transaction {
+            action: Action::Call(receiver),
+            value: U256::from(17),
+            data,
+            gas: U256::from(21_000),
+            gas_price: U256::zero(),
+            nonce: U256::zero().to_string(),
+            block_limit: 100u64,
+            chain_id: 1.into(),
+            version: ENVELOPE_TX_VERSION,
+        }
+        .fake_sign(keypair.address().clone());
+        assert_eq!(t.ethereum_data(), Some((0, U256::zero(), &[][..])));
+
+        let mut state = get_temp_state();
+        state.add_balance(t.sender(), U256::from(34)).unwrap();
+
+        let mut context = Context::default();
+        context.block_quota_limit = U256::from(100_000);
+        let state = Arc::new(RefCell::new(state));
+        let exec = |t: &SignedTransaction| {
+            CitaExecutive::new(
+                Arc::new(EVMBlockDataProvider::new(context.clone())),
+                state.clone(),
+                &context,
+                EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
+            )
+            .exec(t, &BlockSysConfig::default())
+        };
+        // The envelope is not paid for.
+        let executed = exec(&t).unwrap();
+        assert_eq!(executed.quota_used, U256::from(21_000));
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+        assert_eq!(state.borrow_mut().nonce(t.sender()).unwrap(), U256::one());
+
+        // The transaction is signed for the sender nonce 0 only.
+        match exec(&t) {
+            Err(ExecutionError::InvalidNonce) => {}
+            _ => panic!("the ethereum transaction should not be replayed"),
+        }
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+    }
+
+    #[test]
+    fn test_multisig_transfer_for_charge() {
+        use crate::contracts::native::MULTISIG;
+        use crate::contracts::tools::method;
+        use crate::libexecutor::genesis::NativeSpec;
+        use crate::types::errors::AuthenticationError;
+        use crate::types::reserved_addresses;
+        use crate::types::transaction::ENVELOPE_TX_VERSION;
+
+        let a = KeyPair::gen_keypair();
+        let b = KeyPair::gen_keypair();
+        let receiver = Address::from(0x1234);
+        let mut state = get_temp_state();
+        state
+            .add_balance(&a.address(), U256::from(100_000))
+            .unwrap();
+        let state = Arc::new(RefCell::new(state));
+        let context = Context::default();
+        // Multisig is off by default, it's enabled from genesis.
+        let native_factory = Arc::new(
+            NativeFactory::from_specs(&[NativeSpec {
+                name: MULTISIG.to_owned(),
+                address: Address::from_str(reserved_addresses::NATIVE_MULTISIG).unwrap(),
+                activation: 0,
+            }])
+            .unwrap(),
+        );
+        let exec = |t: &SignedTransaction| {
+            CitaExecutive::new(
+                Arc::new(EVMBlockDataProvider::new(context.clone())),
+                state.clone(),
+                &context,
+                EconomicalModel::Charge,
+                native_factory.clone(),
+            )
+            .exec(t, &BlockSysConfig::default())
+        };
+
+        // A 2-of-2 account.
+        let mut data = method::encode_to_u32(b"createAccount(address[],uint256)")
+            .to_be_bytes()
+            .to_vec();
+        data.extend(ethabi::encode(&[
+            ethabi::Token::Array(vec![
+                ethabi::Token::Address(a.address().into()),
+                ethabi::Token::Address(b.address().into()),
+            ]),
+            ethabi::Token::Uint(U256::from(2).into()),
+        ]));
+        let t = Transaction {
+            action: Action::Call(Address::from_str(reserved_addresses::NATIVE_MULTISIG).unwrap()),
+            value: U256::zero(),
+            data,
+            gas: U256::from(100_000),
+            gas_price: U256::one(),
+            nonce: U256::zero().to_string(),
+            block_limit: 100u64,
+            chain_id: 1.into(),
+            version: 2,
+        }
+        .fake_sign(a.address().clone());
+        let account = Address::from(&exec(&t).unwrap().output[12..]);
+        state
+            .borrow_mut()
+            .add_balance(&account, U256::from(100_017))
+            .unwrap();
+
+        let t = Transaction {
+            action: Action::Call(receiver),
+            value: U256::from(17),
+            data: vec![],
+            gas: U256::from(100_000),
+            gas_price: U256::one(),
+            nonce: U256::zero().to_string(),
+            block_limit: 100u64,
+            chain_id: 1.into(),
+            version: ENVELOPE_TX_VERSION,
+        };
+        let sign = |signers: &[&KeyPair], nonce: u64| {
+            let hash = t.multisig_hash(&account, nonce);
+            let mut data = MULTISIG_DATA_PREFIX.to_vec();
+            data.extend_from_slice(&account);
+            data.extend_from_slice(&nonce.to_be_bytes());
+            data.push(signers.len() as u8);
+            for signer in signers {
+                data.extend_from_slice(&Signature::sign(signer.privkey(), &hash).unwrap().to_vec());
+            }
+            data
+        };
+        let wrap = |data: &[u8], outer: &KeyPair| {
+            let mut t = t.clone();
+            t.data = data.to_vec();
+            t.fake_sign(outer.address().clone())
+        };
+
+        // Below the threshold.
+        match exec(&wrap(&sign(&[&a, &a], 0), &a)) {
+            Err(ExecutionError::Authentication(AuthenticationError::NoTransactionPermission)) => {}
+            _ => panic!("a single signer should not send as the account"),
+        }
+
+        let data = sign(&[&a, &b], 0);
+        let t = wrap(&data, &a);
+        assert_eq!(
+            t.multisig(),
+            Ok(Some((
+                account,
+                0,
+                vec![a.address().clone(), b.address().clone()]
+            )))
+        );
+        let executed = exec(&t).unwrap();
+        let schedule = TxGasSchedule::default();
+        let quota_used = schedule.tx_gas + t.data.len() * schedule.tx_data_non_zero_gas;
+        assert_eq!(executed.quota_used, U256::from(quota_used));
+        assert_eq!(
+            state.borrow_mut().balance(&account).unwrap(),
+            U256::from(100_000 - quota_used)
+        );
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+
+        // The same signatures wrapped by another sender make a new transaction,
+        // but the multisig nonce they signed is taken.
+        let other = KeyPair::gen_keypair();
+        let replayed = wrap(&data, &other);
+        assert_ne!(replayed.sender(), t.sender());
+        match exec(&replayed) {
+            Err(ExecutionError::InvalidNonce) => {}
+            _ => panic!("the signatures should not be replayed"),
+        }
+        assert_eq!(
+            state.borrow_mut().balance(&receiver).unwrap(),
+            U256::from(17)
+        );
+    }
+
+    #[test]
+    fn test_fee_distribution_for_charge() {
+        use crate::libexecutor::fee_distribution::FeeDistribution;
+
+        let keypair = KeyPair::gen_keypair();
+        let treasury = Address::from(0x7777);
+        let t = Transaction {
+            action: Action::Call(Address::from(0x1234)),
+            value: U256::zero(),
+            data: vec![],
+            gas: U256::from(100_000),
+            gas_price: U256::one(),
+            nonce: U256::zero().to_string(),
+            block_limit: 100u64,
+            chain_id: 1.into(),
+            version: 2,
+        }
+        .fake_sign(keypair.address().clone());
+
+        let mut state = get_temp_state();
+        state.add_balance(t.sender(), U256::from(100_000)).unwrap();
+        let state = Arc::new(RefCell::new(state));
+
+        let mut context = Context::default();
+        context.coin_base = Address::from(0x5678);
+        let mut conf = BlockSysConfig::default();
+        conf.fee_distribution = FeeDistribution {
+            proposer: 5000,
+            validators: 0,
+            treasury: 3000,
+            burn: 2000,
+            treasury_address: treasury,
+        };
+
+        let executed = CitaExecutive::new(
+            Arc::new(EVMBlockDataProvider::new(context.clone())),
+            state.clone(),
+            &context,
+            EconomicalModel::Charge,
+            Arc::new(NativeFactory::default()),
+        )
+        .exec(&t, &conf)
+        .unwrap();
+
+        let fee = TxGasSchedule::default().tx_gas as u64;
+        assert_eq!(executed.fee_report.total, U256::from(fee));
+        assert_eq!(executed.fee_report.burned, U256::from(fee / 5));
+        assert_eq!(
+            state.borrow_mut().balance(&context.coin_base).unwrap(),
+            U256::from(fee / 2)
+        );
+        assert_eq!(
+            state.borrow_mut().balance(&treasury).unwrap(),
+            U256::from(fee * 3 / 10)
+        );
+        assert_eq!(
+            state.borrow_mut().balance(t.sender()).unwrap(),
+            U256::from(100_000 - fee)
+        );
+    }
+
     #[test]
     fn test_not_enough_cash_for_charge() {
         let keypair = KeyPair::gen_keypair();
@@ -1164,6 +1804,7 @@ mod tests {
                 state.clone(),
                 &context,
                 EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf)
         };
@@ -1205,6 +1846,7 @@ mod tests {
                 state.clone(),
                 &context,
                 EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf)
         };
@@ -1215,6 +1857,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_frozen_account() {
+        use crate::exception::ExecutedException;
+        use crate::types::errors::AuthenticationError;
+
+        let keypair = KeyPair::gen_keypair();
+        let target = Address::from(0x1234);
+        // The proxy calls the target without data and stops:
+        // CALL(gas, target, 0, 0, 0, 0, 0)
+        let proxy = Address::from(0x5678);
+        let proxy_code = "600060006000600060007300000000000000000000000000000000000012345af15000"
+            .from_hex()
+            .unwrap();
+        // STATICCALL(gas, target, 0, 0, 0, 0)
+        let static_proxy = Address::from(0x6789);
+        let static_proxy_code =
+            "60006000600060007300000000000000000000000000000000000012345afa5000"
+                .from_hex()
+                .unwrap();
+        let transfer = |to: Address| {
+            Transaction {
+                action: Action::Call(to),
+                value: U256::from(1),
+                data: vec![],
+                gas: U256::from(100_000),
+                gas_price: U256::one(),
+                nonce: U256::zero().to_string(),
+                block_limit: 100u64,
+                chain_id: 1.into(),
+                version: 2,
+            }
+            .fake_sign(keypair.address().clone())
+        };
+        let exec = |t: &SignedTransaction, conf: &BlockSysConfig| {
+            let mut state = get_temp_state();
+            state.add_balance(t.sender(), U256::from(100_000)).unwrap();
+            state.set_code(&proxy, proxy_code.clone()).unwrap();
+            state
+                .set_code(&static_proxy, static_proxy_code.clone())
+                .unwrap();
+            let mut context = Context::default();
+            context.block_quota_limit = U256::from(100_000);
+            let block_data_provider = EVMBlockDataProvider::new(context.clone());
+            CitaExecutive::new(
+                Arc::new(block_data_provider),
+                Arc::new(RefCell::new(state)),
+                &context,
+                EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
+            )
+            .exec(t, conf)
+        };
+
+        let mut conf = BlockSysConfig::default();
+        conf.frozen_accounts.insert(keypair.address().clone());
+        match exec(&transfer(target), &conf) {
+            Err(ExecutionError::Authentication(AuthenticationError::NoTransactionPermission)) => {}
+            result => assert!(false, "Expected no transaction permission. {:?}", result),
+        }
+
+        // The call target is checked even without the call permission checking.
+        let mut conf = BlockSysConfig::default();
+        conf.frozen_accounts.insert(target);
+        match exec(&transfer(target), &conf) {
+            Err(ExecutionError::Authentication(AuthenticationError::NoCallPermission)) => {}
+            result => assert!(false, "Expected no call permission. {:?}", result),
+        }
+        assert!(exec(&transfer(Address::from(0x9abc)), &conf).is_ok());
+
+        // Neither the super admin nor a proxy could bypass the frozen accounts.
+        conf.super_admin_account = Some(keypair.address().clone());
+        match exec(&transfer(target), &conf) {
+            Err(ExecutionError::Authentication(AuthenticationError::NoCallPermission)) => {}
+            result => assert!(false, "Expected no call permission. {:?}", result),
+        }
+        for proxy in [proxy, static_proxy].iter() {
+            match exec(&transfer(*proxy), &conf).unwrap().exception {
+                Some(ExecutedException::Authentication(AuthenticationError::NoCallPermission)) => {}
+                exception => assert!(false, "Expected internal call refused. {:?}", exception),
+            }
+        }
+        conf.frozen_accounts.clear();
+        for proxy in [proxy, static_proxy].iter() {
+            let result = exec(&transfer(*proxy), &conf).unwrap();
+            assert!(result.exception.is_none());
+        }
+
+        let mut conf = BlockSysConfig::default();
+        conf.super_admin_account = Some(keypair.address().clone());
+        conf.frozen_accounts.insert(keypair.address().clone());
+        match exec(&transfer(target), &conf) {
+            Err(ExecutionError::Authentication(AuthenticationError::NoTransactionPermission)) => {}
+            result => assert!(false, "Expected no transaction permission. {:?}", result),
+        }
+    }
+
     #[test]
     fn test_not_enough_cash_for_quota() {
         let keypair = KeyPair::gen_keypair();
@@ -1245,6 +1983,7 @@ mod tests {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf)
         };
@@ -1303,6 +2042,7 @@ contract HelloWorld {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf)
         };
@@ -1365,6 +2105,7 @@ contract AbiTest {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf);
         }
@@ -1429,6 +2170,7 @@ contract AbiTest {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf);
         }
@@ -1503,6 +2245,7 @@ contract AbiTest {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf);
             assert!(res.is_ok());
@@ -1582,6 +2325,7 @@ contract AbiTest {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf);
             assert!(res.is_ok());
@@ -1676,6 +2420,7 @@ contract FakePermissionManagement {
                 state.clone(),
                 &context,
                 EconomicalModel::Quota,
+                Arc::new(NativeFactory::default()),
             )
             .exec(&t, &conf);
             assert!(res.is_ok());
@@ -32,6 +32,10 @@ pub fn call_pure<B: DB + 'static>(
 ) -> Result<evm::InterpreterResult, VMError> {
     let evm_context = store.borrow().evm_context.clone();
     let evm_cfg = store.borrow().evm_cfg.clone();
+    let precompiled = store
+        .borrow()
+        .native_factory
+        .precompiled(&request.contract.code_address, evm_context.number.low_u64());
     let evm_params = request.clone();
     let evm_data_provider =
         DataProvider::new(block_provider.clone(), state_provider.clone(), store);
@@ -64,6 +68,19 @@ pub fn call_pure<B: DB + 'static>(
         }
     }
 
+    // Execute CITA builtin pre-compiled contracts, once they are activated.
+    if let Some(c) = precompiled {
+        let gas = c.required_gas(&request.input);
+        if request.gas_limit < gas {
+            return Err(VMError::Evm(evm::Error::OutOfGas));
+        }
+        return Ok(evm::InterpreterResult::Normal(
+            c.run(&request.input),
+            request.gas_limit - gas,
+            vec![],
+        ));
+    }
+
     // Run
     let mut evm_it = evm::Interpreter::new(
         evm_context,
@@ -15,5 +15,6 @@
 //! Contracts.
 
 pub mod native;
+pub mod precompiled;
 pub mod solc;
 pub mod tools;
@@ -13,9 +13,16 @@
 // limitations under the License.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
+#[cfg(test)]
+use super::SIMPLE_STORAGE;
+use super::{builtin, CROSS_CHAIN_VERIFY, MULTISIG};
 use crate::cita_executive::VmExecParams;
+use crate::contracts::precompiled::{self, PrecompiledContract};
+use crate::libexecutor::genesis::NativeSpec;
+use crate::types::block_number::BlockNumber;
 use crate::types::context::Context;
 use crate::types::errors::NativeError;
 use crate::types::reserved_addresses;
@@ -45,7 +52,23 @@ impl Clone for Box<dyn Contract> {
     }
 }
 
-// Contract
+/// A native contract, run in place of EVM code at its registered address.
+///
+/// Implementations must uphold the following, since every node executes
+/// them while building the same state root:
+///
+/// * `exec` is deterministic: its result depends only on `params`, `context`
+///   and what it reads through `data_provider`.
+/// * Persistent state lives in the storage of `params.code_address`, read and
+///   written through `data_provider`; fields on `self` do not survive the call.
+/// * The gas left is reported in the returned `InterpreterResult`. Returning
+///   an `Err` reverts every state change made by the call.
+/// * `create` returns a fresh instance, so each call starts from a clean
+///   state.
+///
+/// A new native contract is made available by adding it to
+/// `contracts::native::builtin`, and enabled by name in the `natives` section
+/// of genesis.
 pub trait Contract: Sync + Send + ContractClone {
     fn exec(
         &mut self,
@@ -57,56 +80,267 @@ pub trait Contract: Sync + Send + ContractClone {
     fn create(&self) -> Box<dyn Contract>;
 }
 
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    activation: BlockNumber,
+    contract: Box<dyn Contract>,
+}
+
+#[derive(Clone)]
+struct PrecompiledEntry {
+    name: String,
+    activation: BlockNumber,
+}
+
+/// The native and the pre-compiled contracts enabled by genesis.
 #[derive(Clone)]
 pub struct Factory {
-    contracts: HashMap<Address, Box<dyn Contract>>,
+    contracts: HashMap<Address, Entry>,
+    precompiles: HashMap<Address, PrecompiledEntry>,
 }
 
+/// Activation height, on chains whose genesis has no `natives` section, of
+/// `multisig` and of the pre-compiled contracts. They came after those chains
+/// started, so they stay off there until genesis lists them with an
+/// activation height of their own.
+pub const LATE_ACTIVATION: BlockNumber = BlockNumber::max_value();
+
 impl Factory {
-    pub fn new_contract(&self, address: Address) -> Option<Box<dyn Contract>> {
-        if let Some(contract) = self.contracts.get(&address) {
-            Some(contract.create())
-        } else {
-            None
+    pub fn empty() -> Self {
+        Factory {
+            contracts: HashMap::new(),
+            precompiles: HashMap::new(),
+        }
+    }
+
+    /// Build a factory from the `natives` section of genesis.
+    pub fn from_specs(specs: &[NativeSpec]) -> Result<Self, String> {
+        let mut factory = Factory::empty();
+        for spec in specs {
+            if factory.contains(&spec.address) {
+                return Err(format!(
+                    "native contract address {:?} registered twice",
+                    spec.address
+                ));
+            }
+            if let Some(contract) = builtin(&spec.name) {
+                factory.register_at(spec.address, &spec.name, spec.activation, contract);
+            } else if precompiled::builtin(&spec.name).is_some() {
+                factory.register_precompiled(spec.address, &spec.name, spec.activation);
+            } else {
+                return Err(format!("unknown native contract {}", spec.name));
+            }
         }
+        Ok(factory)
     }
+
+    /// Whether a native or a pre-compiled contract is registered at
+    /// `address`, active or not.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.contracts.contains_key(address) || self.precompiles.contains_key(address)
+    }
+
+    /// Get a fresh instance of the native contract at `address` for a call
+    /// made at `height`.
+    ///
+    /// Returns `Ok(None)` if no native contract is registered there, and an
+    /// error if one is registered but not yet activated.
+    pub fn new_contract(
+        &self,
+        address: Address,
+        height: BlockNumber,
+    ) -> Result<Option<Box<dyn Contract>>, NativeError> {
+        match self.contracts.get(&address) {
+            Some(entry) if height < entry.activation => Err(NativeError::Internal(format!(
+                "native contract {} at {:?} is not activated until height {}",
+                entry.name, address, entry.activation
+            ))),
+            Some(entry) => Ok(Some(entry.contract.create())),
+            None => Ok(None),
+        }
+    }
+
+    /// The address of the native contract registered as `name`, if it is
+    /// active at `height`.
+    pub fn active_address(&self, name: &str, height: BlockNumber) -> Option<Address> {
+        self.contracts
+            .iter()
+            .find(|(_, entry)| entry.name == name && entry.activation <= height)
+            .map(|(address, _)| *address)
+    }
+
+    /// The pre-compiled contract at `address` for a call made at `height`.
+    ///
+    /// Returns `None` before its activation too, the address being an
+    /// ordinary account until then.
+    pub fn precompiled(
+        &self,
+        address: &Address,
+        height: BlockNumber,
+    ) -> Option<Box<dyn PrecompiledContract>> {
+        self.precompiles
+            .get(address)
+            .filter(|entry| entry.activation <= height)
+            .and_then(|entry| precompiled::builtin(&entry.name))
+    }
+
     pub fn register(&mut self, address: Address, contract: Box<dyn Contract>) {
-        self.contracts.insert(address, contract);
+        self.register_at(address, "", 0, contract);
     }
+
+    pub fn register_at(
+        &mut self,
+        address: Address,
+        name: &str,
+        activation: BlockNumber,
+        contract: Box<dyn Contract>,
+    ) {
+        self.contracts.insert(
+            address,
+            Entry {
+                name: name.to_owned(),
+                activation,
+                contract,
+            },
+        );
+    }
+
+    pub fn register_precompiled(&mut self, address: Address, name: &str, activation: BlockNumber) {
+        self.precompiles.insert(
+            address,
+            PrecompiledEntry {
+                name: name.to_owned(),
+                activation,
+            },
+        );
+    }
+
     pub fn unregister(&mut self, address: Address) {
         self.contracts.remove(&address);
+        self.precompiles.remove(&address);
     }
 }
 
+impl fmt::Debug for Factory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.contracts
+                    .iter()
+                    .map(|(address, entry)| (address, (&entry.name, entry.activation))),
+            )
+            .entries(
+                self.precompiles
+                    .iter()
+                    .map(|(address, entry)| (address, (&entry.name, entry.activation))),
+            )
+            .finish()
+    }
+}
+
+/// Used when genesis has no `natives` section.
 impl Default for Factory {
     fn default() -> Self {
-        let mut factory = Factory {
-            contracts: HashMap::new(),
-        };
-        // here we register contracts with addresses defined in genesis.json.
-        {
-            use super::crosschain_verify::CrossChainVerify;
-            factory.register(
-                Address::from_str(reserved_addresses::NATIVE_CROSS_CHAIN_VERIFY).unwrap(),
-                Box::new(CrossChainVerify::default()),
-            );
+        let mut factory = Factory::empty();
+        factory.register_at(
+            Address::from_str(reserved_addresses::NATIVE_CROSS_CHAIN_VERIFY).unwrap(),
+            CROSS_CHAIN_VERIFY,
+            0,
+            builtin(CROSS_CHAIN_VERIFY).unwrap(),
+        );
+        factory.register_at(
+            Address::from_str(reserved_addresses::NATIVE_MULTISIG).unwrap(),
+            MULTISIG,
+            LATE_ACTIVATION,
+            builtin(MULTISIG).unwrap(),
+        );
+        for (name, address) in precompiled::RESERVED.iter() {
+            factory.register_precompiled(*address, name, LATE_ACTIVATION);
         }
         #[cfg(test)]
         {
-            use super::simple_storage::SimpleStorage;
-            factory.register(
+            factory.register_at(
                 Address::from_str(reserved_addresses::NATIVE_SIMPLE_STORAGE).unwrap(),
-                Box::new(SimpleStorage::default()),
-            );
-        }
-        #[cfg(feature = "privatetx")]
-        {
-            use super::zk_privacy::ZkPrivacy;
-            factory.register(
-                Address::from_str(reserved_addresses::NATIVE_ZK_PRIVACY).unwrap(),
-                Box::new(ZkPrivacy::default()),
+                SIMPLE_STORAGE,
+                0,
+                builtin(SIMPLE_STORAGE).unwrap(),
             );
         }
         factory
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_specs() {
+        let address = Address::from_str(reserved_addresses::NATIVE_SIMPLE_STORAGE).unwrap();
+        let factory = Factory::from_specs(&[NativeSpec {
+            name: SIMPLE_STORAGE.to_owned(),
+            address,
+            activation: 10,
+        }])
+        .unwrap();
+
+        assert!(factory.contains(&address));
+        assert!(factory.new_contract(address, 9).is_err());
+        assert!(factory.new_contract(address, 10).unwrap().is_some());
+        assert!(factory
+            .new_contract(Address::from(0x1234), 10)
+            .unwrap()
+            .is_none());
+        assert_eq!(factory.active_address(SIMPLE_STORAGE, 9), None);
+        assert_eq!(factory.active_address(SIMPLE_STORAGE, 10), Some(address));
+    }
+
+    #[test]
+    fn test_from_specs_precompiled() {
+        let address = Address::from_str(reserved_addresses::SM3_ADDRESS).unwrap();
+        let factory = Factory::from_specs(&[NativeSpec {
+            name: precompiled::SM3.to_owned(),
+            address,
+            activation: 10,
+        }])
+        .unwrap();
+
+        assert!(factory.contains(&address));
+        assert!(factory.precompiled(&address, 9).is_none());
+        assert!(factory.precompiled(&address, 10).is_some());
+        assert!(factory.new_contract(address, 10).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_default_late_contracts_are_off() {
+        let address = Address::from_str(reserved_addresses::NATIVE_MULTISIG).unwrap();
+        let factory = Factory::default();
+
+        assert!(factory.contains(&address));
+        assert!(factory.new_contract(address, 1_000_000).is_err());
+        assert_eq!(factory.active_address(MULTISIG, 1_000_000), None);
+        for (_, address) in precompiled::RESERVED.iter() {
+            assert!(factory.contains(address));
+            assert!(factory.precompiled(address, 1_000_000).is_none());
+        }
+    }
+
+    #[test]
+    fn test_from_specs_rejects_bad_specs() {
+        let address = Address::from(0x1234);
+        let unknown = NativeSpec {
+            name: "noSuchContract".to_owned(),
+            address,
+            activation: 0,
+        };
+        assert!(Factory::from_specs(&[unknown]).is_err());
+
+        let twice = NativeSpec {
+            name: SIMPLE_STORAGE.to_owned(),
+            address,
+            activation: 0,
+        };
+        assert!(Factory::from_specs(&[twice.clone(), twice]).is_err());
+    }
+}
@@ -14,7 +14,21 @@
 
 mod crosschain_verify;
 pub mod factory;
-#[cfg(test)]
+pub mod multisig;
 mod simple_storage;
 
 pub use factory::Contract;
+
+pub const CROSS_CHAIN_VERIFY: &str = "crossChainVerify";
+pub const MULTISIG: &str = "multisig";
+pub const SIMPLE_STORAGE: &str = "simpleStorage";
+
+/// The native contracts genesis can enable, by name.
+pub fn builtin(name: &str) -> Option<Box<dyn Contract>> {
+    match name {
+        CROSS_CHAIN_VERIFY => Some(Box::new(crosschain_verify::CrossChainVerify::default())),
+        MULTISIG => Some(Box::new(multisig::Multisig::default())),
+        SIMPLE_STORAGE => Some(Box::new(simple_storage::SimpleStorage::default())),
+        _ => None,
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signer sets of the multi-signature accounts.
+//!
+//! A multisig account has no key of its own. A transaction is sent as the
+//! account when it carries the signatures of at least `threshold` of its
+//! signers, see `SignedTransaction::multisig`. The executor checks them
+//! against the sets kept here with `Multisig::verify_signers`, and takes the
+//! multisig nonce the signatures are made for with `Multisig::use_nonce`.
+
+use super::factory::Contract;
+use crate::cita_executive::VmExecParams;
+use crate::contracts::tools::method as method_tools;
+use crate::storage::{Map, Scalar};
+use crate::types::context::Context;
+use crate::types::errors::NativeError;
+
+use cita_types::{Address, H256, U256};
+use cita_vm::evm::DataProvider;
+use cita_vm::evm::InterpreterResult;
+use std::collections::HashSet;
+use util::sha3;
+
+/// The most signers an account can have.
+pub const MAX_SIGNERS: usize = 16;
+
+lazy_static! {
+    static ref CREATE_ACCOUNT_FUNC: u32 =
+        method_tools::encode_to_u32(b"createAccount(address[],uint256)");
+    static ref SET_SIGNERS_FUNC: u32 =
+        method_tools::encode_to_u32(b"setSigners(address[],uint256)");
+    static ref GET_SIGNERS_FUNC: u32 = method_tools::encode_to_u32(b"getSigners(address)");
+    static ref GET_NONCE_FUNC: u32 = method_tools::encode_to_u32(b"getNonce(address)");
+}
+
+#[derive(Clone)]
+pub struct Multisig {
+    count: Scalar,
+    thresholds: Map,
+    signers: Map,
+    nonces: Map,
+}
+
+impl Contract for Multisig {
+    fn exec(
+        &mut self,
+        params: &VmExecParams,
+        _context: &Context,
+        data_provider: &mut dyn DataProvider,
+    ) -> Result<InterpreterResult, NativeError> {
+        method_tools::extract_to_u32(&params.data[..]).and_then(|signature| match signature {
+            sig if sig == *CREATE_ACCOUNT_FUNC => self.create_account(params, data_provider),
+            sig if sig == *SET_SIGNERS_FUNC => self.set_signers(params, data_provider),
+            sig if sig == *GET_SIGNERS_FUNC => self.get_signers(params, data_provider),
+            sig if sig == *GET_NONCE_FUNC => self.get_nonce(params, data_provider),
+            _ => Err(NativeError::Internal("out of gas".to_string())),
+        })
+    }
+    fn create(&self) -> Box<dyn Contract> {
+        Box::new(Multisig::default())
+    }
+}
+
+impl Default for Multisig {
+    fn default() -> Self {
+        Multisig {
+            count: Scalar::new(H256::from(0)),
+            thresholds: Map::new(H256::from(1)),
+            signers: Map::new(H256::from(2)),
+            nonces: Map::new(H256::from(3)),
+        }
+    }
+}
+
+impl Multisig {
+    /// Whether `signers` reach the threshold of multisig `account`, kept by
+    /// the contract at `contract`.
+    ///
+    /// Signers not in the set of the account and duplicates are not counted.
+    /// An address that is not a multisig account has no signer to reach.
+    pub fn verify_signers(
+        &self,
+        data_provider: &dyn DataProvider,
+        contract: &Address,
+        account: &Address,
+        signers: &[Address],
+    ) -> Result<bool, NativeError> {
+        let (threshold, set) = self.load(data_provider, contract, account)?;
+        if threshold == 0 {
+            return Ok(false);
+        }
+        let set: HashSet<Address> = set.into_iter().collect();
+        let signed = signers
+            .iter()
+            .filter(|signer| set.contains(*signer))
+            .collect::<HashSet<_>>()
+            .len();
+        Ok(signed >= threshold)
+    }
+
+    /// Take `nonce` of multisig `account`, if it is the current one, and move
+    /// the account on to the next nonce.
+    ///
+    /// Returns false for any other nonce, so the signatures of a transaction
+    /// can't be sent again, whoever wraps them.
+    pub fn use_nonce(
+        &self,
+        data_provider: &mut dyn DataProvider,
+        contract: &Address,
+        account: &Address,
+        nonce: u64,
+    ) -> Result<bool, NativeError> {
+        let key = account.to_vec();
+        let current = self.nonces.get(data_provider, contract, &key)?;
+        if current != U256::from(nonce) {
+            return Ok(false);
+        }
+        self.nonces
+            .set(data_provider, contract, &key, current + U256::one())?;
+        Ok(true)
+    }
+
+    fn create_account(
+        &mut self,
+        params: &VmExecParams,
+        data_provider: &mut dyn DataProvider,
+    ) -> Result<InterpreterResult, NativeError> {
+        let gas_cost = 50000;
+        if params.gas < gas_cost {
+            return Err(NativeError::Internal("out of gas".to_string()));
+        }
+        let gas_left = params.gas - gas_cost;
+
+        let (signers, threshold) = decode_signers(&params.data)?;
+        let count = self.count.get(data_provider, &params.code_address)?;
+        let mut seed = params.code_address.to_vec();
+        seed.extend_from_slice(&<[u8; 32]>::from(count));
+        let account = Address::from(&sha3::keccak256(&seed)[12..]);
+        self.count
+            .set(data_provider, &params.code_address, count + U256::one())?;
+        self.store(
+            data_provider,
+            &params.code_address,
+            &account,
+            &signers,
+            threshold,
+        )?;
+
+        let output = ethabi::encode(&[ethabi::Token::Address(account.into())]);
+        Ok(InterpreterResult::Normal(output, gas_left, vec![]))
+    }
+
+    /// Replace the signers of the calling account, which must be a multisig
+    /// account, so changes need the consent of the current signers.
+    fn set_signers(
+        &mut self,
+        params: &VmExecParams,
+        data_provider: &mut dyn DataProvider,
+    ) -> Result<InterpreterResult, NativeError> {
+        let gas_cost = 30000;
+        if params.gas < gas_cost {
+            return Err(NativeError::Internal("out of gas".to_string()));
+        }
+        let gas_left = params.gas - gas_cost;
+
+        let (signers, threshold) = decode_signers(&params.data)?;
+        let account = params.sender;
+        let (current, _) = self.load(data_provider, &params.code_address, &account)?;
+        if current == 0 {
+            return Err(NativeError::Internal(format!(
+                "{:?} is not a multisig account",
+                account
+            )));
+        }
+        self.store(
+            data_provider,
+            &params.code_address,
+            &account,
+            &signers,
+            threshold,
+        )?;
+        Ok(InterpreterResult::Normal(vec![], gas_left, vec![]))
+    }
+
+    fn get_signers(
+        &mut self,
+        params: &VmExecParams,
+        data_provider: &mut dyn DataProvider,
+    ) -> Result<InterpreterResult, NativeError> {
+        let gas_cost = 5000;
+        if params.gas < gas_cost {
+            return Err(NativeError::Internal("out of gas".to_string()));
+        }
+        let gas_left = params.gas - gas_cost;
+
+        let account = ethabi::decode(&[ethabi::ParamType::Address], &params.data[4..])
+            .ok()
+            .and_then(|mut decoded| decoded.remove(0).to_address())
+            .map(Address::from)
+            .ok_or_else(|| NativeError::Internal("decode failed".to_string()))?;
+        let (threshold, signers) = self.load(data_provider, &params.code_address, &account)?;
+
+        let output = ethabi::encode(&[
+            ethabi::Token::Uint(U256::from(threshold).into()),
+            ethabi::Token::Array(
+                signers
+                    .into_iter()
+                    .map(|signer| ethabi::Token::Address(signer.into()))
+                    .collect(),
+            ),
+        ]);
+        Ok(InterpreterResult::Normal(output, gas_left, vec![]))
+    }
+
+    fn get_nonce(
+        &mut self,
+        params: &VmExecParams,
+        data_provider: &mut dyn DataProvider,
+    ) -> Result<InterpreterResult, NativeError> {
+        let gas_cost = 5000;
+        if params.gas < gas_cost {
+            return Err(NativeError::Internal("out of gas".to_string()));
+        }
+        let gas_left = params.gas - gas_cost;
+
+        let account = ethabi::decode(&[ethabi::ParamType::Address], &params.data[4..])
+            .ok()
+            .and_then(|mut decoded| decoded.remove(0).to_address())
+            .map(Address::from)
+            .ok_or_else(|| NativeError::Internal("decode failed".to_string()))?;
+        let nonce = self
+            .nonces
+            .get(data_provider, &params.code_address, &account.to_vec())?;
+
+        let output = ethabi::encode(&[ethabi::Token::Uint(nonce.into())]);
+        Ok(InterpreterResult::Normal(output, gas_left, vec![]))
+    }
+
+    fn load(
+        &self,
+        data_provider: &dyn DataProvider,
+        contract: &Address,
+        account: &Address,
+    ) -> Result<(usize, Vec<Address>), NativeError> {
+        let key = account.to_vec();
+        let threshold = self.thresholds.get(data_provider, contract, &key)?;
+        let signers: Vec<u8> = self.signers.get_bytes(data_provider, contract, &key)?;
+        Ok((
+            threshold.low_u64() as usize,
+            signers.chunks(20).map(Address::from).collect(),
+        ))
+    }
+
+    fn store(
+        &self,
+        data_provider: &mut dyn DataProvider,
+        contract: &Address,
+        account: &Address,
+        signers: &[Address],
+        threshold: usize,
+    ) -> Result<(), NativeError> {
+        let key = account.to_vec();
+        let bytes: Vec<u8> = signers.iter().flat_map(|signer| signer.to_vec()).collect();
+        self.thresholds
+            .set(data_provider, contract, &key, U256::from(threshold))?;
+        self.signers
+            .set_bytes(data_provider, contract, &key, &bytes)
+    }
+}
+
+/// Decode and check the `(address[], uint256)` arguments of a signer set.
+fn decode_signers(data: &[u8]) -> Result<(Vec<Address>, usize), NativeError> {
+    let tokens = vec![
+        ethabi::ParamType::Array(Box::new(ethabi::ParamType::Address)),
+        ethabi::ParamType::Uint(256),
+    ];
+    let mut decoded = ethabi::decode(&tokens, data.get(4..).unwrap_or_default())
+        .map_err(|_| NativeError::Internal("decode failed".to_string()))?;
+    let signers = decoded
+        .remove(0)
+        .to_array()
+        .and_then(|signers| {
+            signers
+                .into_iter()
+                .map(|signer| signer.to_address().map(Address::from))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| NativeError::Internal("decode 1st param failed".to_string()))?;
+    let threshold = decoded
+        .remove(0)
+        .to_uint()
+        .map(|threshold| U256::from(&threshold[..]))
+        .ok_or_else(|| NativeError::Internal("decode 2nd param failed".to_string()))?;
+
+    if signers.is_empty() || signers.len() > MAX_SIGNERS {
+        return Err(NativeError::Internal(format!(
+            "a multisig account needs 1 to {} signers",
+            MAX_SIGNERS
+        )));
+    }
+    let unique: HashSet<_> = signers.iter().collect();
+    if unique.len() != signers.len() || signers.contains(&Address::zero()) {
+        return Err(NativeError::Internal(
+            "duplicate or zero signer".to_string(),
+        ));
+    }
+    if threshold.is_zero() || threshold > U256::from(signers.len()) {
+        return Err(NativeError::Internal(format!(
+            "threshold {} out of 1 to {}",
+            threshold,
+            signers.len()
+        )));
+    }
+    Ok((signers, threshold.low_u64() as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::exemock::DataProviderMock;
+
+    fn call(
+        contract: &mut Multisig,
+        data_provider: &mut DataProviderMock,
+        sender: Address,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>, NativeError> {
+        let mut params = VmExecParams::default();
+        params.code_address = Address::from(0x3003);
+        params.sender = sender;
+        params.gas = 100_000;
+        params.data = data;
+        match contract.exec(&params, &Context::default(), data_provider)? {
+            InterpreterResult::Normal(output, _, _) => Ok(output),
+            _ => unreachable!(),
+        }
+    }
+
+    fn signers_data(func: u32, signers: &[Address], threshold: u64) -> Vec<u8> {
+        let mut data = func.to_be_bytes().to_vec();
+        data.extend(ethabi::encode(&[
+            ethabi::Token::Array(
+                signers
+                    .iter()
+                    .map(|signer| ethabi::Token::Address((*signer).into()))
+                    .collect(),
+            ),
+            ethabi::Token::Uint(U256::from(threshold).into()),
+        ]));
+        data
+    }
+
+    #[test]
+    fn test_multisig() {
+        let mut contract = Multisig::default();
+        let mut data_provider = DataProviderMock::default();
+        let contract_address = Address::from(0x3003);
+        let (a, b, c) = (Address::from(1), Address::from(2), Address::from(3));
+
+        // Bad signer sets.
+        for (signers, threshold) in &[
+            (vec![], 1),
+            (vec![a, b], 0),
+            (vec![a, b], 3),
+            (vec![a, a], 1),
+            (vec![a, Address::zero()], 1),
+        ] {
+            let data = signers_data(*CREATE_ACCOUNT_FUNC, signers, *threshold);
+            assert!(call(&mut contract, &mut data_provider, a, data).is_err());
+        }
+
+        let data = signers_data(*CREATE_ACCOUNT_FUNC, &[a, b, c], 2);
+        let output = call(&mut contract, &mut data_provider, a, data).unwrap();
+        let account = Address::from(&output[12..]);
+        // Each account gets a new address.
+        let data = signers_data(*CREATE_ACCOUNT_FUNC, &[a, b, c], 2);
+        let output = call(&mut contract, &mut data_provider, a, data).unwrap();
+        assert_ne!(Address::from(&output[12..]), account);
+
+        let verify = |data_provider: &DataProviderMock, signers: &[Address]| {
+            Multisig::default()
+                .verify_signers(data_provider, &contract_address, &account, signers)
+                .unwrap()
+        };
+        assert!(verify(&data_provider, &[a, c]));
+        assert!(!verify(&data_provider, &[a]));
+        assert!(!verify(&data_provider, &[a, a]));
+        assert!(!verify(&data_provider, &[a, Address::from(4)]));
+        assert!(!contract
+            .verify_signers(&data_provider, &contract_address, &a, &[a, b, c])
+            .unwrap());
+
+        // Only the account itself changes its signers.
+        let data = signers_data(*SET_SIGNERS_FUNC, &[a], 1);
+        assert!(call(&mut contract, &mut data_provider, a, data.clone()).is_err());
+        call(&mut contract, &mut data_provider, account, data).unwrap();
+        assert!(verify(&data_provider, &[a]));
+        assert!(!verify(&data_provider, &[b, c]));
+
+        let mut data = GET_SIGNERS_FUNC.to_be_bytes().to_vec();
+        data.extend(ethabi::encode(&[ethabi::Token::Address(account.into())]));
+        let output = call(&mut contract, &mut data_provider, a, data).unwrap();
+        assert_eq!(
+            output,
+            ethabi::encode(&[
+                ethabi::Token::Uint(U256::one().into()),
+                ethabi::Token::Array(vec![ethabi::Token::Address(a.into())]),
+            ])
+        );
+
+        // Each nonce is taken once, in order.
+        let nonce = |contract: &mut Multisig, data_provider: &mut DataProviderMock| {
+            let mut data = GET_NONCE_FUNC.to_be_bytes().to_vec();
+            data.extend(ethabi::encode(&[ethabi::Token::Address(account.into())]));
+            U256::from(&call(contract, data_provider, a, data).unwrap()[..])
+        };
+        assert_eq!(nonce(&mut contract, &mut data_provider), U256::zero());
+        let use_nonce = |data_provider: &mut DataProviderMock, nonce: u64| {
+            Multisig::default()
+                .use_nonce(data_provider, &contract_address, &account, nonce)
+                .unwrap()
+        };
+        assert!(!use_nonce(&mut data_provider, 1));
+        assert!(use_nonce(&mut data_provider, 0));
+        assert!(!use_nonce(&mut data_provider, 0));
+        assert!(use_nonce(&mut data_provider, 1));
+        assert_eq!(nonce(&mut contract, &mut data_provider), U256::from(2));
+    }
+}
@@ -258,7 +258,7 @@ fn test_native_contract() {
                 .expect("failed to serialize u64");
         }
         params.data = input;
-        let mut contract = factory.new_contract(native_addr).unwrap();
+        let mut contract = factory.new_contract(native_addr, 0).unwrap().unwrap();
         let _output = contract
             .exec(&params, &context, &mut data_provider)
             .expect("Set value failed.");
@@ -272,7 +272,7 @@ fn test_native_contract() {
             .expect("failed to serialize u32");
         params.data = input;
 
-        let mut contract = factory.new_contract(native_addr).unwrap();
+        let mut contract = factory.new_contract(native_addr, 0).unwrap().unwrap();
         match contract.exec(&params, &context, &mut data_provider) {
             Ok(InterpreterResult::Normal(return_data, _quota_left, _logs)) => {
                 let real = U256::from(&*return_data);
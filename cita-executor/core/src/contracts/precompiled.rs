@@ -0,0 +1,366 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CITA builtin pre-compiled contracts.
+//!
+//! Like the Ethereum builtins in cita-vm, these are pure functions of their
+//! input with a gas cost known before running them. They cover the signature
+//! and hash algorithms CITA chains can be built with, whatever algorithms the
+//! running chain itself uses.
+//!
+//! | Address    | Input                                        | Output              |
+//! |------------|----------------------------------------------|---------------------|
+//! | `ff0001`   | hash(32) signature(64) pubkey(32)            | address, or nothing |
+//! | `ff0002`   | pubkey(32) signature(64) message             | 1 or 0              |
+//! | `ff0003`   | data                                         | SM3 hash            |
+//! | `ff0004`   | pubkey(64) r(32) s(32) message               | 1 or 0              |
+//! | `ff0005`   | hash(32) r(32) s(32) pubkey(64)              | address, or nothing |
+//!
+//! Addresses are returned left padded to 32 bytes and derived from the public
+//! key the same way CITA derives account addresses. SM2 uses the default user
+//! ID, as CITA's SM2 transaction signatures do.
+//!
+//! They are enabled by name in the `natives` section of genesis, with an
+//! activation height, like the native contracts. Before it, the address is an
+//! ordinary account.
+
+use std::str::FromStr;
+
+use crate::types::reserved_addresses;
+use cita_types::Address;
+use hashable::Hashable;
+use libsm::sm2::signature::{SigCtx, Signature as Sm2Signature};
+use libsm::sm3::hash::Sm3Hash;
+use sodiumoxide::crypto::sign::ed25519;
+
+pub trait PrecompiledContract: Send + Sync {
+    /// Gas charged for running the contract with `input`.
+    fn required_gas(&self, input: &[u8]) -> u64;
+
+    fn run(&self, input: &[u8]) -> Vec<u8>;
+}
+
+pub const EDRECOVER: &str = "edRecover";
+pub const ED25519_VERIFY: &str = "ed25519Verify";
+pub const SM3: &str = "sm3";
+pub const SM2_VERIFY: &str = "sm2Verify";
+pub const SM2_VERIFY_HASH: &str = "sm2VerifyHash";
+
+lazy_static! {
+    /// The reserved address of each pre-compiled contract.
+    pub static ref RESERVED: [(&'static str, Address); 5] = [
+        (EDRECOVER, Address::from_str(reserved_addresses::EDRECOVER_ADDRESS).unwrap()),
+        (ED25519_VERIFY, Address::from_str(reserved_addresses::ED25519_VERIFY_ADDRESS).unwrap()),
+        (SM3, Address::from_str(reserved_addresses::SM3_ADDRESS).unwrap()),
+        (SM2_VERIFY, Address::from_str(reserved_addresses::SM2_VERIFY_ADDRESS).unwrap()),
+        (SM2_VERIFY_HASH, Address::from_str(reserved_addresses::SM2_VERIFY_HASH_ADDRESS).unwrap()),
+    ];
+}
+
+/// The pre-compiled contracts genesis can enable, by name.
+pub fn builtin(name: &str) -> Option<Box<dyn PrecompiledContract>> {
+    match name {
+        EDRECOVER => Some(Box::new(EdRecover)),
+        ED25519_VERIFY => Some(Box::new(Ed25519Verify)),
+        SM3 => Some(Box::new(Sm3)),
+        SM2_VERIFY => Some(Box::new(Sm2Verify)),
+        SM2_VERIFY_HASH => Some(Box::new(Sm2VerifyHash)),
+        _ => None,
+    }
+}
+
+/// Whether `address` is reserved for a pre-compiled contract.
+pub fn is_reserved(address: &Address) -> bool {
+    RESERVED.iter().any(|(_, reserved)| reserved == address)
+}
+
+const HASH_WORD_GAS: u64 = 12;
+const SM3_BASE_GAS: u64 = 60;
+const ED25519_GAS: u64 = 2000;
+const SM2_GAS: u64 = 5000;
+
+fn words(input: &[u8]) -> u64 {
+    (input.len() as u64 + 31) / 32
+}
+
+fn bool_word(value: bool) -> Vec<u8> {
+    let mut output = vec![0u8; 32];
+    output[31] = value as u8;
+    output
+}
+
+fn address_word(pubkey: &[u8]) -> Vec<u8> {
+    let address = Address::from(pubkey.to_vec().crypt_hash());
+    let mut output = vec![0u8; 12];
+    output.extend_from_slice(&address);
+    output
+}
+
+/// Copy `input` into a buffer of `len` bytes, zero filled like call data.
+fn padded(input: &[u8], len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    let n = input.len().min(len);
+    buf[..n].copy_from_slice(&input[..n]);
+    buf
+}
+
+fn ed25519_verify(pubkey: &[u8], signature: &[u8], message: &[u8]) -> bool {
+    match (
+        ed25519::PublicKey::from_slice(pubkey),
+        ed25519::Signature::from_slice(signature),
+    ) {
+        (Some(pubkey), Some(signature)) => ed25519::verify_detached(&signature, message, &pubkey),
+        _ => false,
+    }
+}
+
+fn sm2_verify(pubkey: &[u8], r: &[u8], s: &[u8], message: &[u8]) -> bool {
+    let ctx = SigCtx::new();
+    let mut uncompressed = [4u8; 65];
+    uncompressed[1..].copy_from_slice(pubkey);
+    match ctx.load_pubkey(&uncompressed) {
+        Ok(pubkey) => ctx.verify(message, &pubkey, &Sm2Signature::new(r, s)),
+        Err(_) => false,
+    }
+}
+
+/// Ed25519 signature of a 32-byte hash, returning the signer's address.
+#[derive(Clone)]
+pub struct EdRecover;
+
+impl PrecompiledContract for EdRecover {
+    fn required_gas(&self, _input: &[u8]) -> u64 {
+        ED25519_GAS
+    }
+
+    fn run(&self, input: &[u8]) -> Vec<u8> {
+        let input = padded(input, 128);
+        let (hash, signature, pubkey) = (&input[..32], &input[32..96], &input[96..]);
+        if ed25519_verify(pubkey, signature, hash) {
+            address_word(pubkey)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Ed25519 signature of a message of any length.
+#[derive(Clone)]
+pub struct Ed25519Verify;
+
+impl PrecompiledContract for Ed25519Verify {
+    fn required_gas(&self, input: &[u8]) -> u64 {
+        ED25519_GAS + HASH_WORD_GAS * words(input)
+    }
+
+    fn run(&self, input: &[u8]) -> Vec<u8> {
+        if input.len() < 96 {
+            return bool_word(false);
+        }
+        let (pubkey, signature, message) = (&input[..32], &input[32..96], &input[96..]);
+        bool_word(ed25519_verify(pubkey, signature, message))
+    }
+}
+
+#[derive(Clone)]
+pub struct Sm3;
+
+impl PrecompiledContract for Sm3 {
+    fn required_gas(&self, input: &[u8]) -> u64 {
+        SM3_BASE_GAS + HASH_WORD_GAS * words(input)
+    }
+
+    fn run(&self, input: &[u8]) -> Vec<u8> {
+        Sm3Hash::new(input).get_hash().to_vec()
+    }
+}
+
+/// SM2 signature of a message of any length.
+#[derive(Clone)]
+pub struct Sm2Verify;
+
+impl PrecompiledContract for Sm2Verify {
+    fn required_gas(&self, input: &[u8]) -> u64 {
+        SM2_GAS + HASH_WORD_GAS * words(input)
+    }
+
+    fn run(&self, input: &[u8]) -> Vec<u8> {
+        if input.len() < 128 {
+            return bool_word(false);
+        }
+        let (pubkey, r, s, message) =
+            (&input[..64], &input[64..96], &input[96..128], &input[128..]);
+        bool_word(sm2_verify(pubkey, r, s, message))
+    }
+}
+
+/// SM2 signature of a 32-byte hash by the given public key, returning the
+/// address of the key. The key is not recovered from the signature.
+#[derive(Clone)]
+pub struct Sm2VerifyHash;
+
+impl PrecompiledContract for Sm2VerifyHash {
+    fn required_gas(&self, _input: &[u8]) -> u64 {
+        SM2_GAS
+    }
+
+    fn run(&self, input: &[u8]) -> Vec<u8> {
+        let input = padded(input, 160);
+        let (hash, r, s, pubkey) = (&input[..32], &input[32..64], &input[64..96], &input[96..]);
+        if sm2_verify(pubkey, r, s, hash) {
+            address_word(pubkey)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hex::FromHex;
+
+    fn hex(s: &str) -> Vec<u8> {
+        s.from_hex().unwrap()
+    }
+
+    fn left_pad(bytes: &[u8]) -> Vec<u8> {
+        let mut word = vec![0u8; 32 - bytes.len()];
+        word.extend_from_slice(bytes);
+        word
+    }
+
+    #[test]
+    fn test_builtin() {
+        for (name, address) in RESERVED.iter() {
+            assert!(builtin(name).is_some());
+            assert!(is_reserved(address));
+        }
+        assert!(builtin("noSuchContract").is_none());
+        assert!(!is_reserved(&Address::from(1)));
+    }
+
+    #[test]
+    fn test_sm3() {
+        // GB/T 32905-2016, appendix A.
+        let sm3 = builtin(SM3).unwrap();
+        assert_eq!(
+            sm3.run(b"abc"),
+            hex("66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0")
+        );
+        let input = b"abcd".repeat(16);
+        assert_eq!(
+            sm3.run(&input),
+            hex("debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732")
+        );
+        assert_eq!(sm3.required_gas(&input), 60 + 2 * 12);
+    }
+
+    #[test]
+    fn test_ed25519_verify() {
+        // RFC 8032, section 7.1, tests 1 to 3.
+        let vectors = [
+            (
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                 5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+                "",
+            ),
+            (
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                 085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+                "72",
+            ),
+            (
+                "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+                "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+                 18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+                "af82",
+            ),
+        ];
+        let verify = builtin(ED25519_VERIFY).unwrap();
+        for (pubkey, signature, message) in vectors.iter() {
+            let mut input = hex(pubkey);
+            input.extend(hex(signature));
+            input.extend(hex(message));
+            assert_eq!(verify.run(&input), bool_word(true));
+
+            // Any change to the message breaks the signature.
+            input.push(0);
+            assert_eq!(verify.run(&input), bool_word(false));
+        }
+        assert_eq!(verify.run(&[0u8; 10]), bool_word(false));
+    }
+
+    #[test]
+    fn test_edrecover() {
+        let seed = ed25519::Seed::from_slice(&[7u8; 32]).unwrap();
+        let (pubkey, secret) = ed25519::keypair_from_seed(&seed);
+        let hash = [0x5au8; 32];
+        let signature = ed25519::sign_detached(&hash, &secret);
+
+        let mut input = hash.to_vec();
+        input.extend_from_slice(signature.as_ref());
+        input.extend_from_slice(pubkey.as_ref());
+        let edrecover = builtin(EDRECOVER).unwrap();
+        assert_eq!(edrecover.run(&input), address_word(pubkey.as_ref()));
+
+        input[0] ^= 1;
+        assert!(edrecover.run(&input).is_empty());
+    }
+
+    #[test]
+    fn test_sm2_verify() {
+        // GB/T 32918 example on the recommended curve, with the default user ID.
+        let pubkey = hex(
+            "09f9df311e5421a150dd7d161e4bc5c672179fad1833fc076bb08ff356f35020\
+             ccea490ce26775a52dc6ea718cc1aa600aed05fbf35e084a6632f6072da9ad13",
+        );
+        let r = hex("f5a03b0648d2c4630eeac513e1bb81a15944da3827d5b74143ac7eaceee720b3");
+        let s = hex("b1b6aa29df212fd8763182bc0d421ca1bb9038fd1f7f42d4840b69c485bbc1aa");
+        let message = b"message digest".to_vec();
+
+        let verify = builtin(SM2_VERIFY).unwrap();
+        let mut input = pubkey.clone();
+        input.extend_from_slice(&r);
+        input.extend_from_slice(&s);
+        input.extend_from_slice(&message);
+        assert_eq!(verify.run(&input), bool_word(true));
+        assert_eq!(verify.required_gas(&input), 5000 + 5 * 12);
+
+        // Any change to the message breaks the signature.
+        input.push(0);
+        assert_eq!(verify.run(&input), bool_word(false));
+        assert_eq!(verify.run(&[0u8; 10]), bool_word(false));
+    }
+
+    #[test]
+    fn test_sm2_verify_hash() {
+        let ctx = SigCtx::new();
+        let (pk, sk) = ctx.new_keypair();
+        let pubkey = ctx.serialize_pubkey(&pk, false)[1..].to_vec();
+        let hash = [0xa5u8; 32];
+        let signature = ctx.sign(&hash, &sk, &pk);
+        let mut input = hash.to_vec();
+        input.extend(left_pad(&signature.get_r().to_bytes_be()));
+        input.extend(left_pad(&signature.get_s().to_bytes_be()));
+        input.extend_from_slice(&pubkey);
+        let verify_hash = builtin(SM2_VERIFY_HASH).unwrap();
+        assert_eq!(verify_hash.run(&input), address_word(&pubkey));
+
+        input[0] ^= 1;
+        assert!(verify_hash.run(&input).is_empty());
+    }
+}
@@ -17,7 +17,7 @@
 use std::str::FromStr;
 
 use super::ContractCallExt;
-use crate::contracts::tools::method as method_tools;
+use crate::contracts::tools::{decode as decode_tools, method as method_tools};
 use crate::libexecutor::executor::Executor;
 use crate::types::block_number::BlockTag;
 use crate::types::reserved_addresses;
@@ -27,6 +27,8 @@ use ethabi::{decode, ParamType};
 
 lazy_static! {
     static ref STATE_HASH: Vec<u8> = method_tools::encode_to_vec(b"state()");
+    static ref QUERY_FROZEN_ACCOUNTS_HASH: Vec<u8> =
+        method_tools::encode_to_vec(b"queryFrozenAccounts()");
     static ref CONTRACT_ADDRESS: Address =
         Address::from_str(reserved_addresses::EMERGENCY_INTERVENTION).unwrap();
 }
@@ -55,6 +57,24 @@ impl<'a> EmergencyIntervention<'a> {
         info!("Use default emergency intervention state.");
         false
     }
+
+    /// The accounts which can't send transactions nor be called
+    pub fn frozen_accounts(&self, block_tag: BlockTag) -> Option<Vec<Address>> {
+        self.executor
+            .call_method(
+                &*CONTRACT_ADDRESS,
+                &*QUERY_FROZEN_ACCOUNTS_HASH.as_slice(),
+                None,
+                block_tag,
+            )
+            .ok()
+            .and_then(|output| decode_tools::to_address_vec(&output))
+    }
+
+    pub fn default_frozen_accounts() -> Vec<Address> {
+        info!("Use default frozen accounts.");
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
@@ -71,5 +91,10 @@ mod tests {
             .state(BlockTag::Tag(Tag::Pending))
             .unwrap();
         assert_eq!(state, false);
+
+        let frozen_accounts = emergency_intervention
+            .frozen_accounts(BlockTag::Tag(Tag::Pending))
+            .unwrap();
+        assert!(frozen_accounts.is_empty());
     }
 }
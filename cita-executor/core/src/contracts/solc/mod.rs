@@ -66,6 +66,7 @@ impl<'a> ContractCallExt for Executor {
             from,
             to: *address,
             data: Some(encoded_method.to_vec()),
+            ..Default::default()
         };
         trace!("call method request: {:?}", call_request);
         self.eth_call(call_request, block_tag)
@@ -225,6 +225,8 @@ mod tests {
     const SET_BQL: &[u8] = &*b"setBQL(uint256)";
     const MULTI_TXS: &[u8] = &*b"multiTxs(bytes)";
     const SET_STATE: &[u8] = &*b"setState(bool)";
+    const FREEZE: &[u8] = &*b"freeze(address)";
+    const UNFREEZE: &[u8] = &*b"unfreeze(address)";
     const SET_QUOTA_PRICE: &[u8] = &*b"setQuotaPrice(uint256)";
     const SET_VERSION: &[u8] = &*b"setVersion(uint32)";
     const SET_PROTOCOL_VERSION: &[u8] = &*b"setProtocolVersion(uint32)";
@@ -502,6 +504,14 @@ mod tests {
                 cont: H160::from_str(reserved_addresses::EMERGENCY_INTERVENTION).unwrap(),
                 func: method_tools::encode_to_vec(SET_STATE),
             },
+            Resource {
+                cont: H160::from_str(reserved_addresses::EMERGENCY_INTERVENTION).unwrap(),
+                func: method_tools::encode_to_vec(FREEZE),
+            },
+            Resource {
+                cont: H160::from_str(reserved_addresses::EMERGENCY_INTERVENTION).unwrap(),
+                func: method_tools::encode_to_vec(UNFREEZE),
+            },
             // quotaPrice
             Resource {
                 cont: H160::from_str(reserved_addresses::PRICE_MANAGEMENT).unwrap(),
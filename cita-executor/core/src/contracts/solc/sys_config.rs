@@ -21,6 +21,7 @@ use crate::contracts::solc::version_management::VersionManager;
 use crate::contracts::tools::method as method_tools;
 use crate::libexecutor::economical_model::EconomicalModel;
 use crate::libexecutor::executor::Executor;
+use crate::libexecutor::fee_distribution::FeeDistribution;
 use crate::types::block_number::{BlockTag, Tag};
 use crate::types::reserved_addresses;
 
@@ -32,6 +33,8 @@ lazy_static! {
     static ref DELAY_BLOCK_NUMBER: Vec<u8> = method_tools::encode_to_vec(b"getDelayBlockNumber()");
     static ref CALL_PERMISSION_CHECK: Vec<u8> =
         method_tools::encode_to_vec(b"getPermissionCheck()");
+    static ref INTERNAL_CALL_PERMISSION_CHECK: Vec<u8> =
+        method_tools::encode_to_vec(b"getInternalCallPermissionCheck()");
     static ref PERMISSION_SEND_TX_CHECK: Vec<u8> =
         method_tools::encode_to_vec(b"getSendTxPermissionCheck()");
     static ref PERMISSION_CREATE_CONTRACT_CHECK: Vec<u8> =
@@ -51,6 +54,7 @@ lazy_static! {
     static ref ECONOMICAL_MODEL: Vec<u8> = method_tools::encode_to_vec(b"getEconomicalModel()");
     static ref GET_TOKEN_INFO: Vec<u8> = method_tools::encode_to_vec(b"getTokenInfo()");
     static ref AUTO_EXEC: Vec<u8> = method_tools::encode_to_vec(b"getAutoExec()");
+    static ref FEE_DISTRIBUTION: Vec<u8> = method_tools::encode_to_vec(b"getFeeDistribution()");
 }
 
 #[derive(PartialEq, Debug)]
@@ -123,6 +127,22 @@ impl<'a> SysConfig<'a> {
         false
     }
 
+    /// Whether check call permission of the internal calls or not
+    pub fn internal_call_permission_check(&self, block_tag: BlockTag) -> Option<bool> {
+        self.get_value(
+            &[ParamType::Bool],
+            INTERNAL_CALL_PERMISSION_CHECK.as_slice(),
+            block_tag,
+        )
+        .ok()
+        .and_then(|mut x| x.remove(0).to_bool())
+    }
+
+    pub fn default_internal_call_permission_check() -> bool {
+        info!("Use default internal call permission check.");
+        false
+    }
+
     pub fn send_tx_permission_check(&self, block_tag: BlockTag) -> Option<bool> {
         self.get_value(
             &[ParamType::Bool],
@@ -321,7 +341,7 @@ impl<'a> SysConfig<'a> {
                 .unwrap_or_else(SysConfig::default_chain_id);
 
             Some(ChainId::V0(id_v0))
-        } else if version < 3 {
+        } else if version < 4 {
             let id_v1 = self
                 .chain_id_v1(BlockTag::Tag(Tag::Pending))
                 .unwrap_or_else(SysConfig::default_chain_id_v1);
@@ -344,13 +364,47 @@ impl<'a> SysConfig<'a> {
         info!("Use the default autoEXEC.");
         false
     }
+
+    /// How the fee is split in Charge mode, none if the shares are invalid
+    pub fn fee_distribution(&self, block_tag: BlockTag) -> Option<FeeDistribution> {
+        self.get_value(
+            &[
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Address,
+            ],
+            FEE_DISTRIBUTION.as_slice(),
+            block_tag,
+        )
+        .ok()
+        .and_then(|mut x| {
+            let mut share = || x.remove(0).to_uint().map(|x| H256::from(x).low_u64());
+            let (proposer, validators, treasury, burn) = (share()?, share()?, share()?, share()?);
+            let treasury_address = x.remove(0).to_address().map(Address::from)?;
+            Some(FeeDistribution {
+                proposer,
+                validators,
+                treasury,
+                burn,
+                treasury_address,
+            })
+        })
+        .filter(FeeDistribution::is_valid)
+    }
+
+    pub fn default_fee_distribution() -> FeeDistribution {
+        info!("Use the default fee distribution.");
+        FeeDistribution::default()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     extern crate cita_logger as logger;
 
-    use super::{EconomicalModel, SysConfig, TokenInfo};
+    use super::{EconomicalModel, FeeDistribution, SysConfig, TokenInfo};
     use crate::tests::helpers::init_executor;
     use crate::types::block_number::{BlockTag, Tag};
     use cita_types::Address;
@@ -374,6 +428,12 @@ mod tests {
             .unwrap();
         assert_eq!(check_call_permission, false);
 
+        // Test internal_call_permission_check
+        let check_internal_call_permission = config
+            .internal_call_permission_check(BlockTag::Tag(Tag::Pending))
+            .unwrap();
+        assert_eq!(check_internal_call_permission, false);
+
         // Test send_tx_permission_check
         let check_send_tx_permission = config
             .send_tx_permission_check(BlockTag::Tag(Tag::Pending))
@@ -443,5 +503,11 @@ mod tests {
         // Test auto_exec
         let auto_exec = config.auto_exec(BlockTag::Tag(Tag::Pending)).unwrap();
         assert_eq!(auto_exec, false);
+
+        // Test fee_distribution
+        let fee_distribution = config
+            .fee_distribution(BlockTag::Tag(Tag::Pending))
+            .unwrap();
+        assert_eq!(fee_distribution, FeeDistribution::default());
     }
 }
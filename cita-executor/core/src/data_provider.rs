@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::authentication::CallPermissionGuard;
 use crate::cita_executive::{call as ext_call, create as ext_create, CreateKind};
+use crate::contracts::native::factory::Factory as NativeFactory;
+use crate::libexecutor::state_cache::BlockStateCache;
 use cita_trie::DB;
 use cita_types::{Address, H256, U256};
 use cita_vm::evm;
@@ -20,8 +23,15 @@ use cita_vm::state::{State, StateObjectInfo};
 use hashbrown::{HashMap, HashSet};
 use hasher::Hasher;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 
+/// Accounts and their storage keys accessed by a transaction.
+pub type AccessList = BTreeMap<Address, BTreeSet<H256>>;
+
+/// Accounts whose storage or code are written, the writes of the reverted frames included.
+pub type WrittenAccounts = BTreeSet<Address>;
+
 /// BlockDataProvider provides functions to get block's hash from chain.
 ///
 /// Block data(only hash) are required to cita-vm from externalize database.
@@ -66,6 +76,17 @@ pub struct Store {
     pub inused: HashSet<Address>,
     pub evm_context: evm::Context,
     pub evm_cfg: evm::InterpreterConf,
+    // Shared by all the frames of a transaction, it's none when the internal
+    // call permission needn't to be checked.
+    pub(crate) call_permission_guard: Option<Arc<CallPermissionGuard>>,
+    // Shared by all the frames of a transaction, the reverted frames included.
+    pub(crate) access_recorder: Option<Arc<RefCell<AccessList>>>,
+    // Shared by all the frames of a transaction, the reverted frames included.
+    pub(crate) write_recorder: Option<Arc<RefCell<WrittenAccounts>>>,
+    // Shared by all the frames of a transaction, the native contracts of the executor.
+    pub(crate) native_factory: Arc<NativeFactory>,
+    // Shared by all the frames of a transaction, it's none out of the blocks.
+    pub(crate) state_cache: Option<BlockStateCache>,
 }
 
 impl Store {
@@ -85,6 +106,65 @@ impl Store {
         }
         self.inused.insert(address);
     }
+
+    /// Record an accessed account, if the accesses should be recorded.
+    pub fn record_account(&self, address: &Address) {
+        if let Some(ref recorder) = self.access_recorder {
+            recorder.borrow_mut().entry(*address).or_default();
+        }
+    }
+
+    /// Record an accessed storage key, if the accesses should be recorded.
+    pub fn record_storage(&self, address: &Address, key: &H256) {
+        if let Some(ref recorder) = self.access_recorder {
+            recorder
+                .borrow_mut()
+                .entry(*address)
+                .or_default()
+                .insert(*key);
+        }
+    }
+
+    /// Record a written account, if the writes should be recorded.
+    pub fn record_write(&self, address: &Address) {
+        if let Some(ref recorder) = self.write_recorder {
+            recorder.borrow_mut().insert(*address);
+        }
+    }
+
+    /// The value of a storage slot in the state cache. Only the slots of the accounts
+    /// which are not written by the block are the same as at the state it's executed on.
+    pub fn cached_storage(&self, address: &Address, key: &H256) -> Option<H256> {
+        if !self.is_cacheable(address) {
+            return None;
+        }
+        self.state_cache
+            .as_ref()
+            .and_then(|cache| cache.storage(address, key))
+    }
+
+    /// Put a storage slot read from the state into the state cache.
+    pub fn cache_storage(&self, address: &Address, key: &H256, value: H256) {
+        if !self.is_cacheable(address) {
+            return;
+        }
+        if let Some(ref cache) = self.state_cache {
+            cache.insert_storage(*address, *key, value);
+        }
+    }
+
+    fn is_cacheable(&self, address: &Address) -> bool {
+        self.write_recorder
+            .as_ref()
+            .map_or(false, |recorder| !recorder.borrow().contains(address))
+    }
+
+    /// Check the permission of an internal call, always pass if not guarded.
+    pub fn check_call_permission(&self, caller: &Address, cont: &Address, data: &[u8]) -> bool {
+        self.call_permission_guard
+            .as_ref()
+            .map_or(true, |guard| guard.check(caller, cont, data))
+    }
 }
 
 /// An implemention for evm::DataProvider
@@ -107,10 +187,23 @@ impl<B: DB> DataProvider<B> {
             store,
         }
     }
+
+    fn record_account(&self, address: &Address) {
+        self.store.borrow().record_account(address);
+    }
+
+    fn record_write(&self, address: &Address) {
+        self.store.borrow().record_write(address);
+    }
+
+    fn record_storage(&self, address: &Address, key: &H256) {
+        self.store.borrow().record_storage(address, key);
+    }
 }
 
 impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     fn get_balance(&self, address: &Address) -> U256 {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .balance(address)
@@ -145,6 +238,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn get_code_size(&self, address: &Address) -> u64 {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .code_size(address)
@@ -152,6 +246,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn get_code(&self, address: &Address) -> Vec<u8> {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .code(address)
@@ -159,6 +254,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn get_code_hash(&self, address: &Address) -> H256 {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .code_hash(address)
@@ -170,13 +266,21 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn get_storage(&self, address: &Address, key: &H256) -> H256 {
-        self.state_provider
+        self.record_storage(address, key);
+        if let Some(value) = self.store.borrow().cached_storage(address, key) {
+            return value;
+        }
+        let value = self
+            .state_provider
             .borrow_mut()
             .get_storage(address, key)
-            .unwrap_or_else(|_| H256::zero())
+            .unwrap_or_else(|_| H256::zero());
+        self.store.borrow().cache_storage(address, key, value);
+        value
     }
 
     fn set_storage(&mut self, address: &Address, key: H256, value: H256) {
+        self.record_write(address);
         let a = self.get_storage(address, &key);
         self.store
             .borrow_mut()
@@ -196,13 +300,14 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
 
     fn get_storage_origin(&self, address: &Address, key: &H256) -> H256 {
         //self.store.borrow_mut().used(address.clone());
-        match self.store.borrow_mut().origin.get(address) {
-            Some(account) => match account.get(key) {
-                Some(val) => *val,
-                None => self.get_storage(address, key),
-            },
-            None => self.get_storage(address, key),
-        }
+        // The store is borrowed by `get_storage` to record the access.
+        let origin = self
+            .store
+            .borrow()
+            .origin
+            .get(address)
+            .and_then(|account| account.get(key).cloned());
+        origin.unwrap_or_else(|| self.get_storage(address, key))
     }
 
     fn set_storage_origin(&mut self, _address: &Address, _key: H256, _value: H256) {
@@ -214,6 +319,8 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
             return false;
         }
         //self.store.borrow_mut().used(refund_to.clone());
+        self.record_account(refund_to);
+        self.record_write(address);
         self.store.borrow_mut().selfdestruct.insert(*address);
         let b = self.get_balance(address);
 
@@ -237,6 +344,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn is_empty(&self, address: &Address) -> bool {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .is_empty(address)
@@ -244,6 +352,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
     }
 
     fn exist(&self, address: &Address) -> bool {
+        self.record_account(address);
         self.state_provider
             .borrow_mut()
             .exist(address)
@@ -260,6 +369,25 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
             | evm::OpCode::CALLCODE
             | evm::OpCode::DELEGATECALL
             | evm::OpCode::STATICCALL => {
+                // The frame issuing the call is the sender of CALL and
+                // STATICCALL, and the storage owner of CALLCODE and DELEGATECALL.
+                let caller = match opcode {
+                    evm::OpCode::CALL | evm::OpCode::STATICCALL => params.sender,
+                    _ => params.address,
+                };
+                if !self.store.borrow().check_call_permission(
+                    &caller,
+                    &params.contract.code_address,
+                    &params.input,
+                ) {
+                    debug!(
+                        "ext.call refused: no permission to call {:?}",
+                        params.contract.code_address
+                    );
+                    return Err(evm::Error::CallError);
+                }
+                self.record_account(&params.receiver);
+                self.record_account(&params.contract.code_address);
                 let r = ext_call(
                     self.block_provider.clone(),
                     self.state_provider.clone(),
@@ -271,6 +399,7 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
             }
             evm::OpCode::CREATE | evm::OpCode::CREATE2 => {
                 let mut request = params;
+                self.record_account(&request.sender);
                 request.nonce = self
                     .state_provider
                     .borrow_mut()
@@ -301,6 +430,9 @@ impl<B: DB + 'static> evm::DataProvider for DataProvider<B> {
                     _ => unimplemented!(),
                 }
                 .or(Err(evm::Error::CallError));
+                if let Ok(evm::InterpreterResult::Create(_, _, _, ref address)) = r {
+                    self.record_account(address);
+                }
                 debug!("ext.create.result = {:?}", r);
                 r
             }
@@ -15,7 +15,7 @@
 use std::error::Error;
 use std::fmt;
 
-use crate::types::errors::NativeError;
+use crate::types::errors::{AuthenticationError, NativeError};
 use cita_vm::Error as VMError;
 
 // There is not reverted expcetion in VMError, so handle this in ExecutedException.
@@ -23,6 +23,8 @@ use cita_vm::Error as VMError;
 pub enum ExecutedException {
     VM(VMError),
     NativeContract(NativeError),
+    // Refused by the permission check of an internal call.
+    Authentication(AuthenticationError),
     Reverted,
 }
 
@@ -35,6 +37,9 @@ impl fmt::Display for ExecutedException {
             ExecutedException::NativeContract(ref err) => {
                 format!("exception in native contract: {:?}", err)
             }
+            ExecutedException::Authentication(ref err) => {
+                format!("exception in authentication: {}", err)
+            }
             ExecutedException::Reverted => "execution reverted".to_owned(),
         };
         write!(f, "{}", printable)
@@ -24,8 +24,6 @@ extern crate crossbeam_channel;
 extern crate lazy_static;
 #[cfg(test)]
 extern crate cita_crypto;
-#[cfg(feature = "privatetx")]
-extern crate zktx;
 #[macro_use]
 extern crate enum_primitive;
 #[cfg(test)]
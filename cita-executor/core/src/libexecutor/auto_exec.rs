@@ -16,8 +16,9 @@ use crate::cita_executive::{
     build_evm_context, build_vm_exec_params, call as vm_call, ExecutiveParams,
 };
 use crate::cita_vm_helper::get_interpreter_conf;
+use crate::contracts::native::factory::Factory as NativeFactory;
 use crate::contracts::tools::method as method_tools;
-use crate::data_provider::Store as VMSubState;
+use crate::data_provider::{AccessList, Store as VMSubState, WrittenAccounts};
 use crate::libexecutor::block::EVMBlockDataProvider;
 use crate::libexecutor::executor::CitaTrieDB;
 use crate::types::context::Context;
@@ -40,6 +41,9 @@ pub fn auto_exec(
     state: Arc<RefCell<CitaState<CitaTrieDB>>>,
     auto_exec_quota_limit: u64,
     context: Context,
+    native_factory: Arc<NativeFactory>,
+    access_recorder: Option<Arc<RefCell<AccessList>>>,
+    write_recorder: Option<Arc<RefCell<WrittenAccounts>>>,
 ) {
     let hash = &*AUTO_EXEC_HASH;
     let params = ExecutiveParams {
@@ -58,6 +62,10 @@ pub fn auto_exec(
 
     sub_state.evm_context = build_evm_context(&context);
     sub_state.evm_cfg = get_interpreter_conf();
+    sub_state.access_recorder = access_recorder;
+    sub_state.write_recorder = write_recorder;
+    sub_state.native_factory = native_factory;
+    sub_state.record_account(&*AUTO_EXEC_ADDR);
     let sub_state = Arc::new(RefCell::new(sub_state));
 
     match vm_call(
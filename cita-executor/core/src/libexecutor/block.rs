@@ -19,12 +19,16 @@ use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use crate::cita_executive::CitaExecutive;
+use crate::contracts::native::factory::Factory as NativeFactory;
 use crate::core::context::{Context, LastHashes};
-use crate::data_provider::BlockDataProvider;
+use crate::data_provider::{AccessList, BlockDataProvider, WrittenAccounts};
 use crate::exception::ExecutedException;
 use crate::libexecutor::auto_exec::auto_exec;
 use crate::libexecutor::economical_model::EconomicalModel;
 use crate::libexecutor::executor::CitaTrieDB;
+use crate::libexecutor::fee_distribution::FeeReport;
+use crate::libexecutor::state_cache::{BlockStateCache, StateCache};
+use crate::libexecutor::state_diff::{self, StateDiff};
 use crate::libexecutor::sys_config::BlockSysConfig;
 use crate::libexecutor::sys_config::GlobalSysConfig;
 use crate::receipt::Receipt;
@@ -40,6 +44,7 @@ use cita_vm::{
 };
 use hashable::Hashable;
 use libproto::executor::{ExecutedInfo, ReceiptWithOption};
+use parking_lot::Mutex;
 use rlp::Encodable;
 
 lazy_static! {
@@ -58,6 +63,11 @@ pub struct ExecutedBlock {
     account_gas_limit: U256,
     account_gas: HashMap<Address, U256>,
     eth_compatibility: bool,
+    native_factory: Arc<NativeFactory>,
+    access_recorder: Option<Arc<RefCell<AccessList>>>,
+    written_accounts: Arc<RefCell<WrittenAccounts>>,
+    fee_report: FeeReport,
+    state_cache: Option<BlockStateCache>,
 }
 
 impl Deref for ExecutedBlock {
@@ -83,6 +93,7 @@ impl ExecutedBlock {
         state_root: H256,
         last_hashes: Arc<LastHashes>,
         eth_compatibility: bool,
+        native_factory: Arc<NativeFactory>,
     ) -> Result<Self, Error> {
         let state = CitaState::from_existing(Arc::<CitaTrieDB>::clone(&trie_db), state_root)
             .expect("Get state from trie db");
@@ -105,11 +116,35 @@ impl ExecutedBlock {
             current_quota_used: Default::default(),
             receipts: Default::default(),
             eth_compatibility,
+            native_factory,
+            access_recorder: None,
+            written_accounts: Default::default(),
+            fee_report: Default::default(),
+            state_cache: None,
         };
 
         Ok(r)
     }
 
+    /// Record the accounts and storage keys accessed by the block, and keep
+    /// the state diff of the block in the `ClosedBlock`.
+    pub fn record_state_diff(&mut self) {
+        self.access_recorder = Some(Arc::new(RefCell::new(AccessList::new())));
+    }
+
+    /// Take the accounts of the state cache, and read the storage through it,
+    /// if the block is executed on its generation.
+    pub fn use_state_cache(&mut self, cache: Arc<Mutex<StateCache>>) {
+        cache.lock().seed(&self.state.borrow());
+        self.state_cache = Some(BlockStateCache::new(cache, self.state_root));
+    }
+
+    fn record_account(&self, address: &Address) {
+        if let Some(ref recorder) = self.access_recorder {
+            recorder.borrow_mut().entry(*address).or_default();
+        }
+    }
+
     pub fn transactions(&self) -> &[SignedTransaction] {
         self.body.transactions()
     }
@@ -157,14 +192,25 @@ impl ExecutedBlock {
         }
         let block_data_provider = EVMBlockDataProvider::new(context.clone());
 
-        let tx_quota_used = match CitaExecutive::new(
+        // The fee of a failed transaction is charged out of the executive.
+        self.record_account(t.sender());
+        self.record_account(&context.coin_base);
+        let mut executive = CitaExecutive::new(
             Arc::new(block_data_provider),
             self.state.clone(),
             &context,
             conf.economical_model,
-        )
-        .exec(t, &conf)
-        {
+            self.native_factory.clone(),
+        );
+        if let Some(ref recorder) = self.access_recorder {
+            executive = executive.with_access_recorder(recorder.clone());
+        }
+        executive = executive.with_write_recorder(self.written_accounts.clone());
+        if let Some(ref cache) = self.state_cache {
+            executive = executive.with_state_cache(cache.clone());
+        }
+
+        let tx_quota_used = match executive.exec(t, &conf) {
             Ok(ret) => {
                 // Note: ret.quota_used was a current transaction quota used.
                 // FIXME: hasn't handle some errors
@@ -190,6 +236,9 @@ impl ExecutedBlock {
                     ExecutedException::VM(VMError::Evm(EVMError::OutOfBounds)) => {
                         Some(ReceiptError::OutOfBounds)
                     }
+                    ExecutedException::Authentication(AuthenticationError::NoCallPermission) => {
+                        Some(ReceiptError::NoCallPermission)
+                    }
                     ExecutedException::Reverted => Some(ReceiptError::Reverted),
                     _ => Some(ReceiptError::Internal),
                 });
@@ -216,6 +265,7 @@ impl ExecutedBlock {
                 );
 
                 self.receipts.push(receipt);
+                self.fee_report.merge(&ret.fee_report);
                 tx_quota_used
             }
             Err(err) => {
@@ -265,6 +315,7 @@ impl ExecutedBlock {
                         &context.coin_base,
                         tx_quota_used,
                         t.gas_price(),
+                        &conf,
                     );
                 }
 
@@ -298,11 +349,12 @@ impl ExecutedBlock {
     }
 
     fn deal_err_quota_cost(
-        &self,
+        &mut self,
         sender: &Address,
         coin_base: &Address,
         quota: U256,
         quota_price: U256,
+        conf: &BlockSysConfig,
     ) -> U256 {
         if quota_price == U256::zero() {
             return quota;
@@ -320,7 +372,17 @@ impl ExecutedBlock {
         {
             error!("Sub balance failed. tx_fee: {:?}", real_fee);
         } else {
-            let _ = self.state.borrow_mut().add_balance(&coin_base, real_fee);
+            let fee_report =
+                conf.fee_distribution
+                    .split(real_fee, coin_base, &conf.validator_stakes);
+            if !fee_report.recipients.contains_key(coin_base) {
+                let _ = self.state.borrow_mut().add_balance(coin_base, U256::zero());
+            }
+            for (recipient, amount) in &fee_report.recipients {
+                self.record_account(recipient);
+                let _ = self.state.borrow_mut().add_balance(recipient, *amount);
+            }
+            self.fee_report.merge(&fee_report);
         }
         if real_fee == sender_balance {
             sender_balance.checked_div(quota_price).unwrap()
@@ -329,6 +391,31 @@ impl ExecutedBlock {
         }
     }
 
+    /// Apply the transactions before the one at the index, then diff the accounts and
+    /// storage keys accessed by that one. The state should never be committed.
+    pub fn replay_transaction(
+        &mut self,
+        index: usize,
+        sys_config: &GlobalSysConfig,
+    ) -> Option<StateDiff> {
+        let transactions = self.transactions().get(..=index)?.to_vec();
+        let (target, earlier) = transactions.split_last()?;
+        for t in earlier {
+            self.apply_transaction(t, sys_config);
+        }
+
+        self.record_state_diff();
+        // Backup used to read the values before the execution.
+        self.state.borrow_mut().checkpoint();
+        self.apply_transaction(target, sys_config);
+
+        let accessed = self.access_recorder.as_ref()?.borrow().clone();
+        let after = state_diff::snapshot(&mut self.state.borrow_mut(), &accessed);
+        self.state.borrow_mut().revert_checkpoint();
+        let before = state_diff::snapshot(&mut self.state.borrow_mut(), &accessed);
+        Some(state_diff::diff(&before, &after))
+    }
+
     /// Turn this into a `ClosedBlock`.
     pub fn close(self, conf: &BlockSysConfig) -> ClosedBlock {
         let mut context = self.get_context();
@@ -341,10 +428,24 @@ impl ExecutedBlock {
         }
 
         if conf.auto_exec {
-            auto_exec(Arc::clone(&self.state), conf.auto_exec_quota_limit, context);
+            auto_exec(
+                Arc::clone(&self.state),
+                conf.auto_exec_quota_limit,
+                context,
+                self.native_factory.clone(),
+                self.access_recorder.clone(),
+                Some(self.written_accounts.clone()),
+            );
             self.state.borrow_mut().commit().expect("commit trie error");
         }
 
+        // The block is kept without its state diff if the diff failed.
+        let state_diff = self.access_recorder.as_ref().and_then(|recorder| {
+            self.state_diff(&recorder.borrow())
+                .map_err(|err| error!("Diff the state of the block failed: {:?}", err))
+                .ok()
+        });
+
         // Rebuild block
         let mut block = Block::new(self.block);
         let state_root = self.state.borrow().root;
@@ -384,13 +485,29 @@ impl ExecutedBlock {
         .expect("Get state from trie db");
 
         state.cache = RefCell::new(self.state.borrow_mut().cache.to_owned().into_inner());
+        let written_accounts = self.written_accounts.borrow().clone();
 
         ClosedBlock {
             block,
             receipts: self.receipts,
+            parent_state_root: self.state_root,
             state,
+            state_diff,
+            written_accounts,
+            fee_report: self.fee_report,
         }
     }
+
+    /// Diff the accessed accounts between the parent state and the committed state.
+    /// Fresh states are used, so the cache of the `ClosedBlock` is kept untouched.
+    fn state_diff(&self, accessed: &AccessList) -> Result<StateDiff, ExecutionError> {
+        let db = Arc::<CitaTrieDB>::clone(&self.state.borrow().db);
+        let mut before = CitaState::from_existing(Arc::<CitaTrieDB>::clone(&db), self.state_root)?;
+        let mut after = CitaState::from_existing(db, self.state.borrow().root)?;
+        let before = state_diff::snapshot(&mut before, accessed);
+        let after = state_diff::snapshot(&mut after, accessed);
+        Ok(state_diff::changed_only(state_diff::diff(&before, &after)))
+    }
 }
 
 // Block that prepared to commit to db.
@@ -400,7 +517,15 @@ pub struct ClosedBlock {
     /// Protobuf Block
     pub block: Block,
     pub receipts: Vec<Receipt>,
+    /// The state root the block is executed on.
+    pub parent_state_root: H256,
     pub state: CitaState<CitaTrieDB>,
+    /// Only kept when the state diff is recorded.
+    pub state_diff: Option<StateDiff>,
+    /// Accounts whose storage or code are written by the block.
+    pub written_accounts: WrittenAccounts,
+    /// The fees of the block, empty in Quota mode.
+    pub fee_report: FeeReport,
 }
 
 impl ClosedBlock {
@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cita_types::Address;
+use cita_types::{Address, U256};
 use libproto::request::Call;
+use types::call::CallParams;
 use types::Bytes;
 
+pub use types::call::{AccountOverride, CallOverrides, ContextOverride};
+
 /// Call request
 #[derive(Debug, Default, PartialEq)]
 pub struct CallRequest {
@@ -23,6 +26,12 @@ pub struct CallRequest {
     pub from: Option<Address>,
     /// To
     pub to: Address,
+    /// Quota
+    pub gas: Option<U256>,
+    /// Quota price
+    pub gas_price: Option<U256>,
+    /// Value
+    pub value: Option<U256>,
     /// Data
     pub data: Option<Bytes>,
 }
@@ -41,9 +50,20 @@ impl From<Call> for CallRequest {
             } else {
                 Some(call.data)
             },
+            ..Default::default()
         }
     }
 }
 
-#[cfg(test)]
-mod tests {}
+impl From<CallParams> for CallRequest {
+    fn from(params: CallParams) -> Self {
+        CallRequest {
+            from: params.from,
+            to: params.to,
+            gas: params.gas,
+            gas_price: params.gas_price,
+            value: params.value,
+            data: params.data.filter(|data| !data.is_empty()),
+        }
+    }
+}
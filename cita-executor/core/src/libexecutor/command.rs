@@ -15,23 +15,25 @@
 use super::economical_model::EconomicalModel;
 use super::executor::CitaTrieDB;
 use super::executor::{make_consensus_config, Executor};
-use super::sys_config::GlobalSysConfig;
+use super::state_diff::{self, StateDiff};
+use super::sys_config::{BlockSysConfig, GlobalSysConfig, SysConfigSection};
 use crate::cita_executive::{CitaExecutive, ExecutedResult as CitaExecuted};
 use crate::contracts::solc::{
-    sys_config::ChainId, PermissionManagement, SysConfig, VersionManager,
+    sys_config::ChainId, EmergencyIntervention, PriceManagement, SysConfig, VersionManager,
 };
+use crate::data_provider::AccessList;
 use crate::libexecutor::block::EVMBlockDataProvider;
 pub use crate::libexecutor::block::*;
-use crate::libexecutor::call_request::CallRequest;
+use crate::libexecutor::call_request::{CallOverrides, CallRequest};
 use crate::trie_db::TrieDB;
 use crate::types::block_number::{BlockTag, Tag};
 use crate::types::context::Context;
 use crate::types::errors::CallError;
 use crate::types::errors::ExecutionError;
+use crate::types::raw_message::Query;
 use crate::types::transaction::{Action, SignedTransaction, Transaction};
 pub use byteorder::{BigEndian, ByteOrder};
 use cita_database::RocksDB;
-use cita_types::traits::LowerHex;
 use cita_types::{Address, H256, U256};
 use cita_vm::state::{State as CitaState, StateObjectInfo};
 use crossbeam_channel::{Receiver, Sender};
@@ -40,6 +42,8 @@ use jsonrpc_types::rpc_types::{
     MetaData,
 };
 use libproto::ExecutedResult;
+use rustc_hex::ToHex;
+use serde_json::Value;
 use std::cell::RefCell;
 use std::convert::{From, Into};
 use std::fmt;
@@ -56,18 +60,35 @@ pub enum Command {
     BalanceAt(Address, BlockTag),
     NonceAt(Address, BlockTag),
     ETHCall(CallRequest, BlockTag),
+    StateDiff(CallRequest, BlockTag),
+    TransactionStateDiff(OpenBlock, H256),
+    StateDiffAt(u64),
     EstimateQuota(CallRequest, BlockTag),
     SignCall(CallRequest),
     Call(SignedTransaction, BlockTag),
     ChainID,
     Metadata(String),
     EconomicalModel,
+    FrozenAccounts,
+    QuotaPrices(u64),
+    Query(Query),
     LoadExecutedResult(u64),
     Grow(ClosedBlock),
     Exit(BlockTag),
     CloneExecutorReader,
 }
 
+impl Command {
+    /// Whether the command only reads the committed state,
+    /// so it could be answered by a reader instead of the executor.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            Command::Grow(_) | Command::Exit(_) | Command::CloneExecutorReader => false,
+            _ => true,
+        }
+    }
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::large_enum_variant))]
 pub enum CommandResp {
     StateAt(Option<CitaState<CitaTrieDB>>),
@@ -77,16 +98,24 @@ pub enum CommandResp {
     BalanceAt(Option<Bytes>),
     NonceAt(Option<U256>),
     ETHCall(Result<Bytes, String>),
+    StateDiff(Result<StateDiff, String>),
+    TransactionStateDiff(Result<StateDiff, CallError>),
+    StateDiffAt(Option<StateDiff>),
     EstimateQuota(Result<Bytes, String>),
     SignCall(SignedTransaction),
     Call(Result<CitaExecuted, CallError>),
     ChainID(Option<ChainId>),
     Metadata(Result<MetaData, String>),
     EconomicalModel(EconomicalModel),
+    FrozenAccounts(Vec<Address>),
+    QuotaPrices(Vec<(u64, U256)>),
+    Query(Result<Value, String>),
     LoadExecutedResult(ExecutedResult),
     Grow(ExecutedResult),
     Exit,
     CloneExecutorReader(Executor),
+    /// A reader refused the command, which is not read-only.
+    Rejected(String),
 }
 
 impl fmt::Display for Command {
@@ -99,12 +128,18 @@ impl fmt::Display for Command {
             Command::BalanceAt(_, _) => write!(f, "Command::BalanceAt"),
             Command::NonceAt(_, _) => write!(f, "Command::NonceAt"),
             Command::ETHCall(_, _) => write!(f, "Command::ETHCall"),
+            Command::StateDiff(_, _) => write!(f, "Command::StateDiff"),
+            Command::TransactionStateDiff(_, _) => write!(f, "Command::TransactionStateDiff"),
+            Command::StateDiffAt(_) => write!(f, "Command::StateDiffAt"),
             Command::EstimateQuota(_, _) => write!(f, "Command::EstimateQuota"),
             Command::SignCall(_) => write!(f, "Command::SignCall"),
             Command::Call(_, _) => write!(f, "Command::Call"),
             Command::ChainID => write!(f, "Command::ChainID "),
             Command::Metadata(_) => write!(f, "Command::Metadata"),
             Command::EconomicalModel => write!(f, "Command::EconomicalModel"),
+            Command::FrozenAccounts => write!(f, "Command::FrozenAccounts"),
+            Command::QuotaPrices(_) => write!(f, "Command::QuotaPrices"),
+            Command::Query(_) => write!(f, "Command::Query"),
             Command::LoadExecutedResult(_) => write!(f, "Command::LoadExecutedResult"),
             Command::Grow(_) => write!(f, "Command::Grow"),
             Command::Exit(_) => write!(f, "Command::Exit"),
@@ -123,16 +158,23 @@ impl fmt::Display for CommandResp {
             CommandResp::BalanceAt(_) => write!(f, "CommandResp::BalanceAt"),
             CommandResp::NonceAt(_) => write!(f, "CommandResp::NonceAt"),
             CommandResp::ETHCall(_) => write!(f, "CommandResp::ETHCall"),
+            CommandResp::StateDiff(_) => write!(f, "CommandResp::StateDiff"),
+            CommandResp::TransactionStateDiff(_) => write!(f, "CommandResp::TransactionStateDiff"),
+            CommandResp::StateDiffAt(_) => write!(f, "CommandResp::StateDiffAt"),
             CommandResp::EstimateQuota(_) => write!(f, "CommandResp::EstimateQuota"),
             CommandResp::SignCall(_) => write!(f, "CommandResp::SignCall"),
             CommandResp::Call(_) => write!(f, "CommandResp::Call"),
             CommandResp::ChainID(_) => write!(f, "CommandResp::ChainID "),
             CommandResp::Metadata(_) => write!(f, "CommandResp::Metadata"),
             CommandResp::EconomicalModel(_) => write!(f, "CommandResp::EconomicalModel"),
+            CommandResp::FrozenAccounts(_) => write!(f, "CommandResp::FrozenAccounts"),
+            CommandResp::QuotaPrices(_) => write!(f, "CommandResp::QuotaPrices"),
+            CommandResp::Query(_) => write!(f, "CommandResp::Query"),
             CommandResp::LoadExecutedResult(_) => write!(f, "CommandResp::LoadExecutedResult"),
             CommandResp::Grow(_) => write!(f, "CommandResp::Grow"),
             CommandResp::Exit => write!(f, "CommandResp::Exit"),
             CommandResp::CloneExecutorReader(_) => write!(f, "CommandResp::CloneExecurorReader"),
+            CommandResp::Rejected(_) => write!(f, "CommandResp::Rejected"),
         }
     }
 }
@@ -146,12 +188,30 @@ pub trait Commander {
     fn balance_at(&self, address: &Address, block_tag: BlockTag) -> Option<Bytes>;
     fn nonce_at(&self, address: &Address, block_tag: BlockTag) -> Option<U256>;
     fn eth_call(&self, request: CallRequest, block_tag: BlockTag) -> Result<Bytes, String>;
+    fn eth_call_with_overrides(
+        &self,
+        request: CallRequest,
+        block_tag: BlockTag,
+        overrides: CallOverrides,
+    ) -> Result<Bytes, String>;
+    fn state_diff(&self, request: CallRequest, block_tag: BlockTag) -> Result<StateDiff, String>;
     fn estimate_quota(&self, request: CallRequest, block_tag: BlockTag) -> Result<Bytes, String>;
     fn sign_call(&self, request: CallRequest) -> SignedTransaction;
     fn call(&self, t: &SignedTransaction, block_tag: BlockTag) -> Result<CitaExecuted, CallError>;
+    fn call_with_overrides(
+        &self,
+        t: &SignedTransaction,
+        block_tag: BlockTag,
+        overrides: &CallOverrides,
+    ) -> Result<CitaExecuted, CallError>;
+    fn transaction_state_diff(&self, block: OpenBlock, hash: H256) -> Result<StateDiff, CallError>;
+    fn state_diff_at(&self, height: u64) -> Option<StateDiff>;
     fn chain_id(&self) -> Option<ChainId>;
     fn metadata(&self, data: String) -> Result<MetaData, String>;
     fn economical_model(&self) -> EconomicalModel;
+    fn frozen_accounts(&self) -> Vec<Address>;
+    fn quota_prices(&self, from: u64) -> Vec<(u64, U256)>;
+    fn query(&self, query: Query) -> Result<Value, String>;
     fn load_executed_result(&self, height: u64) -> ExecutedResult;
     fn grow(&mut self, closed_block: &ClosedBlock) -> ExecutedResult;
     fn exit(&mut self, rollback_id: BlockTag);
@@ -180,6 +240,13 @@ impl Commander for Executor {
             Command::ETHCall(call_request, block_tag) => {
                 CommandResp::ETHCall(self.eth_call(call_request, block_tag))
             }
+            Command::StateDiff(call_request, block_tag) => {
+                CommandResp::StateDiff(self.state_diff(call_request, block_tag))
+            }
+            Command::TransactionStateDiff(block, hash) => {
+                CommandResp::TransactionStateDiff(self.transaction_state_diff(block, hash))
+            }
+            Command::StateDiffAt(height) => CommandResp::StateDiffAt(self.state_diff_at(height)),
             Command::EstimateQuota(call_request, block_tag) => {
                 CommandResp::EstimateQuota(self.estimate_quota(call_request, block_tag))
             }
@@ -190,12 +257,15 @@ impl Commander for Executor {
             Command::ChainID => CommandResp::ChainID(self.chain_id()),
             Command::Metadata(data) => CommandResp::Metadata(self.metadata(data)),
             Command::EconomicalModel => CommandResp::EconomicalModel(self.economical_model()),
+            Command::FrozenAccounts => CommandResp::FrozenAccounts(self.frozen_accounts()),
+            Command::QuotaPrices(from) => CommandResp::QuotaPrices(self.quota_prices(from)),
+            Command::Query(query) => CommandResp::Query(self.query(query)),
             Command::LoadExecutedResult(height) => {
                 CommandResp::LoadExecutedResult(self.load_executed_result(height))
             }
-            Command::Grow(mut closed_block) => {
+            Command::Grow(closed_block) => {
                 let r = self.grow(&closed_block);
-                closed_block.clear_cache();
+                self.keep_state_cache(closed_block);
                 CommandResp::Grow(r)
             }
             Command::Exit(rollback_id) => {
@@ -253,9 +323,31 @@ impl Commander for Executor {
             .or_else(|e| Err(format!("Call Error {}", e)))
     }
 
+    fn eth_call_with_overrides(
+        &self,
+        request: CallRequest,
+        id: BlockTag,
+        overrides: CallOverrides,
+    ) -> Result<Bytes, String> {
+        let signed = self.sign_call(request);
+        let result = self.call_with_overrides(&signed, id, &overrides);
+        result
+            .map(|b| b.output)
+            .or_else(|e| Err(format!("Call Error {}", e)))
+    }
+
+    fn state_diff(&self, request: CallRequest, id: BlockTag) -> Result<StateDiff, String> {
+        let signed = self.sign_call(request);
+        self.call_state_diff(&signed, id)
+            .or_else(|e| Err(format!("Call Error {}", e)))
+    }
+
     fn estimate_quota(&self, request: CallRequest, id: BlockTag) -> Result<Bytes, String> {
-        // The estimated transaction cost cannot exceed BQL
-        let max_quota = U256::from(self.sys_config.block_quota_limit);
+        // The estimated transaction cost cannot exceed BQL, nor the quota of the call
+        let max_quota = request
+            .gas
+            .unwrap_or_else(U256::max_value)
+            .min(U256::from(self.sys_config.block_quota_limit));
         let precision = U256::from(1024);
 
         let signed = self.sign_call(request);
@@ -304,6 +396,7 @@ impl Commander for Executor {
                 state,
                 &context.clone(),
                 clone_conf.economical_model,
+                self.native_factory.clone(),
             )
             .exec(&tx, &clone_conf)
         };
@@ -372,9 +465,9 @@ impl Commander for Executor {
         Transaction {
             nonce: "".to_string(),
             action: Action::Call(request.to),
-            gas: U256::from(50_000_000),
-            gas_price: U256::zero(),
-            value: U256::zero(),
+            gas: request.gas.unwrap_or_else(|| U256::from(50_000_000)),
+            gas_price: request.gas_price.unwrap_or_else(U256::zero),
+            value: request.value.unwrap_or_else(U256::zero),
             data: request.data.map_or_else(Vec::new, |d| d.to_vec()),
             block_limit: u64::max_value(),
             chain_id: U256::default(),
@@ -384,63 +477,80 @@ impl Commander for Executor {
     }
 
     fn call(&self, t: &SignedTransaction, block_tag: BlockTag) -> Result<CitaExecuted, CallError> {
-        let header = self.block_header(block_tag).ok_or(CallError::StatePruned)?;
-        let last_hashes = self.build_last_hashes(Some(header.hash().unwrap()), header.number());
-        let mut context = Context {
-            block_number: header.number(),
-            coin_base: *header.proposer(),
-            timestamp: if self.eth_compatibility {
-                header.timestamp() / 1000
-            } else {
-                header.timestamp()
-            },
-            difficulty: U256::default(),
-            last_hashes: ::std::sync::Arc::new(last_hashes),
-            quota_used: *header.quota_used(),
-            block_quota_limit: *header.quota_limit(),
-            account_quota_limit: u64::max_value().into(),
-        };
-        context.block_quota_limit = U256::from(self.sys_config.block_quota_limit);
-
-        // FIXME: Need to implement state_at
-        // that's just a copy of the state.
-        //        let mut state = self.state_at(block_tag).ok_or(CallError::StatePruned)?;
-
-        // Never check permission and quota
-        let mut conf = self.sys_config.block_sys_config.clone();
-        conf.exempt_checking();
+        self.call_with_overrides(t, block_tag, &CallOverrides::default())
+    }
 
+    /// Run the transaction at the block, with the state and context overridden.
+    /// The overrides are applied to a throwaway state, which is never committed.
+    fn call_with_overrides(
+        &self,
+        t: &SignedTransaction,
+        block_tag: BlockTag,
+        overrides: &CallOverrides,
+    ) -> Result<CitaExecuted, CallError> {
+        let (context, conf, state) = self.prepare_call(block_tag, overrides)?;
         let block_data_provider = EVMBlockDataProvider::new(context.clone());
-
-        let state_root = if let Some(h) = self.block_header(block_tag) {
-            *h.state_root()
-        } else {
-            error!("Can not get state root from trie db!");
-            return Err(CallError::StatePruned);
-        };
-
-        let state = match CitaState::from_existing(
-            Arc::<TrieDB<RocksDB>>::clone(&self.state_db),
-            state_root,
-        ) {
-            Ok(state_db) => state_db,
-            Err(e) => {
-                error!("Can not get state from trie db! error: {:?}", e);
-                return Err(CallError::StatePruned);
-            }
-        };
-
         let state = Arc::new(RefCell::new(state));
         CitaExecutive::new(
             Arc::new(block_data_provider),
             state,
             &context,
             conf.economical_model,
+            self.native_factory.clone(),
         )
         .exec(t, &conf)
         .map_err(Into::into)
     }
 
+    /// Replay the transaction of the block on the state of its parent, after the
+    /// transactions before it, and diff the accessed accounts and storage keys
+    /// between the states before and after its execution.
+    /// The block is the one kept by the chain, as the executor has no bodies of blocks.
+    fn transaction_state_diff(&self, block: OpenBlock, hash: H256) -> Result<StateDiff, CallError> {
+        let index = block
+            .body()
+            .transactions()
+            .iter()
+            .position(|t| t.get_transaction_hash() == hash)
+            .ok_or(CallError::TransactionNotFound)?;
+        let parent_number = block
+            .number()
+            .checked_sub(1)
+            .ok_or(CallError::TransactionNotFound)?;
+        let parent = self
+            .block_header_by_height(parent_number)
+            .filter(|parent| parent.hash() == Some(*block.parent_hash()))
+            .ok_or(CallError::StatePruned)?;
+        let parent_state_root = *parent.state_root();
+        if CitaState::from_existing(Arc::<CitaTrieDB>::clone(&self.state_db), parent_state_root)
+            .is_err()
+        {
+            return Err(CallError::StatePruned);
+        }
+
+        // The system config in effect when the block was executed.
+        let sys_config = GlobalSysConfig::load(&self, BlockTag::Height(parent_number));
+        let last_hashes = self.build_last_hashes(None, parent_number);
+        let mut executed_block = ExecutedBlock::create(
+            &sys_config.block_sys_config,
+            block,
+            Arc::<CitaTrieDB>::clone(&self.state_db),
+            parent_state_root,
+            last_hashes.into(),
+            self.eth_compatibility,
+            self.native_factory.clone(),
+        )
+        .map_err(|_| CallError::StateCorrupt)?;
+        executed_block
+            .replay_transaction(index, &sys_config)
+            .ok_or(CallError::TransactionNotFound)
+    }
+
+    /// Get the recorded state diff of a block.
+    fn state_diff_at(&self, height: u64) -> Option<StateDiff> {
+        self.state_diff_by_height(height)
+    }
+
     fn chain_id(&self) -> Option<ChainId> {
         let version_manager = VersionManager::new(&self);
         let system_config = SysConfig::new(&self);
@@ -547,6 +657,82 @@ impl Commander for Executor {
         self.sys_config.block_sys_config.economical_model
     }
 
+    fn frozen_accounts(&self) -> Vec<Address> {
+        let mut accounts: Vec<Address> = self
+            .sys_config
+            .block_sys_config
+            .frozen_accounts
+            .iter()
+            .cloned()
+            .collect();
+        accounts.sort();
+        accounts
+    }
+
+    /// The quota prices of `PriceManager` paid by the blocks from `from` up to the next one,
+    /// with the heights they are changed at.
+    fn quota_prices(&self, from: u64) -> Vec<(u64, U256)> {
+        let price_management = PriceManagement::new(self);
+        let mut prices: Vec<(u64, U256)> = Vec::new();
+        for height in from..=self.get_current_height() + 1 {
+            // A block pays the price at the state of its parent.
+            let price = price_management
+                .quota_price(BlockTag::Height(height.saturating_sub(1)))
+                .unwrap_or_else(PriceManagement::default_quota_price);
+            if prices.last().map(|(_, last)| *last) != Some(price) {
+                prices.push((height, price));
+            }
+        }
+        prices
+    }
+
+    /// Answer a query of the CITA namespace, the result is in JSON.
+    fn query(&self, query: Query) -> Result<Value, String> {
+        match query {
+            Query::SysConfigChanges { from, to } => {
+                if from > to {
+                    return Err(format!("Invalid height range [{}, {}]", from, to));
+                }
+                serde_json::to_value(self.sys_config_changes(from, to))
+                    .map_err(|err| format!("Serialize sys config changes error: {}", err))
+            }
+            Query::FeeReport { height } => {
+                if height > self.get_current_height() {
+                    return Err(format!("Block {} is not executed yet", height));
+                }
+                serde_json::to_value(self.fee_report_by_height(height))
+                    .map_err(|err| format!("Serialize fee report error: {}", err))
+            }
+            Query::FrozenAccounts { block_tag } => EmergencyIntervention::new(self)
+                .frozen_accounts(block_tag)
+                .ok_or_else(|| "Query frozen accounts failed".to_owned())
+                .and_then(|accounts| {
+                    serde_json::to_value(accounts)
+                        .map_err(|err| format!("Serialize frozen accounts error: {}", err))
+                }),
+            Query::StateDiff { height } => {
+                if height > self.get_current_height() {
+                    return Err(format!("Block {} is not executed yet", height));
+                }
+                let state_diff = self
+                    .state_diff_at(height)
+                    .ok_or_else(|| format!("State diff of block {} is not recorded", height))?;
+                serde_json::to_value(state_diff)
+                    .map_err(|err| format!("Serialize state diff error: {}", err))
+            }
+            Query::Call {
+                request,
+                block_tag,
+                overrides,
+            } => self
+                .eth_call_with_overrides(CallRequest::from(request), block_tag, overrides)
+                .map(|output| Value::from(format!("0x{}", output.to_hex()))),
+            Query::EstimateQuota { request, block_tag } => self
+                .estimate_quota(CallRequest::from(request), block_tag)
+                .map(|quota| Value::from(format!("{:#x}", U256::from(&quota[..])))),
+        }
+    }
+
     fn load_executed_result(&self, height: u64) -> ExecutedResult {
         self.executed_result_by_height(height)
     }
@@ -563,17 +749,6 @@ impl Commander for Executor {
             closed_block.transactions_root(),
             closed_block.proposer(),
         );
-        let are_permissions_changed = {
-            let cache = closed_block.state.cache.clone();
-            let permission_management = PermissionManagement::new(self);
-            let permissions =
-                permission_management.permission_addresses(BlockTag::Tag(Tag::Pending));
-            cache.into_inner().iter().any(|(address, ref _a)| {
-                &address.lower_hex()[..34] == "ffffffffffffffffffffffffffffffffff"
-                    || permissions.contains(&address)
-            })
-        };
-
         {
             *self.current_header.write() = closed_block.header().clone();
         }
@@ -583,14 +758,34 @@ impl Commander for Executor {
         // Must make sure write into database before load_sys_config
         self.write_batch(closed_block);
 
-        if are_permissions_changed {
-            trace!("Permissions changed, reload global sys config.");
-            self.sys_config = GlobalSysConfig::load(&self, BlockTag::Tag(Tag::Pending));
+        let block_tag = BlockTag::Tag(Tag::Pending);
+        let sections = SysConfigSection::changed_by(
+            &self,
+            &self.sys_config,
+            &closed_block.written_accounts,
+            block_tag,
+        );
+        if !sections.is_empty() {
+            trace!(
+                "System contracts changed, reload {:?} of global sys config.",
+                sections
+            );
+            let mut sys_config = self.sys_config.clone();
+            sys_config.reload(&self, block_tag, &sections);
+            self.record_sys_config_change(closed_block.number(), &self.sys_config, &sys_config);
+            self.sys_config = sys_config;
+        }
+        if cfg!(debug_assertions) {
+            // The incremental reload must be the same as a full one.
+            let mut full = GlobalSysConfig::load(&self, block_tag);
+            full.changed_height = self.sys_config.changed_height;
+            debug_assert_eq!(self.sys_config, full, "Global sys config is not reloaded.");
         }
         let mut executed_result = ExecutedResult::new();
         let consensus_config = make_consensus_config(self.sys_config.clone());
         executed_result.set_config(consensus_config);
         executed_result.set_executed_info(executed_info);
+        self.publish_committed();
         executed_result
     }
 
@@ -605,25 +800,121 @@ impl Commander for Executor {
         let db = self.db.clone();
         // let fake_parent_hash: H256 = Default::default();
         let sys_config = self.sys_config.clone();
+        let committed = self.committed.clone();
         let fsm_req_receiver = self.fsm_req_receiver.clone();
         let fsm_resp_sender = self.fsm_resp_sender.clone();
         let command_req_receiver = self.command_req_receiver.clone();
         let command_resp_sender = self.command_resp_sender.clone();
         let eth_compatibility = self.eth_compatibility;
+        let record_state_diff = self.record_state_diff;
+        let native_factory = self.native_factory.clone();
+        let state_cache = self.state_cache.clone();
         Executor {
             current_header: RwLock::new(current_header),
             state_db,
             db,
             sys_config,
+            committed,
             fsm_req_receiver,
             fsm_resp_sender,
             command_req_receiver,
             command_resp_sender,
             eth_compatibility,
+            record_state_diff,
+            native_factory,
+            state_cache,
         }
     }
 }
 
+impl Executor {
+    /// Run the transaction at the block, and diff the accessed accounts and
+    /// storage keys between the states before and after the execution.
+    fn call_state_diff(
+        &self,
+        t: &SignedTransaction,
+        block_tag: BlockTag,
+    ) -> Result<StateDiff, CallError> {
+        let (context, conf, state) = self.prepare_call(block_tag, &CallOverrides::default())?;
+        let block_data_provider = EVMBlockDataProvider::new(context.clone());
+        let state = Arc::new(RefCell::new(state));
+        let recorder = Arc::new(RefCell::new(AccessList::new()));
+
+        // Backup used to read the values before the execution.
+        state.borrow_mut().checkpoint();
+        let result = CitaExecutive::new(
+            Arc::new(block_data_provider),
+            state.clone(),
+            &context,
+            conf.economical_model,
+            self.native_factory.clone(),
+        )
+        .with_access_recorder(recorder.clone())
+        .exec(t, &conf);
+
+        let accessed = recorder.borrow().clone();
+        let after = state_diff::snapshot(&mut state.borrow_mut(), &accessed);
+        state.borrow_mut().revert_checkpoint();
+        let before = state_diff::snapshot(&mut state.borrow_mut(), &accessed);
+
+        result?;
+        Ok(state_diff::diff(&before, &after))
+    }
+
+    /// Prepare the context, the exempted config and the state at the block
+    /// for a call, with the state and context overridden.
+    /// The overrides are applied to a throwaway state, which is never committed.
+    fn prepare_call(
+        &self,
+        block_tag: BlockTag,
+        overrides: &CallOverrides,
+    ) -> Result<(Context, BlockSysConfig, CitaState<CitaTrieDB>), CallError> {
+        let header = self.block_header(block_tag).ok_or(CallError::StatePruned)?;
+        let last_hashes = self.build_last_hashes(Some(header.hash().unwrap()), header.number());
+        let mut context = Context {
+            block_number: header.number(),
+            coin_base: *header.proposer(),
+            timestamp: if self.eth_compatibility {
+                header.timestamp() / 1000
+            } else {
+                header.timestamp()
+            },
+            difficulty: U256::default(),
+            last_hashes: ::std::sync::Arc::new(last_hashes),
+            quota_used: *header.quota_used(),
+            block_quota_limit: *header.quota_limit(),
+            account_quota_limit: u64::max_value().into(),
+        };
+        context.block_quota_limit = U256::from(self.sys_config.block_quota_limit);
+        overrides.apply_to_context(&mut context);
+
+        // FIXME: Need to implement state_at
+        // that's just a copy of the state.
+        //        let mut state = self.state_at(block_tag).ok_or(CallError::StatePruned)?;
+
+        // Never check permission and quota
+        let mut conf = self.sys_config.block_sys_config.clone();
+        conf.exempt_checking();
+
+        let state_root = *header.state_root();
+        let mut state = match CitaState::from_existing(
+            Arc::<TrieDB<RocksDB>>::clone(&self.state_db),
+            state_root,
+        ) {
+            Ok(state_db) => state_db,
+            Err(e) => {
+                error!("Can not get state from trie db! error: {:?}", e);
+                return Err(CallError::StatePruned);
+            }
+        };
+        overrides
+            .apply_to_state(&mut state)
+            .map_err(|e| CallError::Execution(ExecutionError::Internal(e)))?;
+
+        Ok((context, conf, state))
+    }
+}
+
 // TODO hope someone refactor these public function via macro
 
 pub fn state_at(
@@ -716,6 +1007,44 @@ pub fn eth_call(
     }
 }
 
+pub fn state_diff(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+    call_request: CallRequest,
+    block_tag: BlockTag,
+) -> Result<StateDiff, String> {
+    let _ = command_req_sender.send(Command::StateDiff(call_request, block_tag));
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::StateDiff(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
+pub fn transaction_state_diff(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+    block: OpenBlock,
+    hash: H256,
+) -> Result<StateDiff, CallError> {
+    let _ = command_req_sender.send(Command::TransactionStateDiff(block, hash));
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::TransactionStateDiff(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
+pub fn state_diff_at(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+    height: u64,
+) -> Option<StateDiff> {
+    let _ = command_req_sender.send(Command::StateDiffAt(height));
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::StateDiffAt(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
 pub fn estimate_quota(
     command_req_sender: &Sender<Command>,
     command_resp_receiver: &Receiver<CommandResp>,
@@ -789,6 +1118,41 @@ pub fn economical_model(
     }
 }
 
+pub fn frozen_accounts(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+) -> Vec<Address> {
+    let _ = command_req_sender.send(Command::FrozenAccounts);
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::FrozenAccounts(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
+pub fn quota_prices(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+    from: u64,
+) -> Vec<(u64, U256)> {
+    let _ = command_req_sender.send(Command::QuotaPrices(from));
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::QuotaPrices(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
+pub fn query(
+    command_req_sender: &Sender<Command>,
+    command_resp_receiver: &Receiver<CommandResp>,
+    query: Query,
+) -> Result<Value, String> {
+    let _ = command_req_sender.send(Command::Query(query));
+    match command_resp_receiver.recv().unwrap() {
+        CommandResp::Query(r) => r,
+        _ => unimplemented!(),
+    }
+}
+
 pub fn load_executed_result(
     command_req_sender: &Sender<Command>,
     command_resp_receiver: &Receiver<CommandResp>,
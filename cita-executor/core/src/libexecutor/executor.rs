@@ -13,9 +13,13 @@
 // limitations under the License.
 
 use super::command::{Command, CommandResp, Commander};
+use super::fee_distribution::BlockFeeReport;
 use super::fsm::FSM;
-use super::sys_config::GlobalSysConfig;
+use super::state_cache::StateCache;
+use super::state_diff::{BlockStateDiff, StateDiff};
+use super::sys_config::{GlobalSysConfig, SysConfigChange};
 
+use crate::contracts::native::factory::Factory as NativeFactory;
 use crate::contracts::solc::NodeManager;
 use crate::core::context::LastHashes;
 use crate::header::*;
@@ -25,13 +29,18 @@ use crate::trie_db::TrieDB;
 use crate::types::block_number::{BlockTag, Tag};
 use crate::types::db_indexes;
 use crate::types::db_indexes::DBIndex;
+use crate::types::Bytes;
+use bincode::{self, Infinite};
 pub use byteorder::{BigEndian, ByteOrder};
 use cita_database::{Config, DataCategory, Database, RocksDB, NUM_COLUMNS};
 use cita_types::H256;
 use crossbeam_channel::{Receiver, Sender};
 use libproto::{ConsensusConfig, ExecutedResult};
-use rlp::{decode, encode};
+use parking_lot::Mutex;
+use rlp::{decode, decode_list, encode, encode_list};
 use std::convert::Into;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use util::RwLock;
 
@@ -43,6 +52,9 @@ pub struct Executor {
     pub state_db: Arc<CitaTrieDB>,
     pub db: Arc<dyn Database>,
     pub sys_config: GlobalSysConfig,
+    /// The header and the system config last committed by the executor,
+    /// shared with its query readers.
+    pub committed: Arc<RwLock<(Header, GlobalSysConfig)>>,
 
     pub fsm_req_receiver: Receiver<OpenBlock>,
     pub fsm_resp_sender: Sender<ClosedBlock>,
@@ -50,6 +62,12 @@ pub struct Executor {
     pub command_resp_sender: Sender<CommandResp>,
 
     pub eth_compatibility: bool,
+    /// Keep the state diff of each block in the database.
+    pub record_state_diff: bool,
+    /// The native contracts enabled by genesis.
+    pub native_factory: Arc<NativeFactory>,
+    /// The accounts and storage slots at the state of the last grown block.
+    pub state_cache: Arc<Mutex<StateCache>>,
 }
 
 impl Executor {
@@ -62,8 +80,11 @@ impl Executor {
         command_req_receiver: Receiver<Command>,
         command_resp_sender: Sender<CommandResp>,
         eth_compatibility: bool,
+        record_state_diff: bool,
+        statedb_cache_size: usize,
     ) -> Executor {
         let mut genesis = Genesis::init(&genesis_path);
+        let native_factory = Arc::new(genesis.native_factory());
 
         // TODO: Can remove NUM_COLUMNS(useless)
         let config = Config::with_category_num(NUM_COLUMNS);
@@ -84,18 +105,23 @@ impl Executor {
             }
         };
         let mut executor = Executor {
-            current_header: RwLock::new(current_header),
+            current_header: RwLock::new(current_header.clone()),
             state_db,
             db,
             sys_config: GlobalSysConfig::default(),
+            committed: Arc::new(RwLock::new((current_header, GlobalSysConfig::default()))),
             fsm_req_receiver,
             fsm_resp_sender,
             command_req_receiver,
             command_resp_sender,
             eth_compatibility,
+            record_state_diff,
+            native_factory,
+            state_cache: Arc::new(Mutex::new(StateCache::new(statedb_cache_size))),
         };
 
         executor.sys_config = GlobalSysConfig::load(&executor, BlockTag::Tag(Tag::Pending));
+        executor.publish_committed();
         info!(
             "executor init, current_height: {}, current_hash: {:?}",
             executor.get_current_height(),
@@ -141,6 +167,56 @@ impl Executor {
         }
     }
 
+    /// Create a reader which answers the read-only commands on its own channels,
+    /// see `do_query_loop`.
+    pub fn query_reader(
+        &mut self,
+        command_req_receiver: Receiver<Command>,
+        command_resp_sender: Sender<CommandResp>,
+    ) -> Executor {
+        let mut reader = self.clone_executor_reader();
+        // A reader never executes blocks.
+        let (_, fsm_req_receiver) = crossbeam_channel::unbounded();
+        let (fsm_resp_sender, _) = crossbeam_channel::unbounded();
+        reader.fsm_req_receiver = fsm_req_receiver;
+        reader.fsm_resp_sender = fsm_resp_sender;
+        reader.command_req_receiver = command_req_receiver;
+        reader.command_resp_sender = command_resp_sender;
+        reader.record_state_diff = false;
+        reader
+    }
+
+    /// Answer the read-only commands against the committed state, until the
+    /// command channel is closed. The other commands are rejected.
+    pub fn do_query_loop(&mut self) {
+        while let Ok(command) = self.command_req_receiver.recv() {
+            trace!("reader receive {}", command);
+            let command_resp = if command.is_read_only() {
+                self.follow_committed();
+                self.operate(command)
+            } else {
+                error!("reader could not handle {}", command);
+                CommandResp::Rejected(format!("{}", command))
+            };
+            let _ = self.command_resp_sender.send(command_resp);
+        }
+    }
+
+    /// Share the current header and the system config with the readers,
+    /// after they are written.
+    pub fn publish_committed(&self) {
+        *self.committed.write() = (self.current_header.read().clone(), self.sys_config.clone());
+    }
+
+    // Follow the header and the system config committed by the executor.
+    fn follow_committed(&mut self) {
+        let committed = self.committed.read();
+        if committed.0.hash() != Some(self.get_current_hash()) {
+            *self.current_header.write() = committed.0.clone();
+            self.sys_config = committed.1.clone();
+        }
+    }
+
     #[allow(clippy::zero_ptr, clippy::drop_copy)]
     fn recv(&self) -> (Option<Command>, Option<OpenBlock>) {
         let err_flag = (None, None);
@@ -185,16 +261,21 @@ impl Executor {
                     hash_value,
                 )
                 .expect("Insert rollback hash error.");
+            self.truncate_sys_config_changes(rollback_height);
+            self.remove_block_extras(rollback_height + 1..=self.get_current_height());
+            self.state_cache.lock().clear();
         }
 
         let rollback_header = self.block_header_by_height(rollback_height).unwrap();
         self.current_header = RwLock::new(rollback_header);
+        self.publish_committed();
     }
 
     /// Write data to db
     /// 1. Header
     /// 2. CurrentHash
     /// 3. State
+    /// 4. StateDiff, if recorded
     pub fn write_batch(&self, block: &ClosedBlock) {
         let height = block.number();
         let hash = block.hash().unwrap();
@@ -232,6 +313,167 @@ impl Executor {
         self.db
             .insert(Some(DataCategory::Extra), height_key.to_vec(), hash_value)
             .expect("Insert block hash error.");
+
+        // Insert [height : state_diff]
+        if let Some(ref state_diff) = block.state_diff {
+            let state_diff_key = db_indexes::BlockNumber2StateDiff(height).get_index();
+            let state_diff_value =
+                bincode::serialize(state_diff, Infinite).expect("Serialize state diff error.");
+            self.db
+                .insert(
+                    Some(DataCategory::Extra),
+                    state_diff_key.to_vec(),
+                    state_diff_value,
+                )
+                .expect("Insert state diff error.");
+        }
+
+        // Insert [height : fee_report], if the block has fees
+        if !block.fee_report.total.is_zero() {
+            let fee_report_key = db_indexes::BlockNumber2FeeReport(height).get_index();
+            let fee_report_value =
+                serde_json::to_vec(&block.fee_report).expect("Serialize fee report error.");
+            self.db
+                .insert(Some(DataCategory::Extra), fee_report_key, fee_report_value)
+                .expect("Insert fee report error.");
+        }
+    }
+
+    /// Remove the state diffs and the fee reports of the rolled back blocks,
+    /// so that none of them is left if the blocks are executed again.
+    fn remove_block_extras(&self, heights: RangeInclusive<BlockNumber>) {
+        for height in heights {
+            let state_diff_key = db_indexes::BlockNumber2StateDiff(height).get_index();
+            self.db
+                .remove(Some(DataCategory::Extra), &state_diff_key)
+                .expect("Remove state diff error.");
+            let fee_report_key = db_indexes::BlockNumber2FeeReport(height).get_index();
+            self.db
+                .remove(Some(DataCategory::Extra), &fee_report_key)
+                .expect("Remove fee report error.");
+        }
+    }
+
+    /// Get the fees of a block, empty if it has none.
+    pub fn fee_report_by_height(&self, height: BlockNumber) -> BlockFeeReport {
+        let fee_report_key = db_indexes::BlockNumber2FeeReport(height).get_index();
+        let report = self
+            .db
+            .get(Some(DataCategory::Extra), &fee_report_key)
+            .expect("Get fee report error.")
+            .and_then(|value| serde_json::from_slice(&value).ok())
+            .unwrap_or_default();
+        BlockFeeReport { height, report }
+    }
+
+    /// Get the state diff of a block, only exists when it's recorded.
+    pub fn state_diff_by_height(&self, number: BlockNumber) -> Option<StateDiff> {
+        let state_diff_key = db_indexes::BlockNumber2StateDiff(number).get_index();
+        self.db
+            .get(Some(DataCategory::Extra), &state_diff_key.to_vec())
+            .expect("Get state diff error.")
+            .and_then(|value| bincode::deserialize(&value).ok())
+    }
+
+    /// Export the recorded state diffs of the blocks in `[from, to]` as JSON lines,
+    /// returns the number of exported blocks.
+    pub fn export_state_diffs<W: Write>(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        writer: &mut W,
+    ) -> io::Result<u64> {
+        let mut exported = 0;
+        for height in from..=to {
+            if let Some(state_diff) = self.state_diff_by_height(height) {
+                let line = serde_json::to_string(&BlockStateDiff { height, state_diff })
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                writeln!(writer, "{}", line)?;
+                exported += 1;
+            }
+        }
+        Ok(exported)
+    }
+
+    /// Record the changed fields of the system config at the height, nothing if unchanged.
+    pub fn record_sys_config_change(
+        &self,
+        height: BlockNumber,
+        old: &GlobalSysConfig,
+        new: &GlobalSysConfig,
+    ) {
+        let changes = old.changes(new);
+        if changes.is_empty() {
+            return;
+        }
+        trace!("sys config changed at {}: {:?}", height, changes.keys());
+
+        let change_key = db_indexes::BlockNumber2SysConfigChange(height).get_index();
+        let change_value = serde_json::to_vec(&SysConfigChange { height, changes })
+            .expect("Serialize sys config change error.");
+        self.db
+            .insert(Some(DataCategory::Extra), change_key, change_value)
+            .expect("Insert sys config change error.");
+
+        let mut heights = self.sys_config_changed_heights();
+        heights.push(height);
+        heights.sort();
+        heights.dedup();
+        self.write_sys_config_changed_heights(&heights);
+    }
+
+    /// Heights where the system config was changed, in ascending order.
+    pub fn sys_config_changed_heights(&self) -> Vec<BlockNumber> {
+        let heights_key = db_indexes::SysConfigChangedHeights.get_index();
+        self.db
+            .get(Some(DataCategory::Extra), &heights_key)
+            .unwrap_or(None)
+            .map(|heights| decode_list(&heights))
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Changes of the system config in `[from, to]`, in the order of the heights.
+    pub fn sys_config_changes(&self, from: BlockNumber, to: BlockNumber) -> Vec<SysConfigChange> {
+        self.sys_config_changed_heights()
+            .into_iter()
+            .filter(|height| *height >= from && *height <= to)
+            .filter_map(|height| {
+                let change_key = db_indexes::BlockNumber2SysConfigChange(height).get_index();
+                self.db
+                    .get(Some(DataCategory::Extra), &change_key)
+                    .unwrap_or(None)
+                    .and_then(|change| serde_json::from_slice(&change).ok())
+            })
+            .collect()
+    }
+
+    // The changes above the height are removed, since the blocks will be executed again.
+    fn truncate_sys_config_changes(&self, height: BlockNumber) {
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .sys_config_changed_heights()
+            .into_iter()
+            .partition(|changed| *changed <= height);
+        if removed.is_empty() {
+            return;
+        }
+        for changed in removed {
+            let change_key = db_indexes::BlockNumber2SysConfigChange(changed).get_index();
+            self.db
+                .remove(Some(DataCategory::Extra), &change_key)
+                .expect("Remove sys config change error.");
+        }
+        self.write_sys_config_changed_heights(&kept);
+    }
+
+    fn write_sys_config_changed_heights(&self, heights: &[BlockNumber]) {
+        let heights_key = db_indexes::SysConfigChangedHeights.get_index();
+        self.db
+            .insert(
+                Some(DataCategory::Extra),
+                heights_key,
+                encode_list(heights).into_vec(),
+            )
+            .expect("Insert sys config changed heights error.");
     }
 
     /// Get block hash by number
@@ -373,15 +615,30 @@ impl Executor {
         let last_hashes = self.build_last_hashes(None, open_block.number() - 1);
         // let parent_hash = *open_block.parent_hash();
 
-        ExecutedBlock::create(
+        let mut executed_block = ExecutedBlock::create(
             &self.sys_config.block_sys_config,
             open_block,
             self.state_db.clone(),
             current_state_root,
             last_hashes.into(),
             self.eth_compatibility,
+            self.native_factory.clone(),
         )
-        .unwrap()
+        .unwrap();
+        if self.record_state_diff {
+            executed_block.record_state_diff();
+        }
+        executed_block.use_state_cache(self.state_cache.clone());
+        executed_block
+    }
+
+    /// Keep the state of a grown block in the state cache, for the next block.
+    pub fn keep_state_cache(&self, closed_block: ClosedBlock) {
+        self.state_cache.lock().keep(
+            closed_block.parent_state_root,
+            closed_block.state,
+            &closed_block.written_accounts,
+        );
     }
 }
 
@@ -441,12 +698,12 @@ mod tests {
     extern crate cita_logger as logger;
     extern crate tempdir;
     use crate::libexecutor::command::Commander;
-    use crate::libexecutor::command::{Command, CommandResp};
+    use crate::libexecutor::command::{self, Command, CommandResp};
     use crate::libexecutor::fsm::FSM;
     use crate::tests::helpers;
     use crate::types::block_number::{BlockTag, Tag};
     use cita_crypto::{CreateKey, KeyPair};
-    use cita_types::Address;
+    use cita_types::{Address, U256};
     use std::thread;
     use std::time::Duration;
 
@@ -455,7 +712,7 @@ mod tests {
     // fn test_chain_name_valid_block_number() {
     //     use crate::contracts::solc::sys_config::SysConfig;
     //     use crate::types::reserved_addresses;
-    //     use cita_types::H256;
+    //     use cita_types::{H256, U256};
     //     use rustc_hex::FromHex;
     //     use std::str::FromStr;
 
@@ -537,6 +794,318 @@ mod tests {
         assert_eq!(closed_block_hash, current_hash);
     }
 
+    #[test]
+    fn test_record_state_diff() {
+        use crate::types::raw_message::Query;
+
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let mut executor = helpers::init_executor();
+        executor.record_state_diff = true;
+
+        let data = helpers::generate_contract();
+        let block = helpers::create_block(&executor, Address::from(0), &data, (0, 1), &privkey);
+        let mut closed_block = executor.into_fsm(block.clone());
+        executor.grow(&closed_block);
+        closed_block.clear_cache();
+
+        let height = executor.get_current_height();
+        let state_diff = executor.state_diff_by_height(height).unwrap();
+        assert_eq!(Some(&state_diff), closed_block.state_diff.as_ref());
+        let sender = state_diff.get(&keypair.address()).unwrap();
+        assert!(sender.nonce.is_changed());
+        assert!(state_diff
+            .values()
+            .any(|account| account.code_hash.is_changed()));
+        assert!(executor.state_diff_by_height(height + 1).is_none());
+
+        let queried = executor.query(Query::StateDiff { height }).unwrap();
+        assert_eq!(queried, serde_json::to_value(&state_diff).unwrap());
+        assert!(executor
+            .query(Query::StateDiff { height: height + 1 })
+            .is_err());
+
+        let mut exported = Vec::new();
+        assert_eq!(
+            executor
+                .export_state_diffs(0, height + 1, &mut exported)
+                .unwrap(),
+            1
+        );
+        assert_eq!(String::from_utf8(exported).unwrap().lines().count(), 1);
+
+        // The diff of a rolled back block is removed with it.
+        executor.rollback_current_height(BlockTag::Height(height - 1));
+        assert!(executor.state_diff_by_height(height).is_none());
+    }
+
+    #[test]
+    fn test_transaction_state_diff() {
+        use crate::types::errors::CallError;
+        use cita_types::H256;
+
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let mut executor = helpers::init_executor();
+
+        let data = helpers::generate_contract();
+        let block = helpers::create_block(&executor, Address::from(0), &data, (0, 2), &privkey);
+        let closed_block = executor.into_fsm(block.clone());
+        executor.grow(&closed_block);
+
+        // The second transaction is replayed after the first one.
+        let hash = block.body().transactions()[1].get_transaction_hash();
+        let state_diff = executor
+            .transaction_state_diff(block.clone(), hash)
+            .unwrap();
+        let sender = state_diff.get(&keypair.address()).unwrap();
+        assert_eq!(sender.nonce.from, U256::from(1));
+        assert_eq!(sender.nonce.to, U256::from(2));
+
+        match executor.transaction_state_diff(block, H256::from(1)) {
+            Err(CallError::TransactionNotFound) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sign_call() {
+        use crate::libexecutor::call_request::CallRequest;
+
+        let executor = helpers::init_executor();
+        let request = CallRequest {
+            from: Some(Address::from(1)),
+            to: Address::from(2),
+            gas: Some(U256::from(21_000)),
+            gas_price: Some(U256::one()),
+            value: Some(U256::from(17)),
+            data: None,
+        };
+        let signed = executor.sign_call(request);
+        assert_eq!(signed.sender(), &Address::from(1));
+        assert_eq!(signed.gas, U256::from(21_000));
+        assert_eq!(signed.gas_price(), U256::one());
+        assert_eq!(signed.value, U256::from(17));
+
+        let signed = executor.sign_call(CallRequest::default());
+        assert_eq!(signed.gas, U256::from(50_000_000));
+        assert_eq!(signed.value, U256::zero());
+    }
+
+    #[test]
+    fn test_record_sys_config_change() {
+        use crate::types::raw_message::Query;
+
+        let mut executor = helpers::init_executor();
+        let old = executor.sys_config.clone();
+        let mut new = old.clone();
+        new.changed_height += 1;
+        executor.record_sys_config_change(1, &old, &new);
+        assert!(executor.sys_config_changed_heights().is_empty());
+
+        new.validators.push(Address::from(1));
+        new.block_sys_config.quota_price = U256::from(2);
+        executor.record_sys_config_change(3, &old, &new);
+        executor.record_sys_config_change(5, &new, &old);
+        assert_eq!(executor.sys_config_changed_heights(), vec![3, 5]);
+
+        let changes = executor.sys_config_changes(0, 4);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].height, 3);
+        assert_eq!(
+            changes[0].changes.keys().collect::<Vec<_>>(),
+            vec!["quota_price", "validators"]
+        );
+        assert_eq!(
+            changes[0].changes["validators"].to,
+            serde_json::to_value(&new.validators).unwrap()
+        );
+
+        let changes = executor
+            .query(Query::SysConfigChanges { from: 4, to: 5 })
+            .unwrap();
+        assert_eq!(changes[0]["height"], 5);
+        assert!(executor
+            .query(Query::SysConfigChanges { from: 5, to: 4 })
+            .is_err());
+
+        executor.truncate_sys_config_changes(4);
+        assert_eq!(executor.sys_config_changed_heights(), vec![3]);
+        assert!(executor.sys_config_changes(5, 5).is_empty());
+    }
+
+    #[test]
+    fn test_query_fee_report() {
+        use crate::types::raw_message::Query;
+
+        let executor = helpers::init_executor();
+        let height = executor.get_current_height();
+        let report = executor.query(Query::FeeReport { height }).unwrap();
+        assert_eq!(report["height"], height);
+        assert_eq!(report["total"], "0x0");
+        assert!(executor
+            .query(Query::FeeReport { height: height + 1 })
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_frozen_accounts() {
+        use crate::types::raw_message::Query;
+
+        let executor = helpers::init_executor();
+        let accounts = executor
+            .query(Query::FrozenAccounts {
+                block_tag: BlockTag::Tag(Tag::Latest),
+            })
+            .unwrap();
+        assert_eq!(accounts, serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_sys_config_sections() {
+        use crate::libexecutor::sys_config::{GlobalSysConfig, SysConfigSection};
+        use crate::types::reserved_addresses;
+        use std::collections::BTreeSet;
+        use std::str::FromStr;
+
+        let executor = helpers::init_executor();
+        let block_tag = BlockTag::Tag(Tag::Pending);
+        let changed_by = |written: &[&str]| {
+            let written = written
+                .iter()
+                .map(|address| Address::from_str(address).unwrap())
+                .collect();
+            SysConfigSection::changed_by(&executor, &executor.sys_config, &written, block_tag)
+        };
+        assert!(changed_by(&[]).is_empty());
+        assert!(changed_by(&["0000000000000000000000000000000000000001"]).is_empty());
+        assert_eq!(
+            changed_by(&[
+                reserved_addresses::PRICE_MANAGEMENT,
+                reserved_addresses::NODE_MANAGER,
+                reserved_addresses::ALL_GROUPS,
+            ]),
+            [
+                SysConfigSection::Nodes,
+                SysConfigSection::Groups,
+                SysConfigSection::Price
+            ]
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+        );
+
+        let mut sys_config = GlobalSysConfig::default();
+        sys_config.reload(&executor, block_tag, &SysConfigSection::all());
+        assert_eq!(sys_config, GlobalSysConfig::load(&executor, block_tag));
+    }
+
+    #[test]
+    fn test_query_reader_follows_current_header() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let mut executor = helpers::init_executor();
+
+        let (command_req_sender, command_req_receiver) = crossbeam_channel::bounded(0);
+        let (command_resp_sender, command_resp_receiver) = crossbeam_channel::bounded(0);
+        let mut reader = executor.query_reader(command_req_receiver, command_resp_sender);
+        let handle = thread::spawn(move || reader.do_query_loop());
+
+        let data = helpers::generate_contract();
+        let block = helpers::create_block(&executor, Address::from(0), &data, (0, 1), &privkey);
+        let mut closed_block = executor.into_fsm(block.clone());
+        executor.grow(&closed_block);
+        closed_block.clear_cache();
+
+        let nonce = command::nonce_at(
+            &command_req_sender,
+            &command_resp_receiver,
+            keypair.address(),
+            BlockTag::Tag(Tag::Latest),
+        );
+        assert_eq!(nonce, Some(1.into()));
+
+        // The reader never rolls back the chain.
+        let _ = command_req_sender.send(Command::Exit(BlockTag::Height(0)));
+        match command_resp_receiver.recv().unwrap() {
+            CommandResp::Rejected(_) => {}
+            resp => panic!("unexpected {}", resp),
+        }
+        assert_eq!(executor.get_current_height(), 1);
+
+        drop(command_req_sender);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_state_cache() {
+        use crate::cita_executive::create_address_from_address_and_nonce;
+        use crate::libexecutor::state_cache::StateCache;
+        use parking_lot::Mutex;
+        use std::sync::Arc;
+
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let contract = create_address_from_address_and_nonce(&keypair.address(), &U256::zero());
+        // get(), set(5), get()
+        let get = vec![0x6d, 0x4c, 0xe6, 0x3c];
+        let mut set = vec![0x60, 0xfe, 0x47, 0xb1];
+        set.extend_from_slice(&[0u8; 31]);
+        set.push(5);
+        let blocks = vec![
+            (Address::from(0), helpers::generate_contract(), (0, 1)),
+            (contract, get.clone(), (1, 3)),
+            (contract, set, (3, 4)),
+            (contract, get, (4, 6)),
+        ];
+
+        let mut roots = Vec::new();
+        for capacity in &[0, 1024 * 1024] {
+            let mut executor = helpers::init_executor();
+            executor.state_cache = Arc::new(Mutex::new(StateCache::new(*capacity)));
+            let mut states = Vec::new();
+            for (to, data, nonce) in &blocks {
+                let block = helpers::create_block(&executor, *to, data, *nonce, &privkey);
+                let closed_block = executor.into_fsm(block);
+                executor.grow(&closed_block);
+                states.push(closed_block.state.root);
+                executor.keep_state_cache(closed_block);
+            }
+            roots.push(states);
+
+            let metrics = executor.state_cache.lock().metrics();
+            if *capacity == 0 {
+                assert_eq!(metrics.hits(), 0);
+            } else {
+                // The second reads of each get() block are hits.
+                assert!(metrics.hits() > 0);
+                assert!(metrics.size() > 0);
+                executor.rollback_current_height(BlockTag::Height(1));
+                assert_eq!(metrics.size(), 0);
+            }
+        }
+        assert_eq!(roots[0], roots[1]);
+    }
+
+    #[test]
+    fn test_quota_prices() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let mut executor = helpers::init_executor();
+        let price = U256::from(100_0000);
+        assert_eq!(executor.quota_prices(0), vec![(0, price)]);
+
+        let data = helpers::generate_contract();
+        let block = helpers::create_block(&executor, Address::from(0), &data, (0, 1), &privkey);
+        let mut closed_block = executor.into_fsm(block);
+        executor.grow(&closed_block);
+        closed_block.clear_cache();
+
+        // An unchanged price is not repeated.
+        assert_eq!(executor.quota_prices(0), vec![(0, price)]);
+        assert_eq!(executor.quota_prices(2), vec![(2, price)]);
+    }
+
     #[test]
     fn test_executor_exit() {
         let (_fsm_req_sender, fsm_req_receiver) = crossbeam_channel::unbounded();
@@ -0,0 +1,211 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Distribution of the transaction fees in Charge mode.
+
+use crate::types::block_number::BlockNumber;
+use cita_types::{Address, U256};
+use std::collections::BTreeMap;
+
+/// The shares of the fee are in basis points of it.
+pub const FEE_SHARE_BASE: u64 = 10_000;
+
+/// How the fee of a transaction is split, set by the `SysConfig` contract.
+///
+/// The proposer share goes to the coin base of the block, which is the chain
+/// owner when the fee goes back to the platform. The validators share is
+/// split by their stakes, the treasury share goes to `treasury`, and the burn
+/// share is credited to nobody.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct FeeDistribution {
+    pub proposer: u64,
+    pub validators: u64,
+    pub treasury: u64,
+    pub burn: u64,
+    pub treasury_address: Address,
+}
+
+/// All to the proposer, as before the fee could be split.
+impl Default for FeeDistribution {
+    fn default() -> Self {
+        FeeDistribution {
+            proposer: FEE_SHARE_BASE,
+            validators: 0,
+            treasury: 0,
+            burn: 0,
+            treasury_address: Address::zero(),
+        }
+    }
+}
+
+impl FeeDistribution {
+    /// The shares add up to the whole fee, and the treasury share has
+    /// somewhere to go.
+    pub fn is_valid(&self) -> bool {
+        let total = [self.proposer, self.validators, self.treasury, self.burn]
+            .iter()
+            .try_fold(0u64, |total, share| total.checked_add(*share));
+        total == Some(FEE_SHARE_BASE) && (self.treasury == 0 || !self.treasury_address.is_zero())
+    }
+
+    /// Split `fee` between `coin_base`, the validators weighted by their
+    /// stakes and the treasury.
+    ///
+    /// The validators share is split evenly when no validator has a stake,
+    /// and goes to `coin_base` when there is no validator. What is left by
+    /// the rounding goes to `coin_base` too.
+    pub fn split(
+        &self,
+        fee: U256,
+        coin_base: &Address,
+        validator_stakes: &[(Address, u64)],
+    ) -> FeeReport {
+        let mut report = FeeReport {
+            total: fee,
+            ..Default::default()
+        };
+        if fee.is_zero() {
+            return report;
+        }
+        let share = |basis_points: u64| fee * U256::from(basis_points) / U256::from(FEE_SHARE_BASE);
+
+        report.burned = share(self.burn);
+        let mut credited = report.burned;
+        if self.treasury > 0 {
+            let treasury = share(self.treasury);
+            report.credit(&self.treasury_address, treasury);
+            credited += treasury;
+        }
+
+        let total_stake: u64 = validator_stakes.iter().map(|(_, stake)| *stake).sum();
+        if self.validators > 0 && !validator_stakes.is_empty() {
+            let validators = share(self.validators);
+            for (validator, stake) in validator_stakes {
+                let amount = if total_stake == 0 {
+                    validators / U256::from(validator_stakes.len())
+                } else {
+                    validators * U256::from(*stake) / U256::from(total_stake)
+                };
+                report.credit(validator, amount);
+                credited += amount;
+            }
+        }
+
+        report.credit(coin_base, fee - credited);
+        report
+    }
+}
+
+/// Where the fees went, of a transaction or a whole block.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct FeeReport {
+    pub total: U256,
+    pub burned: U256,
+    pub recipients: BTreeMap<Address, U256>,
+}
+
+impl FeeReport {
+    fn credit(&mut self, address: &Address, amount: U256) {
+        if !amount.is_zero() {
+            *self.recipients.entry(*address).or_default() += amount;
+        }
+    }
+
+    pub fn merge(&mut self, other: &FeeReport) {
+        self.total += other.total;
+        self.burned += other.burned;
+        for (address, amount) in &other.recipients {
+            self.credit(address, *amount);
+        }
+    }
+}
+
+/// The fees of a block, as stored by the executor
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct BlockFeeReport {
+    pub height: BlockNumber,
+    #[serde(flatten)]
+    pub report: FeeReport,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_fee_distribution() {
+        let coin_base = Address::from(1);
+        let report = FeeDistribution::default().split(
+            U256::from(1000),
+            &coin_base,
+            &[(Address::from(2), 1)],
+        );
+        assert_eq!(report.burned, U256::zero());
+        assert_eq!(
+            report.recipients.into_iter().collect::<Vec<_>>(),
+            vec![(coin_base, U256::from(1000))]
+        );
+    }
+
+    #[test]
+    fn test_split_fee() {
+        let coin_base = Address::from(1);
+        let treasury = Address::from(9);
+        let distribution = FeeDistribution {
+            proposer: 4000,
+            validators: 3000,
+            treasury: 2000,
+            burn: 1000,
+            treasury_address: treasury,
+        };
+        assert!(distribution.is_valid());
+
+        let stakes = [(Address::from(2), 1), (Address::from(3), 2)];
+        let report = distribution.split(U256::from(1001), &coin_base, &stakes);
+        assert_eq!(report.total, U256::from(1001));
+        assert_eq!(report.burned, U256::from(100));
+        assert_eq!(report.recipients[&treasury], U256::from(200));
+        assert_eq!(report.recipients[&Address::from(2)], U256::from(100));
+        assert_eq!(report.recipients[&Address::from(3)], U256::from(200));
+        // With the rounding.
+        assert_eq!(report.recipients[&coin_base], U256::from(401));
+
+        // Evenly without stakes, to the proposer without validators.
+        let stakes = [(Address::from(2), 0), (Address::from(3), 0)];
+        let report = distribution.split(U256::from(1000), &coin_base, &stakes);
+        assert_eq!(report.recipients[&Address::from(2)], U256::from(150));
+        let report = distribution.split(U256::from(1000), &coin_base, &[]);
+        assert_eq!(report.recipients[&coin_base], U256::from(700));
+
+        let mut block = FeeReport::default();
+        block.merge(&report);
+        block.merge(&report);
+        assert_eq!(block.total, U256::from(2000));
+        assert_eq!(block.recipients[&coin_base], U256::from(1400));
+    }
+
+    #[test]
+    fn test_invalid_fee_distribution() {
+        let mut distribution = FeeDistribution::default();
+        distribution.burn = 1;
+        assert!(!distribution.is_valid());
+        distribution.proposer -= 1;
+        assert!(distribution.is_valid());
+        distribution.burn = 0;
+        distribution.treasury = 1;
+        assert!(!distribution.is_valid());
+        distribution.proposer = u64::max_value();
+        assert!(!distribution.is_valid());
+    }
+}
@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::contracts::native::factory::Factory as NativeFactory;
 use crate::libexecutor::block::Block;
 use crate::libexecutor::executor::{CitaDB, CitaTrieDB};
+use crate::types::block_number::BlockNumber;
 use crate::types::db_indexes;
 use crate::types::db_indexes::DBIndex;
 use cita_database::{DataCategory, Database};
@@ -31,9 +33,6 @@ use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 
-#[cfg(feature = "privatetx")]
-use zktx::set_param_path;
-
 #[derive(Debug, PartialEq, Deserialize, Clone)]
 pub struct Contract {
     pub nonce: String,
@@ -42,11 +41,26 @@ pub struct Contract {
     pub value: Option<U256>,
 }
 
+/// A native or a pre-compiled contract enabled by genesis, see
+/// `contracts::native::builtin` and `contracts::precompiled::builtin`.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct NativeSpec {
+    pub name: String,
+    pub address: Address,
+    /// Calls before this height fail instead of running the contract.
+    #[serde(default)]
+    pub activation: BlockNumber,
+}
+
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Spec {
     pub alloc: HashMap<String, Contract>,
     pub prevhash: H256,
     pub timestamp: u64,
+    /// Native and pre-compiled contracts to enable. When absent the built-in
+    /// defaults are used.
+    #[serde(default)]
+    pub natives: Option<Vec<NativeSpec>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -66,10 +80,6 @@ impl Genesis {
         let mut pre_hash = H256::zero();
         // resource folder at the same place with genesis file
         let resource_path = Path::new(path).parent().unwrap().join("resource");
-        #[cfg(feature = "privatetx")]
-        {
-            set_param_path(resource_path.join("PARAMS").to_str().unwrap());
-        }
         if resource_path.exists() {
             let file_list_path = resource_path.join("files.list");
             if file_list_path.exists() {
@@ -101,6 +111,16 @@ impl Genesis {
         }
     }
 
+    /// The native contracts enabled by the `natives` section, or the built-in
+    /// defaults when there is none.
+    pub fn native_factory(&self) -> NativeFactory {
+        match self.spec.natives {
+            Some(ref natives) => NativeFactory::from_specs(natives)
+                .unwrap_or_else(|e| panic!("Failed to load native contracts: {}", e)),
+            None => NativeFactory::default(),
+        }
+    }
+
     pub fn lazy_execute(&mut self, state_db: Arc<CitaTrieDB>) -> Result<(), String> {
         let mut state = CitaState::from_existing(
             Arc::<CitaTrieDB>::clone(&state_db),
@@ -199,8 +219,8 @@ impl Genesis {
 
 #[cfg(test)]
 mod test {
-    use crate::libexecutor::genesis::{Contract, Spec};
-    use cita_types::{H256, U256};
+    use crate::libexecutor::genesis::{Contract, NativeSpec, Spec};
+    use cita_types::{Address, H256, U256};
     use serde_json;
     use std::collections::HashMap;
     use std::str::FromStr;
@@ -228,6 +248,7 @@ mod test {
             "prevhash": "0x0000000000000000000000000000000000000000000000000000000000000000",
         });
         let spec = Spec {
+            natives: None,
             prevhash: H256::from_str(
                 "0000000000000000000000000000000000000000000000000000000000000000",
             )
@@ -265,4 +286,39 @@ mod test {
         };
         assert_eq!(serde_json::from_value::<Spec>(genesis).unwrap(), spec);
     }
+    #[test]
+    fn test_spec_natives() {
+        let genesis = json!({
+            "timestamp": 1524000000,
+            "alloc": {},
+            "prevhash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "natives": [
+                {
+                    "name": "crossChainVerify",
+                    "address": "0xffffffffffffffffffffffffffffffffff030002",
+                },
+                {
+                    "name": "simpleStorage",
+                    "address": "0xffffffffffffffffffffffffffffffffff030000",
+                    "activation": 100,
+                },
+            ],
+        });
+        let spec = serde_json::from_value::<Spec>(genesis).unwrap();
+        assert_eq!(
+            spec.natives,
+            Some(vec![
+                NativeSpec {
+                    name: "crossChainVerify".to_owned(),
+                    address: Address::from_str("ffffffffffffffffffffffffffffffffff030002").unwrap(),
+                    activation: 0,
+                },
+                NativeSpec {
+                    name: "simpleStorage".to_owned(),
+                    address: Address::from_str("ffffffffffffffffffffffffffffffffff030000").unwrap(),
+                    activation: 100,
+                },
+            ])
+        );
+    }
 }
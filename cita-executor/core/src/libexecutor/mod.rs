@@ -19,9 +19,12 @@ pub mod call_request;
 pub mod command;
 pub mod economical_model;
 pub mod executor;
+pub mod fee_distribution;
 pub mod fsm;
 pub mod genesis;
 pub mod lru_cache;
+pub mod state_cache;
+pub mod state_diff;
 pub mod sys_config;
 
 pub use self::genesis::Genesis;
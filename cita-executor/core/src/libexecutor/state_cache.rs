@@ -0,0 +1,319 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::data_provider::WrittenAccounts;
+use crate::libexecutor::executor::CitaTrieDB;
+use cita_types::{Address, H256};
+use cita_vm::state::{State as CitaState, StateObjectInfo};
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Bytes of an account besides its code: its address, balance, nonce, storage root and code hash.
+const ACCOUNT_SIZE: usize = 20 + 32 * 4;
+/// Bytes of a storage slot: its address, key and value.
+const SLOT_SIZE: usize = 20 + 32 * 2;
+
+/// Hits and misses of the storage slots, and the size of the state cache.
+#[derive(Debug, Default)]
+pub struct CacheMetrics {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    accounts: AtomicUsize,
+    size: AtomicUsize,
+}
+
+impl CacheMetrics {
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of the cached accounts.
+    pub fn accounts(&self) -> usize {
+        self.accounts.load(Ordering::Relaxed)
+    }
+
+    /// Bytes of the cached accounts and storage slots.
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+}
+
+/// Decoded accounts and storage slots at a state root, the generation, kept between blocks
+/// so the next block doesn't read them from the trie again.
+///
+/// The accounts are the state objects cached by the state of the last grown block, they are
+/// handed to the state of the next block if it's executed on the generation. The first cached
+/// accounts are evicted first.
+///
+/// The storage slots are read by the blocks executed on the generation, the least recently used
+/// ones are evicted first. A block only reads the slots of the accounts it hasn't written, and
+/// the slots of the accounts it has written are dropped when it's grown.
+///
+/// Half of the capacity is for the accounts, and half for the storage slots.
+pub struct StateCache {
+    generation: H256,
+    accounts: Option<CitaState<CitaTrieDB>>,
+    account_sizes: LruCache<Address, usize>,
+    accounts_size: usize,
+    slots: LruCache<(Address, H256), H256>,
+    slots_size: usize,
+    capacity: usize,
+    metrics: Arc<CacheMetrics>,
+}
+
+impl StateCache {
+    /// The cache is disabled if `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        StateCache {
+            generation: H256::zero(),
+            accounts: None,
+            account_sizes: LruCache::new(usize::max_value()),
+            accounts_size: 0,
+            slots: LruCache::new(usize::max_value()),
+            slots_size: 0,
+            capacity: capacity / 2,
+            metrics: Arc::new(CacheMetrics::default()),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<CacheMetrics> {
+        self.metrics.clone()
+    }
+
+    pub fn generation(&self) -> H256 {
+        self.generation
+    }
+
+    fn is_empty(&self) -> bool {
+        self.account_sizes.is_empty() && self.slots.is_empty()
+    }
+
+    /// Hand the cached accounts to the state of a block, if the state is at the generation.
+    /// An empty cache takes the root of the state as its generation.
+    pub fn seed(&mut self, state: &CitaState<CitaTrieDB>) {
+        if state.root != self.generation {
+            if !self.is_empty() {
+                return;
+            }
+            self.generation = state.root;
+        }
+        // The sizes are kept, the accounts come back with the block when it's grown.
+        if let Some(accounts) = self.accounts.take() {
+            state.cache.swap(&accounts.cache);
+        }
+    }
+
+    /// The value of a storage slot at the generation.
+    pub fn storage(&mut self, generation: H256, address: &Address, key: &H256) -> Option<H256> {
+        if generation != self.generation || self.capacity == 0 {
+            return None;
+        }
+        let value = self.slots.get_mut(&(*address, *key)).cloned();
+        if value.is_some() {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        value
+    }
+
+    /// Cache the value of a storage slot read at the generation.
+    pub fn insert_storage(&mut self, generation: H256, address: Address, key: H256, value: H256) {
+        if generation != self.generation || self.capacity < SLOT_SIZE {
+            return;
+        }
+        if self.slots.insert((address, key), value).is_none() {
+            self.slots_size += SLOT_SIZE;
+        }
+        while self.slots_size > self.capacity {
+            match self.slots.remove_lru() {
+                Some(_) => self.slots_size -= SLOT_SIZE,
+                None => break,
+            }
+        }
+        self.update_size();
+    }
+
+    /// Keep the accounts of the committed state of a grown block, whose state root is the next
+    /// generation. The block wrote the storage of the `written` accounts.
+    pub fn keep(
+        &mut self,
+        parent_state_root: H256,
+        mut state: CitaState<CitaTrieDB>,
+        written: &WrittenAccounts,
+    ) {
+        if parent_state_root != self.generation {
+            self.clear();
+        }
+
+        // The slots of the written accounts are changed.
+        let stale: Vec<(Address, H256)> = self
+            .slots
+            .iter()
+            .map(|(slot, _)| *slot)
+            .filter(|(address, _)| written.contains(address))
+            .collect();
+        for slot in stale {
+            self.slots.remove(&slot);
+            self.slots_size -= SLOT_SIZE;
+        }
+
+        // The accounts which are not handed back are dropped, and the sizes of the new
+        // and the written ones are taken.
+        let (gone, sized): (Vec<Address>, Vec<Address>) = {
+            let accounts = state.cache.borrow();
+            let mut known = HashSet::new();
+            let mut gone = Vec::new();
+            for (address, _) in self.account_sizes.iter() {
+                if accounts.contains_key(address) {
+                    known.insert(*address);
+                } else {
+                    gone.push(*address);
+                }
+            }
+            let sized = accounts
+                .keys()
+                .filter(|address| written.contains(address) || !known.contains(address))
+                .cloned()
+                .collect();
+            (gone, sized)
+        };
+        for address in gone {
+            if let Some(size) = self.account_sizes.remove(&address) {
+                self.accounts_size -= size;
+            }
+        }
+        for address in sized {
+            let size = ACCOUNT_SIZE + state.code_size(&address).unwrap_or(0);
+            if let Some(old) = self.account_sizes.insert(address, size) {
+                self.accounts_size -= old;
+            }
+            self.accounts_size += size;
+        }
+        while self.accounts_size > self.capacity {
+            match self.account_sizes.remove_lru() {
+                Some((address, size)) => {
+                    state.cache.borrow_mut().remove(&address);
+                    self.accounts_size -= size;
+                }
+                None => break,
+            }
+        }
+
+        self.generation = state.root;
+        self.accounts = Some(state);
+        self.update_size();
+    }
+
+    /// Drop the cached accounts and slots, the generation is taken again by the next block.
+    pub fn clear(&mut self) {
+        self.generation = H256::zero();
+        self.accounts = None;
+        self.account_sizes.clear();
+        self.accounts_size = 0;
+        self.slots.clear();
+        self.slots_size = 0;
+        self.update_size();
+    }
+
+    fn update_size(&self) {
+        self.metrics
+            .accounts
+            .store(self.account_sizes.len(), Ordering::Relaxed);
+        self.metrics
+            .size
+            .store(self.accounts_size + self.slots_size, Ordering::Relaxed);
+    }
+}
+
+/// The state cache used by a block, at the state root the block is executed on.
+#[derive(Clone)]
+pub struct BlockStateCache {
+    cache: Arc<Mutex<StateCache>>,
+    generation: H256,
+}
+
+impl BlockStateCache {
+    pub fn new(cache: Arc<Mutex<StateCache>>, generation: H256) -> Self {
+        BlockStateCache { cache, generation }
+    }
+
+    pub fn storage(&self, address: &Address, key: &H256) -> Option<H256> {
+        self.cache.lock().storage(self.generation, address, key)
+    }
+
+    pub fn insert_storage(&self, address: Address, key: H256, value: H256) {
+        self.cache
+            .lock()
+            .insert_storage(self.generation, address, key, value);
+    }
+}
+
+impl fmt::Debug for BlockStateCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BlockStateCache")
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StateCache, SLOT_SIZE};
+    use cita_types::{Address, H256};
+
+    #[test]
+    fn test_storage_slots() {
+        let generation = H256::from(1);
+        let mut cache = StateCache::new(SLOT_SIZE * 4);
+        cache.generation = generation;
+        let (a, b) = (Address::from(0xa), Address::from(0xb));
+        cache.insert_storage(generation, a, H256::from(1), H256::from(10));
+        cache.insert_storage(generation, b, H256::from(1), H256::from(20));
+        // Read at another generation.
+        cache.insert_storage(H256::from(2), b, H256::from(2), H256::from(30));
+        assert_eq!(cache.storage(H256::from(2), &a, &H256::from(1)), None);
+        assert_eq!(cache.slots_size, SLOT_SIZE * 2);
+
+        // The slot of `a` is used again, so the slot of `b` is evicted.
+        assert_eq!(
+            cache.storage(generation, &a, &H256::from(1)),
+            Some(H256::from(10))
+        );
+        cache.insert_storage(generation, a, H256::from(2), H256::from(11));
+        assert_eq!(cache.slots_size, SLOT_SIZE * 2);
+        assert_eq!(cache.storage(generation, &b, &H256::from(1)), None);
+        assert_eq!(cache.metrics.hits(), 1);
+        assert_eq!(cache.metrics.misses(), 1);
+
+        cache.clear();
+        assert_eq!(cache.generation, H256::zero());
+        assert_eq!(cache.storage(generation, &a, &H256::from(1)), None);
+        assert_eq!(cache.metrics.size(), 0);
+
+        // Disabled
+        let mut cache = StateCache::new(0);
+        cache.generation = generation;
+        cache.insert_storage(generation, a, H256::from(1), H256::from(10));
+        assert_eq!(cache.storage(generation, &a, &H256::from(1)), None);
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::data_provider::AccessList;
+use cita_trie::DB;
+use cita_types::{Address, H256, U256};
+use cita_vm::state::{State as CitaState, StateObjectInfo};
+use std::collections::BTreeMap;
+
+/// Value of an item before and after the execution
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Diff<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T: PartialEq> Diff<T> {
+    pub fn is_changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// Values of an accessed account before and after the execution
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct AccountDiff {
+    pub balance: Diff<U256>,
+    pub nonce: Diff<U256>,
+    pub code_hash: Diff<H256>,
+    pub storage: BTreeMap<H256, Diff<H256>>,
+}
+
+impl AccountDiff {
+    pub fn is_changed(&self) -> bool {
+        self.balance.is_changed()
+            || self.nonce.is_changed()
+            || self.code_hash.is_changed()
+            || self.storage.values().any(Diff::is_changed)
+    }
+}
+
+/// Values of the accessed accounts, the read only ones included
+pub type StateDiff = BTreeMap<Address, AccountDiff>;
+
+/// State diff of a block, used to export
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockStateDiff {
+    pub height: u64,
+    pub state_diff: StateDiff,
+}
+
+/// Values of an account at some point
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountSnapshot {
+    pub balance: U256,
+    pub nonce: U256,
+    pub code_hash: H256,
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Read the values of the accessed accounts and storage keys.
+pub fn snapshot<B: DB + 'static>(
+    state: &mut CitaState<B>,
+    accessed: &AccessList,
+) -> BTreeMap<Address, AccountSnapshot> {
+    accessed
+        .iter()
+        .map(|(address, keys)| {
+            let storage = keys
+                .iter()
+                .map(|key| (*key, state.get_storage(address, key).unwrap_or_default()))
+                .collect();
+            let account = AccountSnapshot {
+                balance: state.balance(address).unwrap_or_default(),
+                nonce: state.nonce(address).unwrap_or_default(),
+                code_hash: state.code_hash(address).unwrap_or_default(),
+                storage,
+            };
+            (*address, account)
+        })
+        .collect()
+}
+
+/// Compare the snapshots taken before and after the execution.
+pub fn diff(
+    before: &BTreeMap<Address, AccountSnapshot>,
+    after: &BTreeMap<Address, AccountSnapshot>,
+) -> StateDiff {
+    let empty = AccountSnapshot::default();
+    after
+        .iter()
+        .map(|(address, to)| {
+            let from = before.get(address).unwrap_or(&empty);
+            let storage = to
+                .storage
+                .iter()
+                .map(|(key, value)| {
+                    let origin = from.storage.get(key).cloned().unwrap_or_default();
+                    (
+                        *key,
+                        Diff {
+                            from: origin,
+                            to: *value,
+                        },
+                    )
+                })
+                .collect();
+            let account = AccountDiff {
+                balance: Diff {
+                    from: from.balance,
+                    to: to.balance,
+                },
+                nonce: Diff {
+                    from: from.nonce,
+                    to: to.nonce,
+                },
+                code_hash: Diff {
+                    from: from.code_hash,
+                    to: to.code_hash,
+                },
+                storage,
+            };
+            (*address, account)
+        })
+        .collect()
+}
+
+/// Keep only the accounts and storage keys changed by the execution.
+pub fn changed_only(diff: StateDiff) -> StateDiff {
+    diff.into_iter()
+        .filter(|(_, account)| account.is_changed())
+        .map(|(address, mut account)| {
+            account.storage = account
+                .storage
+                .into_iter()
+                .filter(|(_, value)| value.is_changed())
+                .collect();
+            (address, account)
+        })
+        .collect()
+}
+
+/// Get the accounts and storage keys accessed by the execution.
+pub fn access_list(diff: &StateDiff) -> AccessList {
+    diff.iter()
+        .map(|(address, account)| (*address, account.storage.keys().cloned().collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{access_list, changed_only, diff, snapshot};
+    use crate::data_provider::AccessList;
+    use crate::tests::helpers::get_temp_state;
+    use cita_types::{Address, H256, U256};
+    use cita_vm::state::StateObjectInfo;
+
+    #[test]
+    fn test_state_diff() {
+        let mut state = get_temp_state();
+        let sender = Address::from(0x100);
+        let untouched = Address::from(0x200);
+        state.add_balance(&sender, U256::from(100)).unwrap();
+        state.add_balance(&untouched, U256::from(100)).unwrap();
+        state
+            .set_storage(&sender, H256::from(1), H256::from(1))
+            .unwrap();
+
+        let mut accessed = AccessList::new();
+        accessed.entry(sender).or_default().insert(H256::from(1));
+        accessed.entry(sender).or_default().insert(H256::from(2));
+        accessed.entry(untouched).or_default();
+
+        let before = snapshot(&mut state, &accessed);
+        state.sub_balance(&sender, U256::from(10)).unwrap();
+        state.inc_nonce(&sender).unwrap();
+        state
+            .set_storage(&sender, H256::from(2), H256::from(2))
+            .unwrap();
+        let after = snapshot(&mut state, &accessed);
+
+        let accessed_diff = diff(&before, &after);
+        assert_eq!(accessed_diff.len(), 2);
+        assert!(!accessed_diff[&untouched].is_changed());
+        assert_eq!(access_list(&accessed_diff), accessed);
+
+        let changes = changed_only(accessed_diff);
+        assert_eq!(changes.len(), 1);
+        let account = &changes[&sender];
+        assert_eq!(account.balance.from, U256::from(100));
+        assert_eq!(account.balance.to, U256::from(90));
+        assert!(account.nonce.is_changed());
+        assert!(!account.code_hash.is_changed());
+        assert_eq!(account.storage.len(), 1);
+        assert_eq!(account.storage[&H256::from(2)].from, H256::zero());
+        assert_eq!(account.storage[&H256::from(2)].to, H256::from(2));
+
+        let list = access_list(&changes);
+        assert_eq!(list.len(), 1);
+        assert!(list[&sender].contains(&H256::from(2)));
+    }
+}
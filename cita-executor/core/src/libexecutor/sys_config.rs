@@ -17,10 +17,101 @@ use crate::contracts::solc::{
     AccountQuotaLimit, EmergencyIntervention, NodeManager, PermissionManagement, PriceManagement,
     QuotaManager, Resource, SysConfig, UserManagement, VersionManager, AUTO_EXEC_QL_VALUE,
 };
+use crate::data_provider::WrittenAccounts;
 use crate::libexecutor::economical_model::EconomicalModel;
-use crate::types::block_number::BlockTag;
+use crate::libexecutor::fee_distribution::FeeDistribution;
+use crate::types::block_number::{BlockNumber, BlockTag};
+use crate::types::reserved_addresses;
 use cita_types::{Address, U256};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+/// Sections of the `GlobalSysConfig`, each one is loaded from its own system contracts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SysConfigSection {
+    Nodes,
+    Quota,
+    SysConfig,
+    Permissions,
+    Groups,
+    EmergencyIntervention,
+    Version,
+    Price,
+}
+
+lazy_static! {
+    /// The system contracts which the sections are loaded from.
+    /// The permission and group contracts created at runtime are not reserved,
+    /// they are found from the loaded config.
+    static ref SECTION_CONTRACTS: HashMap<Address, SysConfigSection> = [
+        (reserved_addresses::NODE_MANAGER, SysConfigSection::Nodes),
+        (reserved_addresses::QUOTA_MANAGER, SysConfigSection::Quota),
+        (reserved_addresses::SYS_CONFIG, SysConfigSection::SysConfig),
+        (reserved_addresses::AUTHORIZATION, SysConfigSection::Permissions),
+        (reserved_addresses::GROUP, SysConfigSection::Groups),
+        (reserved_addresses::GROUP_MANAGEMENT, SysConfigSection::Groups),
+        (reserved_addresses::ALL_GROUPS, SysConfigSection::Groups),
+        (
+            reserved_addresses::EMERGENCY_INTERVENTION,
+            SysConfigSection::EmergencyIntervention,
+        ),
+        (reserved_addresses::VERSION_MANAGEMENT, SysConfigSection::Version),
+        (reserved_addresses::PRICE_MANAGEMENT, SysConfigSection::Price),
+    ]
+    .iter()
+    .map(|(address, section)| (Address::from_str(address).unwrap(), *section))
+    .collect();
+}
+
+impl SysConfigSection {
+    pub fn all() -> BTreeSet<SysConfigSection> {
+        [
+            SysConfigSection::Nodes,
+            SysConfigSection::Quota,
+            SysConfigSection::SysConfig,
+            SysConfigSection::Permissions,
+            SysConfigSection::Groups,
+            SysConfigSection::EmergencyIntervention,
+            SysConfigSection::Version,
+            SysConfigSection::Price,
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    /// Sections which should be reloaded after the accounts are written.
+    ///
+    /// The permission contracts are only asked for when a written account
+    /// is not a system contract or a known group.
+    pub fn changed_by(
+        executor: &Executor,
+        conf: &GlobalSysConfig,
+        written: &WrittenAccounts,
+        block_tag: BlockTag,
+    ) -> BTreeSet<SysConfigSection> {
+        let mut sections = BTreeSet::new();
+        let mut others = Vec::new();
+        for address in written {
+            if let Some(section) = SECTION_CONTRACTS.get(address) {
+                sections.insert(*section);
+            } else if conf.block_sys_config.group_accounts.contains_key(address) {
+                sections.insert(SysConfigSection::Groups);
+            } else {
+                others.push(*address);
+            }
+        }
+
+        if !others.is_empty() && !sections.contains(&SysConfigSection::Permissions) {
+            let permissions = PermissionManagement::new(executor).permission_addresses(block_tag);
+            if others.iter().any(|address| permissions.contains(address)) {
+                sections.insert(SysConfigSection::Permissions);
+            }
+        }
+        sections
+    }
+}
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct GlobalSysConfig {
@@ -55,98 +146,206 @@ impl GlobalSysConfig {
     // Is there any better solution?
     pub fn load(executor: &Executor, block_tag: BlockTag) -> Self {
         let mut conf = GlobalSysConfig::default();
-        conf.nodes = executor
+        conf.reload(executor, block_tag, &SysConfigSection::all());
+        conf
+    }
+
+    /// Reload the sections only, the others are kept.
+    pub fn reload(
+        &mut self,
+        executor: &Executor,
+        block_tag: BlockTag,
+        sections: &BTreeSet<SysConfigSection>,
+    ) {
+        for section in sections {
+            trace!("Reload {:?} of global sys config.", section);
+            match section {
+                SysConfigSection::Nodes => self.load_nodes(executor, block_tag),
+                SysConfigSection::Quota => self.load_quota(executor, block_tag),
+                SysConfigSection::SysConfig => self.load_sys_config(executor, block_tag),
+                SysConfigSection::Permissions => self.load_permissions(executor, block_tag),
+                SysConfigSection::Groups => self.load_groups(executor, block_tag),
+                SysConfigSection::EmergencyIntervention => {
+                    self.load_emergency_intervention(executor, block_tag)
+                }
+                SysConfigSection::Version => self.load_version(executor, block_tag),
+                SysConfigSection::Price => self.load_price(executor, block_tag),
+            }
+        }
+        self.changed_height = executor.get_current_height() as usize;
+    }
+
+    fn load_nodes(&mut self, executor: &Executor, block_tag: BlockTag) {
+        self.nodes = executor
             .node_manager()
             .shuffled_stake_nodes(block_tag)
             .unwrap_or_else(NodeManager::default_shuffled_stake_nodes);
 
-        conf.validators = executor
+        self.validators = executor
             .node_manager()
             .nodes(block_tag)
             .unwrap_or_else(NodeManager::default_shuffled_stake_nodes);
 
+        let stakes = executor
+            .node_manager()
+            .stakes(block_tag)
+            .unwrap_or_default();
+        self.block_sys_config.validator_stakes = self
+            .validators
+            .iter()
+            .enumerate()
+            .map(|(i, validator)| (*validator, stakes.get(i).cloned().unwrap_or(0)))
+            .collect();
+    }
+
+    fn load_quota(&mut self, executor: &Executor, block_tag: BlockTag) {
         let quota_manager = QuotaManager::new(executor);
-        conf.block_quota_limit = quota_manager
+        self.block_quota_limit = quota_manager
             .block_quota_limit(block_tag)
             .unwrap_or_else(QuotaManager::default_block_quota_limit)
             as usize;
-        conf.block_sys_config.auto_exec_quota_limit = quota_manager
+        self.block_sys_config.auto_exec_quota_limit = quota_manager
             .auto_exec_quota_limit(block_tag)
             .unwrap_or_else(QuotaManager::default_auto_exec_quota_limit);
+
+        let common_quota_limit = quota_manager
+            .account_quota_limit(block_tag)
+            .unwrap_or_else(QuotaManager::default_account_quota_limit);
+        let specific = quota_manager.specific(block_tag);
+
+        self.block_sys_config
+            .account_quota_limit
+            .set_common_quota_limit(common_quota_limit);
+        self.block_sys_config
+            .account_quota_limit
+            .set_specific_quota_limit(specific);
+    }
+
+    fn load_sys_config(&mut self, executor: &Executor, block_tag: BlockTag) {
         let sys_config = SysConfig::new(executor);
-        conf.delay_active_interval = sys_config
+        self.delay_active_interval = sys_config
             .delay_block_number(block_tag)
             .unwrap_or_else(SysConfig::default_delay_block_number)
             as usize;
-        conf.block_sys_config.check_options.call_permission = sys_config
+        self.block_sys_config.check_options.call_permission = sys_config
             .call_permission_check(block_tag)
             .unwrap_or_else(SysConfig::default_call_permission_check);
-        conf.block_sys_config.check_options.send_tx_permission = sys_config
+        self.block_sys_config.check_options.send_tx_permission = sys_config
             .send_tx_permission_check(block_tag)
             .unwrap_or_else(SysConfig::default_send_tx_permission_check);
-        conf.block_sys_config
+        self.block_sys_config.check_options.internal_call_permission = sys_config
+            .internal_call_permission_check(block_tag)
+            .unwrap_or_else(SysConfig::default_internal_call_permission_check);
+        self.block_sys_config
             .check_options
             .create_contract_permission = sys_config
             .create_contract_permission_check(block_tag)
             .unwrap_or_else(SysConfig::default_create_contract_permission_check);
-        conf.block_sys_config.check_options.quota = sys_config
+        self.block_sys_config.check_options.quota = sys_config
             .quota_check(block_tag)
             .unwrap_or_else(SysConfig::default_quota_check);
-        conf.block_sys_config.check_options.fee_back_platform = sys_config
+        self.block_sys_config.check_options.fee_back_platform = sys_config
             .fee_back_platform_check(block_tag)
             .unwrap_or_else(SysConfig::default_fee_back_platform_check);
-        conf.block_sys_config.chain_owner = sys_config
+        self.block_sys_config.chain_owner = sys_config
             .chain_owner(block_tag)
             .unwrap_or_else(SysConfig::default_chain_owner);
-        conf.block_interval = sys_config
+        self.block_interval = sys_config
             .block_interval(block_tag)
             .unwrap_or_else(SysConfig::default_block_interval);
-        conf.block_sys_config.auto_exec = sys_config
+        self.block_sys_config.auto_exec = sys_config
             .auto_exec(block_tag)
             .unwrap_or_else(SysConfig::default_auto_exec);
+        self.block_sys_config.economical_model = sys_config
+            .economical_model(block_tag)
+            .unwrap_or_else(SysConfig::default_economical_model);
+        self.block_sys_config.fee_distribution = sys_config
+            .fee_distribution(block_tag)
+            .unwrap_or_else(SysConfig::default_fee_distribution);
+    }
 
+    fn load_permissions(&mut self, executor: &Executor, block_tag: BlockTag) {
         let permission_manager = PermissionManagement::new(executor);
-        conf.block_sys_config.account_permissions =
+        self.block_sys_config.account_permissions =
             permission_manager.load_account_permissions(block_tag);
-        conf.block_sys_config.super_admin_account =
+        self.block_sys_config.super_admin_account =
             permission_manager.get_super_admin_account(block_tag);
+    }
 
+    fn load_groups(&mut self, executor: &Executor, block_tag: BlockTag) {
         let user_manager = UserManagement::new(executor);
-        conf.block_sys_config.group_accounts = user_manager.load_group_accounts(block_tag);
-        conf.block_sys_config.economical_model = sys_config
-            .economical_model(block_tag)
-            .unwrap_or_else(SysConfig::default_economical_model);
-
-        let common_quota_limit = quota_manager
-            .account_quota_limit(block_tag)
-            .unwrap_or_else(QuotaManager::default_account_quota_limit);
-        let specific = quota_manager.specific(block_tag);
-
-        conf.block_sys_config
-            .account_quota_limit
-            .set_common_quota_limit(common_quota_limit);
-        conf.block_sys_config
-            .account_quota_limit
-            .set_specific_quota_limit(specific);
-        conf.changed_height = executor.get_current_height() as usize;
+        self.block_sys_config.group_accounts = user_manager.load_group_accounts(block_tag);
+    }
 
+    fn load_emergency_intervention(&mut self, executor: &Executor, block_tag: BlockTag) {
         let emergency_manager = EmergencyIntervention::new(executor);
-        conf.emergency_intervention = emergency_manager
+        self.emergency_intervention = emergency_manager
             .state(block_tag)
             .unwrap_or_else(EmergencyIntervention::default_state);
+        self.block_sys_config.frozen_accounts = emergency_manager
+            .frozen_accounts(block_tag)
+            .unwrap_or_else(EmergencyIntervention::default_frozen_accounts)
+            .into_iter()
+            .collect();
+    }
 
+    fn load_version(&mut self, executor: &Executor, block_tag: BlockTag) {
         let version_manager = VersionManager::new(executor);
-        conf.block_sys_config.chain_version = version_manager
+        self.block_sys_config.chain_version = version_manager
             .get_version(block_tag)
             .unwrap_or_else(VersionManager::default_version);
+    }
 
+    fn load_price(&mut self, executor: &Executor, block_tag: BlockTag) {
         let price_management = PriceManagement::new(executor);
-        conf.block_sys_config.quota_price = price_management
+        self.block_sys_config.quota_price = price_management
             .quota_price(block_tag)
             .unwrap_or_else(PriceManagement::default_quota_price);
+    }
 
-        conf
+    /// Changed fields from `self` to `other`, the fields of `block_sys_config` are flattened.
+    /// `changed_height` is where the config is loaded, not a part of it.
+    pub fn changes(&self, other: &GlobalSysConfig) -> BTreeMap<String, FieldChange> {
+        let old_fields = self.fields();
+        let mut new_fields = other.fields();
+        old_fields
+            .into_iter()
+            .filter_map(|(name, from)| {
+                let to = new_fields.remove(&name).unwrap_or(Value::Null);
+                if from == to {
+                    None
+                } else {
+                    Some((name, FieldChange { from, to }))
+                }
+            })
+            .collect()
     }
+
+    fn fields(&self) -> Map<String, Value> {
+        let mut fields = match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+        fields.remove("changed_height");
+        if let Some(Value::Object(block_fields)) = fields.remove("block_sys_config") {
+            fields.extend(block_fields);
+        }
+        fields
+    }
+}
+
+/// A changed field of the system config
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct FieldChange {
+    pub from: Value,
+    pub to: Value,
+}
+
+/// Changes of the system config made by a block
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct SysConfigChange {
+    pub height: BlockNumber,
+    pub changes: BTreeMap<String, FieldChange>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default, Copy)]
@@ -156,6 +355,8 @@ pub struct CheckOptions {
     pub fee_back_platform: bool,
     pub send_tx_permission: bool,
     pub create_contract_permission: bool,
+    /// Check call permission for the internal calls too, work with `call_permission`.
+    pub internal_call_permission: bool,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -171,6 +372,11 @@ pub struct BlockSysConfig {
     pub check_options: CheckOptions,
     pub economical_model: EconomicalModel,
     pub chain_version: u32,
+    pub fee_distribution: FeeDistribution,
+    /// The validators with their stakes, which the validators share of the fee is split by.
+    pub validator_stakes: Vec<(Address, u64)>,
+    /// The accounts frozen by the emergency intervention, which can't send transactions nor be called.
+    pub frozen_accounts: HashSet<Address>,
 }
 
 impl Default for BlockSysConfig {
@@ -187,6 +393,9 @@ impl Default for BlockSysConfig {
             check_options: CheckOptions::default(),
             economical_model: EconomicalModel::Quota,
             chain_version: 0,
+            fee_distribution: FeeDistribution::default(),
+            validator_stakes: Vec::new(),
+            frozen_accounts: HashSet::new(),
         }
     }
 }
@@ -16,6 +16,7 @@ use std::cell::RefCell;
 use std::sync::Arc;
 
 use crate::cita_executive::CitaExecutive;
+use crate::contracts::native::factory::Factory as NativeFactory;
 use crate::libexecutor::{economical_model::EconomicalModel, sys_config::BlockSysConfig};
 use crate::tests::helpers::get_temp_state;
 use crate::types::context::Context;
@@ -56,6 +57,7 @@ fn test_amend_tool() {
         Arc::new(RefCell::new(state)),
         &context,
         EconomicalModel::default(),
+        Arc::new(NativeFactory::default()),
     );
 
     let (key, value) = (H256::from(42), H256::from(42));
@@ -122,6 +122,8 @@ pub fn init_executor2(
         command_req_receiver,
         command_resp_sender,
         false,
+        false,
+        5 * 1024 * 1024,
     );
     executor
 }
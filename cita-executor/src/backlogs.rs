@@ -191,6 +191,15 @@ impl Backlogs {
         &self.current_hash
     }
 
+    // number of the pending blocks waiting for proof or execution
+    pub fn pending_count(&self) -> usize {
+        self.backlogs.len()
+    }
+
+    pub fn max_pending_height(&self) -> Option<u64> {
+        self.backlogs.keys().next_back().cloned()
+    }
+
     pub fn get_completed_result(&self, height: u64) -> Option<&ExecutedResult> {
         self.completed.get(&height)
     }
@@ -418,6 +427,7 @@ pub fn wrap_height(height: usize) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::{wrap_height, Backlog, Backlogs, Priority};
+    use crate::core::contracts::native::factory::Factory as NativeFactory;
     use crate::core::header::OpenHeader;
     use crate::core::libexecutor::block::{BlockBody, ClosedBlock, ExecutedBlock, OpenBlock};
     use crate::core::libexecutor::sys_config::BlockSysConfig;
@@ -490,6 +500,7 @@ mod tests {
             HASH_NULL_RLP,
             Arc::new(Vec::new()),
             false,
+            Arc::new(NativeFactory::default()),
         )
         .unwrap();
         exec_block.close(&BlockSysConfig::default())
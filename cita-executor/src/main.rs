@@ -33,6 +33,7 @@
 //!     | executor | Net       | SyncResponse               |
 //!     | executor | Net       | SignedProposal             |
 //!     | executor | Snapshot  | SnapshotReq                |
+//!     | executor | Jsonrpc   | RawBytes                   |
 //!
 //! 2. Publish channel
 //!
@@ -44,6 +45,8 @@
 //!     | executor | Executor  | Auth      | Miscellaneous  |
 //!     | executor | Executor  | Auth      | BlackList      |
 //!     | executor | Executor  | Chain     | StateSignal    |
+//!     | executor | Executor  | Jsonrpc   | RawBytes       |
+//!     | executor | Executor  | Auth      | RawBytes       |
 //!
 //! ### Key behavior
 //!
@@ -98,6 +101,7 @@ extern crate util;
 
 use crate::core::libexecutor::executor::Executor;
 use crate::postman::Postman;
+use crate::query_pool::QueryPool;
 use cita_directories::DataPath;
 use clap::App;
 use libproto::router::{MsgType, RoutingKey, SubModules};
@@ -108,6 +112,7 @@ use util::set_panic_handler;
 
 mod backlogs;
 mod postman;
+mod query_pool;
 #[cfg(test)]
 mod tests;
 
@@ -120,6 +125,15 @@ pub struct Options {
     genesis_path: String,
     statedb_cache_size: usize,
     eth_compatibility: bool,
+    #[serde(default)]
+    record_state_diff: bool,
+    // Answer the queries in the executor thread if it's 0.
+    #[serde(default = "default_query_workers")]
+    query_workers: usize,
+}
+
+fn default_query_workers() -> usize {
+    4
 }
 
 impl Options {
@@ -130,6 +144,8 @@ impl Options {
             genesis_path: String::from("genesis.json"),
             statedb_cache_size: 5 * 1024 * 1024,
             eth_compatibility: false,
+            record_state_diff: false,
+            query_workers: default_query_workers(),
         }
     }
 
@@ -179,6 +195,7 @@ fn main() {
             Net >> SyncResponse,
             Snapshot >> SnapshotReq,
             Auth >> MiscellaneousReq,
+            Jsonrpc >> RawBytes,
         ]),
         forward_req_sender,
         forward_resp_receiver,
@@ -214,9 +231,21 @@ fn main() {
             command_req_receiver.clone(),
             command_resp_sender.clone(),
             options.eth_compatibility,
+            options.record_state_diff,
+            options.statedb_cache_size,
         );
         let current_height = executor.get_current_height();
         let current_hash = executor.get_current_hash();
+        let state_cache_metrics = executor.state_cache.lock().metrics();
+        let query_pool = if options.query_workers > 0 {
+            Some(QueryPool::new(
+                &mut executor,
+                options.query_workers,
+                mq_resp_sender.clone(),
+            ))
+        } else {
+            None
+        };
         let handle = thread::spawn(move || {
             executor.do_loop();
         });
@@ -231,7 +260,9 @@ fn main() {
             fsm_resp_receiver.clone(),
             command_req_sender.clone(),
             command_resp_receiver.clone(),
-        );
+            query_pool,
+        )
+        .with_state_cache_metrics(state_cache_metrics);
         postman.do_loop();
 
         handle.join().expect(
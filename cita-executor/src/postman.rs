@@ -18,9 +18,10 @@ use crate::core::libexecutor::call_request::CallRequest;
 use crate::core::tx_gas_schedule::TxGasSchedule;
 use crate::types::block_number::{BlockTag, Tag};
 use crate::types::errors::ReceiptError;
+use crate::types::raw_message::{Query, RawMessage, MAX_QUOTA_PRICE_HISTORY_BLOCKS};
 use cita_types::U256;
 use cita_types::{Address, H256};
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{tick, Receiver, Sender};
 use error::ErrorCode;
 use jsonrpc_types::rpc_types::{BlockNumber, CountOrCode};
 use libproto::auth::Miscellaneous;
@@ -29,27 +30,43 @@ use libproto::request::Request_oneof_req as Request;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::{request, response, Message};
 use libproto::{TryFrom, TryInto};
+use serde_json::Value;
 use std::convert::Into;
+use std::time::{Duration, Instant};
 use std::u8;
 
 use crate::core::libexecutor::blacklist::BlackList;
 use crate::core::libexecutor::command;
 use crate::core::libexecutor::lru_cache::LRUCache;
+use crate::core::libexecutor::state_cache::CacheMetrics;
 
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 use super::backlogs::{wrap_height, Backlogs};
+use super::query_pool::QueryPool;
 use cita_vm::state::StateObjectInfo;
 
+// Interval of the heartbeats collected by the health check of jsonrpc
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(3);
+
 pub struct Postman {
     backlogs: Backlogs,
     black_list_cache: RwLock<LRUCache<u64, Address>>,
+    // The frozen accounts published to cita-auth last time.
+    frozen_accounts: Option<Vec<Address>>,
+    // The quota price published to cita-chain last time, paid by the next block.
+    quota_price: Option<U256>,
     mq_req_receiver: Receiver<(String, Vec<u8>)>,
     mq_resp_sender: Sender<(String, Vec<u8>)>,
     fsm_req_sender: Sender<OpenBlock>,
     fsm_resp_receiver: Receiver<ClosedBlock>,
     command_req_sender: Sender<command::Command>,
     command_resp_receiver: Receiver<command::CommandResp>,
+    // Answer the requests of cita-chain in parallel if it's set.
+    query_pool: Option<QueryPool>,
+    heartbeat_ticker: Receiver<Instant>,
+    // Reported in the heartbeats if it's set.
+    state_cache_metrics: Option<Arc<CacheMetrics>>,
 }
 
 impl Postman {
@@ -63,19 +80,31 @@ impl Postman {
         fsm_resp_receiver: Receiver<ClosedBlock>,
         command_req_sender: Sender<command::Command>,
         command_resp_receiver: Receiver<command::CommandResp>,
+        query_pool: Option<QueryPool>,
     ) -> Self {
         Postman {
             backlogs: Backlogs::new(current_height, current_hash),
             black_list_cache: RwLock::new(LRUCache::new(10_000_000)),
+            frozen_accounts: None,
+            quota_price: None,
             mq_req_receiver,
             mq_resp_sender,
             fsm_req_sender,
             fsm_resp_receiver,
             command_req_sender,
             command_resp_receiver,
+            query_pool,
+            heartbeat_ticker: tick(HEARTBEAT_INTERVAL),
+            state_cache_metrics: None,
         }
     }
 
+    /// Report the metrics of the state cache in the heartbeats.
+    pub fn with_state_cache_metrics(mut self, metrics: Arc<CacheMetrics>) -> Self {
+        self.state_cache_metrics = Some(metrics);
+        self
+    }
+
     pub fn do_loop(&mut self) {
         // 1. broadcast current state toward cita-chain
         self.bootstrap_broadcast();
@@ -123,6 +152,14 @@ impl Postman {
         );
 
         self.pub_init_black_list();
+        self.frozen_accounts = None;
+        self.pub_frozen_accounts();
+
+        // The prices of the blocks the chain was told before might be changed by a rollback,
+        // or never told by an older executor.
+        let from = (current_height + 1).saturating_sub(MAX_QUOTA_PRICE_HISTORY_BLOCKS);
+        self.quota_price = None;
+        self.pub_quota_prices(from);
     }
 
     // make sure executor exit also
@@ -136,7 +173,7 @@ impl Postman {
         }
     }
 
-    // listen messages from RabbitMQ and Executor.
+    // listen messages from RabbitMQ and Executor, send the heartbeats meanwhile.
     //
     // Return `(None, None)` if any channel closed
     #[cfg_attr(
@@ -144,22 +181,60 @@ impl Postman {
         allow(clippy::type_complexity, clippy::zero_ptr, clippy::drop_copy)
     )]
     fn recv(&self) -> (Option<(String, Vec<u8>)>, Option<ClosedBlock>) {
-        select! {
-            recv(self.mq_req_receiver) -> mq_req => {
-                match mq_req {
-                    Ok(mq_req) => (Some(mq_req), None),
-                    Err(_) => (None, None),
-                }
-            },
-            recv(self.fsm_resp_receiver) -> fsm_resp => {
-                match fsm_resp {
-                    Ok(fsm_resp) => (None, Some(fsm_resp)),
-                    Err(_) => (None, None),
-                }
+        loop {
+            select! {
+                recv(self.mq_req_receiver) -> mq_req => {
+                    return match mq_req {
+                        Ok(mq_req) => (Some(mq_req), None),
+                        Err(_) => (None, None),
+                    };
+                },
+                recv(self.fsm_resp_receiver) -> fsm_resp => {
+                    return match fsm_resp {
+                        Ok(fsm_resp) => (None, Some(fsm_resp)),
+                        Err(_) => (None, None),
+                    };
+                },
+                recv(self.heartbeat_ticker) -> _ => self.heartbeat(),
             }
         }
     }
 
+    // Report the height and the pending blocks to jsonrpc, blocks pending far above
+    // the current height mean executor is stuck.
+    fn heartbeat(&self) {
+        let current_height = self.get_current_height();
+        let pending_count = self.backlogs.pending_count();
+        let highest = self
+            .backlogs
+            .max_pending_height()
+            .map_or(current_height, |height| height.max(current_height));
+        let state_cache = self.state_cache_metrics.as_ref().map(|metrics| {
+            serde_json::json!({
+                "hits": metrics.hits(),
+                "misses": metrics.misses(),
+                "accounts": metrics.accounts(),
+                "size": metrics.size(),
+            })
+        });
+        // Whether executor keeps up is judged by jsonrpc from the lag.
+        let heartbeat = serde_json::json!({
+            "service": "executor",
+            "height": current_height,
+            "highest": highest,
+            "ready": true,
+            "conditions": {},
+            "details": {
+                "pending_blocks": pending_count,
+                "state_cache": state_cache,
+            },
+        });
+        self.response_mq(
+            routing_key!(Executor >> RawBytes).into(),
+            heartbeat.to_string().into_bytes(),
+        );
+    }
+
     // update executed result into backlogs based on arrived result from executor
     fn handle_fsm_response(&mut self, closed_block: ClosedBlock) {
         let height = closed_block.number();
@@ -168,6 +243,17 @@ impl Postman {
     }
 
     fn handle_mq_message(&mut self, key: &str, msg_vec: Vec<u8>) -> Result<(), BlockTag> {
+        // The raw bytes of jsonrpc are not a message.
+        if RoutingKey::from(key) == routing_key!(Jsonrpc >> RawBytes) {
+            if let Some(RawMessage::Query { request_id, query }) = RawMessage::from_bytes(&msg_vec)
+            {
+                if !query.is_for_chain() {
+                    self.reply_query(request_id, query);
+                }
+            }
+            return Ok(());
+        }
+
         let mut msg = Message::try_from(msg_vec).unwrap();
         trace!("receive {} from RabbitMQ", key);
         match RoutingKey::from(key) {
@@ -334,7 +420,8 @@ impl Postman {
     // 1. Update backlogs
     // 2. Update black list
     // 3. Notify executor to grow up too
-    // 4. Delivery rich status of new height
+    // 4. Update frozen accounts
+    // 5. Delivery rich status of new height
     fn grow_up(&mut self) {
         let next_height = self.get_current_height() + 1;
         match self.backlogs.complete(next_height) {
@@ -346,6 +433,8 @@ impl Postman {
                     &self.command_resp_receiver,
                     closed_block,
                 );
+                self.pub_frozen_accounts();
+                self.pub_quota_prices(next_height + 1);
                 self.backlogs
                     .insert_completed_result(next_height, executed_result);
                 self.send_executed_info_to_chain(next_height).unwrap();
@@ -380,6 +469,50 @@ impl Postman {
         );
     }
 
+    /// Publish the frozen accounts to cita-auth when they are changed.
+    ///
+    /// They are sent as a `RawMessage` in `RawBytes`, and replace the ones sent
+    /// before.
+    fn pub_frozen_accounts(&mut self) {
+        let frozen_accounts =
+            command::frozen_accounts(&self.command_req_sender, &self.command_resp_receiver);
+        if self.frozen_accounts.as_ref() == Some(&frozen_accounts) {
+            return;
+        }
+
+        info!("frozen accounts are {:?}", frozen_accounts);
+        let message = RawMessage::FrozenAccounts {
+            accounts: frozen_accounts.clone(),
+        };
+        self.response_mq(
+            routing_key!(Executor >> RawBytes).into(),
+            message.to_bytes(),
+        );
+        self.frozen_accounts = Some(frozen_accounts);
+    }
+
+    /// Publish the quota prices paid by the blocks from `from` to cita-chain, when the price
+    /// paid by the next block is changed.
+    ///
+    /// They are sent as a `RawMessage` in `RawBytes`, and replace the ones from `from` sent
+    /// before.
+    fn pub_quota_prices(&mut self, from: u64) {
+        let prices =
+            command::quota_prices(&self.command_req_sender, &self.command_resp_receiver, from);
+        let quota_price = prices.last().map(|(_, price)| *price);
+        if quota_price.is_none() || self.quota_price == quota_price {
+            return;
+        }
+
+        info!("quota prices from {} are {:?}", from, prices);
+        let message = RawMessage::QuotaPrices { from, prices };
+        self.response_mq(
+            routing_key!(Executor >> RawBytes).into(),
+            message.to_bytes(),
+        );
+        self.quota_price = quota_price;
+    }
+
     /// Find the public key of all senders that caused the specified error message, and then publish it
     // TODO: I think it is not necessary to distinguish economical_model, maybe remove
     //       this opinion in the future.
@@ -480,240 +613,22 @@ impl Postman {
         );
     }
 
-    fn reply_chain_request(&self, mut req: request::Request) {
-        let mut response = response::Response::new();
-        response.set_request_id(req.take_request_id());
-
-        match req.req.unwrap() {
-            Request::call(call) => {
-                trace!("Chainvm Call {:?}", call);
-                let _ = serde_json::from_str::<BlockNumber>(&call.height)
-                    .map(|block_id| {
-                        let call_request = CallRequest::from(call);
-                        command::eth_call(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            call_request,
-                            block_id.into(),
-                        )
-                        .map(|ok| {
-                            response.set_call_result(ok);
-                        })
-                        .map_err(|err| {
-                            response.set_code(ErrorCode::query_error());
-                            response.set_error_msg(err);
-                        })
-                    })
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    });
-            }
-
-            Request::estimate_quota(call) => {
-                trace!("Estimate quota with params: {:?}", call);
-                let _ = serde_json::from_str::<BlockNumber>(&call.height)
-                    .map(|block_id| {
-                        let call_request = CallRequest::from(call);
-                        command::estimate_quota(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            call_request,
-                            block_id.into(),
-                        )
-                        .map(|ok| {
-                            response.set_call_result(ok);
-                        })
-                        .map_err(|err| {
-                            response.set_code(ErrorCode::query_error());
-                            response.set_error_msg(err);
-                        })
-                    })
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    });
-            }
-
-            Request::transaction_count(tx_count) => {
-                trace!("transaction count request from jsonrpc {:?}", tx_count);
-                let _ = serde_json::from_str::<CountOrCode>(&tx_count)
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    })
-                    .map(|tx_count| {
-                        let address = Address::from_slice(tx_count.address.as_ref());
-                        match command::nonce_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            address,
-                            tx_count.block_id.into(),
-                        ) {
-                            Some(nonce) => {
-                                response.set_transaction_count(u64::from(nonce));
-                            }
-                            None => {
-                                response.set_transaction_count(0);
-                            }
-                        };
-                    });
-            }
-
-            Request::code(code_content) => {
-                trace!("code request from jsonrpc  {:?}", code_content);
-                let _ = serde_json::from_str::<CountOrCode>(&code_content)
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    })
-                    .map(|code_content| {
-                        let address = Address::from_slice(code_content.address.as_ref());
-                        if let Some(code) = command::code_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            address,
-                            code_content.block_id.into(),
-                        ) {
-                            response.set_contract_code(code);
-                        } else {
-                            response.set_contract_code(vec![]);
-                        };
-                    });
-            }
-
-            Request::abi(abi_content) => {
-                trace!("abi request from jsonrpc  {:?}", abi_content);
-                let _ = serde_json::from_str::<CountOrCode>(&abi_content)
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    })
-                    .map(|abi_content| {
-                        let address = Address::from_slice(abi_content.address.as_ref());
-                        if let Some(abi) = command::abi_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            address,
-                            abi_content.block_id.into(),
-                        ) {
-                            response.set_contract_abi(abi);
-                        } else {
-                            response.set_contract_abi(vec![]);
-                        };
-                    });
-            }
-
-            Request::balance(balance_content) => {
-                trace!("balance request from jsonrpc  {:?}", balance_content);
-                let _ = serde_json::from_str::<CountOrCode>(&balance_content)
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    })
-                    .map(|balance_content| {
-                        let address = Address::from_slice(balance_content.address.as_ref());
-                        if let Some(balance) = command::balance_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            address,
-                            balance_content.block_id.into(),
-                        ) {
-                            response.set_balance(balance);
-                        } else {
-                            response.set_balance(vec![]);
-                        };
-                    });
-            }
-
-            Request::meta_data(data) => {
-                match command::metadata(&self.command_req_sender, &self.command_resp_receiver, data)
-                {
-                    Ok(metadata) => {
-                        response.set_meta_data(serde_json::to_string(&metadata).unwrap())
-                    }
-                    Err(error_msg) => {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(error_msg);
-                    }
-                }
-            }
-
-            Request::state_proof(state_info) => {
-                trace!("state_proof info is {:?}", state_info);
-                let _ = serde_json::from_str::<BlockNumber>(&state_info.height)
-                    .map(|block_id| {
-                        match command::state_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            block_id.into(),
-                        )
-                        .and_then(|state| {
-                            state
-                                .get_storage_proof(
-                                    &Address::from(state_info.get_address()),
-                                    &H256::from(state_info.get_position()),
-                                )
-                                .ok()
-                        }) {
-                            Some(state_proof_bs) => {
-                                let buf: Vec<u8> = state_proof_bs.into_iter().flatten().collect();
-                                response.set_state_proof(buf);
-                            }
-                            None => {
-                                response.set_code(ErrorCode::query_error());
-                                response.set_error_msg("get state proof failed".to_string());
-                            }
-                        }
-                    })
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    });
-            }
-
-            Request::storage_key(skey) => {
-                trace!("storage key info is {:?}", skey);
-                let _ = serde_json::from_str::<BlockNumber>(&skey.height)
-                    .map(|block_id| {
-                        match command::state_at(
-                            &self.command_req_sender,
-                            &self.command_resp_receiver,
-                            block_id.into(),
-                        )
-                        .and_then(|mut state| {
-                            state
-                                .get_storage(
-                                    &Address::from(skey.get_address()),
-                                    &H256::from(skey.get_position()),
-                                )
-                                .ok()
-                        }) {
-                            Some(storage_val) => {
-                                response.set_storage_value(storage_val.to_vec());
-                            }
-                            None => {
-                                response.set_code(ErrorCode::query_error());
-                                response
-                                    .set_error_msg("get storage at something failed".to_string());
-                            }
-                        }
-                    })
-                    .map_err(|err| {
-                        response.set_code(ErrorCode::query_error());
-                        response.set_error_msg(format!("{:?}", err));
-                    });
-            }
+    fn reply_chain_request(&self, req: request::Request) {
+        if let Some(ref query_pool) = self.query_pool {
+            query_pool.dispatch(req);
+            return;
+        }
+        let response = reply_request(req, &self.command_req_sender, &self.command_resp_receiver);
+        send_response(&self.mq_resp_sender, response);
+    }
 
-            _ => {
-                error!("bad request msg!!!!");
-            }
-        };
-        let msg: Message = response.into();
-        self.response_mq(
-            routing_key!(Executor >> Response).into(),
-            msg.try_into().unwrap(),
-        );
+    fn reply_query(&self, request_id: Vec<u8>, query: Query) {
+        if let Some(ref query_pool) = self.query_pool {
+            query_pool.dispatch_query(request_id, query);
+            return;
+        }
+        let result = command::query(&self.command_req_sender, &self.command_resp_receiver, query);
+        send_query_result(&self.mq_resp_sender, request_id, result);
     }
 
     fn signal_to_chain(&self) {
@@ -736,6 +651,259 @@ impl Postman {
     }
 }
 
+/// Answer a request of cita-chain by the commands, the executor or a reader takes them.
+pub fn reply_request(
+    mut req: request::Request,
+    command_req_sender: &Sender<command::Command>,
+    command_resp_receiver: &Receiver<command::CommandResp>,
+) -> response::Response {
+    let mut response = response::Response::new();
+    response.set_request_id(req.take_request_id());
+
+    match req.req.unwrap() {
+        Request::call(call) => {
+            trace!("Chainvm Call {:?}", call);
+            let _ = serde_json::from_str::<BlockNumber>(&call.height)
+                .map(|block_id| {
+                    let call_request = CallRequest::from(call);
+                    command::eth_call(
+                        command_req_sender,
+                        command_resp_receiver,
+                        call_request,
+                        block_id.into(),
+                    )
+                    .map(|ok| {
+                        response.set_call_result(ok);
+                    })
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(err);
+                    })
+                })
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                });
+        }
+
+        Request::estimate_quota(call) => {
+            trace!("Estimate quota with params: {:?}", call);
+            let _ = serde_json::from_str::<BlockNumber>(&call.height)
+                .map(|block_id| {
+                    let call_request = CallRequest::from(call);
+                    command::estimate_quota(
+                        command_req_sender,
+                        command_resp_receiver,
+                        call_request,
+                        block_id.into(),
+                    )
+                    .map(|ok| {
+                        response.set_call_result(ok);
+                    })
+                    .map_err(|err| {
+                        response.set_code(ErrorCode::query_error());
+                        response.set_error_msg(err);
+                    })
+                })
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                });
+        }
+
+        Request::transaction_count(tx_count) => {
+            trace!("transaction count request from jsonrpc {:?}", tx_count);
+            let _ = serde_json::from_str::<CountOrCode>(&tx_count)
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                })
+                .map(|tx_count| {
+                    let address = Address::from_slice(tx_count.address.as_ref());
+                    match command::nonce_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        address,
+                        tx_count.block_id.into(),
+                    ) {
+                        Some(nonce) => {
+                            response.set_transaction_count(u64::from(nonce));
+                        }
+                        None => {
+                            response.set_transaction_count(0);
+                        }
+                    };
+                });
+        }
+
+        Request::code(code_content) => {
+            trace!("code request from jsonrpc  {:?}", code_content);
+            let _ = serde_json::from_str::<CountOrCode>(&code_content)
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                })
+                .map(|code_content| {
+                    let address = Address::from_slice(code_content.address.as_ref());
+                    if let Some(code) = command::code_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        address,
+                        code_content.block_id.into(),
+                    ) {
+                        response.set_contract_code(code);
+                    } else {
+                        response.set_contract_code(vec![]);
+                    };
+                });
+        }
+
+        Request::abi(abi_content) => {
+            trace!("abi request from jsonrpc  {:?}", abi_content);
+            let _ = serde_json::from_str::<CountOrCode>(&abi_content)
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                })
+                .map(|abi_content| {
+                    let address = Address::from_slice(abi_content.address.as_ref());
+                    if let Some(abi) = command::abi_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        address,
+                        abi_content.block_id.into(),
+                    ) {
+                        response.set_contract_abi(abi);
+                    } else {
+                        response.set_contract_abi(vec![]);
+                    };
+                });
+        }
+
+        Request::balance(balance_content) => {
+            trace!("balance request from jsonrpc  {:?}", balance_content);
+            let _ = serde_json::from_str::<CountOrCode>(&balance_content)
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                })
+                .map(|balance_content| {
+                    let address = Address::from_slice(balance_content.address.as_ref());
+                    if let Some(balance) = command::balance_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        address,
+                        balance_content.block_id.into(),
+                    ) {
+                        response.set_balance(balance);
+                    } else {
+                        response.set_balance(vec![]);
+                    };
+                });
+        }
+
+        Request::meta_data(data) => {
+            match command::metadata(command_req_sender, command_resp_receiver, data) {
+                Ok(metadata) => response.set_meta_data(serde_json::to_string(&metadata).unwrap()),
+                Err(error_msg) => {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(error_msg);
+                }
+            }
+        }
+
+        Request::state_proof(state_info) => {
+            trace!("state_proof info is {:?}", state_info);
+            let _ = serde_json::from_str::<BlockNumber>(&state_info.height)
+                .map(|block_id| {
+                    match command::state_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        block_id.into(),
+                    )
+                    .and_then(|state| {
+                        state
+                            .get_storage_proof(
+                                &Address::from(state_info.get_address()),
+                                &H256::from(state_info.get_position()),
+                            )
+                            .ok()
+                    }) {
+                        Some(state_proof_bs) => {
+                            let buf: Vec<u8> = state_proof_bs.into_iter().flatten().collect();
+                            response.set_state_proof(buf);
+                        }
+                        None => {
+                            response.set_code(ErrorCode::query_error());
+                            response.set_error_msg("get state proof failed".to_string());
+                        }
+                    }
+                })
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                });
+        }
+
+        Request::storage_key(skey) => {
+            trace!("storage key info is {:?}", skey);
+            let _ = serde_json::from_str::<BlockNumber>(&skey.height)
+                .map(|block_id| {
+                    match command::state_at(
+                        command_req_sender,
+                        command_resp_receiver,
+                        block_id.into(),
+                    )
+                    .and_then(|mut state| {
+                        state
+                            .get_storage(
+                                &Address::from(skey.get_address()),
+                                &H256::from(skey.get_position()),
+                            )
+                            .ok()
+                    }) {
+                        Some(storage_val) => {
+                            response.set_storage_value(storage_val.to_vec());
+                        }
+                        None => {
+                            response.set_code(ErrorCode::query_error());
+                            response.set_error_msg("get storage at something failed".to_string());
+                        }
+                    }
+                })
+                .map_err(|err| {
+                    response.set_code(ErrorCode::query_error());
+                    response.set_error_msg(format!("{:?}", err));
+                });
+        }
+
+        _ => {
+            error!("bad request msg!!!!");
+        }
+    };
+    response
+}
+
+/// Send the response of a request back to cita-chain.
+pub fn send_response(mq_resp_sender: &Sender<(String, Vec<u8>)>, response: response::Response) {
+    let msg: Message = response.into();
+    let key: String = routing_key!(Executor >> Response).into();
+    trace!("send {} into RabbitMQ", key);
+    let _ = mq_resp_sender.send((key, msg.try_into().unwrap()));
+}
+
+/// Send the result of a query back to jsonrpc.
+pub fn send_query_result(
+    mq_resp_sender: &Sender<(String, Vec<u8>)>,
+    request_id: Vec<u8>,
+    result: Result<Value, String>,
+) {
+    let message = RawMessage::QueryResult { request_id, result };
+    let key: String = routing_key!(Executor >> RawBytes).into();
+    trace!("send {} into RabbitMQ", key);
+    let _ = mq_resp_sender.send((key, message.to_bytes()));
+}
+
 #[cfg(test)]
 mod tests {
     use self::helpers::generate_executed_result;
@@ -755,11 +923,25 @@ mod tests {
 
         ::std::thread::spawn(move || {
             let command = command_req_receiver.recv().unwrap();
-            match command {
+            let _ = match command {
                 command::Command::LoadExecutedResult(0) => command_resp_sender.send(
                     command::CommandResp::LoadExecutedResult(libproto::ExecutedResult::new()),
                 ),
                 _ => panic!("received should be Command::LoadExecutedResult(0)"),
+            };
+            let command = command_req_receiver.recv().unwrap();
+            let _ = match command {
+                command::Command::FrozenAccounts => {
+                    command_resp_sender.send(command::CommandResp::FrozenAccounts(Vec::new()))
+                }
+                _ => panic!("received should be Command::FrozenAccounts"),
+            };
+            let command = command_req_receiver.recv().unwrap();
+            match command {
+                command::Command::QuotaPrices(0) => command_resp_sender.send(
+                    command::CommandResp::QuotaPrices(vec![(0, U256::from(100))]),
+                ),
+                _ => panic!("received should be Command::QuotaPrices(0)"),
             }
         });
         postman.bootstrap_broadcast();
@@ -773,6 +955,18 @@ mod tests {
             routing_key!(Executor >> ExecutedResult),
             RoutingKey::from(key)
         );
+
+        // The quota prices are published last.
+        let (key, message) = mq_resp_receiver.try_iter().last().unwrap();
+        assert_eq!(routing_key!(Executor >> RawBytes), RoutingKey::from(key));
+        assert_eq!(
+            RawMessage::from_bytes(&message),
+            Some(RawMessage::QuotaPrices {
+                from: 0,
+                prices: vec![(0, U256::from(100))],
+            })
+        );
+        assert_eq!(postman.quota_price, Some(U256::from(100)));
     }
 
     #[test]
@@ -796,11 +990,25 @@ mod tests {
                 _ => panic!("received should be Command::LoadExecutedResult(3)"),
             }
             let command = command_req_receiver.recv().unwrap();
-            match command {
+            let _ = match command {
                 command::Command::LoadExecutedResult(2) => command_resp_sender.send(
                     command::CommandResp::LoadExecutedResult(libproto::ExecutedResult::new()),
                 ),
                 _ => panic!("received should be Command::LoadExecutedResult(2)"),
+            };
+            let command = command_req_receiver.recv().unwrap();
+            let _ = match command {
+                command::Command::FrozenAccounts => {
+                    command_resp_sender.send(command::CommandResp::FrozenAccounts(Vec::new()))
+                }
+                _ => panic!("received should be Command::FrozenAccounts"),
+            };
+            let command = command_req_receiver.recv().unwrap();
+            match command {
+                command::Command::QuotaPrices(0) => command_resp_sender.send(
+                    command::CommandResp::QuotaPrices(vec![(0, U256::from(100))]),
+                ),
+                _ => panic!("received should be Command::QuotaPrices(0)"),
             }
         });
         postman.bootstrap_broadcast();
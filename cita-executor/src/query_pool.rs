@@ -0,0 +1,93 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A pool of workers answering the requests of cita-chain and the queries of
+//! cita-jsonrpc in parallel.
+//!
+//! Each worker owns a read-only executor reader, so the heavy queries, such as
+//! `estimateQuota`, never hold up the block execution or the other queries.
+
+use crate::core::libexecutor::command;
+use crate::core::libexecutor::executor::Executor;
+use crate::postman::{reply_request, send_query_result, send_response};
+use crate::types::raw_message::Query;
+use crossbeam_channel::{Receiver, Sender};
+use libproto::request;
+use std::thread;
+
+enum Task {
+    Request(request::Request),
+    Query(Vec<u8>, Query),
+}
+
+pub struct QueryPool {
+    task_sender: Sender<Task>,
+}
+
+impl QueryPool {
+    /// Start `workers` workers with readers cloned from the executor.
+    /// The workers exit when the pool is dropped.
+    pub fn new(
+        executor: &mut Executor,
+        workers: usize,
+        mq_resp_sender: Sender<(String, Vec<u8>)>,
+    ) -> Self {
+        let (task_sender, task_receiver) = crossbeam_channel::unbounded();
+        for id in 0..workers {
+            let (command_req_sender, command_req_receiver) = crossbeam_channel::bounded(0);
+            let (command_resp_sender, command_resp_receiver) = crossbeam_channel::bounded(0);
+            let mut reader = executor.query_reader(command_req_receiver, command_resp_sender);
+            thread::Builder::new()
+                .name(format!("query-reader-{}", id))
+                .spawn(move || reader.do_query_loop())
+                .expect("spawn query reader failed");
+
+            let task_receiver: Receiver<Task> = task_receiver.clone();
+            let mq_resp_sender = mq_resp_sender.clone();
+            thread::Builder::new()
+                .name(format!("query-worker-{}", id))
+                .spawn(move || {
+                    // The reader exits with the worker, as the command channel is closed.
+                    while let Ok(task) = task_receiver.recv() {
+                        match task {
+                            Task::Request(req) => {
+                                let response =
+                                    reply_request(req, &command_req_sender, &command_resp_receiver);
+                                send_response(&mq_resp_sender, response);
+                            }
+                            Task::Query(request_id, query) => {
+                                let result = command::query(
+                                    &command_req_sender,
+                                    &command_resp_receiver,
+                                    query,
+                                );
+                                send_query_result(&mq_resp_sender, request_id, result);
+                            }
+                        }
+                    }
+                })
+                .expect("spawn query worker failed");
+        }
+        info!("query pool started with {} workers", workers);
+        QueryPool { task_sender }
+    }
+
+    pub fn dispatch(&self, req: request::Request) {
+        let _ = self.task_sender.send(Task::Request(req));
+    }
+
+    pub fn dispatch_query(&self, request_id: Vec<u8>, query: Query) {
+        let _ = self.task_sender.send(Task::Query(request_id, query));
+    }
+}
@@ -43,6 +43,7 @@ pub fn generate_postman(current_height: u64, current_hash: H256) -> Postman {
         fsm_resp_receiver,
         command_req_sender,
         command_resp_receiver,
+        None,
     )
 }
 
@@ -22,6 +22,8 @@ pub struct Config {
     pub http_config: HttpConfig,
     pub ws_config: WsConfig,
     pub new_tx_flow_config: NewTxFlowConfig,
+    #[serde(default)]
+    pub health_config: HealthConfig,
 }
 
 impl Config {
@@ -36,6 +38,30 @@ pub struct NewTxFlowConfig {
     pub buffer_duration: u32, //in unit of ns
 }
 
+/// Thresholds of `/health` and `/ready`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct HealthConfig {
+    // in unit of s, a service without heartbeat for this long is dead
+    pub heartbeat_timeout: u64,
+    // in unit of s
+    pub peer_count_interval: u64,
+    // a service behind the highest known block for more blocks is not ready
+    pub max_height_lag: u64,
+    pub min_peers: u32,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            heartbeat_timeout: 15,
+            peer_count_interval: 3,
+            max_height_lag: 10,
+            min_peers: 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WsConfig {
     pub enable: bool,
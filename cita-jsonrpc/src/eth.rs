@@ -0,0 +1,630 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum compatible namespace.
+//!
+//! The `eth_*`, `net_*` and `web3_*` calls are renamed to the CITA methods which answer them
+//! before the request is parsed, so they go through the same MQ requests.
+//! The results are translated back into Ethereum's field layout.
+//!
+//! `eth_sendRawTransaction` takes an Ethereum transaction signed as in EIP-155, which is
+//! wrapped in a placeholder transaction for auth to convert, or CITA's `UnverifiedTransaction`.
+//! `eth_call` and `eth_estimateGas` with a gas, a gas price or a value are taken by `ext`.
+
+use libproto::blockchain::{Transaction, UnverifiedTransaction};
+use libproto::{TryFrom, TryInto};
+use serde_json::{Map, Value};
+use types::transaction::RAW_ETHEREUM_TX_PREFIX;
+
+/// `PriceManager` system contract
+const PRICE_MANAGEMENT: &str = "0xffffffffffffffffffffffffffffffffff020010";
+/// Method id of `getQuotaPrice()`
+const GET_QUOTA_PRICE: &str = "0x6bacc53f";
+/// Hash of an empty uncles list
+const EMPTY_UNCLES_HASH: &str =
+    "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347";
+const DEFAULT_BLOCK_TAG: &str = "latest";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EthMethod {
+    ChainId,
+    NetVersion,
+    NetListening,
+    NetPeerCount,
+    ClientVersion,
+    BlockNumber,
+    GasPrice,
+    GetBalance,
+    GetCode,
+    GetStorageAt,
+    GetTransactionCount,
+    Call,
+    EstimateGas,
+    GetLogs,
+    GetTransactionReceipt,
+    GetTransactionByHash,
+    GetBlockByNumber,
+    GetBlockByHash,
+    SendRawTransaction,
+}
+
+impl EthMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let method = match name {
+            "eth_chainId" => EthMethod::ChainId,
+            "net_version" => EthMethod::NetVersion,
+            "net_listening" => EthMethod::NetListening,
+            "net_peerCount" => EthMethod::NetPeerCount,
+            "web3_clientVersion" => EthMethod::ClientVersion,
+            "eth_blockNumber" => EthMethod::BlockNumber,
+            "eth_gasPrice" => EthMethod::GasPrice,
+            "eth_getBalance" => EthMethod::GetBalance,
+            "eth_getCode" => EthMethod::GetCode,
+            "eth_getStorageAt" => EthMethod::GetStorageAt,
+            "eth_getTransactionCount" => EthMethod::GetTransactionCount,
+            "eth_call" => EthMethod::Call,
+            "eth_estimateGas" => EthMethod::EstimateGas,
+            "eth_getLogs" => EthMethod::GetLogs,
+            "eth_getTransactionReceipt" => EthMethod::GetTransactionReceipt,
+            "eth_getTransactionByHash" => EthMethod::GetTransactionByHash,
+            "eth_getBlockByNumber" => EthMethod::GetBlockByNumber,
+            "eth_getBlockByHash" => EthMethod::GetBlockByHash,
+            "eth_sendRawTransaction" => EthMethod::SendRawTransaction,
+            _ => return None,
+        };
+        Some(method)
+    }
+
+    /// The CITA method which answers this one
+    pub fn cita_method(self) -> &'static str {
+        match self {
+            EthMethod::ChainId | EthMethod::NetVersion => "getMetaData",
+            EthMethod::NetListening | EthMethod::NetPeerCount => "peerCount",
+            EthMethod::ClientVersion => "getVersion",
+            EthMethod::BlockNumber => "blockNumber",
+            EthMethod::GasPrice | EthMethod::Call => "call",
+            EthMethod::GetBalance => "getBalance",
+            EthMethod::GetCode => "getCode",
+            EthMethod::GetStorageAt => "getStorageAt",
+            EthMethod::GetTransactionCount => "getTransactionCount",
+            EthMethod::EstimateGas => "estimateQuota",
+            EthMethod::GetLogs => "getLogs",
+            EthMethod::GetTransactionReceipt => "getTransactionReceipt",
+            EthMethod::GetTransactionByHash => "getTransaction",
+            EthMethod::GetBlockByNumber => "getBlockByNumber",
+            EthMethod::GetBlockByHash => "getBlockByHash",
+            EthMethod::SendRawTransaction => "sendRawTransaction",
+        }
+    }
+
+    fn translate_params(self, mut params: Vec<Value>) -> Vec<Value> {
+        match self {
+            EthMethod::ChainId | EthMethod::NetVersion => vec![Value::from(DEFAULT_BLOCK_TAG)],
+            EthMethod::NetListening
+            | EthMethod::NetPeerCount
+            | EthMethod::ClientVersion
+            | EthMethod::BlockNumber => Vec::new(),
+            EthMethod::GasPrice => {
+                let mut call = Map::new();
+                call.insert("to".to_owned(), Value::from(PRICE_MANAGEMENT));
+                call.insert("data".to_owned(), Value::from(GET_QUOTA_PRICE));
+                vec![Value::Object(call), Value::from(DEFAULT_BLOCK_TAG)]
+            }
+            EthMethod::GetBalance | EthMethod::GetCode | EthMethod::GetTransactionCount => {
+                with_block_tag(params, 1)
+            }
+            EthMethod::GetStorageAt => {
+                // CITA takes the position as a 32 bytes key.
+                if let Some(position) = params.get_mut(1) {
+                    if let Some(key) = position.as_str().map(pad_h256) {
+                        *position = Value::from(key);
+                    }
+                }
+                with_block_tag(params, 2)
+            }
+            EthMethod::Call | EthMethod::EstimateGas => {
+                if let Some(call) = params.get_mut(0) {
+                    *call = translate_call_request(call);
+                }
+                with_block_tag(params, 1)
+            }
+            EthMethod::SendRawTransaction => {
+                if let Some(raw) = params.get_mut(0) {
+                    if let Some(wrapped) = raw.as_str().and_then(wrap_raw_transaction) {
+                        *raw = Value::from(wrapped);
+                    }
+                }
+                params
+            }
+            EthMethod::GetLogs
+            | EthMethod::GetTransactionReceipt
+            | EthMethod::GetTransactionByHash
+            | EthMethod::GetBlockByNumber
+            | EthMethod::GetBlockByHash => params,
+        }
+    }
+
+    fn translate_result(self, result: Value) -> Value {
+        match self {
+            EthMethod::ChainId => chain_id(&result).map_or(Value::Null, Value::from),
+            EthMethod::NetVersion => chain_id(&result)
+                .and_then(|id| quantity_to_u64(&id))
+                .map_or(Value::Null, |id| Value::from(id.to_string())),
+            EthMethod::NetListening => Value::Bool(true),
+            EthMethod::ClientVersion => result
+                .get("softwareVersion")
+                .and_then(Value::as_str)
+                .map_or(Value::Null, |version| {
+                    Value::from(format!("CITA/{}", version))
+                }),
+            EthMethod::GasPrice => result
+                .as_str()
+                .map_or(Value::Null, |data| Value::from(trim_quantity(data))),
+            EthMethod::GetLogs => match result {
+                Value::Array(logs) => Value::Array(logs.into_iter().map(translate_log).collect()),
+                other => other,
+            },
+            EthMethod::GetTransactionReceipt => translate_receipt(result),
+            EthMethod::GetTransactionByHash => translate_transaction(result),
+            EthMethod::GetBlockByNumber | EthMethod::GetBlockByHash => translate_block(result),
+            EthMethod::SendRawTransaction => result.get("hash").cloned().unwrap_or(result),
+            EthMethod::NetPeerCount
+            | EthMethod::BlockNumber
+            | EthMethod::GetBalance
+            | EthMethod::GetCode
+            | EthMethod::GetStorageAt
+            | EthMethod::GetTransactionCount
+            | EthMethod::Call
+            | EthMethod::EstimateGas => result,
+        }
+    }
+}
+
+/// Ethereum methods of a request, `None` for the CITA ones.
+/// The calls of a batch are kept in order, and so are the outputs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Translation {
+    methods: Vec<Option<EthMethod>>,
+}
+
+impl Translation {
+    pub fn is_empty(&self) -> bool {
+        self.methods.iter().all(Option::is_none)
+    }
+
+    /// Translate the results of a response into Ethereum's field layout.
+    pub fn translate_response(&self, response: Value) -> Value {
+        match response {
+            Value::Array(outputs) => Value::Array(
+                outputs
+                    .into_iter()
+                    .zip(self.methods.iter())
+                    .map(|(output, method)| translate_output(output, *method))
+                    .collect(),
+            ),
+            output => translate_output(output, self.methods.get(0).cloned().unwrap_or(None)),
+        }
+    }
+}
+
+/// Rename the Ethereum calls of a request to the CITA methods.
+pub fn translate_request(request: Value) -> (Value, Translation) {
+    match request {
+        Value::Array(calls) => {
+            let (calls, methods) = calls.into_iter().map(translate_call).unzip();
+            (Value::Array(calls), Translation { methods })
+        }
+        call => {
+            let (call, method) = translate_call(call);
+            (
+                call,
+                Translation {
+                    methods: vec![method],
+                },
+            )
+        }
+    }
+}
+
+fn translate_call(mut call: Value) -> (Value, Option<EthMethod>) {
+    let method = call
+        .get("method")
+        .and_then(Value::as_str)
+        .and_then(EthMethod::from_name);
+    if let (Some(method), Some(object)) = (method, call.as_object_mut()) {
+        let params = match object.remove("params") {
+            Some(Value::Array(params)) => params,
+            _ => Vec::new(),
+        };
+        object.insert("method".to_owned(), Value::from(method.cita_method()));
+        object.insert(
+            "params".to_owned(),
+            Value::Array(method.translate_params(params)),
+        );
+    }
+    (call, method)
+}
+
+fn translate_output(mut output: Value, method: Option<EthMethod>) -> Value {
+    if let (Some(method), Some(object)) = (method, output.as_object_mut()) {
+        if let Some(result) = object.remove("result") {
+            object.insert("result".to_owned(), method.translate_result(result));
+        }
+    }
+    output
+}
+
+// The block tag is optional in Ethereum, but required in CITA.
+fn with_block_tag(mut params: Vec<Value>, index: usize) -> Vec<Value> {
+    if params.len() <= index {
+        params.resize(index, Value::Null);
+        params.push(Value::from(DEFAULT_BLOCK_TAG));
+    }
+    params
+}
+
+// The calls with a gas, a gas price or a value are taken by `ext`, so only these are left.
+fn translate_call_request(call: &Value) -> Value {
+    let mut request = Map::new();
+    for key in &["from", "to"] {
+        if let Some(value) = call.get(*key).filter(|value| !value.is_null()) {
+            request.insert((*key).to_owned(), value.clone());
+        }
+    }
+    if let Some(data) = call
+        .get("data")
+        .or_else(|| call.get("input"))
+        .filter(|value| !value.is_null())
+    {
+        request.insert("data".to_owned(), data.clone());
+    }
+    Value::Object(request)
+}
+
+// Wrap an Ethereum transaction, an RLP list unlike the protobuf of CITA's.
+fn wrap_raw_transaction(hex: &str) -> Option<String> {
+    let raw = from_hex(hex).filter(|raw| raw.first().map_or(false, |byte| *byte >= 0xc0))?;
+    let mut data = RAW_ETHEREUM_TX_PREFIX.to_vec();
+    data.extend(raw);
+    let mut tx = Transaction::new();
+    tx.set_data(data);
+    let mut un_tx = UnverifiedTransaction::new();
+    un_tx.set_transaction(tx);
+    let bytes: Vec<u8> = un_tx.try_into().ok()?;
+    Some(to_hex(&bytes))
+}
+
+fn translate_log(mut log: Value) -> Value {
+    if let Some(object) = log.as_object_mut() {
+        object.remove("transactionLogIndex");
+        object.insert("removed".to_owned(), Value::Bool(false));
+    }
+    log
+}
+
+fn translate_receipt(mut receipt: Value) -> Value {
+    if let Some(object) = receipt.as_object_mut() {
+        rename(object, "cumulativeQuotaUsed", "cumulativeGasUsed");
+        rename(object, "quotaUsed", "gasUsed");
+        let status = match object.get("errorMessage") {
+            Some(Value::Null) | None => "0x1",
+            Some(_) => "0x0",
+        };
+        object.insert("status".to_owned(), Value::from(status));
+        if let Some(Value::Array(logs)) = object.remove("logs") {
+            let logs = logs.into_iter().map(translate_log).collect();
+            object.insert("logs".to_owned(), Value::Array(logs));
+        }
+    }
+    receipt
+}
+
+fn translate_transaction(mut transaction: Value) -> Value {
+    if let Some(object) = transaction.as_object_mut() {
+        rename(object, "index", "transactionIndex");
+        let content = object
+            .remove("content")
+            .and_then(|content| content.as_str().and_then(from_hex))
+            .and_then(|content| UnverifiedTransaction::try_from(&content[..]).ok());
+        if let Some(unverified_tx) = content {
+            let tx = unverified_tx.get_transaction();
+            let nonce = tx.get_nonce().parse::<u64>().unwrap_or_default();
+            let to = if tx.get_version() == 0 {
+                let to = tx.get_to().trim_start_matches("0x");
+                if to.is_empty() {
+                    Value::Null
+                } else {
+                    Value::from(format!("0x{}", to))
+                }
+            } else if tx.get_to_v1().is_empty() {
+                Value::Null
+            } else {
+                Value::from(to_hex(tx.get_to_v1()))
+            };
+            object.insert("nonce".to_owned(), Value::from(format!("{:#x}", nonce)));
+            object.insert("to".to_owned(), to);
+            object.insert(
+                "value".to_owned(),
+                Value::from(trim_quantity(&to_hex(tx.get_value()))),
+            );
+            object.insert(
+                "gas".to_owned(),
+                Value::from(format!("{:#x}", tx.get_quota())),
+            );
+            object.insert("input".to_owned(), Value::from(to_hex(tx.get_data())));
+
+            // The signature is `r || s || v`.
+            let signature = unverified_tx.get_signature();
+            if signature.len() == 65 {
+                object.insert("r".to_owned(), Value::from(to_hex(&signature[0..32])));
+                object.insert("s".to_owned(), Value::from(to_hex(&signature[32..64])));
+                object.insert("v".to_owned(), Value::from(format!("{:#x}", signature[64])));
+            }
+        }
+    }
+    transaction
+}
+
+fn translate_block(block: Value) -> Value {
+    let (header, transactions) = match (block.get("header"), block.get("body")) {
+        (Some(header), Some(body)) => (header, body.get("transactions")),
+        _ => return block,
+    };
+
+    let mut object = Map::new();
+    object.insert(
+        "hash".to_owned(),
+        block.get("hash").cloned().unwrap_or(Value::Null),
+    );
+    for (from, to) in &[
+        ("number", "number"),
+        ("prevHash", "parentHash"),
+        ("stateRoot", "stateRoot"),
+        ("transactionsRoot", "transactionsRoot"),
+        ("receiptsRoot", "receiptsRoot"),
+        ("quotaUsed", "gasUsed"),
+        ("proposer", "miner"),
+    ] {
+        if let Some(value) = header.get(*from) {
+            object.insert((*to).to_owned(), value.clone());
+        }
+    }
+    // The timestamp of CITA is in milliseconds.
+    if let Some(timestamp) = header.get("timestamp").and_then(Value::as_u64) {
+        object.insert(
+            "timestamp".to_owned(),
+            Value::from(format!("{:#x}", timestamp / 1000)),
+        );
+    }
+    object.insert("nonce".to_owned(), Value::from("0x0000000000000000"));
+    object.insert("difficulty".to_owned(), Value::from("0x0"));
+    object.insert("sha3Uncles".to_owned(), Value::from(EMPTY_UNCLES_HASH));
+    object.insert("uncles".to_owned(), Value::Array(Vec::new()));
+
+    let transactions = match transactions {
+        Some(Value::Array(transactions)) => transactions
+            .iter()
+            .enumerate()
+            .map(|(index, transaction)| {
+                if transaction.is_object() {
+                    let mut transaction = transaction.clone();
+                    if let Some(tx) = transaction.as_object_mut() {
+                        tx.insert("blockHash".to_owned(), object["hash"].clone());
+                        tx.insert(
+                            "blockNumber".to_owned(),
+                            object.get("number").cloned().unwrap_or(Value::Null),
+                        );
+                        tx.insert("index".to_owned(), Value::from(format!("{:#x}", index)));
+                    }
+                    translate_transaction(transaction)
+                } else {
+                    transaction.clone()
+                }
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    object.insert("transactions".to_owned(), Value::Array(transactions));
+
+    Value::Object(object)
+}
+
+// Chain id of the metadata, `chainIdV1` since version 1.
+fn chain_id(metadata: &Value) -> Option<String> {
+    let chain_id_v1 = metadata
+        .get("chainIdV1")
+        .and_then(Value::as_str)
+        .map(trim_quantity)
+        .filter(|id| id != "0x0");
+    chain_id_v1.or_else(|| {
+        metadata
+            .get("chainId")
+            .and_then(Value::as_u64)
+            .map(|id| format!("{:#x}", id))
+    })
+}
+
+fn rename(object: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(value) = object.remove(from) {
+        object.insert(to.to_owned(), value);
+    }
+}
+
+// Remove the leading zeros, as a quantity of Ethereum.
+fn trim_quantity(hex: &str) -> String {
+    let digits = hex.trim_start_matches("0x").trim_start_matches('0');
+    if digits.is_empty() {
+        "0x0".to_owned()
+    } else {
+        format!("0x{}", digits)
+    }
+}
+
+pub fn quantity_to_u64(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok()
+}
+
+fn pad_h256(hex: &str) -> String {
+    format!("0x{:0>64}", hex.trim_start_matches("0x"))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", digits)
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim_start_matches("0x");
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{translate_request, EthMethod};
+
+    #[test]
+    fn test_translate_request() {
+        let request = json!([
+            {"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["0x0000000000000000000000000000000000000001"], "id": 1},
+            {"jsonrpc": "2.0", "method": "blockNumber", "params": [], "id": 2},
+            {"jsonrpc": "2.0", "method": "eth_call", "params": [{"to": "0x0000000000000000000000000000000000000001", "input": "0x12", "gas": "0x1"}], "id": 3},
+            {"jsonrpc": "2.0", "method": "eth_getStorageAt", "params": ["0x0000000000000000000000000000000000000001", "0x1", "0x10"], "id": 4}
+        ]);
+        let (request, translation) = translate_request(request);
+        assert!(!translation.is_empty());
+        assert_eq!(
+            request,
+            json!([
+                {"jsonrpc": "2.0", "method": "getBalance", "params": ["0x0000000000000000000000000000000000000001", "latest"], "id": 1},
+                {"jsonrpc": "2.0", "method": "blockNumber", "params": [], "id": 2},
+                {"jsonrpc": "2.0", "method": "call", "params": [{"to": "0x0000000000000000000000000000000000000001", "data": "0x12"}, "latest"], "id": 3},
+                {"jsonrpc": "2.0", "method": "getStorageAt", "params": ["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000000000000000000000000000001", "0x10"], "id": 4}
+            ])
+        );
+
+        let (request, translation) =
+            translate_request(json!({"jsonrpc": "2.0", "method": "peerCount", "id": 1}));
+        assert!(translation.is_empty());
+        assert_eq!(
+            request,
+            json!({"jsonrpc": "2.0", "method": "peerCount", "id": 1})
+        );
+        assert_eq!(EthMethod::from_name("eth_unknown"), None);
+    }
+
+    #[test]
+    fn test_translate_response() {
+        let (_, translation) = translate_request(json!([
+            {"jsonrpc": "2.0", "method": "eth_chainId", "id": 1},
+            {"jsonrpc": "2.0", "method": "net_version", "id": 2},
+            {"jsonrpc": "2.0", "method": "eth_getTransactionReceipt", "params": ["0x01"], "id": 3},
+            {"jsonrpc": "2.0", "method": "eth_gasPrice", "id": 4}
+        ]));
+        let response = json!([
+            {"jsonrpc": "2.0", "id": 1, "result": {"chainId": 0, "chainIdV1": "0x0000000000000000000000000000000000000000000000000000000000000010"}},
+            {"jsonrpc": "2.0", "id": 2, "result": {"chainId": 0, "chainIdV1": "0x10"}},
+            {"jsonrpc": "2.0", "id": 3, "result": {
+                "quotaUsed": "0x5208",
+                "cumulativeQuotaUsed": "0x5208",
+                "errorMessage": null,
+                "logs": [{"logIndex": "0x0", "transactionLogIndex": "0x0"}]
+            }},
+            {"jsonrpc": "2.0", "id": 4, "error": {"code": -32000, "message": "failed"}}
+        ]);
+        assert_eq!(
+            translation.translate_response(response),
+            json!([
+                {"jsonrpc": "2.0", "id": 1, "result": "0x10"},
+                {"jsonrpc": "2.0", "id": 2, "result": "16"},
+                {"jsonrpc": "2.0", "id": 3, "result": {
+                    "gasUsed": "0x5208",
+                    "cumulativeGasUsed": "0x5208",
+                    "errorMessage": null,
+                    "status": "0x1",
+                    "logs": [{"logIndex": "0x0", "removed": false}]
+                }},
+                {"jsonrpc": "2.0", "id": 4, "error": {"code": -32000, "message": "failed"}}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_translate_raw_transaction() {
+        use super::{from_hex, to_hex, UnverifiedTransaction, RAW_ETHEREUM_TX_PREFIX};
+        use libproto::TryFrom;
+
+        // The example of EIP-155
+        let raw = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+        let (request, _) = translate_request(
+            json!({"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": [raw], "id": 1}),
+        );
+        assert_eq!(request["method"], json!("sendRawTransaction"));
+        let wrapped = from_hex(request["params"][0].as_str().unwrap()).unwrap();
+        let un_tx = UnverifiedTransaction::try_from(&wrapped[..]).unwrap();
+        assert!(un_tx.get_signature().is_empty());
+        let data = un_tx.get_transaction().get_data();
+        assert_eq!(
+            &data[..RAW_ETHEREUM_TX_PREFIX.len()],
+            RAW_ETHEREUM_TX_PREFIX
+        );
+        assert_eq!(
+            data[RAW_ETHEREUM_TX_PREFIX.len()..],
+            from_hex(raw).unwrap()[..]
+        );
+
+        // CITA's transactions are kept.
+        let wrapped = to_hex(&wrapped);
+        let (request, _) = translate_request(
+            json!({"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": [wrapped], "id": 1}),
+        );
+        assert_eq!(request["params"][0], json!(wrapped));
+    }
+
+    #[test]
+    fn test_translate_block() {
+        let (_, translation) = translate_request(
+            json!({"jsonrpc": "2.0", "method": "eth_getBlockByNumber", "params": ["0x1", false], "id": 1}),
+        );
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {
+            "version": 2,
+            "hash": "0x02",
+            "header": {
+                "timestamp": 1_500_000,
+                "prevHash": "0x01",
+                "number": "0x1",
+                "stateRoot": "0x03",
+                "transactionsRoot": "0x04",
+                "receiptsRoot": "0x05",
+                "quotaUsed": "0x0",
+                "proposer": "0x0000000000000000000000000000000000000001"
+            },
+            "body": {"transactions": ["0x06"]}
+        }});
+        let block = &translation.translate_response(response)["result"];
+        assert_eq!(block["parentHash"], json!("0x01"));
+        assert_eq!(block["timestamp"], json!("0x5dc"));
+        assert_eq!(block["gasUsed"], json!("0x0"));
+        assert_eq!(
+            block["miner"],
+            json!("0x0000000000000000000000000000000000000001")
+        );
+        assert_eq!(block["transactions"], json!(["0x06"]));
+    }
+}
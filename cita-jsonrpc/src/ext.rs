@@ -0,0 +1,1167 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CITA methods which have no request in `jsonrpc_types` and libproto.
+//!
+//! These calls are taken out of a request before it's parsed. Each one is sent as a
+//! `RawMessage::Query` in `Jsonrpc >> RawBytes`, and the executor or the chain answers it
+//! with a `RawMessage::QueryResult` of the same request id in its own `RawBytes`.
+//! The outputs are put back at the positions of the calls in a batch.
+//!
+//! `getSysConfigChanges` returns the changes of the system config in the heights
+//! `[from, to]`, and `getValidatorChanges` only the changes of the validators.
+//! `getFeeReport` returns where the fees of a block went.
+//! `getQuotaPriceHistory` returns the quota used and the fees paid in the latest blocks,
+//! answered by the chain from the receipts it keeps.
+//! `getFrozenAccounts` returns the accounts frozen by the emergency intervention at a block.
+//! `getStateDiff` returns the state diff recorded by the executor for a block.
+//! `call` (or `eth_call`) with the overrides of the accounts as the third param, and of the
+//! block context as the fourth, runs the call on a throwaway state with them applied.
+//! `call` and `estimateQuota` (or `eth_call` and `eth_estimateGas`) are also taken when the
+//! call carries a quota, a quota price or a value, which the requests of libproto have not.
+//! `getLogs` returns the logs of a filter in an array, or a page of them as `logs` with the
+//! opaque cursor of the next page as `next`, which is given as the second param to continue.
+//!
+//! The filters (`newFilter`, `newBlockFilter`, `newPendingTransactionFilter`,
+//! `getFilterChanges`, `getFilterLogs` and `uninstallFilter`) are kept by the chain with the
+//! client which created them, and the other clients can't use them. A websocket client is
+//! its connection. The connections of an http client are not kept, so each of its filters
+//! gets a random token instead, which is put in the high 64 bits of the filter id returned
+//! to it, and is taken out of the id again when the filter is used.
+//! The pending transaction filters get the transactions entering the pool of auth.
+
+use crate::eth::quantity_to_u64;
+use crate::helper::{RawSender, RpcMap, TransferType};
+use error::ErrorCode;
+use jsonrpc_types::rpc_response::RpcFailure;
+use jsonrpc_types::rpc_types::BlockNumber as RpcBlockNumber;
+use jsonrpc_types::Error;
+use libproto::router::{MsgType, RoutingKey, SubModules};
+use serde_json::{Map, Value};
+use types::block_number::{BlockTag, Tag};
+use types::call::{CallOverrides, CallParams};
+use types::filter::LogsCursor;
+use types::raw_message::{Query, RawMessage};
+use uuid::Uuid;
+
+/// The client which makes a call, its filters are tied to it.
+#[derive(Debug, Clone, Copy)]
+pub enum Client<'a> {
+    /// A websocket connection
+    Connection(&'a str),
+    /// An http client, whose filters carry their tokens in their ids
+    Http,
+}
+
+/// Fields of the system config which are the validator set
+const VALIDATOR_FIELDS: &[&str] = &["nodes", "validators"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtMethod {
+    SysConfigChanges,
+    ValidatorChanges,
+    FeeReport,
+    QuotaPriceHistory,
+    FrozenAccounts,
+    StateDiff,
+    Call,
+    EstimateQuota,
+    Logs,
+    NewFilter,
+    NewBlockFilter,
+    NewPendingTransactionFilter,
+    FilterChanges,
+    FilterLogs,
+    UninstallFilter,
+}
+
+impl ExtMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let method = match name {
+            "getSysConfigChanges" => ExtMethod::SysConfigChanges,
+            "getValidatorChanges" => ExtMethod::ValidatorChanges,
+            "getFeeReport" => ExtMethod::FeeReport,
+            "getQuotaPriceHistory" => ExtMethod::QuotaPriceHistory,
+            "getFrozenAccounts" => ExtMethod::FrozenAccounts,
+            "getStateDiff" => ExtMethod::StateDiff,
+            "getLogs" => ExtMethod::Logs,
+            "newFilter" => ExtMethod::NewFilter,
+            "newBlockFilter" => ExtMethod::NewBlockFilter,
+            "newPendingTransactionFilter" => ExtMethod::NewPendingTransactionFilter,
+            "getFilterChanges" => ExtMethod::FilterChanges,
+            "getFilterLogs" => ExtMethod::FilterLogs,
+            "uninstallFilter" => ExtMethod::UninstallFilter,
+            _ => return None,
+        };
+        Some(method)
+    }
+
+    /// A call is only taken out of the request if it has overrides, or carries what
+    /// libproto's call has not.
+    fn from_call(name: &str, params: &[Value]) -> Option<Self> {
+        match name {
+            "call" | "eth_call" if params.len() > 2 || has_call_fields(params.get(0)) => {
+                Some(ExtMethod::Call)
+            }
+            "estimateQuota" | "eth_estimateGas" if has_call_fields(params.get(0)) => {
+                Some(ExtMethod::EstimateQuota)
+            }
+            _ => Self::from_name(name),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExtMethod::SysConfigChanges => "getSysConfigChanges",
+            ExtMethod::ValidatorChanges => "getValidatorChanges",
+            ExtMethod::FeeReport => "getFeeReport",
+            ExtMethod::QuotaPriceHistory => "getQuotaPriceHistory",
+            ExtMethod::FrozenAccounts => "getFrozenAccounts",
+            ExtMethod::StateDiff => "getStateDiff",
+            ExtMethod::Call => "call",
+            ExtMethod::EstimateQuota => "estimateQuota",
+            ExtMethod::Logs => "getLogs",
+            ExtMethod::NewFilter => "newFilter",
+            ExtMethod::NewBlockFilter => "newBlockFilter",
+            ExtMethod::NewPendingTransactionFilter => "newPendingTransactionFilter",
+            ExtMethod::FilterChanges => "getFilterChanges",
+            ExtMethod::FilterLogs => "getFilterLogs",
+            ExtMethod::UninstallFilter => "uninstallFilter",
+        }
+    }
+
+    fn is_new_filter(self) -> bool {
+        match self {
+            ExtMethod::NewFilter
+            | ExtMethod::NewBlockFilter
+            | ExtMethod::NewPendingTransactionFilter => true,
+            _ => false,
+        }
+    }
+
+    fn query(self, params: &[Value], client: Client, token: Option<u64>) -> Result<Query, String> {
+        let new_client = || match client {
+            Client::Connection(connection) => connection.to_owned(),
+            Client::Http => token_client(token.unwrap_or_default()),
+        };
+        match self {
+            ExtMethod::SysConfigChanges | ExtMethod::ValidatorChanges => {
+                let from = height_param(params.get(0)).ok_or("Invalid height from")?;
+                let to = height_param(params.get(1)).ok_or("Invalid height to")?;
+                Ok(Query::SysConfigChanges { from, to })
+            }
+            ExtMethod::FeeReport => {
+                let height = height_param(params.get(0)).ok_or("Invalid height")?;
+                Ok(Query::FeeReport { height })
+            }
+            ExtMethod::QuotaPriceHistory => {
+                let block_count = height_param(params.get(0)).ok_or("Invalid block count")?;
+                let percentiles = percentiles_param(params.get(1)).ok_or("Invalid percentiles")?;
+                Ok(Query::QuotaPriceHistory {
+                    block_count,
+                    percentiles,
+                })
+            }
+            ExtMethod::FrozenAccounts => {
+                let block_tag = block_tag_param(params.get(0)).ok_or("Invalid block tag")?;
+                Ok(Query::FrozenAccounts { block_tag })
+            }
+            ExtMethod::StateDiff => {
+                let height = height_param(params.get(0)).ok_or("Invalid height")?;
+                Ok(Query::StateDiff { height })
+            }
+            ExtMethod::Call => {
+                let request = call_param(params.get(0)).ok_or("Invalid call request")?;
+                let block_tag = block_tag_param(params.get(1)).ok_or("Invalid block tag")?;
+                let overrides = overrides_param(params.get(2), params.get(3))?;
+                Ok(Query::Call {
+                    request,
+                    block_tag,
+                    overrides,
+                })
+            }
+            ExtMethod::EstimateQuota => {
+                let request = call_param(params.get(0)).ok_or("Invalid call request")?;
+                let block_tag = block_tag_param(params.get(1)).ok_or("Invalid block tag")?;
+                Ok(Query::EstimateQuota { request, block_tag })
+            }
+            ExtMethod::Logs => {
+                let filter = params
+                    .get(0)
+                    .and_then(|param| serde_json::from_value(param.clone()).ok())
+                    .ok_or("Invalid filter")?;
+                let cursor = cursor_param(params.get(1))?;
+                Ok(Query::Logs { filter, cursor })
+            }
+            ExtMethod::NewFilter => {
+                let filter = params
+                    .get(0)
+                    .and_then(|param| serde_json::from_value(param.clone()).ok())
+                    .ok_or("Invalid filter")?;
+                Ok(Query::NewFilter {
+                    client: new_client(),
+                    filter,
+                })
+            }
+            ExtMethod::NewBlockFilter => Ok(Query::NewBlockFilter {
+                client: new_client(),
+            }),
+            ExtMethod::NewPendingTransactionFilter => Ok(Query::NewPendingTransactionFilter {
+                client: new_client(),
+            }),
+            ExtMethod::FilterChanges => {
+                let (client, id) =
+                    filter_id_param(params.get(0), client).ok_or("Invalid filter id")?;
+                Ok(Query::FilterChanges { client, id })
+            }
+            ExtMethod::FilterLogs => {
+                let (client, id) =
+                    filter_id_param(params.get(0), client).ok_or("Invalid filter id")?;
+                Ok(Query::FilterLogs { client, id })
+            }
+            ExtMethod::UninstallFilter => {
+                let (client, id) =
+                    filter_id_param(params.get(0), client).ok_or("Invalid filter id")?;
+                Ok(Query::UninstallFilter { client, id })
+            }
+        }
+    }
+
+    fn translate_result(self, result: Value, token: Option<u64>) -> Value {
+        match self {
+            ExtMethod::NewFilter
+            | ExtMethod::NewBlockFilter
+            | ExtMethod::NewPendingTransactionFilter => translate_filter_id(result, token),
+            ExtMethod::SysConfigChanges => translate_sys_config_changes(result, None),
+            ExtMethod::ValidatorChanges => {
+                translate_sys_config_changes(result, Some(VALIDATOR_FIELDS))
+            }
+            ExtMethod::FeeReport => translate_fee_report(result),
+            ExtMethod::QuotaPriceHistory => translate_quota_price_history(result),
+            ExtMethod::StateDiff => translate_state_diff(result),
+            ExtMethod::FrozenAccounts
+            | ExtMethod::Call
+            | ExtMethod::EstimateQuota
+            | ExtMethod::Logs
+            | ExtMethod::FilterChanges
+            | ExtMethod::FilterLogs
+            | ExtMethod::UninstallFilter => result,
+        }
+    }
+}
+
+/// What's needed to answer a call: its id and its method.
+#[derive(Debug, Clone)]
+pub struct ExtInfo {
+    jsonrpc: Option<Value>,
+    id: Value,
+    method: ExtMethod,
+    /// The token of a new filter of an http client
+    token: Option<u64>,
+}
+
+impl ExtInfo {
+    /// The output of the call with the result of its query.
+    pub fn output(&self, result: Result<Value, String>) -> Value {
+        match result {
+            Ok(result) => {
+                let mut output = self.output_head();
+                output.insert(
+                    "result".to_owned(),
+                    self.method.translate_result(result, self.token),
+                );
+                Value::Object(output)
+            }
+            Err(msg) => self.failure(Error::server_error(ErrorCode::query_error(), msg)),
+        }
+    }
+
+    fn failure(&self, err: Error) -> Value {
+        let mut output = self.output_head();
+        if let Ok(Value::Object(failure)) = serde_json::to_value(RpcFailure::from(err)) {
+            if let Some(error) = failure.get("error") {
+                output.insert("error".to_owned(), error.clone());
+            }
+        }
+        Value::Object(output)
+    }
+
+    fn output_head(&self) -> Map<String, Value> {
+        let mut output = Map::new();
+        if let Some(ref jsonrpc) = self.jsonrpc {
+            output.insert("jsonrpc".to_owned(), jsonrpc.clone());
+        }
+        output.insert("id".to_owned(), self.id.clone());
+        output
+    }
+}
+
+#[derive(Debug)]
+pub struct ExtCall {
+    info: ExtInfo,
+    query: Result<Query, String>,
+}
+
+impl ExtCall {
+    fn from_call(call: &Value, client: Client) -> Option<Self> {
+        let params: &[Value] = match call.get("params") {
+            Some(Value::Array(params)) => &params[..],
+            _ => &[],
+        };
+        let method = call
+            .get("method")
+            .and_then(Value::as_str)
+            .and_then(|name| ExtMethod::from_call(name, params))?;
+        let token = match client {
+            Client::Http if method.is_new_filter() => Some(random_token()),
+            _ => None,
+        };
+        let info = ExtInfo {
+            jsonrpc: call.get("jsonrpc").cloned(),
+            id: call.get("id").cloned().unwrap_or(Value::Null),
+            method,
+            token,
+        };
+        let query = method.query(params, client, token);
+        Some(ExtCall { info, query })
+    }
+
+    pub fn method(&self) -> ExtMethod {
+        self.info.method
+    }
+
+    /// Send the query of the call, its output is sent to the transfer when it's answered.
+    /// The output of an invalid call is returned at once.
+    pub fn send<F>(
+        self,
+        responses: &RpcMap,
+        sender: &RawSender,
+        transfer: F,
+    ) -> Result<Vec<u8>, Value>
+    where
+        F: FnOnce(ExtInfo) -> TransferType,
+    {
+        let query = match self.query {
+            Ok(query) => query,
+            Err(msg) => return Err(self.info.failure(Error::invalid_params(msg))),
+        };
+        let request_id = Uuid::new_v4().as_bytes().to_vec();
+        responses
+            .lock()
+            .insert(request_id.clone(), transfer(self.info));
+        let message = RawMessage::Query {
+            request_id: request_id.clone(),
+            query,
+        };
+        // NOTE: send failure is handled as timeout error
+        let _ = sender.send((routing_key!(Jsonrpc >> RawBytes).into(), message.to_bytes()));
+        Ok(request_id)
+    }
+}
+
+/// The calls taken out of a request, with their positions in the batch.
+#[derive(Debug, Default)]
+pub struct ExtRequest {
+    batch: bool,
+    len: usize,
+    positions: Vec<usize>,
+    calls: Vec<ExtCall>,
+}
+
+impl ExtRequest {
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn calls(&self) -> &[ExtCall] {
+        &self.calls
+    }
+
+    pub fn take_calls(&mut self) -> Vec<ExtCall> {
+        ::std::mem::replace(&mut self.calls, Vec::new())
+    }
+
+    /// Put the outputs of the calls back into the response of the rest of the request.
+    pub fn merge_response(&self, response: Option<Value>, outputs: Vec<Value>) -> Value {
+        if !self.batch {
+            return outputs.into_iter().next().unwrap_or(Value::Null);
+        }
+        let mut rest = match response {
+            Some(Value::Array(rest)) => rest.into_iter(),
+            // The rest of the batch failed as a whole.
+            Some(failure) => return failure,
+            None => Vec::new().into_iter(),
+        };
+        let mut outputs = self.positions.iter().zip(outputs).peekable();
+        let merged = (0..self.len)
+            .filter_map(|position| match outputs.peek() {
+                Some((call_position, _)) if **call_position == position => {
+                    outputs.next().map(|(_, output)| output)
+                }
+                _ => rest.next(),
+            })
+            .collect();
+        Value::Array(merged)
+    }
+}
+
+/// Take the calls of these methods out of a request of the client.
+/// The rest of the request is `None` if nothing is left.
+pub fn split_request(request: Value, client: Client) -> (Option<Value>, ExtRequest) {
+    match request {
+        Value::Array(calls) => {
+            let mut ext_request = ExtRequest {
+                batch: true,
+                len: calls.len(),
+                ..Default::default()
+            };
+            let mut rest = Vec::new();
+            for (position, call) in calls.into_iter().enumerate() {
+                match ExtCall::from_call(&call, client) {
+                    Some(ext_call) => {
+                        ext_request.positions.push(position);
+                        ext_request.calls.push(ext_call);
+                    }
+                    None => rest.push(call),
+                }
+            }
+            if rest.is_empty() && !ext_request.is_empty() {
+                (None, ext_request)
+            } else {
+                (Some(Value::Array(rest)), ext_request)
+            }
+        }
+        call => match ExtCall::from_call(&call, client) {
+            Some(ext_call) => (
+                None,
+                ExtRequest {
+                    batch: false,
+                    len: 1,
+                    positions: vec![0],
+                    calls: vec![ext_call],
+                },
+            ),
+            None => (Some(call), ExtRequest::default()),
+        },
+    }
+}
+
+/// A height given as a number or a quantity, tags are not accepted.
+pub fn height_param(param: Option<&Value>) -> Option<u64> {
+    match param {
+        Some(Value::Number(number)) => number.as_u64(),
+        Some(Value::String(hex)) if hex.starts_with("0x") => quantity_to_u64(hex),
+        _ => None,
+    }
+}
+
+/// The client and the id of a filter given as a number or a quantity.
+/// The ids of the filters of an http client are 128 bits, with their tokens in the high half.
+fn filter_id_param(param: Option<&Value>, client: Client) -> Option<(String, u64)> {
+    match client {
+        Client::Connection(connection) => height_param(param).map(|id| (connection.to_owned(), id)),
+        Client::Http => {
+            let id = match param {
+                Some(Value::Number(number)) => u128::from(number.as_u64()?),
+                Some(Value::String(hex)) if hex.starts_with("0x") => {
+                    u128::from_str_radix(&hex[2..], 16).ok()?
+                }
+                _ => return None,
+            };
+            Some((token_client((id >> 64) as u64), id as u64))
+        }
+    }
+}
+
+// A random token, the bits of a v4 uuid which are not fixed are folded into 64 bits.
+fn random_token() -> u64 {
+    let uuid = Uuid::new_v4();
+    let bytes = uuid.as_bytes();
+    let mut high = [0u8; 8];
+    let mut low = [0u8; 8];
+    high.copy_from_slice(&bytes[..8]);
+    low.copy_from_slice(&bytes[8..]);
+    u64::from_be_bytes(high) ^ u64::from_be_bytes(low)
+}
+
+// The client of the filters of a token, as the chain keeps it.
+fn token_client(token: u64) -> String {
+    format!("http:{:016x}", token)
+}
+
+/// A height or a tag, `latest` if it's omitted.
+fn block_tag_param(param: Option<&Value>) -> Option<BlockTag> {
+    match param {
+        Some(param) => serde_json::from_value::<RpcBlockNumber>(param.clone())
+            .ok()
+            .map(BlockTag::from),
+        None => Some(BlockTag::Tag(Tag::Latest)),
+    }
+}
+
+// Whether a call carries a quota, a quota price or a value.
+fn has_call_fields(call: Option<&Value>) -> bool {
+    call.map_or(false, |call| {
+        ["gas", "gasPrice", "value"]
+            .iter()
+            .any(|key| call.get(*key).map_or(false, |value| !value.is_null()))
+    })
+}
+
+fn call_param(param: Option<&Value>) -> Option<CallParams> {
+    param.and_then(|param| serde_json::from_value::<CallParams>(param.clone()).ok())
+}
+
+// The cursor a page of logs stopped at, the logs start from the first block if it's omitted.
+fn cursor_param(param: Option<&Value>) -> Result<Option<LogsCursor>, String> {
+    match param {
+        Some(Value::String(cursor)) => cursor.parse().map(Some),
+        Some(Value::Null) | None => Ok(None),
+        Some(_) => Err("Invalid cursor".to_owned()),
+    }
+}
+
+// The overrides of the accounts, by their addresses, and of the block context.
+fn overrides_param(
+    accounts: Option<&Value>,
+    context: Option<&Value>,
+) -> Result<CallOverrides, String> {
+    let mut overrides = CallOverrides::default();
+    if let Some(accounts) = accounts.filter(|accounts| !accounts.is_null()) {
+        overrides.accounts = serde_json::from_value(accounts.clone())
+            .map_err(|err| format!("Invalid account overrides: {}", err))?;
+    }
+    if let Some(context) = context.filter(|context| !context.is_null()) {
+        overrides.context = serde_json::from_value(context.clone())
+            .map_err(|err| format!("Invalid context overrides: {}", err))?;
+    }
+    Ok(overrides)
+}
+
+// The percentiles in basis points. They could be fractional, as Ethereum's, and are all
+// optional.
+fn percentiles_param(param: Option<&Value>) -> Option<Vec<u64>> {
+    match param {
+        Some(Value::Array(percentiles)) => percentiles
+            .iter()
+            .map(|percentile| {
+                percentile
+                    .as_f64()
+                    .filter(|percentile| *percentile >= 0.0 && *percentile <= 100.0)
+                    .map(|percentile| (percentile * 100.0).round() as u64)
+            })
+            .collect(),
+        Some(Value::Null) | None => Some(Vec::new()),
+        Some(_) => None,
+    }
+}
+
+// The heights of the history are numbers.
+fn translate_quota_price_history(mut result: Value) -> Value {
+    if let Some(history) = result.as_object_mut() {
+        if let Some(oldest) = history.get("oldestBlock").and_then(Value::as_u64) {
+            history.insert(
+                "oldestBlock".to_owned(),
+                Value::from(format!("{:#x}", oldest)),
+            );
+        }
+        if let Some(Value::Array(blocks)) = history.get_mut("blocks") {
+            for block in blocks.iter_mut() {
+                if let Some(height) = block.get("height").and_then(Value::as_u64) {
+                    block["height"] = Value::from(format!("{:#x}", height));
+                }
+            }
+        }
+    }
+    result
+}
+
+// The fields of the accounts are in camel case.
+fn translate_state_diff(mut result: Value) -> Value {
+    if let Some(accounts) = result.as_object_mut() {
+        for account in accounts.values_mut() {
+            if let Some(account) = account.as_object_mut() {
+                if let Some(code_hash) = account.remove("code_hash") {
+                    account.insert("codeHash".to_owned(), code_hash);
+                }
+            }
+        }
+    }
+    result
+}
+
+// The token of a new filter of an http client is put in the high 64 bits of its id.
+fn translate_filter_id(result: Value, token: Option<u64>) -> Value {
+    let token = match token {
+        Some(token) => token,
+        None => return result,
+    };
+    match result.as_str().and_then(quantity_to_u64) {
+        Some(id) => Value::from(format!("{:#x}", (u128::from(token) << 64) | u128::from(id))),
+        None => result,
+    }
+}
+
+// The height of the report is a number.
+fn translate_fee_report(mut result: Value) -> Value {
+    if let Some(report) = result.as_object_mut() {
+        if let Some(height) = report.get("height").and_then(Value::as_u64) {
+            report.insert("height".to_owned(), Value::from(format!("{:#x}", height)));
+        }
+    }
+    result
+}
+
+// Only the changes of the given fields are kept if there are some.
+fn translate_sys_config_changes(result: Value, fields: Option<&[&str]>) -> Value {
+    let changes = match result {
+        Value::Array(changes) => changes,
+        other => return other,
+    };
+    let changes = changes
+        .into_iter()
+        .filter_map(|change| {
+            let height = change.get("height").and_then(Value::as_u64)?;
+            let fields_changes: Map<String, Value> = match change.get("changes") {
+                Some(Value::Object(changes)) => changes
+                    .iter()
+                    .filter(|(name, _)| {
+                        fields.map_or(true, |fields| fields.contains(&name.as_str()))
+                    })
+                    .map(|(name, field_change)| (name.clone(), field_change.clone()))
+                    .collect(),
+                _ => Map::new(),
+            };
+            if fields_changes.is_empty() {
+                None
+            } else {
+                Some(json!({
+                    "height": format!("{:#x}", height),
+                    "changes": fields_changes,
+                }))
+            }
+        })
+        .collect();
+    Value::Array(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::sync::oneshot;
+    use pubsub::channel;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use util::Mutex;
+
+    const CONNECTION: &str = "6f1e1b6c-9fd2-4bd4-b07a-e4b2ffa1b8c5";
+    const CLIENT: Client<'static> = Client::Connection(CONNECTION);
+
+    #[test]
+    fn test_split_request() {
+        let (rest, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "blockNumber", "params": [], "id": 1},
+                {"jsonrpc": "2.0", "method": "getSysConfigChanges", "params": ["0x1", 16], "id": 2},
+                {"jsonrpc": "2.0", "method": "peerCount", "params": [], "id": 3}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(
+            rest,
+            Some(json!([
+                {"jsonrpc": "2.0", "method": "blockNumber", "params": [], "id": 1},
+                {"jsonrpc": "2.0", "method": "peerCount", "params": [], "id": 3}
+            ]))
+        );
+        assert_eq!(ext_request.positions, vec![1]);
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::SysConfigChanges { from: 1, to: 16 })
+        );
+
+        let merged = ext_request.merge_response(
+            Some(json!([{"id": 1, "result": "0x1"}, {"id": 3, "result": 2}])),
+            vec![json!({"id": 2, "result": []})],
+        );
+        assert_eq!(
+            merged,
+            json!([
+                {"id": 1, "result": "0x1"},
+                {"id": 2, "result": []},
+                {"id": 3, "result": 2}
+            ])
+        );
+
+        let request = json!({"jsonrpc": "2.0", "method": "blockNumber", "params": [], "id": 1});
+        let (rest, ext_request) = split_request(request.clone(), CLIENT);
+        assert_eq!(rest, Some(request));
+        assert!(ext_request.is_empty());
+
+        let (rest, ext_request) = split_request(
+            json!({"jsonrpc": "2.0", "method": "getValidatorChanges", "params": [1], "id": 1}),
+            CLIENT,
+        );
+        assert_eq!(rest, None);
+        assert!(ext_request.calls[0].query.is_err());
+    }
+
+    #[test]
+    fn test_send_call() {
+        let responses: RpcMap = Arc::new(Mutex::new(HashMap::new()));
+        let (sender, receiver) = channel::unbounded();
+        let (_, mut ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "getSysConfigChanges", "params": [1, 16], "id": 1},
+                {"jsonrpc": "2.0", "method": "getSysConfigChanges", "params": ["latest"], "id": 2}
+            ]),
+            CLIENT,
+        );
+        let mut calls = ext_request.take_calls().into_iter();
+
+        let (tx, _rx) = oneshot::channel();
+        let request_id = calls
+            .next()
+            .unwrap()
+            .send(&responses, &sender, |info| {
+                TransferType::ExtHttp((info, tx))
+            })
+            .unwrap();
+        let (key, body) = receiver.try_recv().unwrap();
+        assert_eq!(key, "jsonrpc.raw_bytes");
+        assert_eq!(
+            RawMessage::from_bytes(&body),
+            Some(RawMessage::Query {
+                request_id: request_id.clone(),
+                query: Query::SysConfigChanges { from: 1, to: 16 },
+            })
+        );
+        assert!(responses.lock().contains_key(&request_id));
+
+        let (tx, _rx) = oneshot::channel();
+        let output = calls
+            .next()
+            .unwrap()
+            .send(&responses, &sender, |info| {
+                TransferType::ExtHttp((info, tx))
+            })
+            .unwrap_err();
+        assert_eq!(output["id"], json!(2));
+        assert_eq!(output["error"]["code"], json!(-32602));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_translate_sys_config_changes() {
+        let changes = json!([
+            {"height": 3, "changes": {"quota_price": {"from": "0x1", "to": "0x2"}}},
+            {"height": 5, "changes": {
+                "validators": {"from": [], "to": ["0x0000000000000000000000000000000000000001"]},
+                "block_quota_limit": {"from": 1, "to": 2}
+            }}
+        ]);
+        let info = ExtInfo {
+            jsonrpc: Some(json!("2.0")),
+            id: json!(1),
+            method: ExtMethod::SysConfigChanges,
+            token: None,
+        };
+        let output = info.output(Ok(changes.clone()));
+        assert_eq!(output["jsonrpc"], json!("2.0"));
+        assert_eq!(output["result"][0]["height"], json!("0x3"));
+        assert_eq!(
+            output["result"][1]["changes"]["block_quota_limit"]["to"],
+            json!(2)
+        );
+
+        let info = ExtInfo {
+            method: ExtMethod::ValidatorChanges,
+            ..info
+        };
+        assert_eq!(
+            info.output(Ok(changes))["result"],
+            json!([{"height": "0x5", "changes": {
+                "validators": {"from": [], "to": ["0x0000000000000000000000000000000000000001"]}
+            }}])
+        );
+
+        let output = info.output(Err("Invalid height range [5, 4]".to_owned()));
+        assert_eq!(output["id"], json!(1));
+        assert_eq!(
+            output["error"]["message"],
+            json!("Invalid height range [5, 4]")
+        );
+    }
+
+    #[test]
+    fn test_translate_fee_report() {
+        let (_, ext_request) = split_request(
+            json!({"jsonrpc": "2.0", "method": "getFeeReport", "params": ["0x10"], "id": 1}),
+            CLIENT,
+        );
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::FeeReport { height: 16 })
+        );
+
+        let report = json!({
+            "height": 16,
+            "total": "0x64",
+            "burned": "0xa",
+            "recipients": {"0x0000000000000000000000000000000000000001": "0x5a"}
+        });
+        let output = ext_request.calls[0].info.output(Ok(report.clone()));
+        assert_eq!(output["result"]["height"], json!("0x10"));
+        assert_eq!(output["result"]["total"], json!("0x64"));
+        assert_eq!(output["result"]["recipients"], report["recipients"]);
+    }
+
+    #[test]
+    fn test_translate_quota_price_history() {
+        let (_, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "getQuotaPriceHistory", "params": ["0x4", [25, 50.5]], "id": 1},
+                {"jsonrpc": "2.0", "method": "getQuotaPriceHistory", "params": [4], "id": 2},
+                {"jsonrpc": "2.0", "method": "getQuotaPriceHistory", "params": [4, [101]], "id": 3}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::QuotaPriceHistory {
+                block_count: 4,
+                percentiles: vec![2500, 5050],
+            })
+        );
+        assert_eq!(
+            ext_request.calls[1].query,
+            Ok(Query::QuotaPriceHistory {
+                block_count: 4,
+                percentiles: vec![],
+            })
+        );
+        assert!(ext_request.calls[2].query.is_err());
+        assert!(ext_request.calls[0].query.as_ref().unwrap().is_for_chain());
+
+        let history = json!({
+            "oldestBlock": 7,
+            "blocks": [{
+                "height": 7,
+                "quotaUsed": "0x3e8",
+                "quotaLimit": "0x7d0",
+                "quotaUsedRatio": 0.5,
+                "quotaPrice": "0x3",
+                "fees": ["0x12c", "0x258"]
+            }],
+            "suggestedQuotaPrice": "0x3"
+        });
+        let output = ext_request.calls[0].info.output(Ok(history.clone()));
+        assert_eq!(output["result"]["oldestBlock"], json!("0x7"));
+        assert_eq!(output["result"]["blocks"][0]["height"], json!("0x7"));
+        assert_eq!(
+            output["result"]["blocks"][0]["fees"],
+            history["blocks"][0]["fees"]
+        );
+        assert_eq!(output["result"]["suggestedQuotaPrice"], json!("0x3"));
+    }
+
+    #[test]
+    fn test_frozen_accounts_query() {
+        let (_, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "getFrozenAccounts", "params": ["0x10"], "id": 1},
+                {"jsonrpc": "2.0", "method": "getFrozenAccounts", "id": 2},
+                {"jsonrpc": "2.0", "method": "getFrozenAccounts", "params": ["newest"], "id": 3}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::FrozenAccounts {
+                block_tag: BlockTag::Height(16),
+            })
+        );
+        assert_eq!(
+            ext_request.calls[1].query,
+            Ok(Query::FrozenAccounts {
+                block_tag: BlockTag::Tag(Tag::Latest),
+            })
+        );
+        assert!(ext_request.calls[2].query.is_err());
+    }
+
+    #[test]
+    fn test_translate_state_diff() {
+        let (_, ext_request) = split_request(
+            json!({"jsonrpc": "2.0", "method": "getStateDiff", "params": ["0x10"], "id": 1}),
+            CLIENT,
+        );
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::StateDiff { height: 16 })
+        );
+
+        let state_diff = json!({
+            "0x0000000000000000000000000000000000000001": {
+                "balance": {"from": "0x1", "to": "0x2"},
+                "nonce": {"from": "0x0", "to": "0x1"},
+                "code_hash": {"from": "0x00", "to": "0x00"},
+                "storage": {}
+            }
+        });
+        let output = ext_request.calls[0].info.output(Ok(state_diff.clone()));
+        let account = &output["result"]["0x0000000000000000000000000000000000000001"];
+        assert_eq!(
+            account["codeHash"],
+            state_diff["0x0000000000000000000000000000000000000001"]["code_hash"]
+        );
+        assert_eq!(account.get("code_hash"), None);
+        assert_eq!(account["balance"]["to"], json!("0x2"));
+    }
+
+    #[test]
+    fn test_call_with_overrides() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "call",
+            "params": [{"to": "0x0000000000000000000000000000000000000100"}, "latest"],
+            "id": 1
+        });
+        let (rest, ext_request) = split_request(request.clone(), CLIENT);
+        assert_eq!(rest, Some(request));
+        assert!(ext_request.is_empty());
+
+        let (rest, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "call", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100", "data": "0x6000"},
+                    "0x10",
+                    {"0x0000000000000000000000000000000000000200": {"balance": "0x2a", "nonce": "0x0"}},
+                    {"blockNumber": 32, "timestamp": 1000}
+                ], "id": 1},
+                {"jsonrpc": "2.0", "method": "eth_call", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100"},
+                    "latest",
+                    {"0x0000000000000000000000000000000000000200": {"code": "0x600"}}
+                ], "id": 2}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(rest, None);
+        assert_eq!(ext_request.calls[0].method(), ExtMethod::Call);
+        match ext_request.calls[0].query {
+            Ok(Query::Call {
+                ref request,
+                ref block_tag,
+                ref overrides,
+            }) => {
+                assert_eq!(request.data, Some(vec![0x60, 0x00]));
+                assert_eq!(*block_tag, BlockTag::Height(16));
+                assert_eq!(overrides.accounts.len(), 1);
+                assert_eq!(overrides.context.block_number, Some(32));
+                assert_eq!(overrides.context.timestamp, Some(1000));
+            }
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+        assert!(ext_request.calls[1].query.is_err());
+
+        let output = ext_request.calls[0].info.output(Ok(json!("0x01")));
+        assert_eq!(output["result"], json!("0x01"));
+    }
+
+    #[test]
+    fn test_call_with_value() {
+        let (rest, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "eth_call", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100", "value": "0x2a", "input": "0x6000"}
+                ], "id": 1},
+                {"jsonrpc": "2.0", "method": "eth_estimateGas", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100", "gas": "0x5208", "gasPrice": "0x1"},
+                    "0x10"
+                ], "id": 2},
+                {"jsonrpc": "2.0", "method": "eth_estimateGas", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100", "gas": null}
+                ], "id": 3}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(
+            rest,
+            Some(json!([
+                {"jsonrpc": "2.0", "method": "eth_estimateGas", "params": [
+                    {"to": "0x0000000000000000000000000000000000000100", "gas": null}
+                ], "id": 3}
+            ]))
+        );
+        assert_eq!(ext_request.calls[0].method(), ExtMethod::Call);
+        match ext_request.calls[0].query {
+            Ok(Query::Call {
+                ref request,
+                ref block_tag,
+                ref overrides,
+            }) => {
+                assert_eq!(request.value, Some(42.into()));
+                assert_eq!(request.data, Some(vec![0x60, 0x00]));
+                assert_eq!(*block_tag, BlockTag::Tag(Tag::Latest));
+                assert!(overrides.is_empty());
+            }
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+        assert_eq!(ext_request.calls[1].method(), ExtMethod::EstimateQuota);
+        match ext_request.calls[1].query {
+            Ok(Query::EstimateQuota {
+                ref request,
+                ref block_tag,
+            }) => {
+                assert_eq!(request.gas, Some(21_000.into()));
+                assert_eq!(request.gas_price, Some(1.into()));
+                assert_eq!(*block_tag, BlockTag::Height(16));
+            }
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+    }
+
+    #[test]
+    fn test_logs_query() {
+        let filter = json!({"fromBlock": "0x1", "toBlock": "latest"});
+        let (rest, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "getLogs", "params": [filter], "id": 1},
+                {"jsonrpc": "2.0", "method": "getLogs", "params": [
+                    filter, "0x000000000000001000000002"
+                ], "id": 2},
+                {"jsonrpc": "2.0", "method": "getLogs", "params": [filter, "0x10"], "id": 3},
+                {"jsonrpc": "2.0", "method": "eth_getLogs", "params": [filter], "id": 4}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(
+            rest,
+            Some(json!([
+                {"jsonrpc": "2.0", "method": "eth_getLogs", "params": [filter], "id": 4}
+            ]))
+        );
+        match ext_request.calls[0].query {
+            Ok(Query::Logs { cursor: None, .. }) => {}
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+        match ext_request.calls[1].query {
+            Ok(Query::Logs {
+                cursor: Some(cursor),
+                ..
+            }) => {
+                assert_eq!(cursor.block_number, 16);
+                assert_eq!(cursor.log_index, 2);
+            }
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+        assert!(ext_request.calls[2].query.is_err());
+
+        let page = json!({"logs": [], "next": "0x000000000000001000000002"});
+        let output = ext_request.calls[0].info.output(Ok(page.clone()));
+        assert_eq!(output["result"], page);
+    }
+
+    #[test]
+    fn test_filter_queries() {
+        let filter = json!({"fromBlock": "0x1", "toBlock": "latest"});
+        let (rest, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "newFilter", "params": [filter], "id": 1},
+                {"jsonrpc": "2.0", "method": "newPendingTransactionFilter", "id": 2},
+                {"jsonrpc": "2.0", "method": "getFilterChanges", "params": ["0x10"], "id": 3},
+                {"jsonrpc": "2.0", "method": "uninstallFilter", "params": ["latest"], "id": 4}
+            ]),
+            CLIENT,
+        );
+        assert_eq!(rest, None);
+        match ext_request.calls[0].query {
+            Ok(Query::NewFilter { ref client, .. }) => assert_eq!(client, CONNECTION),
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+        assert_eq!(
+            ext_request.calls[1].query,
+            Ok(Query::NewPendingTransactionFilter {
+                client: CONNECTION.to_owned(),
+            })
+        );
+        assert_eq!(
+            ext_request.calls[2].query,
+            Ok(Query::FilterChanges {
+                client: CONNECTION.to_owned(),
+                id: 16,
+            })
+        );
+        assert!(ext_request.calls[3].query.is_err());
+
+        let hashes = json!(["0x0000000000000000000000000000000000000000000000000000000000000001"]);
+        let output = ext_request.calls[2].info.output(Ok(hashes.clone()));
+        assert_eq!(output["result"], hashes);
+
+        // The filter ids of a websocket client are the ids of the chain.
+        let output = ext_request.calls[1].info.output(Ok(json!("0x10")));
+        assert_eq!(output["result"], json!("0x10"));
+    }
+
+    #[test]
+    fn test_http_filter_tokens() {
+        let (_, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "newBlockFilter", "id": 1},
+                {"jsonrpc": "2.0", "method": "newBlockFilter", "id": 2}
+            ]),
+            Client::Http,
+        );
+        let clients: Vec<String> = ext_request
+            .calls()
+            .iter()
+            .map(|call| match call.query {
+                Ok(Query::NewBlockFilter { ref client }) => client.clone(),
+                ref query => panic!("Unexpected query {:?}", query),
+            })
+            .collect();
+        // Each filter of an http client has its own token.
+        assert_ne!(clients[0], clients[1]);
+
+        let token = ext_request.calls[0].info.token.unwrap();
+        let output = ext_request.calls[0].info.output(Ok(json!("0x10")));
+        let id = output["result"].as_str().unwrap().to_owned();
+        assert_eq!(id, format!("{:#x}", (u128::from(token) << 64) | 0x10));
+
+        // The token is taken out of the id when the filter is used.
+        let (_, ext_request) = split_request(
+            json!([
+                {"jsonrpc": "2.0", "method": "getFilterChanges", "params": [id], "id": 3},
+                {"jsonrpc": "2.0", "method": "uninstallFilter", "params": ["0x10"], "id": 4}
+            ]),
+            Client::Http,
+        );
+        assert_eq!(
+            ext_request.calls[0].query,
+            Ok(Query::FilterChanges {
+                client: clients[0].clone(),
+                id: 16,
+            })
+        );
+        // An id without the token is not of the client which created the filter.
+        match ext_request.calls[1].query {
+            Ok(Query::UninstallFilter { ref client, id }) => {
+                assert_eq!(id, 16);
+                assert_ne!(*client, clients[0]);
+            }
+            ref query => panic!("Unexpected query {:?}", query),
+        }
+    }
+}
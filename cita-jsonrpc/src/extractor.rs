@@ -19,6 +19,8 @@ use jsonrpc_types::rpc_request::{
 };
 use libproto::request::Request as ProtoRequest;
 
+use crate::eth::{translate_request, Translation};
+use crate::ext::{split_request, Client, ExtRequest};
 use crate::mq_publisher::{HybridRequest, MQRequest};
 use crate::service_error::ServiceError;
 
@@ -37,9 +39,11 @@ pub trait Extractor<T> {
 
 pub type ExtractFuture<T, E> = Box<dyn Future<Item = T, Error = E> + Send + 'static>;
 
-impl FutExtractor<JsonrpcRequest> for hyper::Request<hyper::Body> {
+impl FutExtractor<(Option<JsonrpcRequest>, Translation, ExtRequest)>
+    for hyper::Request<hyper::Body>
+{
     type Error = ServiceError;
-    type Fut = ExtractFuture<JsonrpcRequest, Self::Error>;
+    type Fut = ExtractFuture<(Option<JsonrpcRequest>, Translation, ExtRequest), Self::Error>;
 
     fn extract_from(self) -> Self::Fut {
         use futures::Stream;
@@ -49,7 +53,17 @@ impl FutExtractor<JsonrpcRequest> for hyper::Request<hyper::Body> {
             .concat2()
             .map_err(ServiceError::BodyConcatError)
             .and_then(|chunk| {
-                serde_json::from_slice::<JsonrpcRequest>(&chunk)
+                let value = serde_json::from_slice::<serde_json::Value>(&chunk)
+                    .map_err(ServiceError::JsonrpcSerdeError)?;
+                // Take the calls of `ext` out and rename the Ethereum calls before parsing.
+                let (value, ext_req) = split_request(value, Client::Http);
+                let value = match value {
+                    Some(value) => value,
+                    None => return Ok((None, Translation::default(), ext_req)),
+                };
+                let (value, translation) = translate_request(value);
+                serde_json::from_value::<JsonrpcRequest>(value)
+                    .map(|req| (Some(req), translation, ext_req))
                     .map_err(ServiceError::JsonrpcSerdeError)
             });
 
@@ -0,0 +1,253 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Health of the services, served as `/health` and `/ready`.
+//!
+//! Auth, chain and executor publish heartbeats as `RawBytes` of JSON,
+//! the peer count of network is asked by jsonrpc itself.
+
+use crate::config::HealthConfig;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use util::Mutex;
+
+/// Request id of the peer count requests sent by the health check
+pub const PEER_COUNT_REQUEST_ID: &[u8] = b"health-peer-count";
+
+const NETWORK: &str = "network";
+
+pub type SharedHealth = Arc<Mutex<Health>>;
+
+#[derive(Deserialize, Debug, Clone)]
+struct Heartbeat {
+    service: String,
+    height: u64,
+    highest: Option<u64>,
+    ready: bool,
+    #[serde(default)]
+    conditions: Map<String, Value>,
+    #[serde(default)]
+    details: Value,
+}
+
+#[derive(Debug, Clone)]
+struct ServiceStatus {
+    heartbeat: Heartbeat,
+    last_seen: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Health {
+    config: HealthConfig,
+    services: BTreeMap<String, ServiceStatus>,
+    peer_count: Option<(u32, Instant)>,
+}
+
+impl Health {
+    pub fn new(config: HealthConfig) -> Self {
+        Health {
+            config,
+            services: BTreeMap::new(),
+            peer_count: None,
+        }
+    }
+
+    pub fn update_heartbeat(&mut self, body: &[u8]) -> Result<(), serde_json::Error> {
+        let heartbeat: Heartbeat = serde_json::from_slice(body)?;
+        self.services.insert(
+            heartbeat.service.clone(),
+            ServiceStatus {
+                heartbeat,
+                last_seen: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn update_peer_count(&mut self, peer_count: u32) {
+        self.peer_count = Some((peer_count, Instant::now()));
+    }
+
+    /// Report of `/health` if `check_ready` is false, otherwise of `/ready`.
+    ///
+    /// The first item is whether all the checks are passed.
+    pub fn report(&self, check_ready: bool) -> (bool, Value) {
+        let timeout = Duration::from_secs(self.config.heartbeat_timeout);
+        let is_alive = |last_seen: &Instant| last_seen.elapsed() <= timeout;
+
+        let highest = self
+            .services
+            .values()
+            .filter(|status| is_alive(&status.last_seen))
+            .map(|status| {
+                let heartbeat = &status.heartbeat;
+                heartbeat
+                    .highest
+                    .unwrap_or(heartbeat.height)
+                    .max(heartbeat.height)
+            })
+            .max()
+            .unwrap_or(0);
+
+        let mut passed = true;
+        let mut services = Map::new();
+        for name in &["auth", "chain", "executor"] {
+            let report = match self.services.get(*name) {
+                Some(status) => {
+                    let heartbeat = &status.heartbeat;
+                    let alive = is_alive(&status.last_seen);
+                    let lag = highest.saturating_sub(heartbeat.height);
+                    let ready = alive && heartbeat.ready && lag <= self.config.max_height_lag;
+                    passed &= if check_ready { ready } else { alive };
+
+                    let mut conditions = heartbeat.conditions.clone();
+                    conditions.insert("alive".to_owned(), Value::from(alive));
+                    conditions.insert(
+                        "height_lag_ok".to_owned(),
+                        Value::from(lag <= self.config.max_height_lag),
+                    );
+                    json!({
+                        "alive": alive,
+                        "ready": ready,
+                        "last_seen_secs": status.last_seen.elapsed().as_secs(),
+                        "height": heartbeat.height,
+                        "lag": lag,
+                        "conditions": conditions,
+                        "details": heartbeat.details,
+                    })
+                }
+                None => {
+                    passed = false;
+                    json!({
+                        "alive": false,
+                        "ready": false,
+                        "conditions": { "alive": false },
+                    })
+                }
+            };
+            services.insert((*name).to_owned(), report);
+        }
+
+        let network = match self.peer_count {
+            Some((peer_count, last_seen)) => {
+                let alive = is_alive(&last_seen);
+                let enough_peers = peer_count >= self.config.min_peers;
+                let ready = alive && enough_peers;
+                passed &= if check_ready { ready } else { alive };
+                json!({
+                    "alive": alive,
+                    "ready": ready,
+                    "last_seen_secs": last_seen.elapsed().as_secs(),
+                    "peer_count": peer_count,
+                    "conditions": {
+                        "alive": alive,
+                        "enough_peers": enough_peers,
+                    },
+                })
+            }
+            None => {
+                passed = false;
+                json!({
+                    "alive": false,
+                    "ready": false,
+                    "conditions": { "alive": false },
+                })
+            }
+        };
+        services.insert(NETWORK.to_owned(), network);
+
+        let report = json!({
+            "status": if passed { "ok" } else { "fail" },
+            "highest": highest,
+            "services": services,
+        });
+        (passed, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Health;
+    use crate::config::HealthConfig;
+
+    fn heartbeat(service: &str, height: u64, ready: bool) -> Vec<u8> {
+        json!({
+            "service": service,
+            "height": height,
+            "ready": ready,
+            "conditions": { "not_snapshot": ready },
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_health_and_ready() {
+        let mut health = Health::new(HealthConfig::default());
+        let (passed, report) = health.report(false);
+        assert!(!passed);
+        assert_eq!(report["services"]["auth"]["alive"], false);
+
+        health
+            .update_heartbeat(&heartbeat("auth", 100, true))
+            .unwrap();
+        health
+            .update_heartbeat(&heartbeat("chain", 100, true))
+            .unwrap();
+        health
+            .update_heartbeat(&heartbeat("executor", 100, true))
+            .unwrap();
+        health.update_peer_count(0);
+        assert!(health.report(false).0);
+        let (passed, report) = health.report(true);
+        assert!(!passed);
+        assert_eq!(report["services"]["network"]["ready"], false);
+
+        health.update_peer_count(3);
+        let (passed, report) = health.report(true);
+        assert!(passed);
+        assert_eq!(report["status"], "ok");
+        assert_eq!(
+            report["services"]["chain"]["conditions"]["not_snapshot"],
+            true
+        );
+    }
+
+    #[test]
+    fn test_height_lag() {
+        let mut health = Health::new(HealthConfig::default());
+        health
+            .update_heartbeat(&heartbeat("auth", 100, true))
+            .unwrap();
+        health
+            .update_heartbeat(&heartbeat("chain", 100, true))
+            .unwrap();
+        health
+            .update_heartbeat(&heartbeat("executor", 50, true))
+            .unwrap();
+        health.update_peer_count(3);
+
+        assert!(health.report(false).0);
+        let (passed, report) = health.report(true);
+        assert!(!passed);
+        assert_eq!(report["highest"], 100);
+        assert_eq!(report["services"]["executor"]["lag"], 50);
+        assert_eq!(report["services"]["executor"]["ready"], false);
+        assert_eq!(report["services"]["auth"]["ready"], true);
+
+        assert!(health.update_heartbeat(b"not a heartbeat").is_err());
+    }
+}
@@ -12,12 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::ext::ExtInfo;
 use futures::sync::oneshot;
 use jsonrpc_types::rpc_request::RequestInfo;
 use jsonrpc_types::rpc_response::Output;
 use libproto::request::Request as ProtoRequest;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use pubsub::channel::Sender;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 use util::Mutex;
@@ -27,10 +29,15 @@ pub enum TransferType {
     HTTP((RequestInfo, oneshot::Sender<Output>)),
     /// websocket output sender
     WEBSOCKET((RequestInfo, ws::Sender)),
+    /// http output sender of the calls in `ext`
+    ExtHttp((ExtInfo, oneshot::Sender<Value>)),
+    /// websocket output sender of the calls in `ext`
+    ExtWebsocket((ExtInfo, ws::Sender)),
 }
 
 pub type RpcMap = Arc<Mutex<HashMap<Vec<u8>, TransferType>>>;
 pub type ReqSender = Mutex<Sender<(String, ProtoRequest)>>;
+pub type RawSender = Sender<(String, Vec<u8>)>;
 
 pub fn select_topic(method: &str) -> String {
     match method {
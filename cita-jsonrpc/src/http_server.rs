@@ -28,18 +28,26 @@ use std::sync::Arc;
 use std::time::Duration;
 use util::Mutex;
 
-use crate::extractor::FutExtractor;
-use crate::helper::{ReqSender, RpcMap};
+use crate::eth::Translation;
+use crate::ext::ExtRequest;
+use crate::extractor::{ExtractFuture, FutExtractor};
+use crate::health::SharedHealth;
+use crate::helper::{RawSender, ReqSender, RpcMap};
 use crate::http_header::{Origin, CONTENT_TYPE_JSON_STR, CONTENT_TYPE_PLAIN_TEXT_STR};
-use crate::mq_publisher::{AccessLog as MQAccessLog, MQRequest, Publisher, TimeoutPublisher};
+use crate::mq_publisher::{
+    publish_ext, AccessLog as MQAccessLog, MQRequest, Publisher, TimeoutPublisher,
+};
 use crate::response::{HyperResponseExt, IntoResponse};
+use crate::service_error::ServiceError;
 
 const TCP_BACKLOG: i32 = 1024;
 const CORS_CACHE: u32 = 86_400u32;
 
 struct Inner {
     pub tx: ReqSender,
+    pub raw_tx: Mutex<RawSender>,
     pub responses: RpcMap,
+    pub health: SharedHealth,
     pub timeout: Duration,
     pub http_headers: Headers,
 }
@@ -77,6 +85,7 @@ struct AccessLog {
 enum RpcAccessLog {
     Single(SingleRpcAccessLog),
     Batch(BatchRpcAccessLog),
+    Ext(Vec<&'static str>),
 }
 
 struct SingleRpcAccessLog {
@@ -143,6 +152,10 @@ impl ::std::fmt::Display for AccessLog {
                     write!(f, ", rpc-count=-1")
                 }
             }
+            Some(RpcAccessLog::Ext(ref methods)) => {
+                write!(f, ", rpc-type=ext")?;
+                write!(f, ", rpc-methods={}", methods.join(","))
+            }
             None => write!(f, ", rpc-type=unknown"),
         }
     }
@@ -156,6 +169,7 @@ impl Service for Jsonrpc {
 
     fn call(&mut self, http_req: Request<Self::ReqBody>) -> Self::Future {
         let sender = { self.inner.tx.lock().clone() };
+        let raw_sender = { self.inner.raw_tx.lock().clone() };
         let responses = Arc::clone(&self.inner.responses);
         let timeout = self.inner.timeout;
         let http_headers = self.inner.http_headers.clone();
@@ -165,31 +179,89 @@ impl Service for Jsonrpc {
 
         match (http_req.method(), http_path.as_ref()) {
             (&Method::POST, "/") => {
-                let fut_resp = FutExtractor::<JsonrpcRequest>::extract_from(http_req)
-                    .and_then(FutExtractor::<MQRequest>::extract_from)
-                    .and_then({
-                        let headers = http_headers.clone();
-
-                        move |mq_req| {
-                            // logging
-                            access_log.set_rpc_info(RpcAccessLog::from(mq_req.access_log()));
-                            info!("{}", access_log);
-
-                            let timeout_responses = Arc::clone(&responses);
-                            let pulibsher = Publisher::new(responses, sender, headers);
-                            let pulibsher =
-                                TimeoutPublisher::new(pulibsher, timeout, timeout_responses);
-
-                            pulibsher.publish(mq_req)
+                let fut_resp = FutExtractor::<(
+                    Option<JsonrpcRequest>,
+                    Translation,
+                    ExtRequest,
+                )>::extract_from(http_req)
+                .and_then(|(jsonrpc_req, translation, ext_req)| {
+                    let mq_req: ExtractFuture<Option<MQRequest>, ServiceError> = match jsonrpc_req {
+                        Some(jsonrpc_req) => {
+                            Box::new(FutExtractor::<MQRequest>::extract_from(jsonrpc_req).map(Some))
                         }
-                    })
-                    .then(move |resp| match resp {
-                        Ok(resp) => Ok(resp),
-                        Err(err) => Ok(err.into_response(http_headers)),
-                    });
+                        None => Box::new(future::ok(None)),
+                    };
+                    mq_req.map(|mq_req| (mq_req, translation, ext_req))
+                })
+                .and_then({
+                    let headers = http_headers.clone();
+
+                    move |(mq_req, translation, mut ext_req)| {
+                        // logging
+                        let rpc_info = match mq_req {
+                            Some(ref mq_req) => RpcAccessLog::from(mq_req.access_log()),
+                            None => RpcAccessLog::Ext(
+                                ext_req
+                                    .calls()
+                                    .iter()
+                                    .map(|call| call.method().name())
+                                    .collect(),
+                            ),
+                        };
+                        access_log.set_rpc_info(rpc_info);
+                        info!("{}", access_log);
+
+                        let ext_outputs = publish_ext(
+                            ext_req.take_calls(),
+                            Arc::clone(&responses),
+                            &raw_sender,
+                            timeout,
+                        );
+                        let resp: ExtractFuture<Option<Response<Body>>, ServiceError> = match mq_req {
+                            Some(mq_req) => {
+                                let timeout_responses = Arc::clone(&responses);
+                                let pulibsher =
+                                    Publisher::new(responses, sender, headers.clone());
+                                let pulibsher =
+                                    TimeoutPublisher::new(pulibsher, timeout, timeout_responses);
+
+                                Box::new(
+                                    pulibsher
+                                        .publish(mq_req)
+                                        .and_then(move |resp| translate_response(resp, translation))
+                                        .map(Some),
+                                )
+                            }
+                            None => Box::new(future::ok(None)),
+                        };
+
+                        resp.join(ext_outputs).and_then(move |(resp, outputs)| {
+                            merge_response(resp, outputs, ext_req, headers)
+                        })
+                    }
+                })
+                .then(move |resp| match resp {
+                            Ok(resp) => Ok(resp),
+                            Err(err) => Ok(err.into_response(http_headers)),
+                        });
 
                 Box::new(fut_resp)
             }
+            (&Method::GET, "/health") | (&Method::GET, "/ready") => {
+                let check_ready = http_path == "/ready";
+                let (passed, report) = self.inner.health.lock().report(check_ready);
+                let status = if passed {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                };
+                let resp = Response::default()
+                    .with_headers(http_headers)
+                    .with_status(status)
+                    .with_body(Body::from(report.to_string()));
+
+                Box::new(future::ok(resp))
+            }
             (&Method::OPTIONS, "/") => {
                 info!("{}", access_log);
                 let resp = Response::default().with_headers(handle_preflighted(http_headers));
@@ -208,6 +280,83 @@ impl Service for Jsonrpc {
     }
 }
 
+// Translate the results of the Ethereum calls into Ethereum's field layout.
+fn translate_response(
+    resp: Response<Body>,
+    translation: Translation,
+) -> Box<dyn Future<Item = Response<Body>, Error = ServiceError> + Send> {
+    use futures::Stream;
+
+    if translation.is_empty() {
+        return Box::new(future::ok(resp));
+    }
+
+    let (parts, body) = resp.into_parts();
+    let fut_resp = body
+        .concat2()
+        .map_err(ServiceError::BodyConcatError)
+        .and_then(move |chunk| {
+            let value = serde_json::from_slice::<serde_json::Value>(&chunk).map_err(|err| {
+                error!("json serde response: {}", err);
+                ServiceError::InternalServerError
+            })?;
+            let json_body =
+                serde_json::to_vec(&translation.translate_response(value)).map_err(|err| {
+                    error!("json serde eth response: {}", err);
+                    ServiceError::InternalServerError
+                })?;
+            Ok(Response::from_parts(parts, Body::from(json_body)))
+        });
+
+    Box::new(fut_resp)
+}
+
+// Put the outputs of the calls in `ext` back into the response.
+fn merge_response(
+    resp: Option<Response<Body>>,
+    outputs: Vec<serde_json::Value>,
+    ext_req: ExtRequest,
+    headers: Headers,
+) -> Box<dyn Future<Item = Response<Body>, Error = ServiceError> + Send> {
+    use futures::Stream;
+
+    let resp = match resp {
+        Some(resp) if ext_req.is_empty() => return Box::new(future::ok(resp)),
+        Some(resp) => resp,
+        None => {
+            let value = ext_req.merge_response(None, outputs);
+            return Box::new(future::result(json_response(&value, headers)));
+        }
+    };
+
+    let fut_resp = resp
+        .into_body()
+        .concat2()
+        .map_err(ServiceError::BodyConcatError)
+        .and_then(move |chunk| {
+            let value = serde_json::from_slice::<serde_json::Value>(&chunk).map_err(|err| {
+                error!("json serde response: {}", err);
+                ServiceError::InternalServerError
+            })?;
+            json_response(&ext_req.merge_response(Some(value), outputs), headers)
+        });
+
+    Box::new(fut_resp)
+}
+
+fn json_response(
+    value: &serde_json::Value,
+    headers: Headers,
+) -> Result<Response<Body>, ServiceError> {
+    let json_body = serde_json::to_vec(value).map_err(|err| {
+        error!("json serde ext response: {}", err);
+        ServiceError::InternalServerError
+    })?;
+    Ok(Response::default()
+        .with_headers(headers)
+        .with_body(Body::from(json_body)))
+}
+
 fn handle_preflighted(mut headers: Headers) -> Headers {
     use crate::http_header::{HeaderMapExt, X_REQUESTED_WITH_STR};
 
@@ -235,7 +384,9 @@ impl Server {
     pub fn create(
         addr: &SocketAddr,
         tx: Sender<(String, ProtoRequest)>,
+        raw_tx: RawSender,
         responses: RpcMap,
+        health: SharedHealth,
         timeout: u64,
         allow_origin: &Option<String>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
@@ -252,7 +403,9 @@ impl Server {
         let make_jsonrpc_svc = JsonrpcMakeService {
             inner: Arc::new(Inner {
                 tx: Mutex::new(tx),
+                raw_tx: Mutex::new(raw_tx),
                 responses,
+                health,
                 timeout,
                 http_headers,
             }),
@@ -292,6 +445,8 @@ pub fn listener_from_socket_addr(addr: &SocketAddr) -> std::io::Result<TcpListen
 #[cfg(test)]
 mod integration_test {
     use super::*;
+    use crate::config::HealthConfig;
+    use crate::health::Health;
     use crate::helper::TransferType;
     use futures::{sync::oneshot, Stream};
     use jsonrpc_proto::response::OutputExt;
@@ -333,7 +488,11 @@ mod integration_test {
             .name(format!("test-server-{}", Uuid::new_v4()))
             .spawn(move || {
                 let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-                let server = Server::create(&addr, tx, responses, timeout, &allow_origin).unwrap();
+                let health = Arc::new(Mutex::new(Health::new(HealthConfig::default())));
+                let (raw_tx, _raw_rx) = channel::unbounded();
+                let server =
+                    Server::create(&addr, tx, raw_tx, responses, health, timeout, &allow_origin)
+                        .unwrap();
 
                 let addr = server.local_addr();
                 addr_tx.send((addr, shutdown_tx)).unwrap();
@@ -402,6 +561,7 @@ mod integration_test {
                                     .unwrap(),
                             );
                         }
+                        TransferType::ExtHttp(_) | TransferType::ExtWebsocket(_) => {}
                     }
                 } else {
                     warn!("receive lost request_id {:?}", content.request_id);
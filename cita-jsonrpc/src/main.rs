@@ -27,6 +27,9 @@
 //!     | jsonrpc | Chain     | Response     |
 //!     | jsonrpc | Executor  | Response     |
 //!     | jsonrpc | Net       | Response     |
+//!     | jsonrpc | Auth      | RawBytes     |
+//!     | jsonrpc | Chain     | RawBytes     |
+//!     | jsonrpc | Executor  | RawBytes     |
 //!
 //! 2. Publish channel
 //!
@@ -36,6 +39,8 @@
 //!     | jsonrpc | Jsonrpc   | Chain     | Request           |
 //!     | jsonrpc | Jsonrpc   | Net       | RequestNet        |
 //!     | jsonrpc | jsonrpc   | Net       | RequestPeersInfo  |
+//!     | jsonrpc | Jsonrpc   | Chain     | RawBytes          |
+//!     | jsonrpc | Jsonrpc   | Executor  | RawBytes          |
 //!
 //! ### Key behavior
 //!
@@ -49,21 +54,31 @@
 //! Websocket and Http only write to this structure and write the internal transaction
 //! uuid number and `TransferType`.
 //!
+//! The `RawBytes` of auth, chain and executor are heartbeats, collected into `health::Health`
+//! and served as `/health` and `/ready` of the Http interface. The raw messages of
+//! `types::raw_message` are also sent in `RawBytes`, and those not for jsonrpc are ignored.
+//! The methods in `ext` are queries sent to the chain and the executor in `RawBytes`, their
+//! results come back in the `RawBytes` of these services.
+//!
 
+extern crate common_types as types;
 #[macro_use]
 extern crate libproto;
 #[macro_use]
 extern crate cita_logger as logger;
 #[macro_use]
 extern crate serde_derive;
-#[cfg_attr(test, macro_use)]
+#[macro_use]
 extern crate serde_json;
 #[macro_use]
 extern crate util;
 
 mod config;
+mod eth;
+mod ext;
 mod extractor;
 mod fdlimit;
+mod health;
 mod helper;
 mod http_header;
 mod http_server;
@@ -76,6 +91,7 @@ mod ws_handler;
 
 use crate::config::NewTxFlowConfig;
 use crate::fdlimit::set_fd_limit;
+use crate::health::{Health, PEER_COUNT_REQUEST_ID};
 use crate::http_server::Server;
 use crate::soliloquy::Soliloquy;
 use crate::ws_handler::WsFactory;
@@ -134,6 +150,8 @@ fn main() {
     // used for deal with RequestRpc
     let (tx, rx) = channel::unbounded();
     let soli_resp_tx = tx_sub.clone();
+    // used for the raw messages of `ext`
+    let raw_tx = tx_pub.clone();
 
     start_pubsub(
         "jsonrpc",
@@ -142,6 +160,9 @@ fn main() {
             Chain >> Response,
             Executor >> Response,
             Net >> Response,
+            Auth >> RawBytes,
+            Chain >> RawBytes,
+            Executor >> RawBytes,
         ]),
         tx_sub,
         rx_pub,
@@ -153,7 +174,20 @@ fn main() {
     let responses = Arc::new(Mutex::new(HashMap::with_capacity(backlog_capacity)));
     let http_responses = Arc::clone(&responses);
     let ws_responses = Arc::clone(&responses);
-    let mut mq_handle = mq_handler::MqHandler::new(responses);
+    let health = Arc::new(Mutex::new(Health::new(config.health_config)));
+    let http_health = Arc::clone(&health);
+    let mut mq_handle = mq_handler::MqHandler::new(responses, health);
+
+    // ask network for the peer count regularly, for the health check
+    let peer_count_tx = tx_relay.clone();
+    let peer_count_interval = Duration::from_secs(config.health_config.peer_count_interval);
+    thread::spawn(move || loop {
+        let mut request = reqlib::Request::new();
+        request.set_peercount(true);
+        request.set_request_id(PEER_COUNT_REQUEST_ID.to_vec());
+        let _ = peer_count_tx.send((routing_key!(Jsonrpc >> RequestNet).into(), request));
+        thread::sleep(peer_count_interval);
+    });
 
     //dispatch
     let tx_flow_config = config.new_tx_flow_config;
@@ -208,10 +242,11 @@ fn main() {
     if config.ws_config.enable {
         let ws_config = config.ws_config.clone();
         let tx = tx_relay.clone();
+        let raw_tx = raw_tx.clone();
         thread::spawn(move || {
             let url = ws_config.listen_ip.clone() + ":" + &ws_config.listen_port;
             //let factory = WsFactory::new(ws_responses, tx_pub, 0);
-            let factory = WsFactory::new(ws_responses, tx, 0);
+            let factory = WsFactory::new(ws_responses, tx, raw_tx, 0);
             info!("WebSocket Listening on {}", url);
             let mut ws_build = ws::Builder::new();
             ws_build.with_settings(ws_config.into());
@@ -236,9 +271,16 @@ fn main() {
         let _ = thread::Builder::new()
             .name(String::from("http worker"))
             .spawn(move || {
-                let server =
-                    Server::create(&addr, tx_relay, http_responses, timeout, &allow_origin)
-                        .unwrap();
+                let server = Server::create(
+                    &addr,
+                    tx_relay,
+                    raw_tx,
+                    http_responses,
+                    http_health,
+                    timeout,
+                    &allow_origin,
+                )
+                .unwrap();
                 let jsonrpc_server = server
                     .jsonrpc()
                     .map_err(|err| eprintln!("server err {}", err));
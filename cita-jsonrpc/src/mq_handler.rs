@@ -12,26 +12,52 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::health::{SharedHealth, PEER_COUNT_REQUEST_ID};
 use crate::helper::{RpcMap, TransferType};
 use jsonrpc_proto::response::OutputExt;
 use jsonrpc_types::rpc_response::Output;
 use libproto::router::{MsgType, RoutingKey, SubModules};
 use libproto::Message;
 use libproto::TryFrom;
+use serde_json::Value;
+use types::raw_message::RawMessage;
 
 #[derive(Default)]
 pub struct MqHandler {
     responses: RpcMap,
+    health: SharedHealth,
 }
 
 impl MqHandler {
-    pub fn new(responses: RpcMap) -> Self {
-        MqHandler { responses }
+    pub fn new(responses: RpcMap, health: SharedHealth) -> Self {
+        MqHandler { responses, health }
     }
 
     pub fn handle(&mut self, key: &str, body: &[u8]) -> Result<(), ()> {
         trace!("get msg from routing_key {}", key);
 
+        // Heartbeats are JSON, not protobuf messages
+        match RoutingKey::from(key) {
+            routing_key!(Auth >> RawBytes)
+            | routing_key!(Chain >> RawBytes)
+            | routing_key!(Executor >> RawBytes) => {
+                match RawMessage::from_bytes(body) {
+                    Some(RawMessage::QueryResult { request_id, result }) => {
+                        return self.reply_query(&request_id, result);
+                    }
+                    Some(message) => {
+                        trace!("ignore raw message {:?}", message);
+                        return Ok(());
+                    }
+                    None => {}
+                }
+                return self.health.lock().update_heartbeat(body).map_err(|e| {
+                    error!("heartbeat from {}: {:?}", key, e);
+                });
+            }
+            _ => {}
+        }
+
         let mut msg = Message::try_from(body).map_err(|e| {
             error!("try_from: {:?}", e);
         })?;
@@ -46,6 +72,13 @@ impl MqHandler {
                     error!("empty response message");
                 })?;
 
+                if content.request_id == PEER_COUNT_REQUEST_ID {
+                    self.health
+                        .lock()
+                        .update_peer_count(content.get_peercount());
+                    return Ok(());
+                }
+
                 let resp = {
                     let request_id = &content.request_id;
                     trace!("from response request_id {:?}", request_id);
@@ -72,6 +105,9 @@ impl MqHandler {
                             error!("ws: {:?}", e);
                         })?;
                     }
+                    TransferType::ExtHttp(_) | TransferType::ExtWebsocket(_) => {
+                        warn!("receive response of query {:?}", content.request_id);
+                    }
                 };
             }
             _ => {
@@ -80,4 +116,28 @@ impl MqHandler {
         };
         Ok(())
     }
+
+    fn reply_query(&mut self, request_id: &[u8], result: Result<Value, String>) -> Result<(), ()> {
+        trace!("from query result request_id {:?}", request_id);
+        let resp = self.responses.lock().remove(request_id).ok_or_else(|| {
+            warn!("receive lost request_id {:?}", request_id);
+        })?;
+
+        match resp {
+            TransferType::ExtHttp((info, sender)) => {
+                sender.send(info.output(result)).map_err(|e| {
+                    error!("http: {:?}", e);
+                })?;
+            }
+            TransferType::ExtWebsocket((info, sender)) => {
+                sender.send(info.output(result).to_string()).map_err(|e| {
+                    error!("ws: {:?}", e);
+                })?;
+            }
+            TransferType::HTTP(_) | TransferType::WEBSOCKET(_) => {
+                warn!("receive query result of request {:?}", request_id);
+            }
+        }
+        Ok(())
+    }
 }
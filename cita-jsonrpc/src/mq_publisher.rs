@@ -22,9 +22,11 @@ use jsonrpc_types::{
 };
 use libproto::request::Request as ProtoRequest;
 use pubsub::channel::Sender;
+use serde_json::Value;
 use tokio_timer::{clock, Delay};
 
-use crate::helper::{select_topic, RpcMap, TransferType};
+use crate::ext::ExtCall;
+use crate::helper::{select_topic, RawSender, RpcMap, TransferType};
 use crate::response::{BatchFutureResponse, PublishFutResponse, SingleFutureResponse};
 use crate::service_error::ServiceError;
 type HyperResponse = hyper::Response<hyper::Body>;
@@ -185,3 +187,46 @@ impl TimeoutPublisher {
         Box::new(fut_resp)
     }
 }
+
+/// Send the calls in `ext`, the outputs are in the order of the calls.
+pub fn publish_ext(
+    calls: Vec<ExtCall>,
+    responses: RpcMap,
+    sender: &RawSender,
+    timeout: Duration,
+) -> Box<dyn Future<Item = Vec<Value>, Error = ServiceError> + Send + 'static> {
+    use futures::future::{self, Either};
+
+    let mut req_ids = Vec::new();
+    let outputs = calls
+        .into_iter()
+        .map(|call| {
+            let (tx, rx) = oneshot::channel();
+            match call.send(&responses, sender, |info| TransferType::ExtHttp((info, tx))) {
+                Ok(req_id) => {
+                    req_ids.push(req_id);
+                    Either::A(rx.map_err(|_| ServiceError::MQResponsePollIncompleteError))
+                }
+                Err(output) => Either::B(future::ok(output)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let timeout = Delay::new(clock::now() + timeout);
+    let fut_resp = future::join_all(outputs)
+        .select2(timeout)
+        .then(move |res| match res {
+            Ok(Either::A((outputs, _timeout))) => Ok(outputs),
+            Ok(Either::B((_reach_timeout, _no_resp))) => {
+                let mut guard = responses.lock();
+                for id in req_ids {
+                    guard.remove(&id);
+                }
+                Err(ServiceError::MQRpcTimeout(None))
+            }
+            Err(Either::A((mq_rpc_err, _timeout))) => Err(mq_rpc_err),
+            Err(Either::B((_timeout_err, _mq_rpc_err))) => Err(ServiceError::InternalServerError),
+        });
+
+    Box::new(fut_resp)
+}
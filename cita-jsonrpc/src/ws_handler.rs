@@ -12,7 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::helper::{select_topic, RpcMap, TransferType};
+use crate::ext::{split_request, Client};
+use crate::helper::{select_topic, RawSender, RpcMap, TransferType};
 use jsonrpc_proto::complete::CompleteInto;
 use jsonrpc_types::rpc_request::{PartialRequest, RequestInfo};
 use jsonrpc_types::rpc_response::RpcFailure;
@@ -21,6 +22,7 @@ use libproto::request::Request as ProtoRequest;
 use pubsub::channel::Sender;
 use std::sync::Arc;
 use threadpool::ThreadPool;
+use uuid::Uuid;
 use ws::{self as ws, CloseCode, Factory, Handler};
 
 pub struct WsFactory {
@@ -28,12 +30,14 @@ pub struct WsFactory {
     responses: RpcMap,
     thread_pool: ThreadPool,
     tx: Sender<(String, ProtoRequest)>,
+    raw_tx: RawSender,
 }
 
 impl WsFactory {
     pub fn new(
         responses: RpcMap,
         tx: Sender<(String, ProtoRequest)>,
+        raw_tx: RawSender,
         thread_num: usize,
     ) -> WsFactory {
         let thread_number = if thread_num == 0 {
@@ -46,6 +50,7 @@ impl WsFactory {
             responses,
             thread_pool,
             tx,
+            raw_tx,
         }
     }
 }
@@ -54,9 +59,12 @@ impl Factory for WsFactory {
     type Handler = WsHandler;
     fn connection_made(&mut self, ws: ws::Sender) -> WsHandler {
         WsHandler {
+            // The filters are kept by the connection.
+            client: Uuid::new_v4().to_string(),
             sender: ws,
             responses: Arc::clone(&self.responses),
             tx: self.tx.clone(),
+            raw_tx: self.raw_tx.clone(),
             thread_pool: self.thread_pool.clone(),
         }
     }
@@ -66,13 +74,30 @@ impl Handler for WsHandler {
     fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
         trace!("Server got message '{}'  post thread_pool deal task ", msg);
         let tx = self.tx.clone();
+        let raw_tx = self.raw_tx.clone();
         let response = Arc::clone(&self.responses);
         let sender = self.sender.clone();
+        let client = self.client.clone();
 
         self.thread_pool.execute(move || {
             let mut req_info = RequestInfo::null();
+            let text = msg.into_text().unwrap();
 
-            let _ = serde_json::from_str::<PartialRequest>(&msg.into_text().unwrap())
+            // The calls in `ext` are sent as raw messages.
+            if let Ok(value) = serde_json::from_str(&text) {
+                if let (None, mut ext_req) = split_request(value, Client::Connection(&client)) {
+                    for call in ext_req.take_calls() {
+                        let ws_sender = sender.clone();
+                        let transfer = |info| TransferType::ExtWebsocket((info, ws_sender));
+                        if let Err(output) = call.send(&response, &raw_tx, transfer) {
+                            let _ = sender.send(output.to_string());
+                        }
+                    }
+                    return;
+                }
+            }
+
+            let _ = serde_json::from_str::<PartialRequest>(&text)
                 .map_err(Error::from)
                 .and_then(|part_req| {
                     req_info = part_req.get_info();
@@ -111,8 +136,10 @@ impl Handler for WsHandler {
 
 #[derive(Clone)]
 pub struct WsHandler {
+    client: String,
     responses: RpcMap,
     thread_pool: ThreadPool,
     sender: ws::Sender,
     tx: Sender<(String, ProtoRequest)>,
+    raw_tx: RawSender,
 }
@@ -11,6 +11,7 @@ Contracts:
   SysConfig:
     delayBlockNumber: 1
     checkCallPermission: false
+    checkInternalCallPermission: false
     checkSendTxPermission: false
     checkCreateContractPermission: false
     checkQuota: false
@@ -2,13 +2,20 @@ pragma solidity 0.4.24;
 
 import "../common/Admin.sol";
 import "../common/ReservedAddrPublic.sol";
+import "../lib/AddressArray.sol";
 import "../../interaction/interface/IEmergencyIntervention.sol";
 
 contract EmergencyIntervention is IEmergencyIntervention, ReservedAddrPublic {
     bool public state;
 
+    mapping(address => bool) frozen;
+    address[] frozenAccounts;
+
     Admin admin = Admin(adminAddr);
 
+    event AccountFrozen(address indexed _account);
+    event AccountUnfrozen(address indexed _account);
+
     modifier onlyAdmin {
         if (admin.isAdmin(msg.sender))
             _;
@@ -21,4 +28,49 @@ contract EmergencyIntervention is IEmergencyIntervention, ReservedAddrPublic {
     {
         state = _state;
     }
+
+    /// @notice Freeze an account: it can't send transactions nor be called
+    /// @param _account The account to be frozen
+    function freeze(address _account)
+        public
+        onlyAdmin
+    {
+        require(!frozen[_account], "The account is already frozen.");
+        require(!admin.isAdmin(_account), "The admin can't be frozen.");
+        frozen[_account] = true;
+        frozenAccounts.push(_account);
+        emit AccountFrozen(_account);
+    }
+
+    /// @notice Unfreeze a frozen account
+    /// @param _account The account to be unfrozen
+    function unfreeze(address _account)
+        public
+        onlyAdmin
+    {
+        require(frozen[_account], "The account is not frozen.");
+        frozen[_account] = false;
+        assert(AddressArray.remove(_account, frozenAccounts));
+        emit AccountUnfrozen(_account);
+    }
+
+    /// @notice Check if the account is frozen
+    /// @return true if frozen, false otherwise
+    function isFrozen(address _account)
+        public
+        view
+        returns (bool)
+    {
+        return frozen[_account];
+    }
+
+    /// @notice Query the frozen accounts
+    /// @return The frozen accounts
+    function queryFrozenAccounts()
+        public
+        view
+        returns (address[])
+    {
+        return frozenAccounts;
+    }
 }
@@ -28,6 +28,21 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
     Admin admin = Admin(adminAddr);
     uint chainIdV1;
     bool autoExec;
+    bool checkInternalCallPermission;
+    /// @notice Shares of the fee in basis points, all to the proposer by default
+    uint feeProposerShare = 10000;
+    uint feeValidatorsShare;
+    uint feeTreasuryShare;
+    uint feeBurnShare;
+    address feeTreasury;
+
+    event SetFeeDistribution(
+        uint _proposerShare,
+        uint _validatorsShare,
+        uint _treasuryShare,
+        uint _burnShare,
+        address indexed _treasury
+    );
 
     modifier onlyAdmin {
         if (admin.isAdmin(msg.sender))
@@ -49,6 +64,7 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
     ///    3: _checkQuota
     ///    4: _checkFeeBackPlatform
     ///    5: _autoExec
+    ///    6: _checkInternalCallPermission
     constructor(
         uint _delayBlockNumber,
         address _chainOwner,
@@ -73,6 +89,7 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
         checkQuota = flags[3];
         checkFeeBackPlatform = flags[4];
         autoExec = flags[5];
+        checkInternalCallPermission = flags[6];
         chainOwner = _chainOwner;
         chainName = _chainName;
         chainId = uint32(_chainId);
@@ -109,6 +126,42 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
         chainName = _chainName;
     }
 
+    /// @notice Set how the fee is split in Charge mode
+    /// @param _proposerShare To the proposer, or the chain owner when the fee goes back to the platform
+    /// @param _validatorsShare To all validators, weighted by their stakes
+    /// @param _treasuryShare To the treasury
+    /// @param _burnShare Burned
+    /// @param _treasury The treasury address
+    function setFeeDistribution(
+        uint _proposerShare,
+        uint _validatorsShare,
+        uint _treasuryShare,
+        uint _burnShare,
+        address _treasury
+    )
+        external
+        onlyAdmin
+        returns (bool)
+    {
+        require(
+            _proposerShare + _validatorsShare + _treasuryShare + _burnShare == 10000 &&
+            _proposerShare <= 10000 && _validatorsShare <= 10000 &&
+            _treasuryShare <= 10000 && _burnShare <= 10000,
+            "The shares should add up to 10000."
+        );
+        require(
+            _treasuryShare == 0 || _treasury != address(0x0),
+            "The treasury should be set for the treasury share."
+        );
+        feeProposerShare = _proposerShare;
+        feeValidatorsShare = _validatorsShare;
+        feeTreasuryShare = _treasuryShare;
+        feeBurnShare = _burnShare;
+        feeTreasury = _treasury;
+        emit SetFeeDistribution(_proposerShare, _validatorsShare, _treasuryShare, _burnShare, _treasury);
+        return true;
+    }
+
     function updateToChainIdV1()
         external
         onlyAdmin
@@ -132,6 +185,14 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
         return checkPermission;
     }
 
+    function getInternalCallPermissionCheck()
+        public
+        view
+        returns (bool)
+    {
+        return checkInternalCallPermission;
+    }
+
     function getSendTxPermissionCheck()
         public
         view
@@ -238,6 +299,14 @@ contract SysConfig is ISysConfig, EconomicalType, ReservedAddrPublic {
         avatar = tokenInfo.avatar;
     }
 
+    function getFeeDistribution()
+        public
+        view
+        returns (uint, uint, uint, uint, address)
+    {
+        return (feeProposerShare, feeValidatorsShare, feeTreasuryShare, feeBurnShare, feeTreasury);
+    }
+
     function getAutoExec()
         public
         view
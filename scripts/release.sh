@@ -41,6 +41,7 @@ for binary in \
         create-key-addr \
         create-genesis \
         cita-relayer-parser \
+        cita-block-porter \
         ; do
     if [ "${arch}" == "x86" ]; then
         cp -rf "target/${type}/${binary}" ${install_dir}/bin/
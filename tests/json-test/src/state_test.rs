@@ -16,6 +16,7 @@ use crate::helper::{secret_2_address, string_2_bytes, string_2_h256, string_2_u2
 use crate::json::state::Test;
 
 use core_executor::cita_executive::CitaExecutive;
+use core_executor::contracts::native::factory::Factory as NativeFactory;
 use core_executor::libexecutor::sys_config::BlockSysConfig;
 use core_executor::libexecutor::{block::EVMBlockDataProvider, economical_model::EconomicalModel};
 use core_executor::types::{context::Context, transaction::Transaction}; //,Action,SignedTransaction};
@@ -89,6 +90,7 @@ pub fn test_json_file(p: &str) {
                 state_provider.clone(),
                 &evm_context,
                 EconomicalModel::Charge,
+                Arc::new(NativeFactory::default()),
             );
 
             let mut proto_tx = ProtoTransaction::new();
@@ -0,0 +1,466 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline integrity check of the chain database, and of the executor database
+//! against it.
+
+use crate::import::init_executor;
+use cita_db::{Config as DatabaseConfig, DataCategory, Database, RocksDB, NUM_COLUMNS};
+use cita_types::{Address, H256};
+use core::libchain::chain::get_pruned_height;
+use core_executor::libexecutor::command::Commander;
+use core_executor::libexecutor::executor::{get_current_header, Executor};
+use hashable::Hashable;
+use libproto::blockchain::{Proof as ProtoProof, ProofType};
+use proof::BftProof;
+use rlp::{Decodable, Encodable, UntrustedRlp};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use types::block::BlockBody;
+use types::block_number::{BlockNumber, BlockTag};
+use types::block_receipts::BlockReceipts;
+use types::db_indexes::{
+    BlockNumber2Body, BlockNumber2Hash, BlockNumber2Header, CurrentHash, CurrentProof, DBIndex,
+    Hash2BlockNumber, Hash2BlockReceipts,
+};
+use types::header::Header;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// Header is missing or can't be decoded
+    Header,
+    /// `parent_hash` is not the hash of the previous header
+    ParentLink,
+    /// `Hash2BlockNumber` doesn't point back to the header
+    HashIndex,
+    /// Body is missing or its root is not `transactions_root`
+    Body,
+    /// Receipts are missing or their root is not `receipts_root`
+    Receipts,
+    /// Proof doesn't match the block or isn't signed by the validators
+    Proof,
+    /// Executor's `BlockNumber2Hash` is missing or not the chain's hash
+    ExecutorHash,
+    /// State of `state_root` isn't in the executor database
+    State,
+}
+
+impl IssueKind {
+    fn repair(self) -> &'static str {
+        match self {
+            IssueKind::Header | IssueKind::ParentLink | IssueKind::Proof => {
+                "the chain is broken here, restore a snapshot below this height \
+                 or resync the node from an empty data directory"
+            }
+            IssueKind::HashIndex | IssueKind::Body | IssueKind::Receipts => {
+                "the header is fine but its data is lost, resync the blocks from \
+                 this height, the blocks below it can be kept"
+            }
+            IssueKind::ExecutorHash | IssueKind::State => {
+                "the executor diverged from the chain, re-execute from the previous \
+                 height with the blocks exported by `export-blocks`"
+            }
+        }
+    }
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            IssueKind::Header => "header",
+            IssueKind::ParentLink => "parent link",
+            IssueKind::HashIndex => "hash index",
+            IssueKind::Body => "body",
+            IssueKind::Receipts => "receipts",
+            IssueKind::Proof => "proof",
+            IssueKind::ExecutorHash => "executor hash index",
+            IssueKind::State => "executor state",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct Issue {
+    pub height: BlockNumber,
+    pub kind: IssueKind,
+    pub detail: String,
+}
+
+pub struct Report {
+    pub current_height: BlockNumber,
+    pub pruned_height: BlockNumber,
+    /// Whether the executor database is checked, the proofs are checked with it too.
+    pub executor_checked: bool,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    fn add<S: Into<String>>(&mut self, height: BlockNumber, kind: IssueKind, detail: S) {
+        let detail = detail.into();
+        warn!("block {}: {} {}", height, kind, detail);
+        self.issues.push(Issue {
+            height,
+            kind,
+            detail,
+        });
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "checked blocks 0..={}, bodies and receipts below {} are pruned",
+            self.current_height, self.pruned_height
+        )?;
+        if !self.executor_checked {
+            writeln!(f, "executor database and proofs are not checked")?;
+        }
+        if self.issues.is_empty() {
+            return writeln!(f, "no issues found");
+        }
+
+        for issue in &self.issues {
+            writeln!(f, "block {}: {} {}", issue.height, issue.kind, issue.detail)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "repair report:")?;
+        let mut first_heights: BTreeMap<IssueKind, (BlockNumber, usize)> = BTreeMap::new();
+        for issue in &self.issues {
+            let entry = first_heights.entry(issue.kind).or_insert((issue.height, 0));
+            entry.1 += 1;
+        }
+        for (kind, (height, count)) in first_heights {
+            writeln!(
+                f,
+                "  {} issues: {}, first at block {}: {}",
+                kind,
+                count,
+                height,
+                kind.repair()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_value<T: Decodable>(value: Option<Vec<u8>>) -> Option<T> {
+    value.and_then(|bytes| UntrustedRlp::new(&bytes).as_val().ok())
+}
+
+struct ChainDB {
+    db: RocksDB,
+}
+
+impl ChainDB {
+    fn get<T: Decodable>(&self, category: DataCategory, key: &[u8]) -> Option<T> {
+        decode_value(self.db.get(Some(category), key).unwrap_or(None))
+    }
+
+    fn header(&self, number: BlockNumber) -> Option<Header> {
+        self.get(
+            DataCategory::Headers,
+            &BlockNumber2Header(number).get_index(),
+        )
+    }
+
+    fn body(&self, number: BlockNumber) -> Option<BlockBody> {
+        self.get(DataCategory::Bodies, &BlockNumber2Body(number).get_index())
+    }
+
+    fn receipts(&self, hash: H256) -> Option<BlockReceipts> {
+        self.get(DataCategory::Extra, &Hash2BlockReceipts(hash).get_index())
+    }
+
+    fn number(&self, hash: H256) -> Option<BlockNumber> {
+        self.get(DataCategory::Extra, &Hash2BlockNumber(hash).get_index())
+    }
+
+    fn current_hash(&self) -> Option<H256> {
+        self.get(DataCategory::Extra, &CurrentHash.get_index())
+    }
+
+    fn current_proof(&self) -> Option<ProtoProof> {
+        self.get(DataCategory::Extra, &CurrentProof.get_index())
+    }
+}
+
+/// Walk the chain database at `nosql_path` from the genesis to `CurrentHash`.
+///
+/// If `executor` is `Some((genesis_path, data_path))`, the executor database is
+/// checked against the chain, and the proofs are checked against the validators
+/// from the executor's state.
+pub fn check_db(nosql_path: &str, executor: Option<(&str, String)>) -> Result<Report, String> {
+    let db_config = DatabaseConfig::with_category_num(NUM_COLUMNS);
+    let db = RocksDB::open(nosql_path, &db_config)
+        .map_err(|err| format!("open chain db {} failed: {:?}", nosql_path, err))?;
+    let chain_db = ChainDB { db };
+
+    let current_hash = chain_db
+        .current_hash()
+        .ok_or_else(|| "CurrentHash not found in the chain db".to_owned())?;
+    let current_height = chain_db
+        .number(current_hash)
+        .ok_or_else(|| format!("height of CurrentHash {:?} not found", current_hash))?;
+    let executor = match executor {
+        Some((genesis_path, data_path)) => open_executor(genesis_path, data_path)?,
+        None => None,
+    };
+
+    let mut report = Report {
+        current_height,
+        pruned_height: get_pruned_height(&chain_db.db),
+        executor_checked: executor.is_some(),
+        issues: Vec::new(),
+    };
+    let mut parent: Option<Header> = None;
+    for number in 0..=current_height {
+        let header = match chain_db.header(number) {
+            Some(ref header) if header.number() != number => {
+                report.add(
+                    number,
+                    IssueKind::Header,
+                    format!(
+                        "is stored at {} but its number is {}",
+                        number,
+                        header.number()
+                    ),
+                );
+                parent = None;
+                continue;
+            }
+            Some(header) => header,
+            None => {
+                report.add(number, IssueKind::Header, "is missing or undecodable");
+                parent = None;
+                continue;
+            }
+        };
+        let hash = header.hash().unwrap();
+
+        if let Some(ref parent) = parent {
+            let parent_hash = parent.hash().unwrap();
+            if *header.parent_hash() != parent_hash {
+                report.add(
+                    number,
+                    IssueKind::ParentLink,
+                    format!(
+                        "parent_hash is {:?}, the hash of block {} is {:?}",
+                        header.parent_hash(),
+                        number - 1,
+                        parent_hash
+                    ),
+                );
+            }
+            // The proof of a block is in the header of the next block.
+            if let Some(ref executor) = executor {
+                check_proof(&mut report, executor, parent, header.proof());
+            }
+        }
+        if number == current_height && number > 0 {
+            if let Some(ref executor) = executor {
+                match chain_db.current_proof() {
+                    Some(proof) => check_proof(&mut report, executor, &header, &proof),
+                    None => report.add(number, IssueKind::Proof, "CurrentProof is missing"),
+                }
+            }
+        }
+
+        match chain_db.number(hash) {
+            Some(indexed) if indexed == number => {}
+            Some(indexed) => report.add(
+                number,
+                IssueKind::HashIndex,
+                format!("Hash2BlockNumber of {:?} is {}", hash, indexed),
+            ),
+            None => report.add(
+                number,
+                IssueKind::HashIndex,
+                format!("Hash2BlockNumber of {:?} is missing", hash),
+            ),
+        }
+
+        if number >= report.pruned_height {
+            check_body(&mut report, &chain_db, &header);
+        }
+
+        if let Some(ref executor) = executor {
+            check_executor(&mut report, executor, &header);
+        }
+        parent = Some(header);
+    }
+
+    if let Some(mut executor) = executor {
+        executor.close();
+    }
+    Ok(report)
+}
+
+// The executor is opened only if it has executed blocks, otherwise
+// opening it writes the genesis block into its database.
+fn open_executor(genesis_path: &str, data_path: String) -> Result<Option<Executor>, String> {
+    let statedb_path = data_path.clone() + "/statedb";
+    if !Path::new(&statedb_path).exists() {
+        warn!("executor db {} not found", statedb_path);
+        return Ok(None);
+    }
+    {
+        let db_config = DatabaseConfig::with_category_num(NUM_COLUMNS);
+        let db = RocksDB::open(&statedb_path, &db_config)
+            .map_err(|err| format!("open executor db {} failed: {:?}", statedb_path, err))?;
+        if get_current_header(Arc::new(db)).is_none() {
+            warn!("executor db {} has no blocks", statedb_path);
+            return Ok(None);
+        }
+    }
+    Ok(Some(init_executor(genesis_path, data_path, false)))
+}
+
+fn check_body(report: &mut Report, chain_db: &ChainDB, header: &Header) {
+    let number = header.number();
+    let body = match chain_db.body(number) {
+        Some(body) => body,
+        None => {
+            report.add(number, IssueKind::Body, "is missing or undecodable");
+            return;
+        }
+    };
+    let transactions_root = body.protobuf().transactions_root();
+    if transactions_root != *header.transactions_root() {
+        report.add(
+            number,
+            IssueKind::Body,
+            format!(
+                "root is {:?}, transactions_root is {:?}",
+                transactions_root,
+                header.transactions_root()
+            ),
+        );
+    }
+
+    // Receipts are not saved for the blocks without transactions.
+    let receipts = match chain_db.receipts(header.hash().unwrap()) {
+        Some(block_receipts) => block_receipts.receipts,
+        None if body.transactions().is_empty() => Vec::new(),
+        None => {
+            report.add(number, IssueKind::Receipts, "are missing or undecodable");
+            return;
+        }
+    };
+    if receipts.len() != body.transactions().len() {
+        report.add(
+            number,
+            IssueKind::Receipts,
+            format!(
+                "count is {}, transactions count is {}",
+                receipts.len(),
+                body.transactions().len()
+            ),
+        );
+    }
+    let receipts_root = cita_merklehash::Tree::from_hashes(
+        receipts
+            .iter()
+            .map(|r| r.rlp_bytes().into_vec().crypt_hash())
+            .collect::<Vec<_>>(),
+        cita_merklehash::merge,
+    )
+    .get_root_hash()
+    .cloned()
+    .unwrap_or(cita_merklehash::HASH_NULL);
+    if receipts_root != *header.receipts_root() {
+        report.add(
+            number,
+            IssueKind::Receipts,
+            format!(
+                "root is {:?}, receipts_root is {:?}",
+                receipts_root,
+                header.receipts_root()
+            ),
+        );
+    }
+}
+
+// The proof of block `n` is signed by the validators after block `n - 1`.
+// The genesis block has no proof.
+fn check_proof(report: &mut Report, executor: &Executor, block: &Header, proof: &ProtoProof) {
+    let number = block.number();
+    if number == 0 || proof.get_field_type() != ProofType::Bft {
+        return;
+    }
+    let bft_proof = BftProof::from(proof.clone());
+    if block.proposal_protobuf().crypt_hash() != bft_proof.proposal {
+        report.add(
+            number,
+            IssueKind::Proof,
+            format!("is for proposal {:?}", bft_proof.proposal),
+        );
+        return;
+    }
+    let validators: Vec<Address> = match executor.node_manager().nodes(BlockTag::Height(number - 1))
+    {
+        Some(validators) => validators,
+        None => {
+            report.add(
+                number,
+                IssueKind::Proof,
+                format!("validators after block {} are unknown", number - 1),
+            );
+            return;
+        }
+    };
+    if !bft_proof.check(number as usize, &validators) {
+        report.add(
+            number,
+            IssueKind::Proof,
+            format!("is not signed by the validators {:?}", validators),
+        );
+    }
+}
+
+fn check_executor(report: &mut Report, executor: &Executor, header: &Header) {
+    let number = header.number();
+    let hash = header.hash().unwrap();
+    let executed_hash: Option<H256> = decode_value(
+        executor
+            .db
+            .get(
+                Some(DataCategory::Extra),
+                &BlockNumber2Hash(number).get_index(),
+            )
+            .unwrap_or(None),
+    );
+    match executed_hash {
+        Some(executed_hash) if executed_hash == hash => {}
+        Some(executed_hash) => report.add(
+            number,
+            IssueKind::ExecutorHash,
+            format!("is {:?}, the chain's is {:?}", executed_hash, hash),
+        ),
+        None => report.add(number, IssueKind::ExecutorHash, "is missing"),
+    }
+
+    if executor
+        .gen_state(*header.state_root(), *header.parent_hash())
+        .is_none()
+    {
+        report.add(
+            number,
+            IssueKind::State,
+            format!("of state_root {:?} is missing", header.state_root()),
+        );
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::import::init_executor;
+use crate::stream::{Record, RecordWriter};
+use cita_db::{Config as DatabaseConfig, RocksDB, NUM_COLUMNS};
+use core::libchain::chain::{Chain, Config as ChainConfig};
+use libproto::{BlockWithProof, TryInto};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+
+/// Export the blocks in `[from, to]` of the chain database with their proofs,
+/// returns the number of exported blocks.
+pub fn export_blocks(nosql_path: &str, from: u64, to: u64, file: &str) -> Result<u64, String> {
+    let db_config = DatabaseConfig::with_category_num(NUM_COLUMNS);
+    let db = RocksDB::open(nosql_path, &db_config)
+        .map_err(|err| format!("open chain db {} failed: {:?}", nosql_path, err))?;
+    let chain = Chain::init_chain(Arc::new(db), ChainConfig::default());
+
+    let current_height = chain.get_current_height();
+    let to = to.min(current_height);
+    if from > to {
+        return Err(format!(
+            "no blocks in [{}, {}], the current height is {}",
+            from, to, current_height
+        ));
+    }
+
+    let mut writer =
+        RecordWriter::create(file).map_err(|err| format!("create {} failed: {}", file, err))?;
+    for height in from..=to {
+        let block = chain
+            .block_by_height(height)
+            .map_err(|err| format!("block {}: {}", height, err))?;
+        // The proof of a block is in the next block, the latest one is kept alone.
+        let proof = if height == current_height {
+            chain.current_block_poof()
+        } else {
+            chain.get_block_proof_by_height(height)
+        }
+        .ok_or_else(|| format!("proof of block {} not found", height))?;
+
+        let mut block_with_proof = BlockWithProof::new();
+        block_with_proof.set_blk(block.protobuf());
+        block_with_proof.set_proof(proof);
+        let block_with_proof: Vec<u8> = block_with_proof
+            .try_into()
+            .map_err(|err| format!("encode block {} failed: {:?}", height, err))?;
+        writer
+            .write(&Record {
+                height,
+                block_with_proof,
+            })
+            .map_err(|err| format!("write block {} failed: {}", height, err))?;
+        if height % 1000 == 0 {
+            info!("exported block {}", height);
+        }
+    }
+    writer
+        .finish()
+        .map_err(|err| format!("finish {} failed: {}", file, err))?;
+    Ok(to - from + 1)
+}
+
+/// Export the state diffs recorded by the executor for the blocks in `[from, to]` as
+/// JSON lines, returns the number of exported blocks. Blocks without a recorded diff
+/// are skipped.
+pub fn export_state_diffs(
+    genesis_path: &str,
+    data_path: String,
+    from: u64,
+    to: u64,
+    file: &str,
+) -> Result<u64, String> {
+    let executor = init_executor(genesis_path, data_path, false);
+    let current_height = executor.get_current_height();
+    let to = to.min(current_height);
+    if from > to {
+        return Err(format!(
+            "no blocks in [{}, {}], the current height is {}",
+            from, to, current_height
+        ));
+    }
+
+    let mut writer = BufWriter::new(
+        File::create(file).map_err(|err| format!("create {} failed: {}", file, err))?,
+    );
+    let exported = executor
+        .export_state_diffs(from, to, &mut writer)
+        .map_err(|err| format!("write state diffs failed: {}", err))?;
+    writer
+        .flush()
+        .map_err(|err| format!("finish {} failed: {}", file, err))?;
+    Ok(exported)
+}
@@ -0,0 +1,120 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::stream::RecordReader;
+use cita_types::H256;
+use core_executor::libexecutor::command::Commander;
+use core_executor::libexecutor::executor::Executor;
+use core_executor::libexecutor::fsm::FSM;
+use libproto::{BlockWithProof, TryFrom};
+use types::block::OpenBlock;
+
+/// Same as the default `statedb_cache_size` of executor
+const STATEDB_CACHE_SIZE: usize = 5 * 1024 * 1024;
+
+/// Open the executor database without serving, blocks and commands are
+/// passed to the executor by calling it directly.
+pub fn init_executor(genesis_path: &str, data_path: String, eth_compatibility: bool) -> Executor {
+    let (_fsm_req_sender, fsm_req_receiver) = crossbeam_channel::unbounded();
+    let (fsm_resp_sender, _fsm_resp_receiver) = crossbeam_channel::unbounded();
+    let (_command_req_sender, command_req_receiver) = crossbeam_channel::bounded(0);
+    let (command_resp_sender, _command_resp_receiver) = crossbeam_channel::bounded(0);
+    Executor::init(
+        genesis_path,
+        data_path,
+        fsm_req_receiver,
+        fsm_resp_sender,
+        command_req_receiver,
+        command_resp_sender,
+        eth_compatibility,
+        false,
+        STATEDB_CACHE_SIZE,
+    )
+}
+
+/// Summary of an import
+pub struct ImportReport {
+    pub imported: u64,
+    pub skipped: u64,
+}
+
+/// Execute the exported blocks on the executor database at `data_path`, and check
+/// the state root and the receipts root of each block against the exported header.
+///
+/// It stops at the first mismatch, the mismatched block is not written.
+pub fn import_blocks(
+    genesis_path: &str,
+    data_path: String,
+    file: &str,
+    eth_compatibility: bool,
+) -> Result<ImportReport, String> {
+    let mut executor = init_executor(genesis_path, data_path, eth_compatibility);
+    let mut reader =
+        RecordReader::open(file).map_err(|err| format!("open {} failed: {}", file, err))?;
+    let mut report = ImportReport {
+        imported: 0,
+        skipped: 0,
+    };
+    while let Some(record) = reader
+        .read()
+        .map_err(|err| format!("read {} failed: {}", file, err))?
+    {
+        let height = record.height;
+        let current_height = executor.get_current_height();
+        if height <= current_height {
+            report.skipped += 1;
+            continue;
+        }
+        if height != current_height + 1 {
+            return Err(format!(
+                "block {} is missing, the next block in the file is {}",
+                current_height + 1,
+                height
+            ));
+        }
+
+        let mut block_with_proof = BlockWithProof::try_from(&record.block_with_proof[..])
+            .map_err(|err| format!("decode block {} failed: {:?}", height, err))?;
+        let block = block_with_proof.take_blk();
+        let expected_state_root = H256::from_slice(block.get_header().get_state_root());
+        let expected_receipts_root = H256::from_slice(block.get_header().get_receipts_root());
+
+        let closed_block = executor.into_fsm(OpenBlock::from(block));
+        if *closed_block.state_root() != expected_state_root {
+            return Err(format!(
+                "state root of block {} mismatched, exported {:?}, executed {:?}",
+                height,
+                expected_state_root,
+                closed_block.state_root()
+            ));
+        }
+        if *closed_block.receipts_root() != expected_receipts_root {
+            return Err(format!(
+                "receipts root of block {} mismatched, exported {:?}, executed {:?}",
+                height,
+                expected_receipts_root,
+                closed_block.receipts_root()
+            ));
+        }
+
+        executor.grow(&closed_block);
+        executor.keep_state_cache(closed_block);
+        report.imported += 1;
+        if height % 1000 == 0 {
+            info!("imported block {}", height);
+        }
+    }
+    executor.close();
+    Ok(report)
+}
@@ -0,0 +1,180 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export blocks of cita-chain and import them into cita-executor.
+//!
+//! ```shell
+//! cita-block-porter export-blocks --from 1 --to 10000 --file blocks.gz
+//! cita-block-porter import-blocks --file blocks.gz --genesis genesis.json
+//! cita-block-porter check-db --genesis genesis.json
+//! cita-block-porter export-state-diffs --from 1 --to 10000 --file state_diffs.json
+//! ```
+//!
+//! All commands work on the databases directly, so the node should be stopped.
+//! `import-blocks` executes each block and stops at the first block whose state root
+//! or receipts root is not the same as the exported one.
+//! `check-db` walks the chain database from the genesis, checks it and the executor
+//! database against each other, and prints a repair report.
+//! `export-state-diffs` writes the state diffs kept by the executor, one block per line,
+//! only the blocks executed with `record_state_diff` have them.
+
+#[macro_use]
+extern crate cita_logger as logger;
+extern crate common_types as types;
+
+mod check;
+mod export;
+mod import;
+mod stream;
+
+use cita_directories::DataPath;
+use clap::{App, ArgMatches, SubCommand};
+use std::process;
+
+fn main() {
+    logger::init();
+
+    let matches = App::new("block-porter")
+        .version("0.1")
+        .author("Rivtower")
+        .about("Export and import blocks of CITA")
+        .subcommand(
+            SubCommand::with_name("export-blocks")
+                .about("Export blocks with proofs from the chain database")
+                .arg_from_usage("--data=[path] 'the chain database, default is data/nosql'")
+                .arg_from_usage("--from=[height] 'the first block to export, default is 0'")
+                .arg_from_usage("--to=[height] 'the last block to export, default is the latest'")
+                .arg_from_usage("--file=<file> 'the exported file'"),
+        )
+        .subcommand(
+            SubCommand::with_name("import-blocks")
+                .about(
+                    "Execute the exported blocks and verify their state roots and receipts roots",
+                )
+                .arg_from_usage("--file=<file> 'the exported file'")
+                .arg_from_usage("--genesis=[genesis] 'the genesis file, default is genesis.json'")
+                .arg_from_usage(
+                    "--data=[path] 'the data directory of the executor, default is data'",
+                )
+                .arg_from_usage("--eth-compatibility 'the chain enables eth_compatibility'"),
+        )
+        .subcommand(
+            SubCommand::with_name("check-db")
+                .about("Check the integrity of the chain database and the executor database")
+                .arg_from_usage("--data=[path] 'the chain database, default is data/nosql'")
+                .arg_from_usage("--genesis=[genesis] 'the genesis file, default is genesis.json'")
+                .arg_from_usage(
+                    "--executor-data=[path] 'the data directory of the executor, default is data'",
+                )
+                .arg_from_usage("--chain-only 'skip the executor database and the proofs'"),
+        )
+        .subcommand(
+            SubCommand::with_name("export-state-diffs")
+                .about("Export the state diffs recorded by the executor as JSON lines")
+                .arg_from_usage("--genesis=[genesis] 'the genesis file, default is genesis.json'")
+                .arg_from_usage(
+                    "--data=[path] 'the data directory of the executor, default is data'",
+                )
+                .arg_from_usage("--from=[height] 'the first block to export, default is 0'")
+                .arg_from_usage("--to=[height] 'the last block to export, default is the latest'")
+                .arg_from_usage("--file=<file> 'the exported file'"),
+        )
+        .get_matches();
+
+    let result = match matches.subcommand() {
+        ("export-blocks", Some(matches)) => export(matches),
+        ("import-blocks", Some(matches)) => import(matches),
+        ("check-db", Some(matches)) => check(matches),
+        ("export-state-diffs", Some(matches)) => export_state_diffs(matches),
+        _ => Err(matches.usage().to_owned()),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn parse_height(matches: &ArgMatches, name: &str, default: u64) -> Result<u64, String> {
+    matches.value_of(name).map_or(Ok(default), |height| {
+        height
+            .parse()
+            .map_err(|err| format!("invalid --{} {}: {}", name, height, err))
+    })
+}
+
+fn export(matches: &ArgMatches) -> Result<(), String> {
+    let nosql_path = matches
+        .value_of("data")
+        .map_or_else(DataPath::nosql_path, ToOwned::to_owned);
+    let from = parse_height(matches, "from", 0)?;
+    let to = parse_height(matches, "to", u64::max_value())?;
+    let file = matches.value_of("file").unwrap();
+
+    let exported = export::export_blocks(&nosql_path, from, to, file)?;
+    println!("exported {} blocks to {}", exported, file);
+    Ok(())
+}
+
+fn export_state_diffs(matches: &ArgMatches) -> Result<(), String> {
+    let genesis_path = matches.value_of("genesis").unwrap_or("genesis.json");
+    let data_path = matches
+        .value_of("data")
+        .map_or_else(DataPath::root_node_path, ToOwned::to_owned);
+    let from = parse_height(matches, "from", 0)?;
+    let to = parse_height(matches, "to", u64::max_value())?;
+    let file = matches.value_of("file").unwrap();
+
+    let exported = export::export_state_diffs(genesis_path, data_path, from, to, file)?;
+    println!("exported state diffs of {} blocks to {}", exported, file);
+    Ok(())
+}
+
+fn import(matches: &ArgMatches) -> Result<(), String> {
+    let file = matches.value_of("file").unwrap();
+    let genesis_path = matches.value_of("genesis").unwrap_or("genesis.json");
+    let data_path = matches
+        .value_of("data")
+        .map_or_else(DataPath::root_node_path, ToOwned::to_owned);
+    let eth_compatibility = matches.is_present("eth-compatibility");
+
+    let report = import::import_blocks(genesis_path, data_path, file, eth_compatibility)?;
+    println!(
+        "imported {} blocks from {}, skipped {} blocks already executed",
+        report.imported, file, report.skipped
+    );
+    Ok(())
+}
+
+fn check(matches: &ArgMatches) -> Result<(), String> {
+    let nosql_path = matches
+        .value_of("data")
+        .map_or_else(DataPath::nosql_path, ToOwned::to_owned);
+    let executor = if matches.is_present("chain-only") {
+        None
+    } else {
+        let genesis_path = matches.value_of("genesis").unwrap_or("genesis.json");
+        let data_path = matches
+            .value_of("executor-data")
+            .map_or_else(DataPath::root_node_path, ToOwned::to_owned);
+        Some((genesis_path, data_path))
+    };
+
+    let report = check::check_db(&nosql_path, executor)?;
+    print!("{}", report);
+    if report.issues.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} issues found", report.issues.len()))
+    }
+}
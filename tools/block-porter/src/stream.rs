@@ -0,0 +1,157 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The exported file is a gzip compressed stream of RLP records,
+//! each record is `[height, BlockWithProof in protobuf]`.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rlp::{RlpStream, UntrustedRlp};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+pub struct Record {
+    pub height: u64,
+    pub block_with_proof: Vec<u8>,
+}
+
+pub struct RecordWriter<W: Write> {
+    inner: GzEncoder<W>,
+}
+
+impl RecordWriter<BufWriter<File>> {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        RecordWriter {
+            inner: GzEncoder::new(writer, Compression::default()),
+        }
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&record.height);
+        stream.append(&record.block_with_proof);
+        self.inner.write_all(&stream.out())
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        self.inner.finish()
+    }
+}
+
+pub struct RecordReader<R: Read> {
+    inner: GzDecoder<R>,
+}
+
+impl RecordReader<BufReader<File>> {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(reader: R) -> Self {
+        RecordReader {
+            inner: GzDecoder::new(reader),
+        }
+    }
+
+    /// Read the next record, `None` at the end of the stream.
+    pub fn read(&mut self) -> io::Result<Option<Record>> {
+        let mut prefix = [0u8; 1];
+        if self.inner.read(&mut prefix)? == 0 {
+            return Ok(None);
+        }
+
+        // The record is an RLP list, the length of the payload is in its prefix.
+        let mut item = vec![prefix[0]];
+        let payload_len = match prefix[0] {
+            0xc0..=0xf7 => usize::from(prefix[0] - 0xc0),
+            0xf8..=0xff => {
+                let mut len_bytes = vec![0u8; usize::from(prefix[0] - 0xf7)];
+                self.inner.read_exact(&mut len_bytes)?;
+                item.extend_from_slice(&len_bytes);
+                len_bytes
+                    .iter()
+                    .fold(0usize, |len, byte| (len << 8) | usize::from(*byte))
+            }
+            _ => return Err(invalid_data("record is not an RLP list")),
+        };
+        let header_len = item.len();
+        item.resize(header_len + payload_len, 0);
+        self.inner.read_exact(&mut item[header_len..])?;
+
+        let rlp = UntrustedRlp::new(&item);
+        let height = rlp
+            .val_at(0)
+            .map_err(|err| invalid_data(&format!("{:?}", err)))?;
+        let block_with_proof = rlp
+            .val_at(1)
+            .map_err(|err| invalid_data(&format!("{:?}", err)))?;
+        Ok(Some(Record {
+            height,
+            block_with_proof,
+        }))
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Record, RecordReader, RecordWriter};
+
+    #[test]
+    fn test_records_round_trip() {
+        let records = vec![
+            Record {
+                height: 1,
+                block_with_proof: vec![],
+            },
+            Record {
+                height: 2,
+                block_with_proof: vec![7; 10],
+            },
+            // The length of a long list is in extra bytes.
+            Record {
+                height: 3,
+                block_with_proof: vec![9; 100_000],
+            },
+        ];
+
+        let mut writer = RecordWriter::new(Vec::new());
+        for record in records.iter() {
+            writer.write(record).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+
+        let mut reader = RecordReader::new(&compressed[..]);
+        for record in records.iter() {
+            let read = reader.read().unwrap().unwrap();
+            assert_eq!(read.height, record.height);
+            assert_eq!(read.block_with_proof, record.block_with_proof);
+        }
+        assert!(reader.read().unwrap().is_none());
+    }
+}
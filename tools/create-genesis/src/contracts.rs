@@ -28,6 +28,9 @@ pub struct ContractsData {
 
     #[serde(rename = "PermissionContracts")]
     pub permission_contracts: PermissionContracts,
+
+    #[serde(rename = "NativeContracts", default)]
+    pub native_contracts: BTreeMap<String, NativeInfo>,
 }
 
 impl ContractsData {
@@ -133,6 +136,12 @@ pub struct Info {
     pub file: String,
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct NativeInfo {
+    pub address: String,
+    pub activation: u64,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PermissionContracts {
     pub file: String,
@@ -48,11 +48,19 @@ pub struct Account {
     pub value: U256,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Native {
+    pub name: String,
+    pub address: String,
+    pub activation: u64,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Genesis {
     pub timestamp: u64,
     pub prevhash: String,
     pub alloc: BTreeMap<String, Account>,
+    pub natives: Vec<Native>,
 }
 
 impl Default for Genesis {
@@ -61,6 +69,7 @@ impl Default for Genesis {
             timestamp: 0,
             prevhash: String::default(),
             alloc: BTreeMap::new(),
+            natives: Vec::new(),
         }
     }
 }
@@ -206,6 +215,16 @@ impl<'a> GenesisCreator<'a> {
         genesis.timestamp = self.timestamp;
         genesis.prevhash = self.prevhash.to_owned();
         genesis.alloc = self.accounts.clone();
+        genesis.natives = self
+            .contract_list
+            .native_contracts
+            .iter()
+            .map(|(name, info)| Native {
+                name: name.clone(),
+                address: info.address.clone(),
+                activation: info.activation,
+            })
+            .collect();
         let f = File::create(self.genesis_path.to_owned()).expect("failed to create genesis.json.");
         let _ = serde_json::to_writer_pretty(f, &genesis);
     }
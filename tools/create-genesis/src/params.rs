@@ -110,6 +110,9 @@ pub struct SysConfig {
     #[serde(rename = "checkCallPermission")]
     pub check_call_permission: bool,
 
+    #[serde(rename = "checkInternalCallPermission", default)]
+    pub check_internal_call_permission: bool,
+
     #[serde(rename = "checkSendTxPermission")]
     pub check_send_tx_permission: bool,
 
@@ -179,6 +182,7 @@ impl GetParams for SysConfig {
         flags.push(Token::Bool(self.check_quota));
         flags.push(Token::Bool(self.check_fee_back_platform));
         flags.push(Token::Bool(self.auto_exec));
+        flags.push(Token::Bool(self.check_internal_call_permission));
 
         tokens.push(Token::Array(flags));
         tokens
@@ -14,10 +14,13 @@
 
 use libproto::TryInto;
 use parking_lot::RwLock;
+use std::cell::RefCell;
 use std::convert::Into;
+use std::str::FromStr;
+use std::time::Duration;
 
 use crate::configuration::UpStream;
-use cita_types::{H256, U256};
+use cita_types::{H160, H256, U256};
 use jsonrpc_types::{rpc_request, rpc_types};
 use libproto::blockchain::UnverifiedTransaction;
 
@@ -25,10 +28,19 @@ use libproto::blockchain::UnverifiedTransaction;
 pub enum Error {
     BadStatus,
     Parse,
+    Network,
+    Timeout,
+}
+
+thread_local! {
+    // Share one runtime for all the requests, instead of creating it for each request.
+    static RUNTIME: RefCell<tokio::runtime::Runtime> =
+        RefCell::new(tokio::runtime::Runtime::new().expect("create tokio runtime failed"));
 }
 
 pub struct RpcClient {
     uri: RwLock<hyper::Uri>,
+    timeout: Duration,
 }
 
 impl RpcClient {
@@ -37,6 +49,7 @@ impl RpcClient {
 
         ::std::sync::Arc::new(RpcClient {
             uri: RwLock::new(uri),
+            timeout: upstream.timeout,
         })
     }
 
@@ -50,13 +63,27 @@ impl RpcClient {
             .body(hyper::Body::from(body))
             .unwrap();
 
-        let mut rt = tokio::runtime::Runtime::new().unwrap();
-        let data = rt.block_on(async {
-            let client = hyper::Client::new();
-
-            let resp = client.request(req).await.unwrap();
-            hyper::body::to_bytes(resp.into_body()).await.unwrap()
-        });
+        let timeout = self.timeout;
+        let data = RUNTIME.with(|rt| {
+            rt.borrow_mut().block_on(async {
+                let client = hyper::Client::new();
+                let request = async {
+                    let resp = client.request(req).await.map_err(|err| {
+                        warn!("Send request failed: {:?}.", err);
+                        Error::Network
+                    })?;
+                    hyper::body::to_bytes(resp.into_body())
+                        .await
+                        .map_err(|err| {
+                            warn!("Read response failed: {:?}.", err);
+                            Error::Network
+                        })
+                };
+                tokio::time::timeout(timeout, request)
+                    .await
+                    .map_err(|_| Error::Timeout)?
+            })
+        })?;
         Ok(data.to_vec())
     }
 }
@@ -112,6 +139,56 @@ pub fn cita_get_metadata(upstream: &UpStream) -> Result<rpc_types::MetaData, Err
     Ok(result)
 }
 
+/// A log emitted by a crosschain send, only the fields to relay it are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendLog {
+    pub transaction_hash: H256,
+    pub block_number: u64,
+}
+
+pub fn cita_get_logs(
+    upstream: &UpStream,
+    address: H160,
+    topic: H256,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<SendLog>, Error> {
+    let req = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getLogs",
+        "params": [{
+            "fromBlock": format!("{:#x}", from_block),
+            "toBlock": format!("{:#x}", to_block),
+            "address": format!("{:#x}", address),
+            "topics": [format!("{:#x}", topic)],
+        }],
+    })
+    .to_string();
+    let result = rpc_send_and_get_result_from_reply!(upstream, req, Vec<serde_json::Value>);
+    result
+        .iter()
+        .map(|log| {
+            let transaction_hash = log["transactionHash"]
+                .as_str()
+                .and_then(|hash| H256::from_str(hash.trim_start_matches("0x")).ok());
+            let block_number = log["blockNumber"]
+                .as_str()
+                .and_then(|number| u64::from_str_radix(number.trim_start_matches("0x"), 16).ok());
+            match (transaction_hash, block_number) {
+                (Some(transaction_hash), Some(block_number)) => Ok(SendLog {
+                    transaction_hash,
+                    block_number,
+                }),
+                _ => {
+                    error!("get a malformed log {:?}", log);
+                    Err(Error::Parse)
+                }
+            }
+        })
+        .collect()
+}
+
 pub fn cita_send_transaction(
     upstream: &UpStream,
     utx: &UnverifiedTransaction,
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use cita_crypto::PrivKey;
-use cita_types::U256;
+use cita_types::{H160, H256, U256};
 use std::collections::HashMap;
 use std::time::Duration;
 
@@ -29,16 +29,42 @@ struct Chain {
     pub servers: Vec<UpStream>,
 }
 
+/// A contract which emits the crosschain send events.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Watch {
+    pub chain_id: U256,
+    pub contract: H160,
+    pub topic: H256,
+    /// Start to scan from this height if there is no progress.
+    #[serde(default)]
+    pub from_block: u64,
+}
+
+/// Settings of the long-running relayer mode.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Relayer {
+    pub watches: Vec<Watch>,
+    /// File to persist the progress.
+    pub progress_file: String,
+    pub poll_interval: Duration,
+    /// Max blocks to scan in one query of logs.
+    pub max_scan_blocks: u64,
+    /// Max attempts to relay a transaction before giving it up.
+    pub max_retries: u32,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 struct FileConfig {
     pub private_key: PrivKey,
     pub chains: Vec<Chain>,
+    pub relayer: Option<Relayer>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pkey: PrivKey,
     servers: HashMap<U256, Vec<UpStream>>,
+    relayer: Option<Relayer>,
 }
 
 impl FileConfig {
@@ -58,15 +84,24 @@ impl Config {
     pub fn get_private_key(&self) -> &PrivKey {
         &self.pkey
     }
+    #[inline]
+    pub fn get_relayer(&self) -> Option<&Relayer> {
+        self.relayer.as_ref()
+    }
 }
 
 pub fn parse_configfile(path: &str) -> Config {
     let config = FileConfig::load(path);
     let pkey = config.private_key;
+    let relayer = config.relayer;
     let servers = config
         .chains
         .into_iter()
         .map(|c| (c.id, c.servers))
         .collect();
-    Config { pkey, servers }
+    Config {
+        pkey,
+        servers,
+        relayer,
+    }
 }
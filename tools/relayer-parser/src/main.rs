@@ -22,6 +22,7 @@ extern crate cita_logger as logger;
 mod arguments;
 mod communication;
 mod configuration;
+mod relayer;
 mod transaction;
 
 use cita_crypto::PrivKey;
@@ -39,6 +40,15 @@ fn main() {
     let args = parse_arguments(&matches);
     let cfg = parse_configfile(&args.cfg_file);
 
+    if args.daemon {
+        let relayer_cfg = cfg
+            .get_relayer()
+            .expect("relayer is not set in the config file");
+        relayer::Relayer::new(&cfg, relayer_cfg).run();
+    }
+
+    let chain_id = args.chain_id.expect("chain id is required");
+    let tx_hash = args.tx_hash.expect("transaction hash is required");
     let mut retcode = 1;
     // Get servers list from the config file by the input chain id.
     // Try to get transaction proof from servers in server list.
@@ -47,8 +57,8 @@ fn main() {
     // The chain id of to-chain is in the tx proof.
     // Relay the transaction to each server in to-chain servers list, until succeed.
     let _ = cfg
-        .get_servers(args.chain_id)
-        .and_then(|servers| fetch_txproof(&servers[..], args.tx_hash))
+        .get_servers(chain_id)
+        .and_then(|servers| fetch_txproof(&servers[..], tx_hash))
         .and_then(|tx_proof_rlp| {
             deconstruct_txproof(&tx_proof_rlp[..]).map(|relay_info| (tx_proof_rlp, relay_info))
         })
@@ -62,6 +72,7 @@ fn main() {
                 &cfg.get_private_key(),
                 &tx_proof_rlp[..],
                 &relay_info,
+                "",
             )
         })
         .map(|tx_hash| {
@@ -101,6 +112,7 @@ fn construct_transaction(
     pkey: &PrivKey,
     tx_proof_rlp: &[u8],
     relay_info: &RelayInfo,
+    nonce: &str,
 ) -> Option<UnverifiedTransaction> {
     communication::cita_get_metadata(upstream)
         .ok()
@@ -128,6 +140,7 @@ fn construct_transaction(
                 relay_info.dest_contract,
                 chain_id,
                 height,
+                nonce.to_owned(),
             )
         })
 }
@@ -138,10 +151,11 @@ fn relay_transaction(
     pkey: &PrivKey,
     tx_proof_rlp: &[u8],
     relay_info: &RelayInfo,
+    nonce: &str,
 ) -> Option<H256> {
     let mut ret = None;
     for upstream in servers.iter() {
-        if let Some(utx) = construct_transaction(upstream, pkey, tx_proof_rlp, relay_info) {
+        if let Some(utx) = construct_transaction(upstream, pkey, tx_proof_rlp, relay_info, nonce) {
             if let Ok(hash) = communication::cita_send_transaction(upstream, &utx) {
                 ret = Some(hash);
                 break;
@@ -0,0 +1,293 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cita_types::{H160, H256, U256};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+
+use crate::communication::{self, SendLog};
+use crate::configuration::{Config, Relayer as RelayerConfig, UpStream, Watch};
+use crate::{deconstruct_txproof, fetch_txproof, relay_transaction};
+
+// The proof of a transaction needs the two blocks after it.
+const PROOF_CONFIRMATIONS: u64 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ScanProgress {
+    chain_id: U256,
+    contract: H160,
+    next_block: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PendingRelay {
+    chain_id: U256,
+    tx_hash: H256,
+    block_number: u64,
+    retries: u32,
+}
+
+/// Progress of the relayer, persisted after each change so it could resume after a restart.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Progress {
+    scanned: Vec<ScanProgress>,
+    pending: Vec<PendingRelay>,
+    nonce: u64,
+}
+
+impl Progress {
+    fn load(path: &str) -> Self {
+        if !Path::new(path).exists() {
+            info!("progress file {} not found, start from the beginning", path);
+            return Progress::default();
+        }
+        let file = fs::File::open(path).expect("open progress file failed");
+        let reader = io::BufReader::new(file);
+        serde_json::from_reader(reader).expect("progress file is malformed")
+    }
+
+    fn save(&self, path: &str) -> io::Result<()> {
+        // Replace the file at once, so a crash never leaves a broken progress.
+        let tmp_path = format!("{}.tmp", path);
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, path)
+    }
+
+    fn next_block(&self, watch: &Watch) -> u64 {
+        self.scanned
+            .iter()
+            .find(|p| p.chain_id == watch.chain_id && p.contract == watch.contract)
+            .map_or(watch.from_block, |p| p.next_block)
+    }
+
+    fn set_next_block(&mut self, watch: &Watch, next_block: u64) {
+        match self
+            .scanned
+            .iter_mut()
+            .find(|p| p.chain_id == watch.chain_id && p.contract == watch.contract)
+        {
+            Some(p) => p.next_block = next_block,
+            None => self.scanned.push(ScanProgress {
+                chain_id: watch.chain_id,
+                contract: watch.contract,
+                next_block,
+            }),
+        }
+    }
+
+    fn add_pending(&mut self, chain_id: U256, log: &SendLog) {
+        let exists = self
+            .pending
+            .iter()
+            .any(|p| p.chain_id == chain_id && p.tx_hash == log.transaction_hash);
+        if !exists {
+            self.pending.push(PendingRelay {
+                chain_id,
+                tx_hash: log.transaction_hash,
+                block_number: log.block_number,
+                retries: 0,
+            });
+        }
+    }
+
+    // The nonce is never reused, even if the relayer is restarted.
+    fn next_nonce(&mut self) -> String {
+        self.nonce += 1;
+        format!("relayer-{}", self.nonce)
+    }
+}
+
+pub struct Relayer<'a> {
+    cfg: &'a Config,
+    relayer_cfg: &'a RelayerConfig,
+    progress: Progress,
+}
+
+impl<'a> Relayer<'a> {
+    pub fn new(cfg: &'a Config, relayer_cfg: &'a RelayerConfig) -> Self {
+        let progress = Progress::load(&relayer_cfg.progress_file);
+        Relayer {
+            cfg,
+            relayer_cfg,
+            progress,
+        }
+    }
+
+    pub fn run(mut self) -> ! {
+        info!(
+            "relayer started, watch {} contracts",
+            self.relayer_cfg.watches.len()
+        );
+        loop {
+            self.scan();
+            self.relay();
+            thread::sleep(self.relayer_cfg.poll_interval);
+        }
+    }
+
+    // Scan the new blocks for the crosschain send events.
+    fn scan(&mut self) {
+        for watch in self.relayer_cfg.watches.iter() {
+            let servers = match self.cfg.get_servers(watch.chain_id) {
+                Some(servers) => servers,
+                None => {
+                    error!("no servers for chain {}", watch.chain_id);
+                    continue;
+                }
+            };
+            let latest = match block_number(&servers[..]) {
+                Some(latest) => latest,
+                None => continue,
+            };
+            let from_block = self.progress.next_block(watch);
+            if from_block > latest {
+                continue;
+            }
+            let to_block = ::std::cmp::min(
+                latest,
+                from_block + self.relayer_cfg.max_scan_blocks.max(1) - 1,
+            );
+            let logs = match get_logs(&servers[..], watch, from_block, to_block) {
+                Some(logs) => logs,
+                None => continue,
+            };
+            trace!(
+                "chain {} blocks [{}, {}] have {} crosschain sends",
+                watch.chain_id,
+                from_block,
+                to_block,
+                logs.len()
+            );
+            for log in logs.iter() {
+                self.progress.add_pending(watch.chain_id, log);
+            }
+            self.progress.set_next_block(watch, to_block + 1);
+            self.save();
+        }
+    }
+
+    // Relay the pending transactions whose proofs are ready.
+    fn relay(&mut self) {
+        let pending = self.progress.pending.clone();
+        for relay in pending.iter() {
+            let servers = match self.cfg.get_servers(relay.chain_id) {
+                Some(servers) => servers,
+                None => continue,
+            };
+            let ready = block_number(&servers[..]).map_or(false, |latest| {
+                latest >= relay.block_number + PROOF_CONFIRMATIONS
+            });
+            if !ready {
+                continue;
+            }
+
+            let nonce = self.progress.next_nonce();
+            // Persist the nonce before it's used.
+            self.save();
+            let relayed = fetch_txproof(&servers[..], relay.tx_hash)
+                .and_then(|tx_proof_rlp| {
+                    deconstruct_txproof(&tx_proof_rlp[..])
+                        .map(|relay_info| (tx_proof_rlp, relay_info))
+                })
+                .and_then(|(tx_proof_rlp, relay_info)| {
+                    self.cfg
+                        .get_servers(relay_info.to_chain_id)
+                        .and_then(|to_servers| {
+                            relay_transaction(
+                                &to_servers[..],
+                                self.cfg.get_private_key(),
+                                &tx_proof_rlp[..],
+                                &relay_info,
+                                &nonce,
+                            )
+                        })
+                });
+
+            let index = self
+                .progress
+                .pending
+                .iter()
+                .position(|p| p.chain_id == relay.chain_id && p.tx_hash == relay.tx_hash)
+                .expect("pending relay should exist");
+            match relayed {
+                Some(hash) => {
+                    info!(
+                        "relay {:?} of chain {} by {:?}",
+                        relay.tx_hash, relay.chain_id, hash
+                    );
+                    self.progress.pending.remove(index);
+                }
+                None => {
+                    let retries = relay.retries + 1;
+                    if retries >= self.relayer_cfg.max_retries {
+                        error!(
+                            "give up relaying {:?} of chain {} after {} attempts",
+                            relay.tx_hash, relay.chain_id, retries
+                        );
+                        self.progress.pending.remove(index);
+                    } else {
+                        warn!(
+                            "relay {:?} of chain {} failed, attempts {}",
+                            relay.tx_hash, relay.chain_id, retries
+                        );
+                        self.progress.pending[index].retries = retries;
+                    }
+                }
+            }
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = self.progress.save(&self.relayer_cfg.progress_file) {
+            // Keep running, the progress will be saved next time.
+            error!("save progress failed: {:?}", err);
+        }
+    }
+}
+
+#[inline]
+fn block_number(servers: &[UpStream]) -> Option<u64> {
+    servers
+        .iter()
+        .filter_map(|upstream| communication::cita_block_number(upstream).ok())
+        .next()
+        .map(|height| height.low_u64())
+}
+
+#[inline]
+fn get_logs(
+    servers: &[UpStream],
+    watch: &Watch,
+    from_block: u64,
+    to_block: u64,
+) -> Option<Vec<SendLog>> {
+    servers
+        .iter()
+        .filter_map(|upstream| {
+            communication::cita_get_logs(
+                upstream,
+                watch.contract,
+                watch.topic,
+                from_block,
+                to_block,
+            )
+            .ok()
+        })
+        .next()
+}
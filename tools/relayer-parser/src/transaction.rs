@@ -23,9 +23,10 @@ pub fn construct_transaction(
     dest_contract: H160,
     chain_id: U256,
     height: U256,
+    nonce: String,
 ) -> UnverifiedTransaction {
     let code = encode(dest_hasher, tx_proof_rlp);
-    sign(pkey, dest_contract, code, chain_id, height)
+    sign(pkey, dest_contract, code, chain_id, height, nonce)
 }
 
 #[inline]
@@ -49,8 +50,10 @@ fn sign(
     code: Vec<u8>,
     chain_id: U256,
     height: U256,
+    nonce: String,
 ) -> UnverifiedTransaction {
     let mut tx = Transaction::new();
+    tx.set_nonce(nonce);
     tx.set_data(code);
     tx.set_to_v1(addr.to_vec());
     tx.set_valid_until_block(height.low_u64() + 100);